@@ -1,7 +1,18 @@
 //! API functions exposed to the frontend (Tauri). Only these should be visible to the Tauri app.
 
+use crate::apprules::AppRules;
+use crate::config::{CategoryBudgets, Config, EffectiveSetting, NonWorkingDays, WorkingHours};
+use crate::constants::{DEFAULT_DAILY_GOAL_SECS, FOCUS_TREND_WINDOW_DAYS, WEEKLY_REPORT_TOP_APPS};
 use crate::db::DbHandle;
 use crate::error::SynapseError;
+use crate::metrics::PollTimingStats;
+use crate::platform::{get_foreground_process_name, is_system_dnd, list_running_process_names};
+use crate::session::{FocusSession, SessionManager};
+use crate::sync::SupabaseSync;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
 #[cfg(target_os = "windows")]
 use winreg::enums::*;
 #[cfg(target_os = "windows")]
@@ -10,27 +21,40 @@ use winreg::RegKey;
 /// Returns the total focus time (in seconds) for today.
 pub fn total_focus_time_today(db: &DbHandle) -> Result<i64, SynapseError> {
     let (start_of_day, end_of_day) = today_bounds();
+    focus_time_between(db, start_of_day, end_of_day)
+}
+
+/// Returns the focus time (in seconds) of sessions starting in `[start, end)`.
+fn focus_time_between(db: &DbHandle, start: i64, end: i64) -> Result<i64, SynapseError> {
     let mut stmt = db.conn().prepare(
         "SELECT SUM(COALESCE(end_time, strftime('%s','now')) - start_time) FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2"
     )?;
-    let total: Option<i64> = stmt
-        .query_row([start_of_day, end_of_day], |row| row.get(0))
-        .ok();
+    let total: Option<i64> = stmt.query_row([start, end], |row| row.get(0)).ok();
     Ok(total.unwrap_or(0))
 }
 
 /// Returns the total number of distractions today.
 pub fn total_distractions_today(db: &DbHandle) -> Result<i64, SynapseError> {
     let (start_of_day, end_of_day) = today_bounds();
+    distractions_between(db, start_of_day, end_of_day)
+}
+
+/// Returns the distractions counted in sessions starting in `[start, end)`.
+fn distractions_between(db: &DbHandle, start: i64, end: i64) -> Result<i64, SynapseError> {
     let mut stmt = db.conn().prepare(
         "SELECT SUM(distraction_attempts) FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2"
     )?;
-    let total: Option<i64> = stmt
-        .query_row([start_of_day, end_of_day], |row| row.get(0))
-        .ok();
+    let total: Option<i64> = stmt.query_row([start, end], |row| row.get(0)).ok();
     Ok(total.unwrap_or(0))
 }
 
+/// Returns how many blocked apps were opened today while no session was running. Only
+/// counted when `count_distractions_outside_sessions` is on.
+pub fn distractions_outside_sessions_today(db: &DbHandle) -> Result<i64, SynapseError> {
+    let (start_of_day, end_of_day) = today_bounds();
+    db.outside_session_distractions_between(start_of_day, end_of_day)
+}
+
 /// Returns the total number of focus sessions started today.
 pub fn total_focus_sessions_today(db: &DbHandle) -> Result<i64, SynapseError> {
     let (start_of_day, end_of_day) = today_bounds();
@@ -43,84 +67,1272 @@ pub fn total_focus_sessions_today(db: &DbHandle) -> Result<i64, SynapseError> {
     Ok(count.unwrap_or(0))
 }
 
-#[cfg(target_os = "windows")]
-/// Returns a list of installed (app_name, exe_name) tuples from the Windows registry.
-pub fn get_installed_apps_api() -> Vec<(String, String)> {
-    use std::path::Path;
-    use winreg::enums::*;
-    use winreg::RegKey;
-
-    fn extract_exe_name(path: &str) -> Option<String> {
-        // Strip quotes/arguments/comma and extract only filename ending in .exe
-        path.split(|c| c == ',' || c == ' ' || c == '\"')
-            .find(|s| s.to_lowercase().ends_with(".exe"))
-            .and_then(|p| {
-                Path::new(p)
-                    .file_name()
-                    .map(|f| f.to_string_lossy().to_string())
+/// Today's focus time, distractions and session count.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TodayStats {
+    pub focus_secs: i64,
+    pub distractions: i64,
+    pub sessions: i64,
+}
+
+/// Returns today's focus time, distractions and number of sessions.
+pub fn today_stats(db: &DbHandle) -> Result<TodayStats, SynapseError> {
+    Ok(TodayStats {
+        focus_secs: total_focus_time_today(db)?,
+        distractions: total_distractions_today(db)?,
+        sessions: total_focus_sessions_today(db)?,
+    })
+}
+
+/// Returns the `limit` most recently started sessions, newest first.
+pub fn recent_sessions(db: &DbHandle, limit: u32) -> Result<Vec<SessionRecord>, SynapseError> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id, start_time, end_time, work_apps, distraction_attempts, manual FROM focus_sessions ORDER BY start_time DESC LIMIT ?1",
+    )?;
+    let sessions = stmt
+        .query_map([limit], |row| {
+            Ok(SessionRecord {
+                id: row.get(0)?,
+                start_time: row.get(1)?,
+                end_time: row.get(2)?,
+                work_apps: row.get(3)?,
+                distraction_attempts: row.get(4)?,
+                manual: row.get(5)?,
             })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sessions)
+}
+
+/// Records a session done away from the computer, e.g. a meeting, as a `manual` session.
+///
+/// # Errors
+/// Returns `SynapseError::Config` if `start` is not before `end` or `end` is in the future.
+pub fn add_manual_session(
+    db: &DbHandle,
+    start: i64,
+    end: i64,
+    work_apps: &[String],
+    distraction_attempts: u32,
+) -> Result<Uuid, SynapseError> {
+    add_manual_session_at(db, Local::now().timestamp(), start, end, work_apps, distraction_attempts)
+}
+
+/// Like [`add_manual_session`], with `now` as the current time.
+fn add_manual_session_at(
+    db: &DbHandle,
+    now: i64,
+    start: i64,
+    end: i64,
+    work_apps: &[String],
+    distraction_attempts: u32,
+) -> Result<Uuid, SynapseError> {
+    if start >= end {
+        return Err(SynapseError::Config(format!(
+            "a manual session must start before it ends, got {} to {}",
+            start, end
+        )));
+    }
+    if end > now {
+        return Err(SynapseError::Config(format!(
+            "a manual session cannot end in the future, got end {} after now {}",
+            end, now
+        )));
+    }
+    db.insert_manual_session(start, end, work_apps, distraction_attempts)
+}
+
+/// Deletes today's sessions and their events, keeping earlier history.
+///
+/// Returns the number of sessions deleted. End any active session first so the session
+/// manager is not left tracking a deleted row. The file is vacuumed afterwards to give the
+/// space back; a failed vacuum is only logged.
+pub fn reset_today(db: &DbHandle) -> Result<usize, SynapseError> {
+    let (start_of_day, end_of_day) = today_bounds();
+    let deleted = db.delete_sessions_between(start_of_day, end_of_day)?;
+    if deleted > 0 {
+        if let Err(e) = db.vacuum() {
+            eprintln!("Failed to vacuum the database after reset: {}", e);
+        }
+    }
+    Ok(deleted)
+}
+
+/// Checks Supabase credentials with a throwaway client, leaving the running sync untouched.
+///
+/// # Errors
+/// Returns `SynapseError::Supabase` describing whether the URL is malformed, the server
+/// is unreachable or the key was rejected.
+pub async fn test_supabase_connection(url: String, key: String) -> Result<(), SynapseError> {
+    SupabaseSync::new(key.trim().to_string(), url.trim().to_string())
+        .health_check()
+        .await?;
+    Ok(())
+}
+
+/// Returns the earliest session start and the latest session end among sessions starting on
+/// the day beginning at `day_start`, or `None` if there were none. A session still running
+/// ends now.
+pub fn activity_bounds(db: &DbHandle, day_start: i64) -> Result<Option<(i64, i64)>, SynapseError> {
+    activity_bounds_at(db, day_start, Local::now().timestamp())
+}
+
+/// Computes [`activity_bounds`] as if it were `now`.
+fn activity_bounds_at(
+    db: &DbHandle,
+    day_start: i64,
+    now: i64,
+) -> Result<Option<(i64, i64)>, SynapseError> {
+    let bounds: (Option<i64>, Option<i64>) = db.conn().query_row(
+        "SELECT MIN(start_time), MAX(COALESCE(end_time, ?3)) FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2",
+        [day_start, day_start + 86400, now],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(match bounds {
+        (Some(first), Some(last)) => Some((first, last)),
+        _ => None,
+    })
+}
+
+/// Returns the local day (as its start timestamp) with the most focus time in `[start, end)`
+/// and that many seconds, or `None` if the range has no focus time. Ties go to the earlier day.
+pub fn most_productive_day(
+    db: &DbHandle,
+    start: i64,
+    end: i64,
+) -> Result<Option<(i64, i64)>, SynapseError> {
+    let mut best: Option<(i64, i64)> = None;
+    for (day_start, secs) in focus_by_day(db, start, end)? {
+        let is_better = match best {
+            Some((_, best_secs)) => secs > best_secs,
+            None => secs > 0,
+        };
+        if is_better {
+            best = Some((day_start, secs));
+        }
+    }
+    Ok(best)
+}
+
+/// Returns `(day_start, focus_secs)` for each local day overlapping `[start, end)`, counting
+/// sessions by the day they start in.
+fn focus_by_day(db: &DbHandle, start: i64, end: i64) -> Result<Vec<(i64, i64)>, SynapseError> {
+    let mut days = Vec::new();
+    let (Some(first), Some(last)) = (
+        Local.timestamp_opt(start, 0).earliest(),
+        Local.timestamp_opt(end - 1, 0).earliest(),
+    ) else {
+        return Ok(days);
+    };
+    for date in first
+        .date_naive()
+        .iter_days()
+        .take_while(|date| *date <= last.date_naive())
+    {
+        let (day_start, day_end) = day_bounds(date);
+        let secs = focus_time_between(db, day_start.max(start), day_end.min(end))?;
+        days.push((day_start, secs));
+    }
+    Ok(days)
+}
+
+/// Returns `(day_start, average_secs)` for each of the last `days` local days, ending today,
+/// where the average is the trailing 7-day mean of daily focus time. The first points of the
+/// series average only the days available so far.
+pub fn focus_time_trend(db: &DbHandle, days: u32) -> Result<Vec<(i64, f64)>, SynapseError> {
+    focus_time_trend_through(db, Local::now().date_naive(), days)
+}
+
+/// Computes [`focus_time_trend`] for the `days` local days ending on `last`.
+fn focus_time_trend_through(
+    db: &DbHandle,
+    last: NaiveDate,
+    days: u32,
+) -> Result<Vec<(i64, f64)>, SynapseError> {
+    let series = focus_by_last_days(db, last, days)?;
+    let mut trend = Vec::with_capacity(series.len());
+    let mut window_secs = 0;
+    for (i, &(day_start, secs)) in series.iter().enumerate() {
+        window_secs += secs;
+        if i >= FOCUS_TREND_WINDOW_DAYS {
+            window_secs -= series[i - FOCUS_TREND_WINDOW_DAYS].1;
+        }
+        let window_len = (i + 1).min(FOCUS_TREND_WINDOW_DAYS);
+        trend.push((day_start, window_secs as f64 / window_len as f64));
     }
+    Ok(trend)
+}
+
+/// Returns `(day_start, focus_secs)` for the `days` local days ending on `last`.
+fn focus_by_last_days(
+    db: &DbHandle,
+    last: NaiveDate,
+    days: u32,
+) -> Result<Vec<(i64, i64)>, SynapseError> {
+    let Some(first) = days
+        .checked_sub(1)
+        .and_then(|back| last.checked_sub_days(chrono::Days::new(u64::from(back))))
+    else {
+        return Ok(Vec::new());
+    };
+    focus_by_day(db, day_bounds(first).0, day_bounds(last).1)
+}
 
-    let mut apps = Vec::new();
-
-    let uninstall_paths = [
-        (
-            RegKey::predef(HKEY_LOCAL_MACHINE),
-            r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
-        ),
-        (
-            RegKey::predef(HKEY_LOCAL_MACHINE),
-            r"SOFTWARE\Wow6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
-        ),
-        (
-            RegKey::predef(HKEY_CURRENT_USER),
-            r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
-        ),
-    ];
-
-    for (hive, path) in uninstall_paths.iter() {
-        if let Ok(uninstall) = hive.open_subkey(path) {
-            for item in uninstall.enum_keys().flatten() {
-                if let Ok(subkey) = uninstall.open_subkey(&item) {
-                    let display_name: Result<String, _> = subkey.get_value("DisplayName");
-                    let is_system_component =
-                        subkey.get_value::<u32, _>("SystemComponent").unwrap_or(0) == 1;
-
-                    if let Ok(name) = display_name {
-                        if is_system_component || name.trim().is_empty() {
-                            continue;
-                        }
-
-                        // Try DisplayIcon first, fallback to UninstallString
-                        let exe_source = subkey
-                            .get_value::<String, _>("DisplayIcon")
-                            .ok()
-                            .or_else(|| subkey.get_value("UninstallString").ok());
-
-                        if let Some(source) = exe_source {
-                            if let Some(exe_name) = extract_exe_name(&source) {
-                                apps.push((name.trim().to_string(), exe_name));
-                            }
-                        }
-                    }
-                }
+/// Scores how evenly focus time is spread over the last `days` local days, ending today.
+///
+/// The score is `1 / (1 + cv)`, where `cv` is the standard deviation of daily focus time
+/// divided by its mean: `1.0` means the same amount every day and it falls toward `0.0`
+/// as days vary more. Windows of fewer than two days score `1.0`; a window with no focus
+/// time at all scores `0.0`.
+pub fn consistency_score(db: &DbHandle, days: u32) -> Result<f64, SynapseError> {
+    consistency_score_through(db, Local::now().date_naive(), days)
+}
+
+/// Computes [`consistency_score`] for the `days` local days ending on `last`.
+fn consistency_score_through(
+    db: &DbHandle,
+    last: NaiveDate,
+    days: u32,
+) -> Result<f64, SynapseError> {
+    let series = focus_by_last_days(db, last, days)?;
+    if series.len() < 2 {
+        return Ok(1.0);
+    }
+    let n = series.len() as f64;
+    let mean = series.iter().map(|&(_, secs)| secs as f64).sum::<f64>() / n;
+    if mean == 0.0 {
+        return Ok(0.0);
+    }
+    let variance = series
+        .iter()
+        .map(|&(_, secs)| (secs as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    Ok(1.0 / (1.0 + variance.sqrt() / mean))
+}
+
+/// Focus time so far today compared with the same local time yesterday.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PaceComparison {
+    pub today_secs: i64,
+    pub yesterday_secs: i64,
+    /// `today_secs - yesterday_secs`; positive when today is ahead.
+    pub delta_secs: i64,
+}
+
+/// Compares focus time accumulated today with yesterday's total at the same local time.
+pub fn pace_vs_yesterday(db: &DbHandle) -> Result<PaceComparison, SynapseError> {
+    pace_at(db, Local::now().naive_local())
+}
+
+/// Computes [`pace_vs_yesterday`] as of the local time `now`.
+fn pace_at(db: &DbHandle, now: NaiveDateTime) -> Result<PaceComparison, SynapseError> {
+    let today = now.date();
+    let (today_start, _) = day_bounds(today);
+    let now_ts = local_timestamp(now);
+    let yesterday = today.pred_opt().unwrap_or(today);
+    let (yesterday_start, _) = day_bounds(yesterday);
+    let yesterday_now_ts = local_timestamp(yesterday.and_time(now.time()));
+
+    let today_secs = focus_time_overlapping(db, today_start, now_ts, now_ts)?;
+    let yesterday_secs = focus_time_overlapping(db, yesterday_start, yesterday_now_ts, now_ts)?;
+    Ok(PaceComparison {
+        today_secs,
+        yesterday_secs,
+        delta_secs: today_secs - yesterday_secs,
+    })
+}
+
+/// Returns the focus time (in seconds) falling inside `[start, end)`, clipping sessions
+/// that cross either bound. Open sessions are treated as running until `now`.
+fn focus_time_overlapping(
+    db: &DbHandle,
+    start: i64,
+    end: i64,
+    now: i64,
+) -> Result<i64, SynapseError> {
+    let total: i64 = db.conn().query_row(
+        "SELECT COALESCE(SUM(MIN(COALESCE(end_time, ?3), ?2) - MAX(start_time, ?1)), 0) FROM focus_sessions WHERE start_time < ?2 AND COALESCE(end_time, ?3) > ?1",
+        [start, end, now],
+        |row| row.get(0),
+    )?;
+    Ok(total)
+}
+
+/// Returns the longest stretch (in seconds) of focus today without a distraction.
+pub fn longest_distraction_free_span_today(db: &DbHandle) -> Result<i64, SynapseError> {
+    let (start_of_day, end_of_day) = today_bounds();
+    longest_distraction_free_span(db, start_of_day, end_of_day, Local::now().timestamp())
+}
+
+/// Returns the longest span inside a focus session and within `[start, end)` that contains
+/// no blocked app event. Spans run from a session edge or the end of one distraction to the
+/// start of the next, so time outside sessions never counts. Open sessions run until `now`.
+fn longest_distraction_free_span(
+    db: &DbHandle,
+    start: i64,
+    end: i64,
+    now: i64,
+) -> Result<i64, SynapseError> {
+    let mut sessions = db.conn().prepare(
+        "SELECT id, MAX(start_time, ?1), MIN(COALESCE(end_time, ?3), ?2) FROM focus_sessions WHERE start_time < ?2 AND COALESCE(end_time, ?3) > ?1",
+    )?;
+    let spans = sessions
+        .query_map([start, end, now], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut distractions = db.conn().prepare(
+        "SELECT start_time, COALESCE(end_time, start_time) FROM app_usage_events WHERE session_id = ?1 AND status = 'blocked' AND start_time IS NOT NULL ORDER BY start_time",
+    )?;
+
+    let mut longest = 0;
+    for (session_id, span_start, span_end) in spans {
+        let mut cursor = span_start;
+        let events = distractions
+            .query_map([&session_id], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (distraction_start, distraction_end) in events {
+            longest = longest.max(distraction_start.min(span_end) - cursor);
+            cursor = cursor.max(distraction_end.min(span_end));
+        }
+        longest = longest.max(span_end - cursor);
+    }
+    Ok(longest)
+}
+
+/// Returns the share (0 to 1) of today's session time during which some app was in focus.
+/// Session time with no app event recorded, such as a locked screen or an empty desktop,
+/// counts as idle. Returns 0 if there was no session time today.
+pub fn focus_efficiency_today(db: &DbHandle) -> Result<f64, SynapseError> {
+    let (start_of_day, end_of_day) = today_bounds();
+    focus_efficiency(db, start_of_day, end_of_day, Local::now().timestamp())
+}
+
+/// Computes the focus efficiency of sessions within `[start, end)`, clipping sessions and
+/// their app events to the window. Open sessions and events run until `now`.
+fn focus_efficiency(
+    db: &DbHandle,
+    start: i64,
+    end: i64,
+    now: i64,
+) -> Result<f64, SynapseError> {
+    let mut sessions = db.conn().prepare(
+        "SELECT id, MAX(start_time, ?1), MIN(COALESCE(end_time, ?3), ?2) FROM focus_sessions WHERE start_time < ?2 AND COALESCE(end_time, ?3) > ?1",
+    )?;
+    let spans = sessions
+        .query_map([start, end, now], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut events = db.conn().prepare(
+        "SELECT start_time, COALESCE(end_time, start_time + duration_secs, ?2) FROM app_usage_events WHERE session_id = ?1 AND start_time IS NOT NULL ORDER BY start_time",
+    )?;
+
+    let (mut active, mut total) = (0, 0);
+    for (session_id, span_start, span_end) in spans {
+        total += span_end - span_start;
+        let intervals = events
+            .query_map(params![session_id, now], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        // Overlapping events are merged so no second counts twice
+        let mut cursor = span_start;
+        for (event_start, event_end) in intervals {
+            let from = event_start.max(cursor);
+            let to = event_end.min(span_end);
+            if to > from {
+                active += to - from;
+                cursor = to;
             }
         }
     }
+    if total <= 0 {
+        return Ok(0.0);
+    }
+    Ok((active as f64 / total as f64).clamp(0.0, 1.0))
+}
+
+/// Lifetime totals across every recorded focus session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AllTimeStats {
+    pub total_focus_secs: i64,
+    pub total_sessions: i64,
+    pub total_distractions: i64,
+    /// Local date of the earliest session, or `None` when there are no sessions.
+    pub first_session_date: Option<NaiveDate>,
+    /// Number of distinct local days with at least one session.
+    pub active_days: i64,
+}
 
-    // Sort and deduplicate by app name
-    apps.sort_by(|a, b| a.0.cmp(&b.0));
-    apps.dedup_by(|a, b| a.0 == b.0);
-    apps
+/// Returns lifetime totals, computed in a single query over all sessions.
+pub fn all_time_stats(db: &DbHandle) -> Result<AllTimeStats, SynapseError> {
+    let (total_focus_secs, total_sessions, total_distractions, first_start, active_days) =
+        db.conn().query_row(
+            "SELECT COALESCE(SUM(COALESCE(end_time, strftime('%s','now')) - start_time), 0), COUNT(*), COALESCE(SUM(distraction_attempts), 0), MIN(start_time), COUNT(DISTINCT date(start_time, 'unixepoch', 'localtime')) FROM focus_sessions",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            },
+        )?;
+    let first_session_date = first_start
+        .and_then(|ts| Local.timestamp_opt(ts, 0).single())
+        .map(|dt| dt.date_naive());
+    Ok(AllTimeStats {
+        total_focus_secs,
+        total_sessions,
+        total_distractions,
+        first_session_date,
+        active_days,
+    })
+}
+
+/// Counts completed sessions starting in `[start, end)` by duration bucket.
+///
+/// Returns `(bucket_start_secs, count)` pairs for consecutive buckets of `bucket_secs`
+/// (e.g. 0–15m, 15–30m, …) up to the longest session, including empty buckets in between.
+/// Returns an empty list when there are no completed sessions in range.
+pub fn session_length_histogram(
+    db: &DbHandle,
+    bucket_secs: i64,
+    start: i64,
+    end: i64,
+) -> Result<Vec<(i64, i64)>, SynapseError> {
+    if bucket_secs <= 0 {
+        return Err(SynapseError::Config(format!(
+            "bucket_secs must be positive, got {}",
+            bucket_secs
+        )));
+    }
+    let mut stmt = db.conn().prepare(
+        "SELECT (end_time - start_time) / ?1 AS bucket, COUNT(*) FROM focus_sessions WHERE end_time IS NOT NULL AND end_time >= start_time AND start_time >= ?2 AND start_time < ?3 GROUP BY bucket ORDER BY bucket",
+    )?;
+    let counts = stmt
+        .query_map([bucket_secs, start, end], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(&(last_bucket, _)) = counts.last() else {
+        return Ok(Vec::new());
+    };
+    let mut histogram: Vec<(i64, i64)> = (0..=last_bucket)
+        .map(|bucket| (bucket * bucket_secs, 0))
+        .collect();
+    for (bucket, count) in counts {
+        histogram[bucket as usize].1 = count;
+    }
+    Ok(histogram)
 }
 
-use chrono::{Local, TimeZone};
+/// Counts the reasons given for distractions in events starting in `[start, end)`.
+///
+/// Reasons are grouped case-insensitively and returned as `(reason, count)` pairs, most
+/// common first.
+pub fn distraction_reasons_summary(
+    db: &DbHandle,
+    start: i64,
+    end: i64,
+) -> Result<Vec<(String, i64)>, SynapseError> {
+    let mut stmt = db.conn().prepare(
+        "SELECT lower(trim(reason)) AS key, COUNT(*) AS n FROM app_usage_events WHERE reason IS NOT NULL AND trim(reason) != '' AND start_time >= ?1 AND start_time < ?2 GROUP BY key ORDER BY n DESC, key",
+    )?;
+    let summary = stmt
+        .query_map([start, end], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(summary)
+}
+
+/// Focus time spent in one app, keyed by its stable usage key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppUsage {
+    pub app_key: String,
+    /// The latest display name recorded for the app, or the key if none is known.
+    pub display_name: String,
+    pub focus_secs: i64,
+    pub events: i64,
+}
+
+/// Returns focus time per app for events starting in `[start, end)`, most used first.
+pub fn app_usage_between(
+    db: &DbHandle,
+    start: i64,
+    end: i64,
+) -> Result<Vec<AppUsage>, SynapseError> {
+    let mut stmt = db.conn().prepare(
+        "SELECT e.process_name, COALESCE(n.display_name, e.process_name), COALESCE(SUM(e.duration_secs), 0) AS secs, COUNT(*) FROM app_usage_events e LEFT JOIN app_names n ON n.app_key = e.process_name WHERE e.start_time >= ?1 AND e.start_time < ?2 GROUP BY e.process_name ORDER BY secs DESC, e.process_name",
+    )?;
+    let usage = stmt
+        .query_map(params![start, end], |row| {
+            Ok(AppUsage {
+                app_key: row.get(0)?,
+                display_name: row.get(1)?,
+                focus_secs: row.get(2)?,
+                events: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(usage)
+}
+
+/// Returns today's focus time per app category against its daily budget, as
+/// `(category, used_secs, budget_secs)` sorted by category. Categories come from the app
+/// rules and budgets from the config; a category without a budget has `budget_secs` 0.
+pub fn category_usage_today(db: &DbHandle) -> Result<Vec<(String, i64, i64)>, SynapseError> {
+    let rules = AppRules::new()?;
+    let config = Config::load()?;
+    let (start_of_day, end_of_day) = today_bounds();
+    category_usage_between(db, &rules, &config.category_budgets, start_of_day, end_of_day)
+}
+
+/// Like [`category_usage_today`], for events starting in `[start, end)`. Every budgeted
+/// category is listed, even when unused.
+pub fn category_usage_between(
+    db: &DbHandle,
+    rules: &AppRules,
+    budgets: &CategoryBudgets,
+    start: i64,
+    end: i64,
+) -> Result<Vec<(String, i64, i64)>, SynapseError> {
+    let mut used: BTreeMap<String, i64> = budgets
+        .iter()
+        .map(|(category, _)| (category.to_string(), 0))
+        .collect();
+    for (app, secs) in db.focus_secs_by_app_between(start, end)? {
+        if let Some(category) = rules.category_of(&app) {
+            *used.entry(category.to_string()).or_default() += secs;
+        }
+    }
+    Ok(used
+        .into_iter()
+        .map(|(category, used_secs)| {
+            let budget_secs = budgets.get(&category).unwrap_or(0) as i64;
+            (category, used_secs, budget_secs)
+        })
+        .collect())
+}
+
+/// Returns the apps in the foreground during the last `seconds`, with their focus time in
+/// that window, most used first. Events are recorded when an app loses focus, so the app in
+/// focus right now only shows up once the user switches away.
+pub fn recent_foreground_apps(
+    db: &DbHandle,
+    seconds: i64,
+) -> Result<Vec<(String, i64)>, SynapseError> {
+    recent_foreground_apps_at(db, Local::now().timestamp(), seconds)
+}
+
+/// Like [`recent_foreground_apps`], for the window of `seconds` ending at `now`.
+fn recent_foreground_apps_at(
+    db: &DbHandle,
+    now: i64,
+    seconds: i64,
+) -> Result<Vec<(String, i64)>, SynapseError> {
+    let since = now - seconds.max(0);
+    let mut stmt = db.conn().prepare(
+        "SELECT process_name, SUM(MIN(COALESCE(end_time, start_time + duration_secs), ?2) - MAX(start_time, ?1)) AS secs FROM app_usage_events WHERE start_time < ?2 AND COALESCE(end_time, start_time + duration_secs) > ?1 GROUP BY process_name ORDER BY secs DESC, process_name",
+    )?;
+    let apps = stmt
+        .query_map([since, now], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(apps)
+}
+
+/// Local rows that have not been pushed to Supabase yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingSummary {
+    pub sessions: i64,
+    pub events: i64,
+    /// Start time of the oldest pending session or event, or `None` when nothing is pending.
+    pub oldest_pending: Option<i64>,
+}
+
+/// Summarizes what the next sync would push, based on the `is_synced` flags.
+pub fn pending_sync_summary(db: &DbHandle) -> Result<PendingSummary, SynapseError> {
+    let (sessions, oldest_session): (i64, Option<i64>) = db.conn().query_row(
+        "SELECT COUNT(*), MIN(start_time) FROM focus_sessions WHERE is_synced = 0",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let (events, oldest_event): (i64, Option<i64>) = db.conn().query_row(
+        "SELECT COUNT(*), MIN(start_time) FROM app_usage_events WHERE is_synced = 0",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(PendingSummary {
+        sessions,
+        events,
+        oldest_pending: oldest_session.into_iter().chain(oldest_event).min(),
+    })
+}
+
+/// A session that has not been pushed to Supabase, with the last push error recorded for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnsyncedSession {
+    pub id: String,
+    pub start_time: i64,
+    pub end_time: Option<i64>,
+    /// The most recent push error, or `None` if no push has failed yet (e.g. sync is off).
+    pub last_error: Option<String>,
+    pub last_failed_at: Option<i64>,
+}
+
+/// Lists the sessions not yet synced, oldest first, each with its latest sync failure.
+pub fn unsynced_sessions(db: &DbHandle) -> Result<Vec<UnsyncedSession>, SynapseError> {
+    let mut stmt = db.conn().prepare(
+        "SELECT s.id, s.start_time, s.end_time, f.error, f.failed_at FROM focus_sessions s LEFT JOIN sync_failures f ON f.id = (SELECT id FROM sync_failures WHERE session_id = s.id ORDER BY failed_at DESC, id DESC LIMIT 1) WHERE s.is_synced = 0 ORDER BY s.start_time, s.id",
+    )?;
+    let sessions = stmt
+        .query_map([], |row| {
+            Ok(UnsyncedSession {
+                id: row.get(0)?,
+                start_time: row.get(1)?,
+                end_time: row.get(2)?,
+                last_error: row.get(3)?,
+                last_failed_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sessions)
+}
+
+/// Everything needed to triage a bug report in one place. Secrets are redacted.
+#[derive(Debug, Serialize)]
+pub struct DiagnosticSnapshot {
+    pub platform: PlatformReport,
+    pub database: DatabaseReport,
+    pub rules: RulesReport,
+    pub sync: SyncReport,
+    /// The session in progress, if the backend is running one.
+    pub session: Option<FocusSession>,
+    /// How long recent polls took, if the backend is running.
+    pub poll_timing: Option<PollTimingStats>,
+    pub config: Config,
+}
+
+/// Results of calling each platform probe once; failures are reported, not raised.
+#[derive(Debug, Serialize)]
+pub struct PlatformReport {
+    pub os: &'static str,
+    pub foreground_app: Result<Option<String>, String>,
+    pub running_processes: Result<usize, String>,
+    pub do_not_disturb: Result<bool, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseReport {
+    /// Output of `PRAGMA integrity_check`; `"ok"` when the database is healthy.
+    pub integrity: String,
+    pub sessions: i64,
+    pub events: i64,
+    pub pending_sync: PendingSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulesReport {
+    pub whitelist: usize,
+    pub blacklist: usize,
+    /// Entries present in both lists.
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncReport {
+    pub configured: bool,
+    pub url: Option<String>,
+    /// Whether an API key is set; the key itself is never included.
+    pub api_key_set: bool,
+}
+
+/// The settings in effect, each with where its value came from. Secrets are redacted.
+#[derive(Debug, Serialize)]
+pub struct EffectiveConfig {
+    /// Path of the config file that was read, whether or not it exists.
+    pub file_path: String,
+    pub file_found: bool,
+    pub settings: Vec<EffectiveSetting>,
+    pub sync: SyncReport,
+}
+
+/// Resolves the config from `synapse.toml` and the environment and reports every value
+/// in effect with its source, to answer "why isn't my setting taking effect".
+///
+/// # Errors
+/// Returns `SynapseError::Config` if the file or a variable cannot be read or parsed.
+pub fn effective_config() -> Result<EffectiveConfig, SynapseError> {
+    let contents = Config::read_file()?;
+    effective_config_from(contents.as_deref(), |key| std::env::var(key).ok())
+}
+
+/// Like [`effective_config`], with the file contents given and variables read through
+/// `lookup`.
+///
+/// # Errors
+/// Returns `SynapseError::Config` if the file or a variable cannot be parsed.
+pub fn effective_config_from<F>(
+    file_contents: Option<&str>,
+    lookup: F,
+) -> Result<EffectiveConfig, SynapseError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    Ok(EffectiveConfig {
+        file_path: Config::file_path(),
+        file_found: file_contents.is_some(),
+        settings: Config::effective_from_sources(file_contents, &lookup)?,
+        sync: sync_report(&lookup),
+    })
+}
+
+/// Reports the Supabase settings read through `lookup`; only the key's presence is kept.
+fn sync_report<F>(lookup: &F) -> SyncReport
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let url = lookup("SUPABASE_URL").filter(|url| !url.trim().is_empty());
+    let api_key_set = lookup("SUPABASE_API_KEY").is_some_and(|key| !key.trim().is_empty());
+    SyncReport {
+        configured: url.is_some() && api_key_set,
+        url,
+        api_key_set,
+    }
+}
+
+/// Collects a [`DiagnosticSnapshot`]. Supabase settings are read through `lookup`
+/// (usually the environment) and only their presence is reported for the key.
+pub fn diagnostic_snapshot<F>(
+    db: &DbHandle,
+    rules: &AppRules,
+    session: Option<FocusSession>,
+    poll_timing: Option<PollTimingStats>,
+    config: Config,
+    lookup: F,
+) -> Result<DiagnosticSnapshot, SynapseError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let platform = PlatformReport {
+        os: std::env::consts::OS,
+        foreground_app: get_foreground_process_name().map_err(|e| e.to_string()),
+        running_processes: list_running_process_names()
+            .map(|names| names.len())
+            .map_err(|e| e.to_string()),
+        do_not_disturb: is_system_dnd().map_err(|e| e.to_string()),
+    };
+
+    let integrity: String = db
+        .conn()
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    let count = |table: &str| -> Result<i64, SynapseError> {
+        let sql = format!("SELECT COUNT(*) FROM {}", table);
+        Ok(db.conn().query_row(&sql, [], |row| row.get(0))?)
+    };
+    let database = DatabaseReport {
+        integrity,
+        sessions: count("focus_sessions")?,
+        events: count("app_usage_events")?,
+        pending_sync: pending_sync_summary(db)?,
+    };
+
+    let blacklist: Vec<String> = rules.blacklist().iter().map(|b| b.to_lowercase()).collect();
+    let mut conflicts: Vec<String> = rules
+        .whitelist()
+        .iter()
+        .filter(|w| blacklist.contains(&w.to_lowercase()))
+        .cloned()
+        .collect();
+    conflicts.sort();
+    conflicts.dedup();
+    let (whitelist, blacklist) = rules.rule_count();
+    let rules = RulesReport {
+        whitelist,
+        blacklist,
+        conflicts,
+    };
+
+    let sync = sync_report(&lookup);
+
+    Ok(DiagnosticSnapshot {
+        platform,
+        database,
+        rules,
+        sync,
+        session,
+        poll_timing,
+        config,
+    })
+}
+
+/// Returns the average and longest duration of `mgr`'s recent polls, overall and split into
+/// listing processes, resolving the foreground app and processing the result, to show
+/// whether the platform probes are what keeps the CPU busy.
+pub fn poll_timing_stats(mgr: &SessionManager) -> PollTimingStats {
+    mgr.poll_timing_stats()
+}
+
+/// How the current rules classify an app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerdictKind {
+    /// Whitelisted: counts as work and can start a session.
+    Work,
+    /// Triggers a distraction: blacklisted, or blocked by a setting such as strict mode.
+    Blocked,
+    /// Neither work nor a distraction.
+    Neutral,
+}
+
+/// Result of evaluating a single app name against the rules.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppVerdict {
+    pub verdict: VerdictKind,
+    /// The rule entry that produced the verdict (`None` for neutral apps and for apps blocked
+    /// by a setting rather than a rule).
+    pub matched_rule: Option<String>,
+}
+
+/// Evaluates an app name the way `mgr` would classify it in the foreground right now,
+/// without touching any session state.
+///
+/// Besides the rules this applies the manager's overlap precedence, strict mode,
+/// unknown-app policy, category budgets and snoozes; see [`SessionManager::classify_app`].
+pub fn evaluate_app(mgr: &SessionManager, app_name: &str) -> AppVerdict {
+    let rules = mgr.apprules();
+    match mgr.classify_app(app_name) {
+        (true, _) => AppVerdict {
+            verdict: VerdictKind::Blocked,
+            matched_rule: rules.blocking_rule(app_name).map(str::to_string),
+        },
+        (false, true) => AppVerdict {
+            verdict: VerdictKind::Work,
+            matched_rule: rules.work_rule(app_name).map(str::to_string),
+        },
+        (false, false) => AppVerdict {
+            verdict: VerdictKind::Neutral,
+            matched_rule: None,
+        },
+    }
+}
+
+/// Returns whitelist and blacklist entries that have not matched any observed process
+/// since `since` (seconds since epoch), in rule-file order.
+///
+/// A rule and its `.exe` variant count as one entry, so only the first listed is reported.
+pub fn unused_rules(
+    db: &DbHandle,
+    rules: &AppRules,
+    since: i64,
+) -> Result<Vec<String>, SynapseError> {
+    fn base_name(rule: &str) -> &str {
+        rule.strip_suffix(".exe").unwrap_or(rule)
+    }
+    let matched = db.rules_matched_since(since)?;
+    let mut seen: Vec<&str> = Vec::new();
+    let mut unused = Vec::new();
+    for rule in rules.whitelist().iter().chain(rules.blacklist()) {
+        let base = base_name(rule);
+        if seen.contains(&base) {
+            continue;
+        }
+        seen.push(base);
+        if !matched.iter().any(|m| base_name(m) == base) {
+            unused.push(rule.clone());
+        }
+    }
+    Ok(unused)
+}
+
+/// A `focus_sessions` row as stored in the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub id: String,
+    pub start_time: i64,
+    pub end_time: Option<i64>,
+    pub work_apps: Option<String>,
+    pub distraction_attempts: Option<i64>,
+    /// True for sessions entered by hand rather than tracked.
+    #[serde(default)]
+    pub manual: bool,
+}
+
+/// An `app_usage_events` row as stored in the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub id: Option<String>,
+    pub process_name: String,
+    pub status: String,
+    pub session_id: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub duration_secs: Option<i64>,
+    /// The blacklist rule that blocked the app, if any.
+    #[serde(default)]
+    pub matched_rule: Option<String>,
+    /// The user's reason for the distraction, if given.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// All sessions started on a local day, plus their app usage events, for offline backup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayExport {
+    /// Start of the exported local day (seconds since epoch).
+    pub day_start: i64,
+    pub sessions: Vec<SessionRecord>,
+    pub events: Vec<EventRecord>,
+}
+
+/// Exports every session starting in `[day_start, day_start + 1 day)` together with its events.
+pub fn export_day(db: &DbHandle, day_start: i64) -> Result<DayExport, SynapseError> {
+    let day_end = day_start + 86400;
+    let mut stmt = db.conn().prepare(
+        "SELECT id, start_time, end_time, work_apps, distraction_attempts, manual FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2 ORDER BY start_time",
+    )?;
+    let sessions = stmt
+        .query_map([day_start, day_end], |row| {
+            Ok(SessionRecord {
+                id: row.get(0)?,
+                start_time: row.get(1)?,
+                end_time: row.get(2)?,
+                work_apps: row.get(3)?,
+                distraction_attempts: row.get(4)?,
+                manual: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut stmt = db.conn().prepare(
+        "SELECT e.id, e.process_name, e.status, e.session_id, e.start_time, e.end_time, e.duration_secs, e.matched_rule, e.reason FROM app_usage_events e JOIN focus_sessions s ON e.session_id = s.id WHERE s.start_time >= ?1 AND s.start_time < ?2 ORDER BY e.start_time",
+    )?;
+    let events = stmt
+        .query_map([day_start, day_end], |row| {
+            Ok(EventRecord {
+                id: row.get(0)?,
+                process_name: row.get(1)?,
+                status: row.get(2)?,
+                session_id: row.get(3)?,
+                start_time: row.get(4)?,
+                end_time: row.get(5)?,
+                duration_secs: row.get(6)?,
+                matched_rule: row.get(7)?,
+                reason: row.get(8)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(DayExport {
+        day_start,
+        sessions,
+        events,
+    })
+}
+
+/// Imports a [`DayExport`], skipping sessions and events whose ids already exist.
+///
+/// Returns the number of rows actually inserted, so re-importing the same export is a no-op.
+pub fn import_day(db: &DbHandle, export: &DayExport) -> Result<usize, SynapseError> {
+    let tx = db.conn().unchecked_transaction()?;
+    let mut inserted = 0;
+    for s in &export.sessions {
+        inserted += tx.execute(
+            "INSERT OR IGNORE INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts, manual) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![s.id, s.start_time, s.end_time, s.work_apps, s.distraction_attempts, s.manual],
+        )?;
+    }
+    for e in &export.events {
+        inserted += tx.execute(
+            "INSERT OR IGNORE INTO app_usage_events (id, process_name, status, session_id, start_time, end_time, duration_secs, matched_rule, reason) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![e.id, e.process_name, e.status, e.session_id, e.start_time, e.end_time, e.duration_secs, e.matched_rule, e.reason],
+        )?;
+    }
+    tx.commit()?;
+    Ok(inserted)
+}
+
+/// A day's focus goal compared with the focus time actually achieved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GoalDay {
+    pub date: NaiveDate,
+    pub goal_secs: i64,
+    pub achieved_secs: i64,
+    pub met: bool,
+    /// True when no date or weekday goal was set and the default goal applied.
+    pub is_default_goal: bool,
+}
+
+/// Returns goal vs. achieved focus time for every working local day in `[start, end]`.
+///
+/// Days in `non_working` are left out. Days without an explicit goal use
+/// [`DEFAULT_DAILY_GOAL_SECS`]. An empty list is returned when `end` is before `start`.
+pub fn goal_history(
+    db: &DbHandle,
+    start: NaiveDate,
+    end: NaiveDate,
+    non_working: &NonWorkingDays,
+) -> Result<Vec<GoalDay>, SynapseError> {
+    let mut history = Vec::new();
+    for date in start
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .filter(|date| !non_working.contains(*date))
+    {
+        history.push(goal_day(db, date)?);
+    }
+    Ok(history)
+}
+
+/// Compares `date`'s focus goal with the focus time achieved on it.
+fn goal_day(db: &DbHandle, date: NaiveDate) -> Result<GoalDay, SynapseError> {
+    let explicit_goal = db.get_goal_for_day(date)?;
+    let goal_secs = explicit_goal.unwrap_or(DEFAULT_DAILY_GOAL_SECS);
+    let (day_start, day_end) = day_bounds(date);
+    let achieved_secs = focus_time_between(db, day_start, day_end)?;
+    Ok(GoalDay {
+        date,
+        goal_secs,
+        achieved_secs,
+        met: achieved_secs >= goal_secs,
+        is_default_goal: explicit_goal.is_none(),
+    })
+}
+
+/// Returns the projected seconds until today's focus goal is met, if today's pace (focus time
+/// so far over the time elapsed since local midnight) keeps up.
+///
+/// `None` means the goal is already met or there has been no focus today to project from.
+pub fn eta_to_goal(db: &DbHandle) -> Result<Option<i64>, SynapseError> {
+    let now = Local::now();
+    eta_to_goal_at(db, now.date_naive(), now.timestamp())
+}
+
+/// Computes [`eta_to_goal`] as if it were `now` on local day `today`.
+fn eta_to_goal_at(db: &DbHandle, today: NaiveDate, now: i64) -> Result<Option<i64>, SynapseError> {
+    let progress = goal_day(db, today)?;
+    let remaining = progress.goal_secs - progress.achieved_secs;
+    let (day_start, _) = day_bounds(today);
+    let elapsed = now - day_start;
+    if remaining <= 0 || progress.achieved_secs <= 0 || elapsed <= 0 {
+        return Ok(None);
+    }
+    // remaining / (achieved / elapsed), rounded up
+    let eta = (remaining as i128 * elapsed as i128 + progress.achieved_secs as i128 - 1)
+        / progress.achieved_secs as i128;
+    Ok(Some(eta as i64))
+}
+
+/// Returns today's focus goal in seconds, scaled to today's working hours.
+///
+/// The goal is `goal × hours_today / default_hours`, rounded to the nearest second, where
+/// `hours_today` comes from today's date, else its weekday, else the `default` entry of
+/// `working_hours`. A 4-hour Friday against an 8-hour default day halves the goal. Without
+/// working hours for today or a `default` day the goal is returned unscaled.
+pub fn scaled_goal_today(db: &DbHandle, working_hours: &WorkingHours) -> Result<i64, SynapseError> {
+    scaled_goal_on(db, Local::now().date_naive(), working_hours)
+}
+
+/// Computes [`scaled_goal_today`] for local day `date`.
+fn scaled_goal_on(
+    db: &DbHandle,
+    date: NaiveDate,
+    working_hours: &WorkingHours,
+) -> Result<i64, SynapseError> {
+    let goal_secs = db.get_goal_for_day(date)?.unwrap_or(DEFAULT_DAILY_GOAL_SECS);
+    Ok((goal_secs as f64 * working_hours.day_fraction(date)).round() as i64)
+}
+
+/// Returns how many consecutive working days, ending today, met their focus goal.
+///
+/// Days in `non_working` are stepped over rather than breaking the streak. Today only
+/// adds to the streak once its goal is met; until then the streak runs to yesterday.
+pub fn goal_streak(db: &DbHandle, non_working: &NonWorkingDays) -> Result<u32, SynapseError> {
+    goal_streak_through(db, Local::now().date_naive(), non_working)
+}
+
+/// Computes [`goal_streak`] as if `today` were the current local day.
+fn goal_streak_through(
+    db: &DbHandle,
+    today: NaiveDate,
+    non_working: &NonWorkingDays,
+) -> Result<u32, SynapseError> {
+    let first_start: Option<i64> = db
+        .conn()
+        .query_row("SELECT MIN(start_time) FROM focus_sessions", [], |row| row.get(0))?;
+    let Some(first) = first_start.and_then(|ts| Local.timestamp_opt(ts, 0).earliest()) else {
+        return Ok(0);
+    };
+    let first = first.date_naive();
+    let mut streak = 0;
+    let mut date = today;
+    while date >= first {
+        if !non_working.contains(date) {
+            if goal_day(db, date)?.met {
+                streak += 1;
+            } else if date != today {
+                break;
+            }
+        }
+        let Some(previous) = date.pred_opt() else {
+            break;
+        };
+        date = previous;
+    }
+    Ok(streak)
+}
+
+/// Focus time and sessions started on one local day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DayActivity {
+    pub date: NaiveDate,
+    pub focus_secs: i64,
+    pub sessions: i64,
+}
+
+/// Returns the activity of every day in the calendar month containing `month_start`, first
+/// day first. Days without sessions are included with zeros.
+pub fn month_activity(db: &DbHandle, month_start: NaiveDate) -> Result<Vec<DayActivity>, SynapseError> {
+    let first = month_start.with_day(1).unwrap_or(month_start);
+    let mut days = Vec::with_capacity(31);
+    for date in first.iter_days().take_while(|date| date.month() == first.month()) {
+        let (start, end) = day_bounds(date);
+        let sessions: i64 = db.conn().query_row(
+            "SELECT COUNT(*) FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2",
+            [start, end],
+            |row| row.get(0),
+        )?;
+        days.push(DayActivity {
+            date,
+            focus_secs: focus_time_between(db, start, end)?,
+            sessions,
+        });
+    }
+    Ok(days)
+}
+
+/// Builds a self-contained HTML report for the seven days from `week_start`: total focus
+/// time, sessions and distractions, a bar per day and the most used apps. Inline styles
+/// only, so the page prints to PDF as-is.
+pub fn weekly_report_html(db: &DbHandle, week_start: NaiveDate) -> Result<String, SynapseError> {
+    let days: Vec<NaiveDate> = week_start.iter_days().take(7).collect();
+    let mut daily = Vec::with_capacity(days.len());
+    for date in &days {
+        let (start, end) = day_bounds(*date);
+        daily.push((*date, focus_time_between(db, start, end)?));
+    }
+    let (week_begin, _) = day_bounds(days[0]);
+    let (_, week_end) = day_bounds(days[days.len() - 1]);
+    let total_focus: i64 = daily.iter().map(|(_, secs)| secs).sum();
+    let distractions = distractions_between(db, week_begin, week_end)?;
+    let sessions: i64 = db.conn().query_row(
+        "SELECT COUNT(*) FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2",
+        [week_begin, week_end],
+        |row| row.get(0),
+    )?;
+    let top_apps = app_usage_between(db, week_begin, week_end)?;
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">");
+    html.push_str(&format!(
+        "<title>Synapse weekly report: {} to {}</title>",
+        days[0],
+        days[days.len() - 1]
+    ));
+    html.push_str("<style>body{font-family:sans-serif;margin:2em;color:#222}table{border-collapse:collapse;width:100%}td,th{padding:4px 8px;text-align:left;border-bottom:1px solid #ddd}.bar{background:#4a7bd0;height:14px}</style>");
+    html.push_str("</head><body>\n");
+    html.push_str(&format!(
+        "<h1>Weekly report</h1>\n<p>{} to {}</p>\n",
+        days[0],
+        days[days.len() - 1]
+    ));
+
+    html.push_str("<h2>Totals</h2>\n<table>\n");
+    html.push_str(&format!(
+        "<tr><th>Focus time</th><td>{}</td></tr>\n",
+        format_hours_minutes(total_focus)
+    ));
+    html.push_str(&format!("<tr><th>Sessions</th><td>{}</td></tr>\n", sessions));
+    html.push_str(&format!("<tr><th>Distractions</th><td>{}</td></tr>\n", distractions));
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Focus per day</h2>\n<table>\n");
+    let longest_day = daily.iter().map(|(_, secs)| *secs).max().unwrap_or(0).max(1);
+    for (date, secs) in &daily {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td style=\"width:60%\"><div class=\"bar\" style=\"width:{}%\"></div></td><td>{}</td></tr>\n",
+            date.format("%a %Y-%m-%d"),
+            secs * 100 / longest_day,
+            format_hours_minutes(*secs)
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Top apps</h2>\n");
+    if top_apps.is_empty() {
+        html.push_str("<p>No app usage recorded.</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>App</th><th>Focus time</th></tr>\n");
+        for app in top_apps.iter().take(WEEKLY_REPORT_TOP_APPS) {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&app.display_name),
+                format_hours_minutes(app.focus_secs)
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body></html>\n");
+    Ok(html)
+}
+
+/// Formats seconds as `"2h 05m"`.
+fn format_hours_minutes(secs: i64) -> String {
+    let minutes = secs.max(0) / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Escapes text for use in HTML element content.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns a list of installed (app_name, exe_name) tuples from the Windows registry.
+///
+/// Served from a cache refreshed every `INSTALLED_APPS_CACHE_TTL_SECS`; see
+/// [`crate::platform::installed_apps::refresh_installed_apps_cache`] to rescan sooner.
+pub fn get_installed_apps_api() -> Vec<(String, String)> {
+    crate::platform::installed_apps()
+}
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeZone};
 
 /// Helper: Returns (start_of_day, end_of_day) as UNIX timestamps for today in Local Time.
 fn today_bounds() -> (i64, i64) {
-    let now = Local::now();
-    // Get start of today in local time
-    let start = now.date_naive().and_hms_opt(0, 0, 0).unwrap();
+    day_bounds(Local::now().date_naive())
+}
+
+/// Converts a local date-time to a UNIX timestamp, taking the earlier instant when ambiguous.
+fn local_timestamp(dt: NaiveDateTime) -> i64 {
+    // A time skipped by a DST jump maps to the same wall-clock time an hour later
+    Local
+        .from_local_datetime(&dt)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(dt + chrono::Duration::hours(1))).earliest())
+        .unwrap_or_else(|| Local.from_utc_datetime(&dt))
+        .timestamp()
+}
+
+/// Returns the `[start, end)` timestamps of a local calendar day.
+fn day_bounds(date: NaiveDate) -> (i64, i64) {
+    // Get start of the day in local time
+    let start = date.and_hms_opt(0, 0, 0).unwrap();
     // Convert to UTC timestamp for DB comparison
     // We need to assume the DB stores UTC timestamps (SystemTime::now())
     // but we want to filter for records that fall within "Today" in Local time.
@@ -145,3 +1357,842 @@ impl DbConn for DbHandle {
         &self.conn
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> AppRules {
+        AppRules::test_with_rules(
+            vec!["code.exe".to_string(), "both.exe".to_string()],
+            vec!["discord.exe".to_string(), "both.exe".to_string()],
+        )
+    }
+
+    fn manager(rules: AppRules) -> SessionManager {
+        SessionManager::new(rules, DbHandle::test_in_memory_with_schema(), None, None)
+    }
+
+    #[test]
+    fn evaluate_app_reports_work_with_rule() {
+        let verdict = evaluate_app(&manager(rules()), "Code.exe");
+        assert_eq!(verdict.verdict, VerdictKind::Work);
+        assert_eq!(verdict.matched_rule.as_deref(), Some("code.exe"));
+    }
+
+    #[test]
+    fn evaluate_app_reports_blocked_with_rule() {
+        let verdict = evaluate_app(&manager(rules()), "DISCORD.EXE");
+        assert_eq!(verdict.verdict, VerdictKind::Blocked);
+        assert_eq!(verdict.matched_rule.as_deref(), Some("discord.exe"));
+    }
+
+    #[test]
+    fn evaluate_app_reports_neutral_without_rule() {
+        let verdict = evaluate_app(&manager(rules()), "calc.exe");
+        assert_eq!(verdict.verdict, VerdictKind::Neutral);
+        assert!(verdict.matched_rule.is_none());
+    }
+
+    #[test]
+    fn evaluate_app_applies_the_unknown_app_policy() {
+        let mut mgr = manager(rules());
+        mgr.register_installed_apps(vec![("Calculator".to_string(), "calc.exe".to_string())])
+            .unwrap();
+        mgr.set_unknown_app_policy(crate::config::UnknownAppPolicy::TreatAsDistraction);
+        let verdict = evaluate_app(&mgr, "mystery.exe");
+        assert_eq!(verdict.verdict, VerdictKind::Blocked);
+        assert!(verdict.matched_rule.is_none());
+        assert_eq!(evaluate_app(&mgr, "calc.exe").verdict, VerdictKind::Neutral);
+    }
+
+    fn insert_session(db: &DbHandle, id: &str, start: i64, end: i64, distractions: i64) {
+        db.conn()
+            .execute(
+                "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![id, start, end, "code.exe", distractions],
+            )
+            .unwrap();
+    }
+
+    fn insert_event(db: &DbHandle, id: &str, session_id: &str, start: i64, end: i64) {
+        db.conn()
+            .execute(
+                "INSERT INTO app_usage_events (id, process_name, status, session_id, start_time, end_time, duration_secs) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![id, "code.exe", "allowed", session_id, start, end, end - start],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn export_wipe_import_round_trip() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let day_start = 1_700_000_000;
+        insert_session(&db, "s1", day_start + 100, day_start + 700, 1);
+        insert_session(&db, "s2", day_start + 1000, day_start + 1600, 0);
+        insert_session(&db, "other-day", day_start - 500, day_start - 100, 3);
+        insert_event(&db, "e1", "s1", day_start + 100, day_start + 400);
+        insert_event(&db, "e2", "s2", day_start + 1000, day_start + 1600);
+        insert_event(&db, "e3", "other-day", day_start - 500, day_start - 100);
+
+        let export = export_day(&db, day_start).unwrap();
+        assert_eq!(export.sessions.len(), 2);
+        assert_eq!(export.events.len(), 2);
+
+        let json = serde_json::to_string(&export).unwrap();
+        let restored: DayExport = serde_json::from_str(&json).unwrap();
+
+        db.conn().execute("DELETE FROM app_usage_events", []).unwrap();
+        db.conn().execute("DELETE FROM focus_sessions", []).unwrap();
+        assert_eq!(import_day(&db, &restored).unwrap(), 4);
+        assert_eq!(export_day(&db, day_start).unwrap(), export);
+
+        // Re-importing is idempotent thanks to the ids
+        assert_eq!(import_day(&db, &restored).unwrap(), 0);
+        assert_eq!(export_day(&db, day_start).unwrap(), export);
+    }
+
+    #[test]
+    fn goal_history_reports_met_and_missed_days() {
+        use crate::types::GoalKey;
+        let db = DbHandle::test_in_memory_with_schema();
+        // 2024-01-01 is a Monday
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let wednesday = tuesday.succ_opt().unwrap();
+        db.set_goal(GoalKey::Weekday(chrono::Weekday::Mon), 3600).unwrap();
+        db.set_goal(GoalKey::Date(tuesday), 1800).unwrap();
+
+        let (mon_start, _) = day_bounds(monday);
+        let (tue_start, _) = day_bounds(tuesday);
+        insert_session(&db, "mon-1", mon_start + 36_000, mon_start + 38_000, 0);
+        insert_session(&db, "mon-2", mon_start + 40_000, mon_start + 42_000, 0);
+        insert_session(&db, "tue", tue_start + 36_000, tue_start + 36_600, 0);
+
+        let history = goal_history(&db, monday, wednesday, &NonWorkingDays::default()).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!((history[0].goal_secs, history[0].achieved_secs), (3600, 4000));
+        assert!(history[0].met && !history[0].is_default_goal);
+        assert_eq!((history[1].goal_secs, history[1].achieved_secs), (1800, 600));
+        assert!(!history[1].met);
+        assert_eq!(history[2].goal_secs, DEFAULT_DAILY_GOAL_SECS);
+        assert!(history[2].is_default_goal && !history[2].met);
+
+        let none = NonWorkingDays::default();
+        assert!(goal_history(&db, wednesday, monday, &none).unwrap().is_empty());
+
+        let midweek_off = NonWorkingDays {
+            weekdays: vec![chrono::Weekday::Tue],
+            holidays: vec![wednesday],
+        };
+        let history = goal_history(&db, monday, wednesday, &midweek_off).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].date, monday);
+    }
+
+    /// Meets the default daily goal on each of the given days of January 2024.
+    fn meet_goal_on(db: &DbHandle, days: &[u32]) {
+        for &d in days {
+            let (start, _) = day_bounds(NaiveDate::from_ymd_opt(2024, 1, d).unwrap());
+            let id = format!("jan-{}", d);
+            insert_session(db, &id, start + 3600, start + 3600 + DEFAULT_DAILY_GOAL_SECS, 0);
+        }
+    }
+
+    #[test]
+    fn activity_bounds_span_first_start_to_last_end() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (day_start, _) = day_bounds(NaiveDate::from_ymd_opt(2024, 1, 9).unwrap());
+        assert_eq!(activity_bounds_at(&db, day_start, day_start + 86400).unwrap(), None);
+
+        insert_session(&db, "late", day_start + 14 * 3600, day_start + 17 * 3600, 0);
+        insert_session(&db, "early", day_start + 8 * 3600, day_start + 10 * 3600, 0);
+        // A session on the next day doesn't count
+        insert_session(&db, "tomorrow", day_start + 86400 + 3600, day_start + 86400 + 7200, 0);
+        assert_eq!(
+            activity_bounds_at(&db, day_start, day_start + 86400).unwrap(),
+            Some((day_start + 8 * 3600, day_start + 17 * 3600))
+        );
+
+        // A running session ends now
+        db.conn()
+            .execute(
+                "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES ('open', ?1, NULL, 'code', 0)",
+                [day_start + 18 * 3600],
+            )
+            .unwrap();
+        let now = day_start + 19 * 3600;
+        assert_eq!(
+            activity_bounds_at(&db, day_start, now).unwrap(),
+            Some((day_start + 8 * 3600, now))
+        );
+    }
+
+    #[test]
+    fn month_activity_covers_every_day_of_the_month() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let leap_day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let (start, _) = day_bounds(leap_day);
+        insert_session(&db, "leap", start + 3600, start + 5400, 0);
+        insert_session(&db, "leap-2", start + 7200, start + 7500, 0);
+
+        let february = month_activity(&db, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()).unwrap();
+        assert_eq!(february.len(), 29);
+        assert_eq!(february[0].date, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        let last = &february[28];
+        assert_eq!((last.date, last.focus_secs, last.sessions), (leap_day, 2100, 2));
+        assert!(february[..28].iter().all(|day| day.focus_secs == 0 && day.sessions == 0));
+
+        // Any day of the month selects the whole month
+        let march = month_activity(&db, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()).unwrap();
+        assert_eq!(march.len(), 31);
+        assert_eq!(march[30].date, NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+        assert_eq!(month_activity(&db, NaiveDate::from_ymd_opt(2023, 2, 1).unwrap()).unwrap().len(), 28);
+    }
+
+    #[test]
+    fn eta_to_goal_projects_todays_pace() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 9).unwrap();
+        let (midnight, _) = day_bounds(today);
+        db.set_goal(crate::types::GoalKey::Date(today), 4 * 3600).unwrap();
+
+        // No focus yet: no pace to project from
+        assert_eq!(eta_to_goal_at(&db, today, midnight + 8 * 3600).unwrap(), None);
+
+        // 2h of focus by noon is 1h per 6h, so the remaining 2h take another 12h
+        insert_session(&db, "morning", midnight + 9 * 3600, midnight + 11 * 3600, 0);
+        let noon = midnight + 12 * 3600;
+        assert_eq!(eta_to_goal_at(&db, today, noon).unwrap(), Some(12 * 3600));
+
+        // Ahead of pace the projection shrinks, and a met goal has none
+        insert_session(&db, "midday", midnight + 11 * 3600, midnight + 12 * 3600 + 2700, 0);
+        let ahead = eta_to_goal_at(&db, today, midnight + 13 * 3600).unwrap().unwrap();
+        assert!(ahead < 3600, "{}", ahead);
+        insert_session(&db, "afternoon", midnight + 13 * 3600, midnight + 14 * 3600, 0);
+        assert_eq!(eta_to_goal_at(&db, today, midnight + 14 * 3600).unwrap(), None);
+    }
+
+    #[test]
+    fn scaled_goal_is_proportional_to_working_hours() {
+        let db = DbHandle::test_in_memory_with_schema();
+        db.set_goal(crate::types::GoalKey::Weekday(chrono::Weekday::Thu), 6 * 3600)
+            .unwrap();
+        // 2024-01-11 is a Thursday, 2024-01-12 a Friday
+        let thursday = NaiveDate::from_ymd_opt(2024, 1, 11).unwrap();
+        let friday = NaiveDate::from_ymd_opt(2024, 1, 12).unwrap();
+        let half_day: WorkingHours = "default=8,2024-01-11=4".parse().unwrap();
+
+        // A full working day keeps the goal, a half day halves it
+        assert_eq!(scaled_goal_on(&db, friday, &half_day).unwrap(), DEFAULT_DAILY_GOAL_SECS);
+        assert_eq!(scaled_goal_on(&db, thursday, &half_day).unwrap(), 3 * 3600);
+        let short_fridays: WorkingHours = "default=8,fri=6".parse().unwrap();
+        assert_eq!(
+            scaled_goal_on(&db, friday, &short_fridays).unwrap(),
+            DEFAULT_DAILY_GOAL_SECS * 3 / 4
+        );
+        // No working hours configured: the goal is unscaled
+        let none = WorkingHours::default();
+        assert_eq!(scaled_goal_on(&db, thursday, &none).unwrap(), 6 * 3600);
+    }
+
+    #[test]
+    fn goal_streak_steps_over_non_working_days() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let weekends = NonWorkingDays {
+            weekdays: vec![chrono::Weekday::Sat, chrono::Weekday::Sun],
+            holidays: vec![],
+        };
+        // Thu 4th, Fri 5th and Mon 8th met; the weekend in between had no focus
+        meet_goal_on(&db, &[4, 5, 8]);
+        // Tue 9th is in progress and doesn't break the streak yet
+        let tuesday = NaiveDate::from_ymd_opt(2024, 1, 9).unwrap();
+        assert_eq!(goal_streak_through(&db, tuesday, &weekends).unwrap(), 3);
+        // Counting the weekend, Sunday is a miss
+        let none = NonWorkingDays::default();
+        assert_eq!(goal_streak_through(&db, tuesday, &none).unwrap(), 1);
+    }
+
+    #[test]
+    fn goal_streak_breaks_on_a_missed_working_day() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let mut weekends = NonWorkingDays {
+            weekdays: vec![chrono::Weekday::Sat, chrono::Weekday::Sun],
+            holidays: vec![],
+        };
+        // Wed 3rd is missed
+        meet_goal_on(&db, &[1, 2, 4, 5, 8, 9]);
+        let tuesday = NaiveDate::from_ymd_opt(2024, 1, 9).unwrap();
+        assert_eq!(goal_streak_through(&db, tuesday, &weekends).unwrap(), 4);
+
+        // As a holiday, the 3rd no longer breaks it
+        weekends.holidays.push(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        assert_eq!(goal_streak_through(&db, tuesday, &weekends).unwrap(), 6);
+        // Nothing recorded yet
+        let empty = DbHandle::test_in_memory_with_schema();
+        assert_eq!(goal_streak_through(&empty, tuesday, &weekends).unwrap(), 0);
+    }
+
+    #[test]
+    fn all_time_stats_aggregates_every_day() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let empty = all_time_stats(&db).unwrap();
+        assert_eq!(empty.total_sessions, 0);
+        assert_eq!(empty.total_focus_secs, 0);
+        assert!(empty.first_session_date.is_none());
+        assert_eq!(empty.active_days, 0);
+
+        let first = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let later = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let (first_start, _) = day_bounds(first);
+        let (later_start, _) = day_bounds(later);
+        insert_session(&db, "a", first_start + 3600, first_start + 5400, 2);
+        insert_session(&db, "b", first_start + 36_000, first_start + 36_600, 1);
+        insert_session(&db, "c", later_start + 7200, later_start + 9000, 4);
+
+        let stats = all_time_stats(&db).unwrap();
+        assert_eq!(stats.total_focus_secs, 1800 + 600 + 1800);
+        assert_eq!(stats.total_sessions, 3);
+        assert_eq!(stats.total_distractions, 7);
+        assert_eq!(stats.first_session_date, Some(first));
+        assert_eq!(stats.active_days, 2);
+    }
+
+    #[test]
+    fn session_length_histogram_buckets_by_duration() {
+        let db = DbHandle::test_in_memory_with_schema();
+        assert!(session_length_histogram(&db, 900, 0, i64::MAX).unwrap().is_empty());
+        assert!(session_length_histogram(&db, 0, 0, i64::MAX).is_err());
+
+        let base = 1_700_000_000;
+        insert_session(&db, "5m", base, base + 300, 0);
+        insert_session(&db, "14m", base + 1000, base + 1000 + 840, 0);
+        insert_session(&db, "20m", base + 5000, base + 5000 + 1200, 0);
+        insert_session(&db, "50m", base + 9000, base + 9000 + 3000, 0);
+        insert_session(&db, "out-of-range", base + 100_000, base + 100_300, 0);
+        db.conn()
+            .execute(
+                "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES ('open', ?1, NULL, '', 0)",
+                [base + 20_000],
+            )
+            .unwrap();
+
+        let histogram = session_length_histogram(&db, 900, base, base + 50_000).unwrap();
+        assert_eq!(histogram, vec![(0, 2), (900, 1), (1800, 0), (2700, 1)]);
+    }
+
+    #[test]
+    fn longest_distraction_free_span_is_bounded_by_sessions() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let base = 1_700_000_000;
+        insert_session(&db, "s1", base, base + 3600, 2);
+        for (id, start) in [("d1", base + 600), ("d2", base + 3000)] {
+            insert_event(&db, id, "s1", start, start + 60);
+            db.conn()
+                .execute(
+                    "UPDATE app_usage_events SET status = 'blocked' WHERE id = ?1",
+                    [id],
+                )
+                .unwrap();
+        }
+        insert_event(&db, "work", "s1", base + 1000, base + 2000);
+
+        // Between the distractions beats both edges (600s and 540s)
+        assert_eq!(
+            longest_distraction_free_span(&db, base, base + 86_400, base + 86_400).unwrap(),
+            2340
+        );
+        // A window ending mid-gap only counts the part inside it
+        assert_eq!(
+            longest_distraction_free_span(&db, base, base + 1800, base + 86_400).unwrap(),
+            1140
+        );
+
+        // The idle hour between sessions does not count, but a clean session does
+        insert_session(&db, "s2", base + 7200, base + 9600, 0);
+        assert_eq!(
+            longest_distraction_free_span(&db, base, base + 86_400, base + 86_400).unwrap(),
+            2400
+        );
+    }
+
+    #[test]
+    fn focus_efficiency_counts_unrecorded_session_time_as_idle() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let base = 1_700_000_000;
+        assert_eq!(focus_efficiency(&db, base, base + 86_400, base + 86_400).unwrap(), 0.0);
+
+        // A 1000s session with a 250s idle gap, and overlapping events counted once
+        insert_session(&db, "s1", base, base + 1000, 0);
+        insert_event(&db, "e1", "s1", base, base + 500);
+        insert_event(&db, "e2", "s1", base + 400, base + 600);
+        insert_event(&db, "e3", "s1", base + 850, base + 1000);
+        assert_eq!(focus_efficiency(&db, base, base + 86_400, base + 86_400).unwrap(), 0.75);
+
+        // A fully active session lifts the ratio; time between sessions never counts
+        insert_session(&db, "s2", base + 5000, base + 6000, 0);
+        insert_event(&db, "e4", "s2", base + 5000, base + 6000);
+        assert_eq!(focus_efficiency(&db, base, base + 86_400, base + 86_400).unwrap(), 0.875);
+
+        // A window cutting the first session clips it and its events
+        assert_eq!(focus_efficiency(&db, base + 500, base + 1000, base + 86_400).unwrap(), 0.5);
+    }
+
+    #[test]
+    fn pending_sync_summary_counts_unsynced_rows() {
+        let db = DbHandle::test_in_memory_with_schema();
+        assert_eq!(
+            pending_sync_summary(&db).unwrap(),
+            PendingSummary {
+                sessions: 0,
+                events: 0,
+                oldest_pending: None
+            }
+        );
+
+        let base = 1_700_000_000;
+        insert_session(&db, "old", base, base + 600, 0);
+        insert_session(&db, "s1", base + 1000, base + 1600, 0);
+        insert_session(&db, "s2", base + 2000, base + 2600, 0);
+        insert_event(&db, "e1", "old", base + 100, base + 200);
+        insert_event(&db, "e2", "s1", base + 1100, base + 1200);
+        insert_event(&db, "e3", "s2", base + 2100, base + 2200);
+        db.conn()
+            .execute("UPDATE focus_sessions SET is_synced = 1 WHERE id = 'old'", [])
+            .unwrap();
+        db.conn()
+            .execute("UPDATE app_usage_events SET is_synced = 1 WHERE id IN ('e1', 'e2')", [])
+            .unwrap();
+
+        let summary = pending_sync_summary(&db).unwrap();
+        assert_eq!((summary.sessions, summary.events), (2, 1));
+        assert_eq!(summary.oldest_pending, Some(base + 1000));
+
+        // Changing a synced session makes it pending again
+        db.conn()
+            .execute("UPDATE focus_sessions SET end_time = ?1 WHERE id = 'old'", [base + 700])
+            .unwrap();
+        let summary = pending_sync_summary(&db).unwrap();
+        assert_eq!((summary.sessions, summary.events), (3, 1));
+        assert_eq!(summary.oldest_pending, Some(base));
+    }
+
+    #[test]
+    fn reset_today_keeps_yesterday() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (today, _) = today_bounds();
+        let yesterday = today - 86_400;
+        insert_session(&db, "yesterday", yesterday + 3600, yesterday + 7200, 2);
+        insert_event(&db, "y-event", "yesterday", yesterday + 3600, yesterday + 3700);
+        insert_session(&db, "today-1", today + 60, today + 660, 1);
+        insert_session(&db, "today-2", today + 1000, today + 1300, 3);
+        insert_event(&db, "t-event", "today-1", today + 60, today + 160);
+
+        assert_eq!(reset_today(&db).unwrap(), 2);
+        assert_eq!(total_focus_time_today(&db).unwrap(), 0);
+        assert_eq!(total_distractions_today(&db).unwrap(), 0);
+        assert_eq!(total_focus_sessions_today(&db).unwrap(), 0);
+
+        let export = export_day(&db, yesterday).unwrap();
+        assert_eq!(export.sessions.len(), 1);
+        assert_eq!(export.events.len(), 1);
+        let events: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM app_usage_events", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(events, 1);
+    }
+
+    #[test]
+    fn most_productive_day_picks_the_largest_and_earliest_on_ties() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let day = |d: u32| day_bounds(NaiveDate::from_ymd_opt(2024, 3, d).unwrap()).0;
+        let (range_start, range_end) = (day(4), day(9));
+        assert_eq!(most_productive_day(&db, range_start, range_end).unwrap(), None);
+
+        insert_session(&db, "mon", day(4) + 3600, day(4) + 5400, 0);
+        insert_session(&db, "tue-1", day(5) + 3600, day(5) + 7200, 0);
+        insert_session(&db, "tue-2", day(5) + 9000, day(5) + 10_800, 0);
+        insert_session(&db, "wed", day(6) + 3600, day(6) + 5400, 0);
+        // Outside the range
+        insert_session(&db, "sun", day(3) + 3600, day(3) + 36_000, 0);
+        assert_eq!(
+            most_productive_day(&db, range_start, range_end).unwrap(),
+            Some((day(5), 5400))
+        );
+
+        // Thursday ties Tuesday; the earlier day wins
+        insert_session(&db, "thu", day(7) + 3600, day(7) + 9000, 0);
+        assert_eq!(
+            most_productive_day(&db, range_start, range_end).unwrap(),
+            Some((day(5), 5400))
+        );
+        assert_eq!(
+            most_productive_day(&db, day(6), range_end).unwrap(),
+            Some((day(7), 5400))
+        );
+        assert_eq!(most_productive_day(&db, range_end, range_end).unwrap(), None);
+    }
+
+    #[test]
+    fn focus_time_trend_is_a_trailing_seven_day_average() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let day = |d: u32| day_bounds(date(d)).0;
+        // Day d has d * 600 seconds of focus, except the 3rd and 10th with none
+        for d in (1..=14).filter(|d| *d != 3 && *d != 10) {
+            let start = day(d) + 3600;
+            insert_session(&db, &format!("s{}", d), start, start + i64::from(d) * 600, 0);
+        }
+        // After the requested range
+        insert_session(&db, "later", day(15) + 3600, day(15) + 36_000, 0);
+
+        let trend = focus_time_trend_through(&db, date(14), 14).unwrap();
+        assert_eq!(trend.len(), 14);
+        assert_eq!(trend[0], (day(1), 600.0));
+        assert_eq!(trend[1], (day(2), 900.0));
+        // 600 + 1200 + 0 + 2400 + 3000 + 3600 + 4200
+        assert_eq!(trend[6], (day(7), 15_000.0 / 7.0));
+        // 2400 + 3000 + 3600 + 4200 + 4800 + 5400 + 0
+        assert_eq!(trend[9], (day(10), 23_400.0 / 7.0));
+        // 4800 + 5400 + 0 + 6600 + 7200 + 7800 + 8400
+        assert_eq!(trend[13], (day(14), 40_200.0 / 7.0));
+
+        // A shorter series starts with partial windows of its own days
+        let trend = focus_time_trend_through(&db, date(14), 3).unwrap();
+        assert_eq!(trend, vec![(day(12), 7200.0), (day(13), 7500.0), (day(14), 7800.0)]);
+        assert!(focus_time_trend_through(&db, date(14), 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn consistency_score_is_one_for_identical_days() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        for d in 1..=7 {
+            let start = day_bounds(date(d)).0 + 3600;
+            insert_session(&db, &format!("s{}", d), start, start + 3600, 0);
+        }
+        assert_eq!(consistency_score_through(&db, date(7), 7).unwrap(), 1.0);
+        // Too few days to judge, and an empty window
+        assert_eq!(consistency_score_through(&db, date(7), 1).unwrap(), 1.0);
+        assert_eq!(consistency_score_through(&db, date(20), 7).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn consistency_score_drops_for_variable_days() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        // Four hours on the 1st and nothing on the other three days:
+        // mean 3600, standard deviation sqrt(3) * 3600, so the score is 1 / (1 + sqrt(3))
+        let start = day_bounds(date(1)).0 + 3600;
+        insert_session(&db, "binge", start, start + 4 * 3600, 0);
+        let score = consistency_score_through(&db, date(4), 4).unwrap();
+        assert!((score - 1.0 / (1.0 + 3f64.sqrt())).abs() < 1e-9);
+        assert!(score < 0.5);
+    }
+
+    #[test]
+    fn diagnostic_snapshot_has_every_section_and_no_key() {
+        let db = DbHandle::test_in_memory_with_schema();
+        insert_session(&db, "s1", 1_700_000_000, 1_700_000_600, 1);
+        let rules = AppRules::test_with_rules(
+            vec!["code".to_string(), "both".to_string()],
+            vec!["steam".to_string(), "both".to_string()],
+        );
+        let secret = "sb-secret-key-123";
+        let env = |key: &str| match key {
+            "SUPABASE_URL" => Some("https://example.supabase.co/rest/v1".to_string()),
+            "SUPABASE_API_KEY" => Some(secret.to_string()),
+            _ => None,
+        };
+
+        let snapshot =
+            diagnostic_snapshot(&db, &rules, None, None, Config::default(), env).unwrap();
+        assert_eq!(snapshot.database.integrity, "ok");
+        assert_eq!(snapshot.database.sessions, 1);
+        assert_eq!(snapshot.rules.conflicts, vec!["both".to_string()]);
+        assert!(snapshot.sync.configured && snapshot.sync.api_key_set);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        for section in ["platform", "database", "rules", "sync", "session", "poll_timing", "config"] {
+            assert!(json.contains(&format!("\"{}\":", section)), "missing {}", section);
+        }
+        assert!(!json.contains(secret));
+    }
+
+    #[test]
+    fn recent_sessions_are_newest_first_and_limited() {
+        let db = DbHandle::test_in_memory_with_schema();
+        insert_session(&db, "a", 1_000, 1_100, 0);
+        insert_session(&db, "c", 3_000, 3_100, 2);
+        insert_session(&db, "b", 2_000, 2_100, 1);
+
+        let recent = recent_sessions(&db, 2).unwrap();
+        let ids: Vec<&str> = recent.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b"]);
+        assert_eq!(recent[0].distraction_attempts, Some(2));
+        assert!(recent_sessions(&db, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn manual_session_is_flagged_and_validated() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let now = 1_700_000_000;
+        let apps = vec!["whiteboard".to_string()];
+        let id = add_manual_session_at(&db, now, now - 3600, now - 600, &apps, 0).unwrap();
+
+        let sessions = recent_sessions(&db, 10).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, id.to_string());
+        assert_eq!(sessions[0].end_time, Some(now - 600));
+        assert_eq!(sessions[0].work_apps.as_deref(), Some("whiteboard"));
+        assert!(sessions[0].manual);
+
+        // Ends before it starts, zero length, or ends in the future
+        for (start, end) in [(now - 600, now - 3600), (now - 600, now - 600), (now - 60, now + 60)] {
+            assert!(matches!(
+                add_manual_session_at(&db, now, start, end, &apps, 0),
+                Err(SynapseError::Config(_))
+            ));
+        }
+        assert_eq!(recent_sessions(&db, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn recent_foreground_apps_only_counts_the_window() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let now = 1_700_000_000;
+        for (app, start, end) in [
+            ("old.exe", now - 300, now - 120),
+            ("code.exe", now - 90, now - 40),
+            ("steam.exe", now - 40, now - 30),
+            ("code.exe", now - 30, now - 5),
+        ] {
+            db.insert_app_usage_event(app, crate::types::UsageStatus::Allowed, None, start, end, end - start, None, None)
+                .unwrap();
+        }
+
+        // The first code.exe event is clipped to the window's start
+        assert_eq!(
+            recent_foreground_apps_at(&db, now, 60).unwrap(),
+            vec![("code.exe".to_string(), 45), ("steam.exe".to_string(), 10)]
+        );
+        assert!(recent_foreground_apps_at(&db, now, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn category_usage_sums_events_per_category_against_budgets() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let rules = AppRules::from_file(crate::apprules::AppRulesFile {
+            categories: [
+                ("Social".to_string(), vec!["discord".to_string(), "slack".to_string()]),
+                ("games".to_string(), vec!["steam".to_string()]),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+        let budgets: CategoryBudgets = "social=3600, games=600, reading=1800".parse().unwrap();
+        let base = 1_700_000_000;
+        for (app, start, secs) in [
+            ("discord", base, 2400),
+            ("Slack", base + 2400, 1500),
+            ("steam", base + 4000, 300),
+            ("code", base + 4300, 5000),
+            // Outside the range
+            ("steam", base + 90_000, 9000),
+        ] {
+            db.insert_app_usage_event(app, crate::types::UsageStatus::Allowed, None, start, start + secs, secs, None, None)
+                .unwrap();
+        }
+
+        let usage = category_usage_between(&db, &rules, &budgets, base, base + 86_400).unwrap();
+        assert_eq!(
+            usage,
+            vec![
+                ("games".to_string(), 300, 600),
+                ("reading".to_string(), 0, 1800),
+                ("social".to_string(), 3900, 3600),
+            ]
+        );
+        let over: Vec<_> = usage.iter().filter(|(_, used, budget)| used > budget).collect();
+        assert_eq!(over.len(), 1);
+        assert_eq!(over[0].0, "social");
+    }
+
+    #[test]
+    fn weekly_report_has_sections_and_totals() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let (mon, _) = day_bounds(monday);
+        let (wed, _) = day_bounds(NaiveDate::from_ymd_opt(2024, 3, 6).unwrap());
+        let (next_mon, _) = day_bounds(NaiveDate::from_ymd_opt(2024, 3, 11).unwrap());
+        insert_session(&db, "s1", mon + 3600, mon + 3600 + 7200, 3);
+        insert_session(&db, "s2", wed + 3600, wed + 3600 + 1800, 2);
+        insert_session(&db, "s3", next_mon + 3600, next_mon + 7200, 5);
+        for (app, start, secs) in [("code.exe", mon + 3600, 4000), ("<steam>", wed + 3600, 600)] {
+            db.insert_app_usage_event(app, crate::types::UsageStatus::Allowed, None, start, start + secs, secs, None, None)
+                .unwrap();
+        }
+
+        let html = weekly_report_html(&db, monday).unwrap();
+        for heading in ["<h1>Weekly report</h1>", "<h2>Totals</h2>", "<h2>Focus per day</h2>", "<h2>Top apps</h2>"] {
+            assert!(html.contains(heading), "missing {}", heading);
+        }
+        assert!(html.contains("<tr><th>Focus time</th><td>2h 30m</td></tr>"));
+        assert!(html.contains("<tr><th>Sessions</th><td>2</td></tr>"));
+        assert!(html.contains("<tr><th>Distractions</th><td>5</td></tr>"));
+        assert!(html.contains("Mon 2024-03-04"));
+        assert!(html.contains("Sun 2024-03-10"));
+        assert!(html.contains("<td>code.exe</td><td>1h 06m</td>"));
+        assert!(html.contains("&lt;steam&gt;"));
+    }
+
+    #[test]
+    fn unsynced_sessions_carry_their_latest_sync_error() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let base = 1_700_000_000;
+        let failed = Uuid::new_v4();
+        insert_session(&db, &failed.to_string(), base, base + 600, 0);
+        insert_session(&db, "synced", base + 1000, base + 1600, 0);
+        insert_session(&db, "never-tried", base + 2000, base + 2600, 0);
+        db.conn()
+            .execute("UPDATE focus_sessions SET is_synced = 1 WHERE id = 'synced'", [])
+            .unwrap();
+        db.record_sync_failure(failed, "HTTP error: connection refused", base + 700)
+            .unwrap();
+        db.record_sync_failure(failed, "API error: 401 - bad key", base + 800)
+            .unwrap();
+
+        let unsynced = unsynced_sessions(&db).unwrap();
+        assert_eq!(unsynced.len(), 2);
+        assert_eq!(unsynced[0].id, failed.to_string());
+        assert_eq!(unsynced[0].last_error.as_deref(), Some("API error: 401 - bad key"));
+        assert_eq!(unsynced[0].last_failed_at, Some(base + 800));
+        assert_eq!(unsynced[1].id, "never-tried");
+        assert_eq!(unsynced[1].last_error, None);
+        assert!(unsynced.iter().all(|s| s.id != "synced"));
+    }
+
+    #[test]
+    fn distraction_reasons_are_grouped_and_ranked() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let base = 1_700_000_000;
+        insert_session(&db, "s1", base, base + 3600, 4);
+        for (i, reason) in ["Needed a break", "waiting on a build", "needed a break ", "  "]
+            .iter()
+            .enumerate()
+        {
+            let id = format!("e{}", i);
+            let start = base + 100 * i as i64;
+            insert_event(&db, &id, "s1", start, start + 60);
+            db.conn()
+                .execute(
+                    "UPDATE app_usage_events SET reason = ?1 WHERE id = ?2",
+                    params![reason, id],
+                )
+                .unwrap();
+        }
+        insert_event(&db, "no-reason", "s1", base + 1000, base + 1060);
+        insert_event(&db, "late", "s1", base + 5000, base + 5060);
+        db.conn()
+            .execute("UPDATE app_usage_events SET reason = 'bored' WHERE id = 'late'", [])
+            .unwrap();
+
+        let summary = distraction_reasons_summary(&db, base, base + 3600).unwrap();
+        assert_eq!(
+            summary,
+            vec![
+                ("needed a break".to_string(), 2),
+                ("waiting on a build".to_string(), 1)
+            ]
+        );
+        assert!(distraction_reasons_summary(&db, 0, base).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pace_vs_yesterday_compares_same_time_of_day() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let today = NaiveDate::from_ymd_opt(2024, 5, 8).unwrap();
+        let yesterday = today.pred_opt().unwrap();
+        let now = today.and_hms_opt(12, 0, 0).unwrap();
+        let (today_start, _) = day_bounds(today);
+        let (yesterday_start, _) = day_bounds(yesterday);
+
+        // Yesterday: 1h in the morning, plus a session crossing noon of which 30m counts
+        let (y, t, hour) = (yesterday_start, today_start, 3600);
+        insert_session(&db, "y-1", y + 9 * hour, y + 10 * hour, 0);
+        insert_session(&db, "y-2", y + 11 * hour + 1800, y + 14 * hour, 0);
+        // Today: 2h, ahead of yesterday's 1.5h
+        insert_session(&db, "t-1", t + 8 * hour, t + 10 * hour, 0);
+
+        let pace = pace_at(&db, now).unwrap();
+        assert_eq!(pace.yesterday_secs, 5400);
+        assert_eq!(pace.today_secs, 7200);
+        assert_eq!(pace.delta_secs, 1800);
+
+        let pace = pace_at(&db, today.and_hms_opt(9, 0, 0).unwrap()).unwrap();
+        assert_eq!(pace.today_secs, 3600);
+        assert_eq!(pace.yesterday_secs, 0);
+        let pace = pace_at(&db, today.and_hms_opt(9, 30, 0).unwrap()).unwrap();
+        assert_eq!((pace.today_secs, pace.yesterday_secs), (5400, 1800));
+
+        // An early session yesterday puts today behind
+        insert_session(&db, "y-3", y + hour, y + 4 * hour, 0);
+        let pace = pace_at(&db, today.and_hms_opt(9, 30, 0).unwrap()).unwrap();
+        assert_eq!((pace.today_secs, pace.yesterday_secs), (5400, 12_600));
+        assert_eq!(pace.delta_secs, -7200);
+    }
+
+    #[test]
+    fn unused_rules_excludes_rules_matched_in_window() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let rules = AppRules::test_with_rules(
+            vec!["code".to_string(), "code.exe".to_string(), "vim".to_string()],
+            vec!["steam".to_string(), "discord".to_string()],
+        );
+        db.record_rule_match("code.exe", 2_000).unwrap();
+        db.record_rule_match("steam", 2_500).unwrap();
+        db.record_rule_match("discord", 500).unwrap();
+
+        assert_eq!(
+            unused_rules(&db, &rules, 1_000).unwrap(),
+            vec!["vim".to_string(), "discord".to_string()]
+        );
+        assert_eq!(unused_rules(&db, &rules, 0).unwrap(), vec!["vim".to_string()]);
+    }
+
+    #[test]
+    fn evaluate_app_blacklist_takes_precedence() {
+        let verdict = evaluate_app(&manager(rules()), "both.exe");
+        assert_eq!(verdict.verdict, VerdictKind::Blocked);
+        assert_eq!(verdict.matched_rule.as_deref(), Some("both.exe"));
+    }
+
+    #[test]
+    fn evaluate_app_follows_whitelist_wins_precedence() {
+        let mut mgr = manager(rules());
+        mgr.set_overlap_precedence(crate::config::OverlapPrecedence::WhitelistWins);
+        let verdict = evaluate_app(&mgr, "both.exe");
+        assert_eq!(verdict.verdict, VerdictKind::Work);
+        assert_eq!(verdict.matched_rule.as_deref(), Some("both.exe"));
+        assert_eq!(evaluate_app(&mgr, "discord.exe").verdict, VerdictKind::Blocked);
+    }
+
+    #[test]
+    fn evaluate_app_blocks_unlisted_apps_in_strict_mode() {
+        let mut mgr = manager(AppRules::from_file(crate::apprules::AppRulesFile {
+            whitelist: vec!["code.exe".to_string()],
+            neutral: vec!["explorer.exe".to_string()],
+            ..Default::default()
+        }));
+        assert_eq!(evaluate_app(&mgr, "calc.exe").verdict, VerdictKind::Neutral);
+
+        mgr.set_strict_mode(true);
+        let verdict = evaluate_app(&mgr, "calc.exe");
+        assert_eq!(verdict.verdict, VerdictKind::Blocked);
+        assert!(verdict.matched_rule.is_none());
+        assert_eq!(evaluate_app(&mgr, "code.exe").verdict, VerdictKind::Work);
+        assert_eq!(evaluate_app(&mgr, "explorer.exe").verdict, VerdictKind::Neutral);
+    }
+}
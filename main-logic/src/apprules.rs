@@ -1,16 +1,57 @@
 //! Application rules module: handles loading, parsing, and checking whitelist/blacklist rules for process names.
 
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use crate::error::SynapseError;
 
+/// Default pattern for `version_suffix_pattern`: trailing version numbers
+/// (`"Firefox 122.0"`, `"Zoom v5.17"`) and parenthetical suffixes (`"Slack (64-bit)"`).
+pub const DEFAULT_VERSION_SUFFIX_PATTERN: &str = r"(\s+v?\d+(\.\d+)*|\s*\([^)]*\))+$";
+
+/// Words marking a process as another app's helper when `group_helper_processes` is on,
+/// e.g. `chrome_crashpad_handler` or `Code Helper (Renderer)`.
+const HELPER_MARKERS: &[&str] = &["helper", "crashpad", "renderer", "gpu", "utility", "broker", "plugin"];
+
 /// Structure for deserializing the application rules JSON file.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct AppRulesFile {
-    whitelist: Vec<String>,
-    blacklist: Vec<String>,
+    pub whitelist: Vec<String>,
+    pub blacklist: Vec<String>,
+    /// Apps that are neither work nor distractions; only consulted in strict mode.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub neutral: Vec<String>,
+    /// Regular expressions marking matching apps as work, checked after the whitelist.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub whitelist_regex: Vec<String>,
+    /// Regular expressions marking matching apps as blocked, checked after the blacklist.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blacklist_regex: Vec<String>,
+    /// Alias mappings from raw process/display names to a canonical name.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub aliases: HashMap<String, String>,
+    /// Apps that must *all* be running for a session to start. When empty, any whitelisted app suffices.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_work_apps: Vec<String>,
+    /// Strip version numbers and parenthetical suffixes from names before matching and logging.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strip_version_suffixes: bool,
+    /// Overrides [`DEFAULT_VERSION_SUFFIX_PATTERN`]; every match is removed from the name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_suffix_pattern: Option<String>,
+    /// App categories for category budgets: category name -> the apps in it.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub categories: HashMap<String, Vec<String>>,
+    /// Helper processes folded into their parent app: parent -> its helpers.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub helper_processes: HashMap<String, Vec<String>>,
+    /// Also fold running processes named like a running app plus a helper marker
+    /// (`chrome_crashpad_handler` next to `chrome`) into that app.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub group_helper_processes: bool,
 }
 
 /// Application rules for process whitelisting and blacklisting.
@@ -18,42 +59,416 @@ pub struct AppRulesFile {
 pub struct AppRules {
     whitelist: Vec<String>,
     blacklist: Vec<String>,
+    neutral: Vec<String>,
+    /// Compiled `whitelist_regex` patterns, matched case-insensitively.
+    whitelist_regex: Vec<Regex>,
+    /// Compiled `blacklist_regex` patterns, matched case-insensitively.
+    blacklist_regex: Vec<Regex>,
+    /// Lowercased alias mappings (raw name -> canonical name).
+    aliases: HashMap<String, String>,
+    /// Required work apps, each expanded to its platform-specific name variants.
+    required_work_apps: Vec<Vec<String>>,
+    /// Suffix pattern stripped from names before matching, when enabled.
+    version_suffix: Option<Regex>,
+    /// Lowercased category of each categorized app, keyed by its expanded names.
+    categories: HashMap<String, String>,
+    /// Canonical parent of each listed helper process, keyed by the helper's expanded names.
+    helper_parents: HashMap<String, String>,
+    /// Whether helpers are also recognized by name next to their running parent.
+    group_helper_processes: bool,
+}
+
+/// Returns true if `name` matches the rule `pattern`, ignoring ASCII case.
+///
+/// A pattern containing `*` (any run of characters) or `?` (any one character) is matched
+/// as a glob, so `jetbrains-*` covers every JetBrains IDE; any other pattern must equal the
+/// name exactly.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return pattern.eq_ignore_ascii_case(name);
+    }
+    let pattern: Vec<char> = pattern.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let name: Vec<char> = name.chars().map(|c| c.to_ascii_lowercase()).collect();
+    // Greedy matching that backtracks to the most recent `*` on a mismatch
+    let (mut p, mut n) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = last_star {
+            p = star + 1;
+            n = matched + 1;
+            last_star = Some((star, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Contents of a legacy `blacklist.json`: a bare list of app names, or an object holding
+/// them under `blacklist`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LegacyBlacklist {
+    Names(Vec<String>),
+    File { blacklist: Vec<String> },
+}
+
+/// Longest stretch of the offending line quoted in a parse error.
+const ERROR_SNIPPET_WIDTH: usize = 60;
+
+/// Parses rules JSON, reporting syntax errors as
+/// `Failed to parse <source> at line L, column C: <reason>` followed by the offending line
+/// and a caret under the error position.
+fn parse_rules_json(source: &str, contents: &str) -> Result<AppRulesFile, SynapseError> {
+    serde_json::from_str(contents).map_err(|e| {
+        let (line, column) = (e.line(), e.column());
+        if line == 0 {
+            return SynapseError::Config(format!("Failed to parse {}: {}", source, e));
+        }
+        // serde_json appends its own "at line L column C"; it is reported up front instead
+        let full = e.to_string();
+        let reason = full.split(" at line ").next().unwrap_or(&full);
+        let mut message = format!(
+            "Failed to parse {} at line {}, column {}: {}",
+            source, line, column, reason
+        );
+        if let Some(snippet) = error_snippet(contents, line, column) {
+            message.push('\n');
+            message.push_str(&snippet);
+        }
+        SynapseError::Config(message)
+    })
+}
+
+/// Returns up to `ERROR_SNIPPET_WIDTH` characters of `line` (1-based) around `column`, with a
+/// caret line beneath pointing at the column.
+fn error_snippet(contents: &str, line: usize, column: usize) -> Option<String> {
+    let text: Vec<char> = contents.lines().nth(line.checked_sub(1)?)?.chars().collect();
+    let column = column.clamp(1, text.len().max(1)) - 1;
+    let start = column.saturating_sub(ERROR_SNIPPET_WIDTH / 2);
+    let end = (start + ERROR_SNIPPET_WIDTH).min(text.len());
+    let quoted: String = text[start..end].iter().collect();
+    if quoted.trim().is_empty() {
+        return None;
+    }
+    Some(format!("  {}\n  {}^", quoted, " ".repeat(column - start)))
 }
 
 impl AppRules {
-    /// Loads application rules from `apprules.json` if present, or uses empty rules otherwise.
+    /// Loads application rules from `SYNAPSE_APPRULES_JSON` if set, otherwise from the file at
+    /// `APPRULES_PATH` (default `apprules.json`) if present, or uses empty rules otherwise.
     ///
     /// # Errors
-    /// Returns `SynapseError` if the file cannot be read or parsed.
+    /// Returns `SynapseError` if the variable or file cannot be read or parsed.
     pub fn new() -> Result<Self, SynapseError> {
-        let path_str = std::env::var("APPRULES_PATH").unwrap_or_else(|_| "apprules.json".to_string());
-        let path = Path::new(&path_str);
+        Self::load(&Self::path_from_env("apprules.json"))
+    }
+
+    /// Returns the rules file named by `APPRULES_PATH`, or `default` if it is unset. Resolve
+    /// it once and use it for loading, watching and saving, so they all agree on the file.
+    pub fn path_from_env(default: &str) -> PathBuf {
+        std::env::var("APPRULES_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(default))
+    }
+
+    /// Loads application rules from `SYNAPSE_APPRULES_JSON` if set, otherwise from the file at
+    /// `path` if present, or uses empty rules otherwise.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the variable or file cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, SynapseError> {
+        if let Some(rules) = Self::from_env_json()? {
+            return Ok(rules);
+        }
+        Self::from_path(path)
+    }
+
+    /// Loads application rules from the file at `path`, or uses empty rules if it does not
+    /// exist.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the file cannot be read or parsed.
+    pub fn from_path(path: &Path) -> Result<Self, SynapseError> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
         if path.exists() {
             let contents = fs::read_to_string(path)
-                .map_err(|e| SynapseError::Config(format!("Failed to read apprules.json: {}", e)))?;
-            let parsed: AppRulesFile = serde_json::from_str(&contents)
-                .map_err(|e| SynapseError::Config(format!("Failed to parse apprules.json: {}", e)))?;
-            Ok(AppRules {
-                whitelist: Self::expand_names(parsed.whitelist),
-                blacklist: Self::expand_names(parsed.blacklist),
-            })
+                .map_err(|e| SynapseError::Config(format!("Failed to read {}: {}", name, e)))?;
+            let parsed = parse_rules_json(&name, &contents)?;
+            Self::try_from_file(parsed)
         } else {
-            println!("    apprules.json not found - using empty rules.");
-            let whitelist: Vec<String> = Vec::new();
-            let blacklist: Vec<String> = Vec::new();
-            Ok(AppRules {
-                whitelist: Self::expand_names(whitelist),
-                blacklist: Self::expand_names(blacklist),
-            })
+            println!("    {} not found - using empty rules.", name);
+            Ok(Self::from_file(AppRulesFile::default()))
         }
     }
 
+    /// Replaces these rules in place with the ones in the file at `path`. On error the
+    /// current rules are left unchanged.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the file cannot be read or parsed.
+    pub fn reload_from_path(&mut self, path: &Path) -> Result<(), SynapseError> {
+        *self = Self::from_path(path)?;
+        Ok(())
+    }
+
+    /// Parses rules from the JSON in `SYNAPSE_APPRULES_JSON`, for deployments without a rules
+    /// file. Returns `None` if the variable is unset.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the variable is not valid rules JSON.
+    pub fn from_env_json() -> Result<Option<Self>, SynapseError> {
+        Self::from_json_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Whether the rules come from `SYNAPSE_APPRULES_JSON`, in which case edits to the rules
+    /// file must not replace them.
+    pub fn env_json_is_set() -> bool {
+        std::env::var_os("SYNAPSE_APPRULES_JSON").is_some()
+    }
+
+    fn from_json_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, SynapseError> {
+        let Some(json) = lookup("SYNAPSE_APPRULES_JSON") else {
+            return Ok(None);
+        };
+        let parsed = parse_rules_json("SYNAPSE_APPRULES_JSON", &json)?;
+        Self::try_from_file(parsed).map(Some)
+    }
+
     /// Construct AppRules directly from whitelist and blacklist (for tests and integration).
     pub fn test_with_rules(whitelist: Vec<String>, blacklist: Vec<String>) -> Self {
-        AppRules {
-            whitelist: Self::expand_names(whitelist),
-            blacklist: Self::expand_names(blacklist),
+        Self::from_file(AppRulesFile {
+            whitelist,
+            blacklist,
+            ..Default::default()
+        })
+    }
+
+    /// Construct AppRules with alias mappings (for tests and integration).
+    pub fn test_with_aliases(
+        whitelist: Vec<String>,
+        blacklist: Vec<String>,
+        aliases: HashMap<String, String>,
+    ) -> Self {
+        Self::from_file(AppRulesFile {
+            whitelist,
+            blacklist,
+            aliases,
+            ..Default::default()
+        })
+    }
+
+    /// Reads the app names from a legacy `blacklist.json`, to be merged into the blacklist.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the file cannot be read or parsed.
+    pub fn import_legacy_blacklist(path: &Path) -> Result<Vec<String>, SynapseError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            SynapseError::Config(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let legacy: LegacyBlacklist = serde_json::from_str(&contents).map_err(|e| {
+            SynapseError::Config(format!("Failed to parse {}: {}", path.display(), e))
+        })?;
+        let names = match legacy {
+            LegacyBlacklist::Names(names) | LegacyBlacklist::File { blacklist: names } => names,
+        };
+        Ok(names
+            .into_iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect())
+    }
+
+    /// Folds a legacy `blacklist.json` next to the rules file into the rules file's blacklist,
+    /// once, at startup. See [`migrate_legacy_blacklist_at`](Self::migrate_legacy_blacklist_at).
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if either file cannot be read, parsed or written.
+    pub fn migrate_legacy_blacklist() -> Result<bool, SynapseError> {
+        let path_str = std::env::var("APPRULES_PATH").unwrap_or_else(|_| "apprules.json".to_string());
+        let rules_path = Path::new(&path_str);
+        let legacy_path = rules_path.with_file_name("blacklist.json");
+        Self::migrate_legacy_blacklist_at(&legacy_path, rules_path)
+    }
+
+    /// Adds the entries of the legacy blacklist at `legacy_path` that the rules file at
+    /// `rules_path` doesn't already block, saves the rules file and renames the legacy file to
+    /// `blacklist.json.migrated` so it is only imported once. Returns whether anything was
+    /// migrated; without a legacy file nothing is touched.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if either file cannot be read, parsed or written. The
+    /// legacy file is left in place in that case.
+    pub fn migrate_legacy_blacklist_at(legacy_path: &Path, rules_path: &Path) -> Result<bool, SynapseError> {
+        if !legacy_path.exists() {
+            return Ok(false);
+        }
+        let legacy = Self::import_legacy_blacklist(legacy_path)?;
+        let mut rules = if rules_path.exists() {
+            let contents = fs::read_to_string(rules_path)
+                .map_err(|e| SynapseError::Config(format!("Failed to read apprules.json: {}", e)))?;
+            parse_rules_json("apprules.json", &contents)?
+        } else {
+            AppRulesFile::default()
+        };
+        let mut added = 0;
+        for name in legacy {
+            if !rules.blacklist.iter().any(|entry| entry.eq_ignore_ascii_case(&name)) {
+                rules.blacklist.push(name);
+                added += 1;
+            }
+        }
+        let json = serde_json::to_string_pretty(&rules)
+            .map_err(|e| SynapseError::Config(format!("Failed to serialize app rules: {}", e)))?;
+        fs::write(rules_path, json)
+            .map_err(|e| SynapseError::Config(format!("Failed to write apprules.json: {}", e)))?;
+        let migrated = legacy_path.with_file_name("blacklist.json.migrated");
+        fs::rename(legacy_path, &migrated).map_err(|e| {
+            SynapseError::Config(format!("Failed to rename {}: {}", legacy_path.display(), e))
+        })?;
+        println!(
+            "    Migrated {} app(s) from {} into {}",
+            added,
+            legacy_path.display(),
+            rules_path.display()
+        );
+        Ok(true)
+    }
+
+    /// Like [`from_file`](Self::from_file), but rejects rules with an invalid
+    /// `whitelist_regex` or `blacklist_regex` pattern instead of skipping it.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` naming the first pattern that doesn't compile.
+    pub fn try_from_file(file: AppRulesFile) -> Result<Self, SynapseError> {
+        for pattern in file.whitelist_regex.iter().chain(&file.blacklist_regex) {
+            Self::compile_regex(pattern)?;
+        }
+        Ok(Self::from_file(file))
+    }
+
+    /// Builds rules from a parsed rules file, canonicalizing every entry through the alias map.
+    ///
+    /// An invalid `version_suffix_pattern` is reported and replaced by the default pattern;
+    /// invalid regex rules are reported and skipped.
+    pub fn from_file(file: AppRulesFile) -> Self {
+        let aliases: HashMap<String, String> = file
+            .aliases
+            .into_iter()
+            .map(|(raw, canonical)| (raw.trim().to_lowercase(), canonical.trim().to_lowercase()))
+            .collect();
+        let version_suffix = if file.strip_version_suffixes {
+            Some(Self::version_suffix_regex(file.version_suffix_pattern.as_deref()))
+        } else {
+            None
+        };
+        let mut rules = AppRules {
+            whitelist: Vec::new(),
+            blacklist: Vec::new(),
+            neutral: Vec::new(),
+            whitelist_regex: Self::compile_valid_regexes(&file.whitelist_regex),
+            blacklist_regex: Self::compile_valid_regexes(&file.blacklist_regex),
+            required_work_apps: Vec::new(),
+            aliases,
+            version_suffix,
+            categories: HashMap::new(),
+            helper_parents: HashMap::new(),
+            group_helper_processes: file.group_helper_processes,
+        };
+        rules.whitelist = Self::expand_names(rules.canonicalize_all(file.whitelist));
+        rules.blacklist = Self::expand_names(rules.canonicalize_all(file.blacklist));
+        rules.neutral = Self::expand_names(rules.canonicalize_all(file.neutral));
+        rules.required_work_apps = rules
+            .canonicalize_all(file.required_work_apps)
+            .into_iter()
+            .map(|name| Self::expand_names(vec![name]))
+            .collect();
+        for (category, apps) in file.categories {
+            let category = category.trim().to_lowercase();
+            for name in Self::expand_names(rules.canonicalize_all(apps)) {
+                rules.categories.insert(name, category.clone());
+            }
+        }
+        for (parent, helpers) in file.helper_processes {
+            let parent = rules.normalize(&parent);
+            for name in Self::expand_names(rules.canonicalize_all(helpers)) {
+                rules.helper_parents.insert(name, parent.clone());
+            }
+        }
+        rules
+    }
+
+    /// Compiles a `whitelist_regex`/`blacklist_regex` pattern, ignoring case.
+    fn compile_regex(pattern: &str) -> Result<Regex, SynapseError> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| SynapseError::Config(format!("Invalid regex rule '{}': {}", pattern, e)))
+    }
+
+    /// Compiles every valid pattern, reporting and skipping the rest.
+    fn compile_valid_regexes(patterns: &[String]) -> Vec<Regex> {
+        patterns
+            .iter()
+            .filter_map(|pattern| match Self::compile_regex(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    eprintln!("    {} - skipping it.", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn version_suffix_regex(pattern: Option<&str>) -> Regex {
+        if let Some(pattern) = pattern {
+            match Regex::new(pattern) {
+                Ok(re) => return re,
+                Err(e) => eprintln!(
+                    "    Invalid version_suffix_pattern '{}': {} - using the default pattern.",
+                    pattern, e
+                ),
+            }
+        }
+        Regex::new(DEFAULT_VERSION_SUFFIX_PATTERN).expect("default version suffix pattern is valid")
+    }
+
+    /// Maps every name to its canonical form, dropping duplicates while keeping order.
+    fn canonicalize_all(&self, names: Vec<String>) -> Vec<String> {
+        let mut canonical: Vec<String> = Vec::new();
+        for name in names {
+            let name = self.normalize(&name);
+            if !canonical.contains(&name) {
+                canonical.push(name);
+            }
+        }
+        canonical
+    }
+
+    /// Returns the canonical name for a raw process or display name.
+    ///
+    /// Names are lowercased, stripped of version suffixes (when enabled) and looked up in
+    /// the alias map, so `"Code.exe"` and `"Code - Insiders"` can both resolve to `"vscode"`.
+    /// Unmapped names are returned lowercased.
+    pub fn normalize(&self, process_name: &str) -> String {
+        let mut name = process_name.trim().to_lowercase();
+        if let Some(re) = &self.version_suffix {
+            let stripped = re.replace_all(&name, "").trim().to_string();
+            // Never strip a name down to nothing
+            if !stripped.is_empty() {
+                name = stripped;
+            }
         }
+        self.aliases.get(&name).cloned().unwrap_or(name)
     }
 
     /// Expands process names for platform-specific matching (e.g., adds `.exe` on Windows).
@@ -64,7 +479,7 @@ impl AppRules {
             expanded.push(name_lc.clone());
             #[cfg(target_os = "windows")]
             {
-                if !name_lc.ends_with(".exe") && !name_lc.contains(".exe") {
+                if !name_lc.ends_with(".exe") && !name_lc.contains(".exe") && !name_lc.ends_with('*') {
                     expanded.push(format!("{}.exe", name_lc));
                 }
             }
@@ -81,12 +496,26 @@ impl AppRules {
     /// # Errors
     /// Returns `SynapseError` if the file cannot be written or serialized.
     pub fn update_rules(&mut self, whitelist: Vec<String>, blacklist: Vec<String>) -> Result<(), SynapseError> {
+        let path_str = std::env::var("APPRULES_PATH").unwrap_or_else(|_| "apprules.json".to_string());
+        self.update_rules_at(whitelist, blacklist, Path::new(&path_str))
+    }
+
+    /// Updates the whitelist and blacklist, then saves them to the rules file at `path`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the file cannot be written or serialized.
+    pub fn update_rules_at(
+        &mut self,
+        whitelist: Vec<String>,
+        blacklist: Vec<String>,
+        path: &Path,
+    ) -> Result<(), SynapseError> {
         log::info!("[DEBUG] update_rules called");
         log::info!("[DEBUG] Incoming whitelist: {:?}", whitelist);
         log::info!("[DEBUG] Incoming blacklist: {:?}", blacklist);
 
-        self.whitelist = Self::expand_names(whitelist); // Expand .exe names if needed
-        self.blacklist = Self::expand_names(blacklist); // Expand .exe names if needed
+        self.whitelist = Self::expand_names(self.canonicalize_all(whitelist)); // Expand .exe names if needed
+        self.blacklist = Self::expand_names(self.canonicalize_all(blacklist)); // Expand .exe names if needed
 
         log::info!("[DEBUG] Expanded whitelist: {:?}", self.whitelist);
         log::info!("[DEBUG] Expanded blacklist: {:?}", self.blacklist);
@@ -94,6 +523,20 @@ impl AppRules {
         let rules = AppRulesFile {
             whitelist: self.whitelist.iter().map(|s| s.to_string()).collect(),
             blacklist: self.blacklist.iter().map(|s| s.to_string()).collect(),
+            neutral: self.neutral.clone(),
+            whitelist_regex: self.whitelist_regex.iter().map(|re| re.as_str().to_string()).collect(),
+            blacklist_regex: self.blacklist_regex.iter().map(|re| re.as_str().to_string()).collect(),
+            aliases: self.aliases.clone(),
+            required_work_apps: self.required_work_apps().map(str::to_string).collect(),
+            strip_version_suffixes: self.version_suffix.is_some(),
+            version_suffix_pattern: self
+                .version_suffix
+                .as_ref()
+                .map(|re| re.as_str().to_string())
+                .filter(|pattern| pattern != DEFAULT_VERSION_SUFFIX_PATTERN),
+            categories: self.categories_by_name(),
+            helper_processes: self.helpers_by_parent(),
+            group_helper_processes: self.group_helper_processes,
         };
 
         let json = serde_json::to_string_pretty(&rules)
@@ -101,8 +544,6 @@ impl AppRules {
                 log::error!("[DEBUG] Failed to serialize app rules: {}", e);
                 SynapseError::Config(format!("Failed to serialize app rules: {}", e))
             })?;
-        let path_str = std::env::var("APPRULES_PATH").unwrap_or_else(|_| "apprules.json".to_string());
-        let path = Path::new(&path_str);
 
         log::info!("[DEBUG] Writing rules to: {}", path.display());
         fs::write(path, json)
@@ -116,25 +557,186 @@ impl AppRules {
         Ok(())
     }
 
-    /// Checks if a process name is in the whitelist.
+    /// Moves `app_name` to the whitelist, dropping it from the blacklist, and saves the
+    /// rules to `path`. Used when the user marks a blocked app as work.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the file cannot be written or serialized.
+    pub fn whitelist_app_at(&mut self, app_name: &str, path: &Path) -> Result<(), SynapseError> {
+        let app = self.normalize(app_name);
+        let names = Self::expand_names(vec![app.clone()]);
+        let mut whitelist = self.whitelist.clone();
+        if !self.is_work_app(&app) {
+            whitelist.push(app);
+        }
+        let blacklist = self
+            .blacklist
+            .iter()
+            .filter(|name| !names.contains(name))
+            .cloned()
+            .collect();
+        self.update_rules_at(whitelist, blacklist, path)
+    }
+
+    /// Checks if a process name (after normalization) is in the whitelist.
     pub fn is_work_app(&self, process_name: &str) -> bool {
-        self.whitelist.iter().any(|name| name.eq_ignore_ascii_case(process_name))
+        self.work_rule(process_name).is_some()
     }
 
-    /// Checks if a process name is in the blacklist.
+    /// Checks if a process name (after normalization) is in the blacklist.
     pub fn is_blocked(&self, process_name: &str) -> bool {
-        self.blacklist.iter().any(|name| name.eq_ignore_ascii_case(process_name))
+        self.blocking_rule(process_name).is_some()
     }
 
-    /// Returns a reference to the whitelist.
-    pub fn whitelist(&self) -> &Vec<String> {
+    /// Checks if a process name (after normalization) is in the neutral list.
+    pub fn is_neutral(&self, process_name: &str) -> bool {
+        let process_name = self.normalize(process_name);
+        self.neutral
+            .iter()
+            .any(|name| matches_pattern(name, &process_name))
+    }
+
+    /// Returns the whitelist entry, or failing that the `whitelist_regex` pattern, that
+    /// matches a process name, if any.
+    pub fn work_rule(&self, process_name: &str) -> Option<&str> {
+        let process_name = self.normalize(process_name);
+        self.whitelist
+            .iter()
+            .find(|name| matches_pattern(name, &process_name))
+            .map(String::as_str)
+            .or_else(|| {
+                self.whitelist_regex
+                    .iter()
+                    .find(|re| re.is_match(&process_name))
+                    .map(Regex::as_str)
+            })
+    }
+
+    /// Returns the blacklist entry, or failing that the `blacklist_regex` pattern, that
+    /// matches a process name, if any.
+    pub fn blocking_rule(&self, process_name: &str) -> Option<&str> {
+        let process_name = self.normalize(process_name);
+        self.blacklist
+            .iter()
+            .find(|name| matches_pattern(name, &process_name))
+            .map(String::as_str)
+            .or_else(|| {
+                self.blacklist_regex
+                    .iter()
+                    .find(|re| re.is_match(&process_name))
+                    .map(Regex::as_str)
+            })
+    }
+
+    /// Returns the category of a process name (after normalization), if it has one.
+    pub fn category_of(&self, process_name: &str) -> Option<&str> {
+        self.categories
+            .get(&self.normalize(process_name))
+            .map(String::as_str)
+    }
+
+    /// Returns the categories as written in the rules file, each with its sorted apps.
+    fn categories_by_name(&self) -> HashMap<String, Vec<String>> {
+        let mut categories: HashMap<String, Vec<String>> = HashMap::new();
+        for (app, category) in &self.categories {
+            categories.entry(category.clone()).or_default().push(app.clone());
+        }
+        for apps in categories.values_mut() {
+            apps.sort();
+        }
+        categories
+    }
+
+    /// Returns the helper processes as written in the rules file, grouped by parent.
+    fn helpers_by_parent(&self) -> HashMap<String, Vec<String>> {
+        let mut helpers: HashMap<String, Vec<String>> = HashMap::new();
+        for (helper, parent) in &self.helper_parents {
+            helpers.entry(parent.clone()).or_default().push(helper.clone());
+        }
+        for names in helpers.values_mut() {
+            names.sort();
+        }
+        helpers
+    }
+
+    /// Returns the app `process_name` is a helper of, given the `running` processes: the
+    /// parent listed in `helper_processes`, or with `group_helper_processes` a running app
+    /// whose name prefixes it followed by a helper marker. Returns `None` for non-helpers.
+    pub fn helper_parent(&self, process_name: &str, running: &[String]) -> Option<String> {
+        let name = self.normalize(process_name);
+        if let Some(parent) = self.helper_parents.get(&name) {
+            return Some(parent.clone());
+        }
+        if !self.group_helper_processes {
+            return None;
+        }
+        let stem = name.trim_end_matches(".exe");
+        let (parent, rest) = running.iter().find_map(|candidate| {
+            let parent = self.normalize(candidate);
+            let parent_stem = parent.trim_end_matches(".exe");
+            let rest = stem.strip_prefix(parent_stem)?;
+            let separated = rest.starts_with([' ', '_', '-', '.']);
+            (separated && !parent_stem.is_empty()).then(|| (candidate.clone(), rest.to_string()))
+        })?;
+        HELPER_MARKERS
+            .iter()
+            .any(|marker| rest.contains(marker))
+            .then_some(parent)
+    }
+
+    /// Folds helper processes into their parent app (see [`helper_parent`](Self::helper_parent)),
+    /// keeping the first occurrence of each name, so one app with many helpers counts once.
+    pub fn fold_helper_processes(&self, running: &[String]) -> Vec<String> {
+        let mut folded: Vec<String> = Vec::new();
+        for name in running {
+            let name = self.helper_parent(name, running).unwrap_or_else(|| name.clone());
+            if !folded.contains(&name) {
+                folded.push(name);
+            }
+        }
+        folded
+    }
+
+    /// Returns the whitelisted names, expanded to their platform-specific variants.
+    pub fn whitelist(&self) -> &[String] {
         &self.whitelist
     }
 
-    /// Returns a reference to the blacklist.
-    pub fn blacklist(&self) -> &Vec<String> {
+    /// Returns the blacklisted names, expanded to their platform-specific variants.
+    pub fn blacklist(&self) -> &[String] {
         &self.blacklist
     }
+
+    /// Returns the number of whitelist and blacklist entries, after expansion.
+    pub fn rule_count(&self) -> (usize, usize) {
+        (self.whitelist.len(), self.blacklist.len())
+    }
+
+    /// Returns true if the running processes satisfy the session start condition.
+    ///
+    /// With no `required_work_apps` configured, any running whitelisted app suffices.
+    /// Otherwise every required app must be running.
+    pub fn work_condition_met(&self, running_processes: &[String]) -> bool {
+        if self.required_work_apps.is_empty() {
+            return running_processes.iter().any(|name| self.is_work_app(name));
+        }
+        let running: Vec<String> = running_processes.iter().map(|p| self.normalize(p)).collect();
+        self.required_work_apps.iter().all(|variants| {
+            running
+                .iter()
+                .any(|p| variants.iter().any(|v| v.eq_ignore_ascii_case(p)))
+        })
+    }
+
+    /// Returns the canonical names of the required work apps.
+    pub fn required_work_apps(&self) -> impl Iterator<Item = &str> {
+        self.required_work_apps.iter().map(|variants| variants[0].as_str())
+    }
+
+    /// Returns a reference to the alias mappings.
+    pub fn aliases(&self) -> &HashMap<String, String> {
+        &self.aliases
+    }
 }
 
 /// Public function to update apprules.json without managing state in src-tauri.
@@ -173,6 +775,25 @@ mod tests {
         fs::remove_file(path).unwrap();
     }
 
+    /// Looks up `SYNAPSE_APPRULES_JSON` as `json`, with every other variable unset.
+    fn rules_json_var(json: &'static str) -> impl Fn(&str) -> Option<String> {
+        move |name| (name == "SYNAPSE_APPRULES_JSON").then(|| json.to_string())
+    }
+
+    #[test]
+    fn loads_rules_from_env_json_without_a_file() {
+        let json = r#"{"whitelist": ["Code"], "blacklist": ["steam"]}"#;
+        let rules = AppRules::from_json_lookup(rules_json_var(json)).unwrap().unwrap();
+        assert!(rules.is_work_app("code"));
+        assert!(rules.is_blocked("Steam"));
+
+        assert!(AppRules::from_json_lookup(|_: &str| None).unwrap().is_none());
+        let err = AppRules::from_json_lookup(rules_json_var(r#"{"whitelist": ["code"]"#))
+            .err()
+            .expect("malformed JSON is an error");
+        assert!(matches!(err, SynapseError::Config(msg) if msg.contains("SYNAPSE_APPRULES_JSON")));
+    }
+
     #[test]
     fn checks_whitelist_case_insensitive() {
         let rules = AppRules::test_with_rules(vec!["notepad.exe".to_string()], vec!["chrome.exe".to_string()]);
@@ -233,6 +854,304 @@ mod tests {
         fs::remove_file(path).unwrap();
     }
 
+    #[test]
+    fn parse_errors_report_line_column_and_snippet() {
+        let json = "{\n  \"whitelist\": [\"code\"],\n  \"blacklist\": [\"steam\" \"discord\"]\n}";
+        let Err(SynapseError::Config(message)) = parse_rules_json("apprules.json", json) else {
+            panic!("malformed rules should fail to parse");
+        };
+        assert!(message.starts_with("Failed to parse apprules.json at line 3, column"), "{}", message);
+        assert!(!message.contains(" at line 3 column"), "{}", message);
+        assert!(message.contains("\"blacklist\": [\"steam\" \"discord\"]"), "{}", message);
+        assert!(message.lines().last().unwrap().trim_end().ends_with('^'));
+
+        // Truncated input is reported at the end of the text
+        let Err(SynapseError::Config(message)) = parse_rules_json("apprules.json", "{\"whitelist\": [") else {
+            panic!("truncated rules should fail to parse");
+        };
+        assert!(message.contains("at line 1, column"), "{}", message);
+    }
+
+    fn vscode_aliases() -> HashMap<String, String> {
+        let mut aliases = HashMap::new();
+        aliases.insert("code.exe".to_string(), "vscode".to_string());
+        aliases.insert("Code - Insiders".to_string(), "vscode".to_string());
+        aliases.insert("Visual Studio Code".to_string(), "vscode".to_string());
+        aliases
+    }
+
+    #[test]
+    fn normalize_collapses_aliases_to_canonical_name() {
+        let rules = AppRules::test_with_aliases(vec![], vec![], vscode_aliases());
+        assert_eq!(rules.normalize("Code.exe"), "vscode");
+        assert_eq!(rules.normalize("code - insiders"), "vscode");
+        assert_eq!(rules.normalize("Visual Studio Code"), "vscode");
+        assert_eq!(rules.normalize("Notepad.exe"), "notepad.exe");
+    }
+
+    #[test]
+    fn aliased_names_match_canonical_rule() {
+        let rules = AppRules::test_with_aliases(
+            vec!["vscode".to_string()],
+            vec!["discord".to_string()],
+            vscode_aliases(),
+        );
+        assert!(rules.is_work_app("code.exe"));
+        assert!(rules.is_work_app("Code - Insiders"));
+        assert!(rules.is_work_app("Visual Studio Code"));
+        assert!(!rules.is_blocked("code.exe"));
+    }
+
+    #[test]
+    fn aliased_rule_entries_collapse_to_one() {
+        let rules = AppRules::test_with_aliases(
+            vec!["code.exe".to_string(), "Code - Insiders".to_string(), "vscode".to_string()],
+            vec![],
+            vscode_aliases(),
+        );
+        assert_eq!(rules.whitelist().iter().filter(|n| n.as_str() == "vscode").count(), 1);
+        assert!(rules.is_work_app("visual studio code"));
+    }
+
+    #[test]
+    fn rule_count_matches_expanded_lists() {
+        let rules = AppRules::test_with_rules(
+            vec!["code".to_string(), "alacritty".to_string()],
+            vec!["steam".to_string()],
+        );
+        assert!(rules.whitelist().iter().any(|name| name == "code"));
+        assert!(rules.blacklist().iter().all(|name| name.starts_with("steam")));
+        assert_eq!(rules.rule_count(), (rules.whitelist().len(), rules.blacklist().len()));
+        assert!(rules.rule_count().0 >= 2);
+        assert_eq!(AppRules::from_file(AppRulesFile::default()).rule_count(), (0, 0));
+    }
+
+    #[test]
+    fn parses_aliases_from_json() {
+        let json = r#"{"whitelist": ["vscode"], "blacklist": [], "aliases": {"code.exe": "vscode"}}"#;
+        let parsed: AppRulesFile = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.aliases.get("code.exe").map(String::as_str), Some("vscode"));
+        let json = r#"{"whitelist": [], "blacklist": []}"#;
+        let parsed: AppRulesFile = serde_json::from_str(json).unwrap();
+        assert!(parsed.aliases.is_empty());
+    }
+
+    #[test]
+    fn neutral_apps_are_matched_after_normalization() {
+        let json = r#"{"whitelist": ["code"], "blacklist": [], "neutral": ["Explorer"]}"#;
+        let rules = AppRules::from_file(serde_json::from_str(json).unwrap());
+        assert!(rules.is_neutral("explorer"));
+        assert!(rules.is_neutral(" Explorer "));
+        assert!(!rules.is_neutral("code"));
+        assert!(!rules.is_work_app("explorer"));
+    }
+
+    #[test]
+    fn glob_patterns_match_process_names() {
+        assert!(matches_pattern("code*", "code.exe"));
+        assert!(matches_pattern("?hrome.exe", "chrome.exe"));
+        assert!(matches_pattern("jetbrains-*", "JetBrains-CLion"));
+        assert!(matches_pattern("*-insiders", "code-insiders"));
+        assert!(matches_pattern("a*b*c", "axxbyyc"));
+        assert!(!matches_pattern("?hrome.exe", "hrome.exe"));
+        assert!(!matches_pattern("jetbrains-*", "jetbrains"));
+        assert!(matches_pattern("notepad.exe", "Notepad.exe"));
+        assert!(!matches_pattern("notepad.exe", "notepad.exe.bak"));
+        assert!(!matches_pattern("notepad.exe", "notepad"));
+
+        let rules = AppRules::test_with_rules(
+            vec!["code*".to_string(), "notepad.exe".to_string()],
+            vec!["?hrome.exe".to_string()],
+        );
+        assert!(rules.is_work_app("code.exe"));
+        assert!(rules.is_work_app("Code - Insiders"));
+        assert!(rules.is_work_app("notepad.exe"));
+        assert!(!rules.is_work_app("notepad++.exe"));
+        assert!(rules.is_blocked("chrome.exe"));
+        assert!(!rules.is_blocked("chromium.exe"));
+        assert!(!rules.whitelist().iter().any(|name| name.ends_with("*.exe")));
+    }
+
+    #[test]
+    fn regex_rules_match_after_plain_names() {
+        let json = r#"{
+            "whitelist": ["code"],
+            "blacklist": ["steam"],
+            "whitelist_regex": ["^jetbrains-(idea|pycharm)$"],
+            "blacklist_regex": ["^(chrome|msedge)\\.exe$"]
+        }"#;
+        let rules = AppRules::try_from_file(serde_json::from_str(json).unwrap()).unwrap();
+        assert_eq!(rules.work_rule("code"), Some("code"));
+        assert_eq!(rules.work_rule("JetBrains-PyCharm"), Some("^jetbrains-(idea|pycharm)$"));
+        assert!(!rules.is_work_app("jetbrains-clion"));
+        assert!(rules.is_blocked("chrome.exe"));
+        assert!(rules.is_blocked("MSEdge.exe"));
+        assert!(!rules.is_blocked("chromium.exe"));
+        assert!(rules.is_blocked("steam"));
+
+        let clone = rules.clone();
+        assert!(clone.is_blocked("msedge.exe"));
+    }
+
+    #[test]
+    fn invalid_regex_rule_is_a_config_error() {
+        let file = AppRulesFile {
+            blacklist_regex: vec!["(chrome".to_string()],
+            ..Default::default()
+        };
+        match AppRules::try_from_file(file.clone()) {
+            Err(SynapseError::Config(msg)) => assert!(msg.contains("'(chrome'"), "{}", msg),
+            other => panic!("expected a config error, got {:?}", other.map(|_| ())),
+        }
+        // Building without validation skips the bad pattern instead
+        assert!(!AppRules::from_file(file).is_blocked("chrome"));
+    }
+
+    #[test]
+    fn legacy_blacklist_migrates_into_unified_rules() {
+        let dir = std::env::temp_dir().join(format!("synapse_legacy_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("blacklist.json");
+        let rules_path = dir.join("apprules.json");
+        fs::write(&legacy, r#"["Discord", "steam", " "]"#).unwrap();
+        fs::write(
+            &rules_path,
+            r#"{"whitelist": ["code"], "blacklist": ["Steam"], "aliases": {"code.exe": "code"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            AppRules::import_legacy_blacklist(&legacy).unwrap(),
+            vec!["Discord".to_string(), "steam".to_string()]
+        );
+        assert!(AppRules::migrate_legacy_blacklist_at(&legacy, &rules_path).unwrap());
+
+        let migrated: AppRulesFile =
+            serde_json::from_str(&fs::read_to_string(&rules_path).unwrap()).unwrap();
+        assert_eq!(migrated.blacklist, vec!["Steam".to_string(), "Discord".to_string()]);
+        assert_eq!(migrated.whitelist, vec!["code".to_string()]);
+        assert_eq!(migrated.aliases.get("code.exe").map(String::as_str), Some("code"));
+        assert!(AppRules::from_file(migrated).is_blocked("discord"));
+        assert!(!legacy.exists());
+        assert!(dir.join("blacklist.json.migrated").exists());
+
+        // Already migrated: nothing happens
+        assert!(!AppRules::migrate_legacy_blacklist_at(&legacy, &rules_path).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn legacy_blacklist_object_form_and_bad_files() {
+        let dir = std::env::temp_dir().join(format!("synapse_legacy_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("blacklist.json");
+        fs::write(&legacy, r#"{"blacklist": ["twitch"]}"#).unwrap();
+        assert_eq!(AppRules::import_legacy_blacklist(&legacy).unwrap(), vec!["twitch".to_string()]);
+
+        fs::write(&legacy, "not json").unwrap();
+        assert!(matches!(
+            AppRules::migrate_legacy_blacklist_at(&legacy, &dir.join("apprules.json")),
+            Err(SynapseError::Config(_))
+        ));
+        // A failed migration leaves both files as they were
+        assert!(legacy.exists());
+        assert!(!dir.join("apprules.json").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reload_from_path_reads_the_given_file_in_place() {
+        let dir = std::env::temp_dir().join(format!("synapse_reload_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("custom_rules.json");
+        fs::write(&path, r#"{"whitelist": ["code"], "blacklist": ["steam"]}"#).unwrap();
+
+        let mut rules = AppRules::from_path(&path).unwrap();
+        assert!(rules.is_work_app("code"));
+        assert!(rules.is_blocked("steam"));
+
+        fs::write(&path, r#"{"whitelist": ["vim"], "blacklist": ["discord"]}"#).unwrap();
+        rules.reload_from_path(&path).unwrap();
+        assert!(rules.is_work_app("vim"));
+        assert!(!rules.is_work_app("code"));
+        assert!(rules.is_blocked("discord"));
+
+        // A bad edit keeps the last good rules and names the file
+        fs::write(&path, "{ not json").unwrap();
+        let Err(SynapseError::Config(message)) = rules.reload_from_path(&path) else {
+            panic!("malformed rules should fail to reload");
+        };
+        assert!(message.contains("custom_rules.json"), "{}", message);
+        assert!(rules.is_work_app("vim"));
+
+        // A missing file means empty rules, as at startup
+        fs::remove_dir_all(&dir).unwrap();
+        rules.reload_from_path(&path).unwrap();
+        assert!(!rules.is_work_app("vim"));
+    }
+
+    fn editor_and_terminal_required() -> AppRules {
+        AppRules::from_file(AppRulesFile {
+            whitelist: vec!["code".to_string(), "alacritty".to_string()],
+            required_work_apps: vec!["code".to_string(), "alacritty".to_string()],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn work_condition_requires_all_required_apps() {
+        let rules = editor_and_terminal_required();
+        assert!(!rules.work_condition_met(&["code".to_string()]));
+        assert!(!rules.work_condition_met(&["alacritty".to_string(), "bash".to_string()]));
+        assert!(rules.work_condition_met(&["Code".to_string(), "alacritty".to_string()]));
+        assert_eq!(rules.required_work_apps().collect::<Vec<_>>(), vec!["code", "alacritty"]);
+    }
+
+    #[test]
+    fn work_condition_defaults_to_any_work_app() {
+        let rules = AppRules::test_with_rules(vec!["code".to_string(), "alacritty".to_string()], vec![]);
+        assert!(rules.work_condition_met(&["code".to_string()]));
+        assert!(!rules.work_condition_met(&["bash".to_string()]));
+    }
+
+    fn version_stripping_rules(pattern: Option<&str>) -> AppRules {
+        AppRules::from_file(AppRulesFile {
+            whitelist: vec!["Firefox".to_string()],
+            blacklist: vec!["Slack".to_string()],
+            strip_version_suffixes: true,
+            version_suffix_pattern: pattern.map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn versioned_names_collapse_to_base_name() {
+        let rules = version_stripping_rules(None);
+        for name in ["Firefox 122.0", "firefox 123.0.1", "Firefox v124", "Firefox (64-bit)", "Firefox 122.0 (64-bit)"] {
+            assert_eq!(rules.normalize(name), "firefox", "{}", name);
+            assert!(rules.is_work_app(name), "{}", name);
+        }
+        assert_eq!(rules.normalize("Slack (64-bit)"), "slack");
+        assert_eq!(rules.blocking_rule("Slack 4.36.140"), Some("slack"));
+        // Digits that are part of the name itself are kept
+        assert_eq!(rules.normalize("python3.11"), "python3.11");
+        assert_eq!(rules.normalize("(64-bit)"), "(64-bit)");
+    }
+
+    #[test]
+    fn version_stripping_is_opt_in_and_configurable() {
+        let rules = AppRules::test_with_rules(vec!["Firefox".to_string()], vec![]);
+        assert!(!rules.is_work_app("Firefox 122.0"));
+
+        let rules = version_stripping_rules(Some(r"\s+-\s+nightly$"));
+        assert_eq!(rules.normalize("Firefox - Nightly"), "firefox");
+        assert_eq!(rules.normalize("Firefox 122.0"), "firefox 122.0");
+
+        // An invalid pattern falls back to the default
+        let rules = version_stripping_rules(Some("(unclosed"));
+        assert_eq!(rules.normalize("Firefox 122.0"), "firefox");
+    }
+
     #[test]
     fn updates_and_saves_rules_with_exe() {
         let mut rules = AppRules::test_with_rules(vec!["notepad".to_string()], vec!["chrome".to_string()]);
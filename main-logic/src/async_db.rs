@@ -0,0 +1,159 @@
+//! Async DB module: runs database work on tokio's blocking pool so async callers
+//! (such as Tauri commands) never block the runtime on SQLite I/O.
+
+use crate::api;
+use crate::db::DbHandle;
+use crate::error::SynapseError;
+
+/// Async front-end over the SQLite database.
+///
+/// Each call opens its own connection inside `spawn_blocking`, so concurrent
+/// callers only contend on SQLite's own locking, never on a shared mutex.
+#[derive(Debug, Clone)]
+pub struct AsyncDbHandle {
+    db_path: String,
+}
+
+impl AsyncDbHandle {
+    /// Creates a handle for the database at `db_path`.
+    pub fn open(db_path: impl Into<String>) -> Self {
+        Self {
+            db_path: db_path.into(),
+        }
+    }
+
+    /// Creates a handle for the database named by `SYNAPSE_DB_PATH`.
+    pub fn from_env() -> Self {
+        Self::open(DbHandle::path_from_env())
+    }
+
+    /// Runs `f` against a fresh connection on the blocking thread pool.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the database cannot be opened, `f` fails, or the task panics.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, SynapseError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DbHandle) -> Result<T, SynapseError> + Send + 'static,
+    {
+        self.run_with(DbHandle::open, f).await
+    }
+
+    /// Like [`run`](Self::run), but against a read-only connection that skips schema
+    /// creation, for queries that never write.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the database doesn't exist yet, `f` fails (including any
+    /// write attempt), or the task panics.
+    pub async fn read<T, F>(&self, f: F) -> Result<T, SynapseError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DbHandle) -> Result<T, SynapseError> + Send + 'static,
+    {
+        self.run_with(DbHandle::open_readonly, f).await
+    }
+
+    /// Runs `f` on the blocking thread pool against a connection made by `open`.
+    async fn run_with<T, F>(
+        &self,
+        open: fn(&str) -> Result<DbHandle, SynapseError>,
+        f: F,
+    ) -> Result<T, SynapseError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DbHandle) -> Result<T, SynapseError> + Send + 'static,
+    {
+        let db_path = self.db_path.clone();
+        tokio::task::spawn_blocking(move || {
+            let db = open(&db_path)?;
+            f(&db)
+        })
+        .await
+        .map_err(|e| SynapseError::Other(format!("Database task failed: {}", e)))?
+    }
+
+    /// Async version of [`api::total_focus_time_today`].
+    pub async fn total_focus_time_today(&self) -> Result<i64, SynapseError> {
+        self.run(api::total_focus_time_today).await
+    }
+
+    /// Async version of [`api::total_distractions_today`].
+    pub async fn total_distractions_today(&self) -> Result<i64, SynapseError> {
+        self.run(api::total_distractions_today).await
+    }
+
+    /// Async version of [`api::total_focus_sessions_today`].
+    pub async fn total_focus_sessions_today(&self) -> Result<i64, SynapseError> {
+        self.run(api::total_focus_sessions_today).await
+    }
+
+    /// Async version of [`api::export_day`].
+    pub async fn export_day(&self, day_start: i64) -> Result<api::DayExport, SynapseError> {
+        self.run(move |db| api::export_day(db, day_start)).await
+    }
+
+    /// Async version of [`api::import_day`].
+    pub async fn import_day(&self, export: api::DayExport) -> Result<usize, SynapseError> {
+        self.run(move |db| api::import_day(db, &export)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use uuid::Uuid;
+
+    fn temp_db() -> (AsyncDbHandle, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("synapse_async_{}.db", Uuid::new_v4()));
+        (AsyncDbHandle::open(path.to_str().unwrap()), path)
+    }
+
+    #[tokio::test]
+    async fn queries_run_against_the_database() {
+        let (db, path) = temp_db();
+        db.run(|db| {
+            db.execute_sql(
+                "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES ('a', strftime('%s','now'), strftime('%s','now'), 'code', 3)",
+                &[],
+            )?;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(db.total_focus_sessions_today().await.unwrap(), 1);
+        assert_eq!(db.total_distractions_today().await.unwrap(), 3);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_callers_do_not_block_each_other() {
+        let (db, path) = temp_db();
+        // Create the schema up front so the callers below only read
+        db.total_focus_sessions_today().await.unwrap();
+
+        let started = Instant::now();
+        let calls: Vec<_> = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    db.run(|db| {
+                        std::thread::sleep(Duration::from_millis(300));
+                        crate::api::total_focus_sessions_today(db)
+                    })
+                    .await
+                })
+            })
+            .collect();
+        // The runtime stays responsive while the DB calls are in flight
+        let tick = Instant::now();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(tick.elapsed() < Duration::from_millis(250));
+        for call in calls {
+            assert_eq!(call.await.unwrap().unwrap(), 0);
+        }
+        // Four 300ms calls ran in parallel rather than back to back
+        assert!(started.elapsed() < Duration::from_millis(1000));
+        let _ = std::fs::remove_file(path);
+    }
+}
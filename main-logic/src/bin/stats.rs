@@ -3,7 +3,8 @@ use dotenvy;
 
 fn main() {
     dotenvy::from_filename("../src-tauri/.env").ok();
-    let db = match DbHandle::new() {
+    // Only reads, so it never blocks the backend's writes
+    let db = match DbHandle::open_readonly(&DbHandle::path_from_env()) {
         Ok(db) => db,
         Err(e) => {
             eprintln!("Failed to open DB: {:?}", e);
@@ -0,0 +1,1556 @@
+//! Config module: runtime settings resolved from `synapse.toml` and environment variables,
+//! falling back to defaults. Environment variables override the file.
+
+use crate::constants::{
+    DEFAULT_HTTP_API_PORT, DEFAULT_LIVE_SOCKET_PORT, DEFAULT_LOG_KEEP_COUNT, DEFAULT_LOG_PATH, DEFAULT_MAX_WORK_APPS,
+    DEFAULT_MIN_SLEEP_SECS, DEFAULT_POPUP_SUPPRESSION_SECS, DEFAULT_SYNC_FAILURE_THRESHOLD, DEFAULT_SYNC_MAX_CONCURRENCY,
+    MAIN_LOOP_SLEEP_MS,
+};
+use crate::error::SynapseError;
+use crate::types::GoalKey;
+use chrono::{Datelike, NaiveDate, Weekday};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{BuildHasher, Hasher};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Whether distractions interrupt the user or are only recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnforcementMode {
+    /// Log distractions and show the distraction popup.
+    #[default]
+    Enforce,
+    /// Log distractions and update counters, but never show a popup.
+    Observe,
+}
+
+impl FromStr for EnforcementMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "enforce" => Ok(Self::Enforce),
+            "observe" => Ok(Self::Observe),
+            other => Err(format!("expected 'enforce' or 'observe', got '{}'", other)),
+        }
+    }
+}
+
+/// Which rule applies to an app listed in both the whitelist and the blacklist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPrecedence {
+    /// The app is a distraction: it is blocked and does not count as a work app.
+    #[default]
+    BlacklistWins,
+    /// The app is work: it can start a session and is never blocked.
+    WhitelistWins,
+}
+
+impl FromStr for OverlapPrecedence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "blacklist_wins" => Ok(Self::BlacklistWins),
+            "whitelist_wins" => Ok(Self::WhitelistWins),
+            other => Err(format!(
+                "expected 'blacklist_wins' or 'whitelist_wins', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// What to do with a foreground app that has no installed-apps match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownAppPolicy {
+    /// Treat the app as if nothing were in the foreground.
+    Ignore,
+    /// Track the app under its executable name.
+    #[default]
+    UseRawName,
+    /// Track the app under its executable name and flag it as a distraction.
+    TreatAsDistraction,
+}
+
+impl FromStr for UnknownAppPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "use_raw_name" => Ok(Self::UseRawName),
+            "treat_as_distraction" => Ok(Self::TreatAsDistraction),
+            other => Err(format!(
+                "expected 'ignore', 'use_raw_name' or 'treat_as_distraction', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// What happens to a running session when the system's UTC offset changes between polls,
+/// e.g. after flying across timezones or at a daylight-saving switch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimezoneChangePolicy {
+    /// End the session at the change and start a new one, so every session lies within a
+    /// single offset and its local-day attribution is unambiguous.
+    #[default]
+    SplitSession,
+    /// Keep the session running; it stays anchored to the day it started in.
+    KeepSession,
+}
+
+impl FromStr for TimezoneChangePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "split_session" => Ok(Self::SplitSession),
+            "keep_session" => Ok(Self::KeepSession),
+            other => Err(format!(
+                "expected 'split_session' or 'keep_session', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// What happens to a running session when the system sleeps, e.g. a laptop lid closed
+/// mid-session and opened hours later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SleepPolicy {
+    /// End the session when the system went to sleep and start a new one at wake.
+    #[default]
+    SplitSession,
+    /// End the session when the system went to sleep; the next one starts once a work app
+    /// is in the foreground again.
+    EndSession,
+    /// Keep the session running, so the time asleep counts towards it.
+    KeepSession,
+}
+
+impl FromStr for SleepPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "split_session" => Ok(Self::SplitSession),
+            "end_session" => Ok(Self::EndSession),
+            "keep_session" => Ok(Self::KeepSession),
+            other => Err(format!(
+                "expected 'split_session', 'end_session' or 'keep_session', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// Whether Synapse may terminate blocked apps. Off unless explicitly enabled, since a
+/// killed app loses any unsaved work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillBlockedApps {
+    /// Never terminate apps; the popup's close action is refused.
+    #[default]
+    Never,
+    /// Terminate a blocked app when the user picks close in the distraction popup.
+    OnRequest,
+    /// Also terminate blocked apps as soon as they are detected during a session.
+    OnDetection,
+}
+
+impl FromStr for KillBlockedApps {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "never" => Ok(Self::Never),
+            "on_request" => Ok(Self::OnRequest),
+            "on_detection" => Ok(Self::OnDetection),
+            other => Err(format!(
+                "expected 'never', 'on_request' or 'on_detection', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// Sound played when a distraction popup is shown.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PopupSound {
+    /// No sound of its own. The native Windows message box still uses its usual sound.
+    #[default]
+    None,
+    /// The platform's standard warning sound.
+    System,
+    /// A sound file, e.g. a `.wav`.
+    Custom(PathBuf),
+}
+
+impl PopupSound {
+    /// Returns the sound to actually play: `None` when silent, and the system sound in
+    /// place of a custom file that does not exist.
+    pub fn resolved(&self) -> Option<PopupSound> {
+        match self {
+            Self::None => None,
+            Self::System => Some(Self::System),
+            Self::Custom(path) if path.is_file() => Some(self.clone()),
+            Self::Custom(path) => {
+                eprintln!(
+                    "Popup sound {} not found - using the system sound.",
+                    path.display()
+                );
+                Some(Self::System)
+            }
+        }
+    }
+}
+
+impl FromStr for PopupSound {
+    type Err = String;
+
+    /// Parses `none`, `system`, or any other value as the path of a sound file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("expected 'none', 'system' or a sound file path".to_string()),
+            value if value.eq_ignore_ascii_case("none") => Ok(Self::None),
+            value if value.eq_ignore_ascii_case("system") => Ok(Self::System),
+            path => Ok(Self::Custom(PathBuf::from(path))),
+        }
+    }
+}
+
+impl TryFrom<String> for PopupSound {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PopupSound> for String {
+    fn from(sound: PopupSound) -> Self {
+        match sound {
+            PopupSound::None => "none".to_string(),
+            PopupSound::System => "system".to_string(),
+            PopupSound::Custom(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// Which identifier app usage is stored under, so an app's history stays together even if
+/// its display name changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageKey {
+    /// The lowercased executable file name, without any directory.
+    #[default]
+    ExecutableName,
+    /// The process name exactly as reported, including any directory.
+    FullPath,
+}
+
+impl UsageKey {
+    /// Returns the key `process_name` is stored under.
+    pub fn key_for(&self, process_name: &str) -> String {
+        let name = process_name.trim().trim_matches('"');
+        match self {
+            UsageKey::ExecutableName => name
+                .rsplit(['/', '\\'])
+                .next()
+                .unwrap_or(name)
+                .to_lowercase(),
+            UsageKey::FullPath => name.to_string(),
+        }
+    }
+}
+
+impl FromStr for UsageKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "executable_name" => Ok(Self::ExecutableName),
+            "full_path" => Ok(Self::FullPath),
+            other => Err(format!(
+                "expected 'executable_name' or 'full_path', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// Days that are not expected to have focus time: recurring weekdays plus specific holidays.
+/// Streaks and goal history step over them instead of counting them as misses.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(try_from = "Vec<String>")]
+pub struct NonWorkingDays {
+    pub weekdays: Vec<Weekday>,
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl NonWorkingDays {
+    /// Returns true if `date` falls on a non-working weekday or is a holiday.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.weekdays.contains(&date.weekday()) || self.holidays.contains(&date)
+    }
+}
+
+impl TryFrom<Vec<String>> for NonWorkingDays {
+    type Error = String;
+
+    /// Parses weekday names (`"sat"`, `"sunday"`) and `YYYY-MM-DD` holidays.
+    fn try_from(entries: Vec<String>) -> Result<Self, Self::Error> {
+        let mut days = Self::default();
+        for entry in entries {
+            match entry.parse::<GoalKey>()? {
+                GoalKey::Weekday(weekday) => days.weekdays.push(weekday),
+                GoalKey::Date(date) => days.holidays.push(date),
+            }
+        }
+        Ok(days)
+    }
+}
+
+impl FromStr for NonWorkingDays {
+    type Err = String;
+
+    /// Parses a comma-separated list of weekdays and `YYYY-MM-DD` holidays.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+            .try_into()
+    }
+}
+
+/// Hours worked per day, used to scale the daily focus goal on shorter days. `default` is a
+/// full working day; weekday names (`"fri"`) and `YYYY-MM-DD` dates override it.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(try_from = "BTreeMap<String, f64>")]
+pub struct WorkingHours(BTreeMap<String, f64>);
+
+impl WorkingHours {
+    /// Returns the hours worked on `date`: its own entry, else its weekday's, else `default`.
+    pub fn hours_on(&self, date: NaiveDate) -> Option<f64> {
+        self.0
+            .get(&GoalKey::Date(date).storage_key())
+            .or_else(|| self.0.get(&GoalKey::Weekday(date.weekday()).storage_key()))
+            .or_else(|| self.0.get("default"))
+            .copied()
+    }
+
+    /// Returns the share of a full working day worked on `date`, or `1.0` if either is not
+    /// configured.
+    pub fn day_fraction(&self, date: NaiveDate) -> f64 {
+        match (self.hours_on(date), self.0.get("default")) {
+            (Some(hours), Some(&full_day)) if full_day > 0.0 => hours / full_day,
+            _ => 1.0,
+        }
+    }
+}
+
+impl TryFrom<BTreeMap<String, f64>> for WorkingHours {
+    type Error = String;
+
+    /// Accepts `default`, weekday and `YYYY-MM-DD` keys with 0 to 24 hours each.
+    fn try_from(entries: BTreeMap<String, f64>) -> Result<Self, Self::Error> {
+        let mut hours = BTreeMap::new();
+        for (key, value) in entries {
+            let key = key.trim().to_lowercase();
+            let key = if key == "default" {
+                key
+            } else {
+                key.parse::<GoalKey>()?.storage_key()
+            };
+            if !(0.0..=24.0).contains(&value) {
+                return Err(format!("working hours for '{}' must be 0 to 24, got {}", key, value));
+            }
+            hours.insert(key, value);
+        }
+        Ok(Self(hours))
+    }
+}
+
+impl FromStr for WorkingHours {
+    type Err = String;
+
+    /// Parses a comma-separated list of `day=hours` entries.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = BTreeMap::new();
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (day, hours) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected day=hours, got '{}'", entry))?;
+            let hours = hours
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| format!("invalid hours for '{}': {}", day.trim(), e))?;
+            entries.insert(day.to_string(), hours);
+        }
+        entries.try_into()
+    }
+}
+
+/// Daily time budgets per app category, in seconds. Categories are matched case-insensitively.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(from = "BTreeMap<String, u64>")]
+pub struct CategoryBudgets(BTreeMap<String, u64>);
+
+impl CategoryBudgets {
+    /// Returns the budget for `category` in seconds, if it has one.
+    pub fn get(&self, category: &str) -> Option<u64> {
+        self.0.get(&category.trim().to_lowercase()).copied()
+    }
+
+    /// Iterates over the budgeted categories and their budgets, sorted by category.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.0.iter().map(|(category, secs)| (category.as_str(), *secs))
+    }
+}
+
+impl From<BTreeMap<String, u64>> for CategoryBudgets {
+    fn from(budgets: BTreeMap<String, u64>) -> Self {
+        Self(
+            budgets
+                .into_iter()
+                .map(|(category, secs)| (category.trim().to_lowercase(), secs))
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for CategoryBudgets {
+    type Err = String;
+
+    /// Parses a comma-separated list of `category=seconds` entries.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut budgets = BTreeMap::new();
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (category, secs) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected category=seconds, got '{}'", entry))?;
+            let secs = secs
+                .trim()
+                .parse::<u64>()
+                .map_err(|e| format!("invalid budget for '{}': {}", category.trim(), e))?;
+            budgets.insert(category.to_string(), secs);
+        }
+        Ok(budgets.into())
+    }
+}
+
+/// Optional data uploaded when syncing. Session ids and start/end times are always sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncField {
+    /// The session's work apps.
+    WorkApps,
+    /// The session's distraction count.
+    DistractionAttempts,
+    /// The project taken from window titles.
+    Project,
+    /// Per-app usage events.
+    AppUsageEvents,
+}
+
+impl SyncField {
+    /// Every optional field, in upload order.
+    pub const ALL: [SyncField; 4] = [
+        SyncField::WorkApps,
+        SyncField::DistractionAttempts,
+        SyncField::Project,
+        SyncField::AppUsageEvents,
+    ];
+}
+
+impl FromStr for SyncField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "work_apps" => Ok(Self::WorkApps),
+            "distraction_attempts" => Ok(Self::DistractionAttempts),
+            "project" => Ok(Self::Project),
+            "app_usage_events" => Ok(Self::AppUsageEvents),
+            other => Err(format!(
+                "expected 'work_apps', 'distraction_attempts', 'project' or 'app_usage_events', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// The optional fields uploaded when syncing. Defaults to all of them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "Vec<SyncField>")]
+pub struct SyncFields(BTreeSet<SyncField>);
+
+impl SyncFields {
+    /// Returns true if `field` is uploaded.
+    pub fn includes(&self, field: SyncField) -> bool {
+        self.0.contains(&field)
+    }
+}
+
+impl Default for SyncFields {
+    fn default() -> Self {
+        Self(SyncField::ALL.into_iter().collect())
+    }
+}
+
+impl From<Vec<SyncField>> for SyncFields {
+    fn from(fields: Vec<SyncField>) -> Self {
+        Self(fields.into_iter().collect())
+    }
+}
+
+impl FromStr for SyncFields {
+    type Err = String;
+
+    /// Parses a comma-separated list of fields; `none` uploads timing only.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("none") {
+            return Ok(Self(BTreeSet::new()));
+        }
+        s.split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(SyncField::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self::from)
+    }
+}
+
+/// Runtime settings for the backend loop and session manager.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Config {
+    /// Base sleep between polls, in milliseconds (`SYNAPSE_POLL_INTERVAL_MS`).
+    pub poll_interval_ms: u64,
+    /// Maximum random deviation applied to the poll interval, as a percentage
+    /// of the base interval (`SYNAPSE_POLL_JITTER_PCT`). `0` disables jitter.
+    pub poll_jitter_pct: u8,
+    /// Whether blocked apps trigger popups (`SYNAPSE_ENFORCEMENT_MODE`).
+    pub enforcement_mode: EnforcementMode,
+    /// Seconds after a session ends during which a returning work app resumes it
+    /// (`SYNAPSE_SESSION_RESUME_SECS`). `0` disables resuming.
+    pub session_resume_secs: u64,
+    /// Sessions running longer than this many seconds are split into a new session
+    /// (`SYNAPSE_MAX_SESSION_SECS`). `0` disables splitting.
+    pub max_session_secs: u64,
+    /// Process name of the Synapse app itself, ignored as a foreground app
+    /// (`SYNAPSE_SELF_PROCESS_NAME`). `None` uses the running executable's name.
+    pub self_process_name: Option<String>,
+    /// Maximum number of Supabase sync requests in flight at once
+    /// (`SYNAPSE_SYNC_MAX_CONCURRENCY`). Applied at startup only.
+    pub sync_max_concurrency: usize,
+    /// Consecutive sync failures after which syncing pauses for a growing cooldown
+    /// (`SYNAPSE_SYNC_FAILURE_THRESHOLD`). `0` never pauses. Applied at startup only.
+    pub sync_failure_threshold: u32,
+    /// Optional data uploaded when syncing (`SYNAPSE_SYNC_FIELDS`), e.g. `work_apps,project`;
+    /// session timing is always sent. Applied at startup only.
+    pub sync_fields: SyncFields,
+    /// Which rule wins for an app in both the whitelist and the blacklist
+    /// (`SYNAPSE_OVERLAP_PRECEDENCE`).
+    pub overlap_precedence: OverlapPrecedence,
+    /// How foreground apps missing from the installed-apps list are handled
+    /// (`SYNAPSE_UNKNOWN_APP_POLICY`).
+    pub unknown_app_policy: UnknownAppPolicy,
+    /// Maximum distinct work apps stored per session; the ones with the least focus time
+    /// are dropped beyond it (`SYNAPSE_MAX_WORK_APPS`).
+    pub max_work_apps: usize,
+    /// Seconds after a popup during which the same blocked app does not trigger another
+    /// (`SYNAPSE_POPUP_SUPPRESSION_SECS`). `0` shows a popup on every new visit.
+    pub popup_suppression_secs: u64,
+    /// Most distraction popups shown per local day (`SYNAPSE_MAX_POPUPS_PER_DAY`); later
+    /// distractions that day are still recorded. `0` means no cap.
+    pub max_popups_per_day: u32,
+    /// Minimum share of a session, in percent, a work app must spend in the foreground to
+    /// stay in the session's work apps (`SYNAPSE_MIN_WORK_APP_FOCUS_PCT`). `0` keeps every
+    /// work app that was running.
+    pub min_work_app_focus_pct: u8,
+    /// Weekdays and holidays skipped by streaks and goal history
+    /// (`SYNAPSE_NON_WORKING_DAYS`, e.g. `sat,sun,2024-12-25`).
+    pub non_working_days: NonWorkingDays,
+    /// Which identifier app usage is stored under (`SYNAPSE_USAGE_KEY`).
+    pub usage_key: UsageKey,
+    /// Address the live event socket listens on when built with the `live-socket` feature
+    /// (`SYNAPSE_LIVE_SOCKET_ADDR`). Loopback by default. Applied at startup only.
+    pub live_socket_addr: SocketAddr,
+    /// Regex extracting the project from a work app's window title
+    /// (`SYNAPSE_PROJECT_TITLE_PATTERN`). The first capture group is the project, or the whole
+    /// match if there is none. When set, switching projects ends the session and starts another.
+    pub project_title_pattern: Option<String>,
+    /// Address the HTTP API listens on when the headless backend is built with the
+    /// `http-api` feature (`SYNAPSE_HTTP_API_ADDR`). Loopback by default. Applied at startup only.
+    pub http_api_addr: SocketAddr,
+    /// Log file errors, app usage and `tracing` events are written to (`SYNAPSE_LOG_PATH`).
+    /// Applied at startup only.
+    pub log_path: PathBuf,
+    /// Rotated `synapse.log.N` archives kept; older ones are deleted when the log rotates
+    /// (`SYNAPSE_LOG_KEEP_COUNT`). `0` keeps none. Applied at startup only.
+    pub log_keep_count: usize,
+    /// What happens to a running session when the system timezone changes
+    /// (`SYNAPSE_TIMEZONE_CHANGE_POLICY`).
+    pub timezone_change_policy: TimezoneChangePolicy,
+    /// What happens to a running session when the system sleeps (`SYNAPSE_SLEEP_POLICY`).
+    pub sleep_policy: SleepPolicy,
+    /// Shortest system sleep, in seconds, that `sleep_policy` applies to
+    /// (`SYNAPSE_MIN_SLEEP_SECS`). `0` ignores sleeps entirely.
+    pub min_sleep_secs: u64,
+    /// Seconds without a focus session after which a reminder is shown, once per stretch
+    /// (`SYNAPSE_FOCUS_REMINDER_SECS`). `0` disables reminders.
+    pub focus_reminder_secs: u64,
+    /// Daily focus-time budget per app category, in seconds (`SYNAPSE_CATEGORY_BUDGETS`,
+    /// e.g. `social=3600,games=1800`). Apps get their category from `apprules.json`.
+    pub category_budgets: CategoryBudgets,
+    /// Treat apps in a category that is over today's budget as distractions during sessions
+    /// (`SYNAPSE_BLOCK_OVER_BUDGET_CATEGORIES`). Whitelisted apps are never escalated.
+    pub block_over_budget_categories: bool,
+    /// Sound played with each distraction popup (`SYNAPSE_POPUP_SOUND`): `none`, `system`
+    /// or the path of a sound file. A missing file falls back to the system sound.
+    pub popup_sound: PopupSound,
+    /// Whether blocked apps may be terminated (`SYNAPSE_KILL_BLOCKED_APPS`): `never`,
+    /// `on_request` from the popup, or `on_detection`.
+    pub kill_blocked_apps: KillBlockedApps,
+    /// Shell command run when a focus session starts (`SYNAPSE_ON_SESSION_START_CMD`), with
+    /// the session's details in `SYNAPSE_*` environment variables. Applied at startup only.
+    pub on_session_start_cmd: Option<String>,
+    /// Shell command run when a focus session ends (`SYNAPSE_ON_SESSION_END_CMD`). Applied at
+    /// startup only.
+    pub on_session_end_cmd: Option<String>,
+    /// Whether blocked apps opened while no session runs are counted as distractions
+    /// (`SYNAPSE_COUNT_DISTRACTIONS_OUTSIDE_SESSIONS`).
+    pub count_distractions_outside_sessions: bool,
+    /// Whether every foreground app that is neither whitelisted nor neutral counts as a
+    /// distraction, instead of only blacklisted ones (`SYNAPSE_STRICT_MODE`).
+    pub strict_mode: bool,
+    /// Hours worked per day (`SYNAPSE_WORKING_HOURS`, e.g. `default=8,fri=4`); the daily
+    /// goal is scaled by each day's share of a `default` day.
+    pub working_hours: WorkingHours,
+    /// Sessions end once this many distractions are counted
+    /// (`SYNAPSE_MAX_DISTRACTIONS_PER_SESSION`). `0` disables the limit.
+    pub max_distractions_per_session: u32,
+    /// Whether usage events also store the process name exactly as the OS reported it,
+    /// before aliases and helper folding (`SYNAPSE_STORE_RAW_PROCESS_NAMES`).
+    pub store_raw_process_names: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: MAIN_LOOP_SLEEP_MS,
+            poll_jitter_pct: 0,
+            enforcement_mode: EnforcementMode::default(),
+            session_resume_secs: 0,
+            max_session_secs: 0,
+            self_process_name: None,
+            sync_max_concurrency: DEFAULT_SYNC_MAX_CONCURRENCY,
+            sync_failure_threshold: DEFAULT_SYNC_FAILURE_THRESHOLD,
+            sync_fields: SyncFields::default(),
+            overlap_precedence: OverlapPrecedence::default(),
+            unknown_app_policy: UnknownAppPolicy::default(),
+            max_work_apps: DEFAULT_MAX_WORK_APPS,
+            popup_suppression_secs: DEFAULT_POPUP_SUPPRESSION_SECS,
+            max_popups_per_day: 0,
+            min_work_app_focus_pct: 0,
+            non_working_days: NonWorkingDays::default(),
+            usage_key: UsageKey::default(),
+            live_socket_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_LIVE_SOCKET_PORT)),
+            project_title_pattern: None,
+            http_api_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_HTTP_API_PORT)),
+            log_path: PathBuf::from(DEFAULT_LOG_PATH),
+            log_keep_count: DEFAULT_LOG_KEEP_COUNT,
+            timezone_change_policy: TimezoneChangePolicy::default(),
+            sleep_policy: SleepPolicy::default(),
+            min_sleep_secs: DEFAULT_MIN_SLEEP_SECS,
+            focus_reminder_secs: 0,
+            category_budgets: CategoryBudgets::default(),
+            block_over_budget_categories: false,
+            popup_sound: PopupSound::default(),
+            kill_blocked_apps: KillBlockedApps::default(),
+            on_session_start_cmd: None,
+            on_session_end_cmd: None,
+            count_distractions_outside_sessions: false,
+            strict_mode: false,
+            working_hours: WorkingHours::default(),
+            max_distractions_per_session: 0,
+            store_raw_process_names: true,
+        }
+    }
+}
+
+/// Where the value of an effective setting came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+}
+
+/// One setting as resolved from defaults, `synapse.toml` and environment variables.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveSetting {
+    /// The `synapse.toml` key; the variable is `SYNAPSE_` plus the key in upper case.
+    pub key: String,
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+}
+
+/// Settings as written in `synapse.toml`; every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    poll_interval_ms: Option<u64>,
+    poll_jitter_pct: Option<u8>,
+    enforcement_mode: Option<EnforcementMode>,
+    session_resume_secs: Option<u64>,
+    max_session_secs: Option<u64>,
+    self_process_name: Option<String>,
+    sync_max_concurrency: Option<usize>,
+    sync_failure_threshold: Option<u32>,
+    sync_fields: Option<SyncFields>,
+    overlap_precedence: Option<OverlapPrecedence>,
+    unknown_app_policy: Option<UnknownAppPolicy>,
+    max_work_apps: Option<usize>,
+    popup_suppression_secs: Option<u64>,
+    max_popups_per_day: Option<u32>,
+    min_work_app_focus_pct: Option<u8>,
+    non_working_days: Option<NonWorkingDays>,
+    usage_key: Option<UsageKey>,
+    live_socket_addr: Option<SocketAddr>,
+    project_title_pattern: Option<String>,
+    http_api_addr: Option<SocketAddr>,
+    log_path: Option<PathBuf>,
+    log_keep_count: Option<usize>,
+    timezone_change_policy: Option<TimezoneChangePolicy>,
+    sleep_policy: Option<SleepPolicy>,
+    min_sleep_secs: Option<u64>,
+    focus_reminder_secs: Option<u64>,
+    category_budgets: Option<CategoryBudgets>,
+    block_over_budget_categories: Option<bool>,
+    popup_sound: Option<PopupSound>,
+    kill_blocked_apps: Option<KillBlockedApps>,
+    on_session_start_cmd: Option<String>,
+    on_session_end_cmd: Option<String>,
+    count_distractions_outside_sessions: Option<bool>,
+    strict_mode: Option<bool>,
+    working_hours: Option<WorkingHours>,
+    max_distractions_per_session: Option<u32>,
+    store_raw_process_names: Option<bool>,
+}
+
+impl Config {
+    /// Loads the config from `synapse.toml` (if present) and environment variables.
+    ///
+    /// The file is read from `SYNAPSE_CONFIG_PATH`, defaulting to `synapse.toml`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the file or a variable cannot be parsed or is out of range.
+    pub fn load() -> Result<Self, SynapseError> {
+        let contents = Self::read_file()?;
+        Self::from_sources(contents.as_deref(), |key| std::env::var(key).ok())
+    }
+
+    /// Reads the config file's contents, or `None` if there is no file.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the file exists but cannot be read.
+    pub fn read_file() -> Result<Option<String>, SynapseError> {
+        match std::fs::read_to_string(Self::file_path()) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SynapseError::Config(format!(
+                "Failed to read {}: {}",
+                Self::file_path(),
+                e
+            ))),
+        }
+    }
+
+    /// Returns the config file path from `SYNAPSE_CONFIG_PATH`, or `synapse.toml`.
+    pub fn file_path() -> String {
+        std::env::var("SYNAPSE_CONFIG_PATH").unwrap_or_else(|_| "synapse.toml".to_string())
+    }
+
+    /// Loads the config from environment variables, using defaults for unset values.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if a variable is set but cannot be parsed.
+    pub fn from_env() -> Result<Self, SynapseError> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Loads the config using `lookup` to resolve variable names (for tests and embedding).
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if a variable is set but cannot be parsed.
+    pub fn from_lookup<F>(lookup: F) -> Result<Self, SynapseError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Self::from_sources(None, lookup)
+    }
+
+    /// Builds the config from optional `synapse.toml` contents, then applies variables
+    /// resolved through `lookup` on top.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the file or a variable cannot be parsed or is out of range.
+    pub fn from_sources<F>(file_contents: Option<&str>, lookup: F) -> Result<Self, SynapseError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut config = Self::default();
+        if let Some(contents) = file_contents {
+            let file: ConfigFile = toml::from_str(contents).map_err(|e| {
+                SynapseError::Config(format!("Invalid {}: {}", Self::file_path(), e))
+            })?;
+            config.apply_file(file);
+        }
+        config.apply_env(&lookup)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Resolves the config like [`from_sources`](Self::from_sources) and reports every
+    /// setting's value in effect with where it came from. A variable wins over the file,
+    /// which wins over the default.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the file or a variable cannot be parsed or is out of range.
+    pub fn effective_from_sources<F>(
+        file_contents: Option<&str>,
+        lookup: F,
+    ) -> Result<Vec<EffectiveSetting>, SynapseError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let config = Self::from_sources(file_contents, &lookup)?;
+        let file_keys: toml::Table = match file_contents {
+            Some(contents) => toml::from_str(contents).map_err(|e| {
+                SynapseError::Config(format!("Invalid {}: {}", Self::file_path(), e))
+            })?,
+            None => toml::Table::new(),
+        };
+        let serde_json::Value::Object(values) = serde_json::to_value(&config)? else {
+            return Err(SynapseError::Config("Config did not serialize to a map".to_string()));
+        };
+        Ok(values
+            .into_iter()
+            .map(|(key, value)| {
+                let source = if lookup(&format!("SYNAPSE_{}", key.to_uppercase())).is_some() {
+                    ConfigSource::Env
+                } else if file_keys.contains_key(&key) {
+                    ConfigSource::File
+                } else {
+                    ConfigSource::Default
+                };
+                EffectiveSetting { key, value, source }
+            })
+            .collect())
+    }
+
+    fn apply_file(&mut self, file: ConfigFile) {
+        if let Some(v) = file.poll_interval_ms {
+            self.poll_interval_ms = v;
+        }
+        if let Some(v) = file.poll_jitter_pct {
+            self.poll_jitter_pct = v;
+        }
+        if let Some(v) = file.enforcement_mode {
+            self.enforcement_mode = v;
+        }
+        if let Some(v) = file.session_resume_secs {
+            self.session_resume_secs = v;
+        }
+        if let Some(v) = file.max_session_secs {
+            self.max_session_secs = v;
+        }
+        if let Some(v) = file.self_process_name {
+            self.self_process_name = Some(v);
+        }
+        if let Some(v) = file.sync_max_concurrency {
+            self.sync_max_concurrency = v;
+        }
+        if let Some(v) = file.sync_failure_threshold {
+            self.sync_failure_threshold = v;
+        }
+        if let Some(v) = file.sync_fields {
+            self.sync_fields = v;
+        }
+        if let Some(v) = file.overlap_precedence {
+            self.overlap_precedence = v;
+        }
+        if let Some(v) = file.unknown_app_policy {
+            self.unknown_app_policy = v;
+        }
+        if let Some(v) = file.max_work_apps {
+            self.max_work_apps = v;
+        }
+        if let Some(v) = file.popup_suppression_secs {
+            self.popup_suppression_secs = v;
+        }
+        if let Some(v) = file.max_popups_per_day {
+            self.max_popups_per_day = v;
+        }
+        if let Some(v) = file.min_work_app_focus_pct {
+            self.min_work_app_focus_pct = v;
+        }
+        if let Some(v) = file.non_working_days {
+            self.non_working_days = v;
+        }
+        if let Some(v) = file.usage_key {
+            self.usage_key = v;
+        }
+        if let Some(v) = file.live_socket_addr {
+            self.live_socket_addr = v;
+        }
+        if let Some(v) = file.project_title_pattern {
+            self.project_title_pattern = Some(v);
+        }
+        if let Some(v) = file.http_api_addr {
+            self.http_api_addr = v;
+        }
+        if let Some(v) = file.log_path {
+            self.log_path = v;
+        }
+        if let Some(v) = file.log_keep_count {
+            self.log_keep_count = v;
+        }
+        if let Some(v) = file.timezone_change_policy {
+            self.timezone_change_policy = v;
+        }
+        if let Some(v) = file.sleep_policy {
+            self.sleep_policy = v;
+        }
+        if let Some(v) = file.min_sleep_secs {
+            self.min_sleep_secs = v;
+        }
+        if let Some(v) = file.focus_reminder_secs {
+            self.focus_reminder_secs = v;
+        }
+        if let Some(v) = file.category_budgets {
+            self.category_budgets = v;
+        }
+        if let Some(v) = file.block_over_budget_categories {
+            self.block_over_budget_categories = v;
+        }
+        if let Some(v) = file.popup_sound {
+            self.popup_sound = v;
+        }
+        if let Some(v) = file.kill_blocked_apps {
+            self.kill_blocked_apps = v;
+        }
+        if let Some(v) = file.on_session_start_cmd {
+            self.on_session_start_cmd = Some(v);
+        }
+        if let Some(v) = file.on_session_end_cmd {
+            self.on_session_end_cmd = Some(v);
+        }
+        if let Some(v) = file.count_distractions_outside_sessions {
+            self.count_distractions_outside_sessions = v;
+        }
+        if let Some(v) = file.strict_mode {
+            self.strict_mode = v;
+        }
+        if let Some(v) = file.working_hours {
+            self.working_hours = v;
+        }
+        if let Some(v) = file.max_distractions_per_session {
+            self.max_distractions_per_session = v;
+        }
+        if let Some(v) = file.store_raw_process_names {
+            self.store_raw_process_names = v;
+        }
+    }
+
+    fn validate(&self) -> Result<(), SynapseError> {
+        if self.poll_jitter_pct > 100 {
+            return Err(SynapseError::Config(format!(
+                "poll_jitter_pct (SYNAPSE_POLL_JITTER_PCT) must be between 0 and 100, got {}",
+                self.poll_jitter_pct
+            )));
+        }
+        if self.min_work_app_focus_pct > 100 {
+            return Err(SynapseError::Config(format!(
+                "min_work_app_focus_pct (SYNAPSE_MIN_WORK_APP_FOCUS_PCT) must be between 0 and 100, got {}",
+                self.min_work_app_focus_pct
+            )));
+        }
+        if self.poll_interval_ms == 0 {
+            return Err(SynapseError::Config(
+                "poll_interval_ms (SYNAPSE_POLL_INTERVAL_MS) must be greater than 0".to_string(),
+            ));
+        }
+        if self.sync_max_concurrency == 0 {
+            return Err(SynapseError::Config(
+                "sync_max_concurrency (SYNAPSE_SYNC_MAX_CONCURRENCY) must be greater than 0"
+                    .to_string(),
+            ));
+        }
+        if self.max_work_apps == 0 {
+            return Err(SynapseError::Config(
+                "max_work_apps (SYNAPSE_MAX_WORK_APPS) must be greater than 0".to_string(),
+            ));
+        }
+        if self.log_path.as_os_str().is_empty() {
+            return Err(SynapseError::Config(
+                "log_path (SYNAPSE_LOG_PATH) must not be empty".to_string(),
+            ));
+        }
+        self.project_title_regex()?;
+        Ok(())
+    }
+
+    /// Compiles `project_title_pattern`, if set.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the pattern is not a valid regex.
+    pub fn project_title_regex(&self) -> Result<Option<Regex>, SynapseError> {
+        self.project_title_pattern
+            .as_deref()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    SynapseError::Config(format!(
+                        "project_title_pattern (SYNAPSE_PROJECT_TITLE_PATTERN) is not a valid regex: {}",
+                        e
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    fn apply_env<F>(&mut self, lookup: &F) -> Result<(), SynapseError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if let Some(v) = parse_var(lookup, "SYNAPSE_POLL_INTERVAL_MS")? {
+            self.poll_interval_ms = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_POLL_JITTER_PCT")? {
+            self.poll_jitter_pct = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_ENFORCEMENT_MODE")? {
+            self.enforcement_mode = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_SESSION_RESUME_SECS")? {
+            self.session_resume_secs = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_MAX_SESSION_SECS")? {
+            self.max_session_secs = v;
+        }
+        if let Some(v) = lookup("SYNAPSE_SELF_PROCESS_NAME") {
+            self.self_process_name = Some(v.trim().to_string());
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_SYNC_MAX_CONCURRENCY")? {
+            self.sync_max_concurrency = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_SYNC_FAILURE_THRESHOLD")? {
+            self.sync_failure_threshold = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_SYNC_FIELDS")? {
+            self.sync_fields = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_OVERLAP_PRECEDENCE")? {
+            self.overlap_precedence = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_UNKNOWN_APP_POLICY")? {
+            self.unknown_app_policy = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_MAX_WORK_APPS")? {
+            self.max_work_apps = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_POPUP_SUPPRESSION_SECS")? {
+            self.popup_suppression_secs = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_MAX_POPUPS_PER_DAY")? {
+            self.max_popups_per_day = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_MIN_WORK_APP_FOCUS_PCT")? {
+            self.min_work_app_focus_pct = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_NON_WORKING_DAYS")? {
+            self.non_working_days = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_USAGE_KEY")? {
+            self.usage_key = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_LIVE_SOCKET_ADDR")? {
+            self.live_socket_addr = v;
+        }
+        if let Some(v) = lookup("SYNAPSE_PROJECT_TITLE_PATTERN") {
+            // Not trimmed: leading or trailing spaces can be part of the pattern
+            self.project_title_pattern = Some(v).filter(|p| !p.trim().is_empty());
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_HTTP_API_ADDR")? {
+            self.http_api_addr = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_LOG_PATH")? {
+            self.log_path = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_LOG_KEEP_COUNT")? {
+            self.log_keep_count = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_TIMEZONE_CHANGE_POLICY")? {
+            self.timezone_change_policy = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_SLEEP_POLICY")? {
+            self.sleep_policy = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_MIN_SLEEP_SECS")? {
+            self.min_sleep_secs = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_FOCUS_REMINDER_SECS")? {
+            self.focus_reminder_secs = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_CATEGORY_BUDGETS")? {
+            self.category_budgets = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_BLOCK_OVER_BUDGET_CATEGORIES")? {
+            self.block_over_budget_categories = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_POPUP_SOUND")? {
+            self.popup_sound = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_KILL_BLOCKED_APPS")? {
+            self.kill_blocked_apps = v;
+        }
+        if let Some(v) = lookup("SYNAPSE_ON_SESSION_START_CMD") {
+            self.on_session_start_cmd = Some(v.trim().to_string()).filter(|cmd| !cmd.is_empty());
+        }
+        if let Some(v) = lookup("SYNAPSE_ON_SESSION_END_CMD") {
+            self.on_session_end_cmd = Some(v.trim().to_string()).filter(|cmd| !cmd.is_empty());
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_COUNT_DISTRACTIONS_OUTSIDE_SESSIONS")? {
+            self.count_distractions_outside_sessions = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_STRICT_MODE")? {
+            self.strict_mode = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_WORKING_HOURS")? {
+            self.working_hours = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_MAX_DISTRACTIONS_PER_SESSION")? {
+            self.max_distractions_per_session = v;
+        }
+        if let Some(v) = parse_var(lookup, "SYNAPSE_STORE_RAW_PROCESS_NAMES")? {
+            self.store_raw_process_names = v;
+        }
+        Ok(())
+    }
+
+    /// Returns how long the main loop should sleep before the next poll, with jitter applied.
+    pub fn poll_sleep(&self) -> Duration {
+        if self.poll_jitter_pct == 0 {
+            return Duration::from_millis(self.poll_interval_ms);
+        }
+        jittered_interval(self.poll_interval_ms, self.poll_jitter_pct, random_unit())
+    }
+}
+
+/// Config shared between the backend loop and the config watcher, replaced atomically on reload.
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    inner: Arc<RwLock<Config>>,
+}
+
+impl LiveConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(config)),
+        }
+    }
+
+    /// Returns a snapshot of the current config.
+    pub fn get(&self) -> Config {
+        self.inner.read().unwrap().clone()
+    }
+
+    /// Reloads from `synapse.toml` and the environment. See [`LiveConfig::reload_from`].
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the new config is invalid; the old one is kept.
+    pub fn reload(&self) -> Result<Config, SynapseError> {
+        self.replace_with(Config::load())
+    }
+
+    /// Rebuilds the config from the given sources and swaps it in only if it is valid.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the new config is invalid; the old one is kept.
+    pub fn reload_from<F>(
+        &self,
+        file_contents: Option<&str>,
+        lookup: F,
+    ) -> Result<Config, SynapseError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        self.replace_with(Config::from_sources(file_contents, lookup))
+    }
+
+    fn replace_with(&self, loaded: Result<Config, SynapseError>) -> Result<Config, SynapseError> {
+        let config = loaded?;
+        *self.inner.write().unwrap() = config.clone();
+        Ok(config)
+    }
+}
+
+/// Watches the config file and reloads `live` whenever it changes, calling `on_reload`
+/// with each successfully applied config. Invalid edits are logged and ignored.
+///
+/// Returns `None` if there is no config file to watch.
+pub fn watch_config_file<F>(
+    live: LiveConfig,
+    shutdown_flag: Arc<AtomicBool>,
+    on_reload: F,
+) -> Option<JoinHandle<()>>
+where
+    F: Fn(&Config) + Send + 'static,
+{
+    let path_str = Config::file_path();
+    if !Path::new(&path_str).exists() {
+        return None;
+    }
+    Some(thread::spawn(move || {
+        let (tx, rx) = channel();
+        let path = Path::new(&path_str);
+        let mut watcher = match RecommendedWatcher::new(tx, notify::Config::default()) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("[ConfigWatcher] Failed to create watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(path, RecursiveMode::NonRecursive) {
+            eprintln!("[ConfigWatcher] Failed to watch {}: {}", path.display(), e);
+            return;
+        }
+        println!("[ConfigWatcher] Watching {}", path.display());
+        while !shutdown_flag.load(Ordering::SeqCst) {
+            if let Ok(Ok(Event {
+                kind: EventKind::Modify(_),
+                ..
+            })) = rx.recv_timeout(Duration::from_secs(1))
+            {
+                match live.reload() {
+                    Ok(config) => {
+                        println!("[ConfigWatcher] Config reloaded: {:?}", config);
+                        tracing::info!(config = ?config, "config reloaded");
+                        on_reload(&config);
+                    }
+                    Err(e) => {
+                        eprintln!("[ConfigWatcher] Keeping previous config: {}", e);
+                        tracing::warn!(error = %e, "config reload rejected");
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// Parses an optional variable, naming it in the error when the value is malformed.
+fn parse_var<T, F>(lookup: &F, key: &str) -> Result<Option<T>, SynapseError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+    F: Fn(&str) -> Option<String>,
+{
+    match lookup(key) {
+        Some(raw) => raw
+            .trim()
+            .parse::<T>()
+            .map(Some)
+            .map_err(|e| SynapseError::Config(format!("Invalid {} '{}': {}", key, raw, e))),
+        None => Ok(None),
+    }
+}
+
+/// Scales `base_ms` by up to `jitter_pct` percent in either direction.
+///
+/// `unit` is a sample in `[-1.0, 1.0]` selecting where in the jitter range the result falls.
+pub fn jittered_interval(base_ms: u64, jitter_pct: u8, unit: f64) -> Duration {
+    let unit = unit.clamp(-1.0, 1.0);
+    let spread = base_ms as f64 * f64::from(jitter_pct.min(100)) / 100.0;
+    let ms = (base_ms as f64 + spread * unit).max(0.0);
+    Duration::from_millis(ms.round() as u64)
+}
+
+/// Returns a cheap pseudo-random sample in `[-1.0, 1.0]`.
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() as f64 / u64::MAX as f64) * 2.0 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn defaults_disable_jitter() {
+        let config = Config::from_lookup(lookup(&[])).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.poll_jitter_pct, 0);
+        assert_eq!(config.poll_sleep(), Duration::from_millis(MAIN_LOOP_SLEEP_MS));
+    }
+
+    #[test]
+    fn reads_overrides_from_lookup() {
+        let config = Config::from_lookup(lookup(&[
+            ("SYNAPSE_POLL_INTERVAL_MS", "2000"),
+            ("SYNAPSE_POLL_JITTER_PCT", "25"),
+            ("SYNAPSE_SESSION_RESUME_SECS", "90"),
+            ("SYNAPSE_MAX_SESSION_SECS", "7200"),
+        ]))
+        .unwrap();
+        assert_eq!(config.poll_interval_ms, 2000);
+        assert_eq!(config.poll_jitter_pct, 25);
+        assert_eq!(config.session_resume_secs, 90);
+        assert_eq!(config.max_session_secs, 7200);
+    }
+
+    #[test]
+    fn parses_enforcement_mode() {
+        assert_eq!(
+            Config::from_lookup(lookup(&[])).unwrap().enforcement_mode,
+            EnforcementMode::Enforce
+        );
+        let config =
+            Config::from_lookup(lookup(&[("SYNAPSE_ENFORCEMENT_MODE", "Observe")])).unwrap();
+        assert_eq!(config.enforcement_mode, EnforcementMode::Observe);
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_ENFORCEMENT_MODE", "strict")]));
+        assert!(matches!(result, Err(SynapseError::Config(msg)) if msg.contains("SYNAPSE_ENFORCEMENT_MODE")));
+    }
+
+    #[test]
+    fn parses_overlap_precedence() {
+        assert_eq!(
+            Config::from_lookup(lookup(&[])).unwrap().overlap_precedence,
+            OverlapPrecedence::BlacklistWins
+        );
+        let config =
+            Config::from_lookup(lookup(&[("SYNAPSE_OVERLAP_PRECEDENCE", "whitelist_wins")]))
+                .unwrap();
+        assert_eq!(config.overlap_precedence, OverlapPrecedence::WhitelistWins);
+        let config =
+            Config::from_sources(Some("overlap_precedence = \"whitelist_wins\""), lookup(&[]))
+                .unwrap();
+        assert_eq!(config.overlap_precedence, OverlapPrecedence::WhitelistWins);
+        assert!(Config::from_lookup(lookup(&[("SYNAPSE_OVERLAP_PRECEDENCE", "both")])).is_err());
+    }
+
+    #[test]
+    fn parses_unknown_app_policy() {
+        assert_eq!(
+            Config::from_lookup(lookup(&[])).unwrap().unknown_app_policy,
+            UnknownAppPolicy::UseRawName
+        );
+        let config = Config::from_lookup(lookup(&[(
+            "SYNAPSE_UNKNOWN_APP_POLICY",
+            "treat_as_distraction",
+        )]))
+        .unwrap();
+        assert_eq!(config.unknown_app_policy, UnknownAppPolicy::TreatAsDistraction);
+        let config =
+            Config::from_sources(Some("unknown_app_policy = \"ignore\""), lookup(&[])).unwrap();
+        assert_eq!(config.unknown_app_policy, UnknownAppPolicy::Ignore);
+    }
+
+    #[test]
+    fn rejects_malformed_values() {
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_POLL_JITTER_PCT", "lots")]));
+        assert!(matches!(result, Err(SynapseError::Config(msg)) if msg.contains("SYNAPSE_POLL_JITTER_PCT")));
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_POLL_JITTER_PCT", "150")]));
+        assert!(matches!(result, Err(SynapseError::Config(_))));
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_SYNC_MAX_CONCURRENCY", "0")]));
+        assert!(matches!(result, Err(SynapseError::Config(_))));
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_MAX_WORK_APPS", "0")]));
+        assert!(matches!(result, Err(SynapseError::Config(_))));
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_MIN_WORK_APP_FOCUS_PCT", "101")]));
+        assert!(matches!(result, Err(SynapseError::Config(_))));
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_LIVE_SOCKET_ADDR", "localhost")]));
+        assert!(matches!(result, Err(SynapseError::Config(_))));
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_PROJECT_TITLE_PATTERN", "(unclosed")]));
+        assert!(matches!(result, Err(SynapseError::Config(_))));
+    }
+
+    #[test]
+    fn file_values_are_overridden_by_env() {
+        let file =
+            "poll_interval_ms = 1500\nenforcement_mode = \"observe\"\nmax_session_secs = 3600\n";
+        let config = Config::from_sources(Some(file), lookup(&[])).unwrap();
+        assert_eq!(config.poll_interval_ms, 1500);
+        assert_eq!(config.enforcement_mode, EnforcementMode::Observe);
+        assert_eq!(config.max_session_secs, 3600);
+
+        let config =
+            Config::from_sources(Some(file), lookup(&[("SYNAPSE_POLL_INTERVAL_MS", "900")]))
+                .unwrap();
+        assert_eq!(config.poll_interval_ms, 900);
+        assert!(Config::from_sources(Some("poll_interval = 5"), lookup(&[])).is_err());
+    }
+
+    #[test]
+    fn log_path_comes_from_file_or_env() {
+        let config = Config::from_lookup(lookup(&[])).unwrap();
+        assert_eq!(config.log_path, PathBuf::from("synapse.log"));
+        let file = "log_path = \"logs/focus.log\"\n";
+        let config = Config::from_sources(Some(file), lookup(&[])).unwrap();
+        assert_eq!(config.log_path, PathBuf::from("logs/focus.log"));
+        let config =
+            Config::from_sources(Some(file), lookup(&[("SYNAPSE_LOG_PATH", "/var/log/synapse.log")]))
+                .unwrap();
+        assert_eq!(config.log_path, PathBuf::from("/var/log/synapse.log"));
+        assert!(Config::from_lookup(lookup(&[("SYNAPSE_LOG_PATH", " ")])).is_err());
+    }
+
+    #[test]
+    fn effective_settings_report_their_source() {
+        let settings = Config::effective_from_sources(
+            Some("poll_interval_ms = 1500\nmax_session_secs = 3600\n"),
+            lookup(&[("SYNAPSE_POLL_INTERVAL_MS", "900"), ("SYNAPSE_USAGE_KEY", "full_path")]),
+        )
+        .unwrap();
+        let setting = |key: &str| settings.iter().find(|s| s.key == key).unwrap().clone();
+
+        assert_eq!(setting("poll_interval_ms").source, ConfigSource::Env);
+        assert_eq!(setting("poll_interval_ms").value, serde_json::json!(900));
+        assert_eq!(setting("usage_key").source, ConfigSource::Env);
+        assert_eq!(setting("usage_key").value, serde_json::json!("full_path"));
+        assert_eq!(setting("max_session_secs").source, ConfigSource::File);
+        assert_eq!(setting("max_session_secs").value, serde_json::json!(3600));
+        assert_eq!(setting("log_keep_count").source, ConfigSource::Default);
+        assert_eq!(
+            setting("log_keep_count").value,
+            serde_json::json!(Config::default().log_keep_count)
+        );
+    }
+
+    #[test]
+    fn live_reload_applies_valid_config_and_keeps_old_on_error() {
+        let live = LiveConfig::new(Config::default());
+        let reloaded = live
+            .reload_from(Some("poll_interval_ms = 2500"), lookup(&[]))
+            .unwrap();
+        assert_eq!(reloaded.poll_interval_ms, 2500);
+        assert_eq!(live.get().poll_sleep(), Duration::from_millis(2500));
+
+        // Out of range, malformed and unknown keys are all rejected
+        for bad in [
+            "poll_jitter_pct = 150",
+            "poll_interval_ms = ",
+            "poll_interval = 10",
+        ] {
+            assert!(live.reload_from(Some(bad), lookup(&[])).is_err(), "{}", bad);
+            assert_eq!(live.get().poll_interval_ms, 2500);
+        }
+    }
+
+    #[test]
+    fn jittered_interval_stays_within_bounds() {
+        assert_eq!(jittered_interval(1000, 20, -1.0), Duration::from_millis(800));
+        assert_eq!(jittered_interval(1000, 20, 1.0), Duration::from_millis(1200));
+        assert_eq!(jittered_interval(1000, 20, 0.0), Duration::from_millis(1000));
+        assert_eq!(jittered_interval(1000, 0, 1.0), Duration::from_millis(1000));
+        // Out-of-range samples are clamped
+        assert_eq!(jittered_interval(1000, 20, 5.0), Duration::from_millis(1200));
+    }
+
+    #[test]
+    fn working_hours_parse_from_file_and_env() {
+        let file = "working_hours = { default = 8, Fri = 4, \"2024-12-24\" = 2.5 }\n";
+        let config = Config::from_sources(Some(file), lookup(&[])).unwrap();
+        let hours = &config.working_hours;
+        // 2024-12-23 is a Monday, 2024-12-27 a Friday
+        assert_eq!(hours.hours_on(NaiveDate::from_ymd_opt(2024, 12, 23).unwrap()), Some(8.0));
+        assert_eq!(hours.day_fraction(NaiveDate::from_ymd_opt(2024, 12, 27).unwrap()), 0.5);
+        assert_eq!(hours.hours_on(NaiveDate::from_ymd_opt(2024, 12, 24).unwrap()), Some(2.5));
+
+        let config =
+            Config::from_lookup(lookup(&[("SYNAPSE_WORKING_HOURS", "mon=6, ")])).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 12, 23).unwrap();
+        assert_eq!(config.working_hours.hours_on(monday), Some(6.0));
+        // Without a default day there is nothing to scale against
+        assert_eq!(config.working_hours.day_fraction(monday), 1.0);
+        for bad in ["mon=25", "someday=4", "mon"] {
+            let result = Config::from_lookup(lookup(&[("SYNAPSE_WORKING_HOURS", bad)]));
+            assert!(matches!(result, Err(SynapseError::Config(_))), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn non_working_days_parse_from_file_and_env() {
+        let file = "non_working_days = [\"sat\", \"Sunday\", \"2024-12-25\"]\n";
+        let config = Config::from_sources(Some(file), lookup(&[])).unwrap();
+        let days = &config.non_working_days;
+        assert_eq!(days.weekdays, vec![Weekday::Sat, Weekday::Sun]);
+        assert!(days.contains(NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()));
+        // 2024-12-27 is a Friday
+        assert!(!days.contains(NaiveDate::from_ymd_opt(2024, 12, 27).unwrap()));
+
+        let config =
+            Config::from_lookup(lookup(&[("SYNAPSE_NON_WORKING_DAYS", "fri, ")])).unwrap();
+        assert_eq!(config.non_working_days.weekdays, vec![Weekday::Fri]);
+        let result = Config::from_lookup(lookup(&[("SYNAPSE_NON_WORKING_DAYS", "someday")]));
+        assert!(matches!(result, Err(SynapseError::Config(_))));
+    }
+
+    #[test]
+    fn usage_key_strips_directories_for_executable_names() {
+        let key = UsageKey::ExecutableName;
+        assert_eq!(key.key_for("C:\\Program Files\\VS Code\\Code.exe"), "code.exe");
+        assert_eq!(key.key_for("\"/usr/bin/Code\""), "code");
+        assert_eq!(key.key_for("code.exe"), "code.exe");
+        assert_eq!(UsageKey::FullPath.key_for(" C:\\Apps\\Code.exe "), "C:\\Apps\\Code.exe");
+    }
+
+    #[test]
+    fn poll_sleep_with_jitter_stays_within_bounds() {
+        let config = Config {
+            poll_interval_ms: 1000,
+            poll_jitter_pct: 10,
+            ..Config::default()
+        };
+        for _ in 0..100 {
+            let sleep = config.poll_sleep();
+            assert!(sleep >= Duration::from_millis(900));
+            assert!(sleep <= Duration::from_millis(1100));
+        }
+    }
+}
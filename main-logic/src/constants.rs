@@ -6,4 +6,93 @@ pub const SUMMARY_INTERVAL_SECS: u64 = 60;
 /// Main loop sleep duration in milliseconds.
 pub const MAIN_LOOP_SLEEP_MS: u64 = 1000;
 
+/// Maximum age (seconds) of a saved in-flight app that is still restored on startup.
+pub const INFLIGHT_APP_MAX_AGE_SECS: i64 = 300;
+
+/// Daily focus goal (seconds) used for days without an explicit date or weekday goal.
+pub const DEFAULT_DAILY_GOAL_SECS: i64 = 2 * 60 * 60;
+
+/// Number of days averaged for each point of the focus-time trend.
+pub const FOCUS_TREND_WINDOW_DAYS: usize = 7;
+
+/// Default maximum number of Supabase sync requests in flight at once.
+pub const DEFAULT_SYNC_MAX_CONCURRENCY: usize = 4;
+
+/// Default number of consecutive sync failures that pause syncing.
+pub const DEFAULT_SYNC_FAILURE_THRESHOLD: u32 = 3;
+
+/// Seconds syncing stays paused after the first trip; doubled on each further trip.
+pub const SYNC_PAUSE_BASE_SECS: u64 = 60;
+
+/// Upper bound on how long syncing stays paused after repeated trips.
+pub const SYNC_PAUSE_MAX_SECS: u64 = 60 * 60;
+
+/// Seconds before a queued session is retried after its first failure; doubled on each
+/// further failure.
+pub const SESSION_RETRY_BASE_SECS: i64 = 60;
+
+/// Upper bound on the wait between retries of a queued session.
+pub const SESSION_RETRY_MAX_SECS: i64 = 60 * 60;
+
+/// Failed attempts after which a queued session is no longer retried automatically.
+pub const SESSION_RETRY_MAX_ATTEMPTS: i64 = 10;
+
+/// Default shortest system sleep, in seconds, that ends the running session.
+pub const DEFAULT_MIN_SLEEP_SECS: u64 = 60;
+
+/// Default maximum number of distinct work apps stored per session.
+pub const DEFAULT_MAX_WORK_APPS: usize = 50;
+
+/// Default seconds during which a blocked app does not trigger a second popup.
+pub const DEFAULT_POPUP_SUPPRESSION_SECS: u64 = 30;
+
+/// Minimum seconds between persisted matches of the same app rule.
+pub const RULE_MATCH_RECORD_INTERVAL_SECS: u64 = 60;
+
+/// Default loopback port of the live event socket.
+pub const DEFAULT_LIVE_SOCKET_PORT: u16 = 47_600;
+
+/// Seconds the installed-apps list is cached before it is scanned again.
+pub const INSTALLED_APPS_CACHE_TTL_SECS: u64 = 10 * 60;
+
+/// Default loopback port of the HTTP API.
+pub const DEFAULT_HTTP_API_PORT: u16 = 47_601;
+
+/// Sessions returned by the HTTP API's recent-sessions endpoint when no limit is given.
+pub const DEFAULT_RECENT_SESSIONS_LIMIT: u32 = 20;
+
+/// Most sessions the HTTP API's recent-sessions endpoint returns at once.
+pub const MAX_RECENT_SESSIONS_LIMIT: u32 = 500;
+
+/// Version of the database schema this build creates and understands. Bump it with every
+/// schema change.
+///
+/// 2 added `focus_sessions.manual`; 3 added `app_usage_events.raw_name`.
+pub const SCHEMA_VERSION: i64 = 3;
+
+/// Log file written when `log_path` is not configured.
+pub const DEFAULT_LOG_PATH: &str = "synapse.log";
+
+/// Size in bytes at which the log file is rotated to `synapse.log.1`.
+pub const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Default number of rotated log archives kept; older ones are deleted.
+pub const DEFAULT_LOG_KEEP_COUNT: usize = 5;
+
+/// Minimum seconds between automatic database vacuums at startup.
+pub const VACUUM_INTERVAL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Seconds a session start/end hook command may run before it is killed.
+pub const HOOK_TIMEOUT_SECS: u64 = 30;
+
+/// Apps listed in the weekly report's top apps table.
+pub const WEEKLY_REPORT_TOP_APPS: usize = 5;
+
+/// Most recent polls whose timings are averaged in the poll timing stats.
+pub const POLL_TIMING_WINDOW: usize = 120;
+
+/// Milliseconds of further change events folded into one app rules reload, so an editor's
+/// burst of writes reloads the file once, after it is complete.
+pub const APPRULES_RELOAD_DEBOUNCE_MS: u64 = 300;
+
 // Add more constants here as needed. 
@@ -1,9 +1,19 @@
 //! Database module: handles SQLite connection, schema, and event/session storage.
 
+use crate::constants::{
+    SCHEMA_VERSION, SESSION_RETRY_BASE_SECS, SESSION_RETRY_MAX_ATTEMPTS, SESSION_RETRY_MAX_SECS,
+    VACUUM_INTERVAL_SECS,
+};
 use crate::error::SynapseError;
-use crate::types::AppUsageEvent;
-use rusqlite::{params, Connection};
+use crate::metrics::MetricsSnapshot;
+use crate::session::FocusSession;
+use crate::types::{AppUsageEvent, GoalKey, LocalTimeEvent, UsageStatus};
+use chrono::{Datelike, Local, NaiveDate, TimeZone};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::env;
+use std::time::{Duration, UNIX_EPOCH};
 use uuid::Uuid;
 
 /// Handle for interacting with the SQLite database.
@@ -18,24 +28,90 @@ impl DbHandle {
     /// # Errors
     /// Returns `SynapseError` if the database cannot be opened or tables cannot be created.
     pub fn new() -> Result<Self, SynapseError> {
-        let db_path =
-            std::env::var("SYNAPSE_DB_PATH").unwrap_or_else(|_| "synapse_metrics.db".to_string());
+        Self::open(&Self::path_from_env())
+    }
+
+    /// Returns the database path from `SYNAPSE_DB_PATH`, or the default file name.
+    pub fn path_from_env() -> String {
+        std::env::var("SYNAPSE_DB_PATH").unwrap_or_else(|_| "synapse_metrics.db".to_string())
+    }
 
-        let conn = Connection::open(&db_path)
+    /// Opens or creates the SQLite database at `db_path` and ensures required tables exist.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the database cannot be opened or tables cannot be created.
+    pub fn open(db_path: &str) -> Result<Self, SynapseError> {
+        let conn = Connection::open(db_path)
             .map_err(|e| SynapseError::Db(rusqlite::Error::ToSqlConversionFailure(Box::new(e))))?;
         // Enable foreign key support
         conn.execute("PRAGMA foreign_keys = ON", []).ok();
+        Self::create_schema(&conn)
+            .map_err(|e| SynapseError::Db(rusqlite::Error::ToSqlConversionFailure(Box::new(e))))?;
+        Ok(DbHandle { conn })
+    }
+
+    /// Opens the existing database at `db_path` read-only, without creating any tables,
+    /// so stats readers never take a write lock the backend has to wait for.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the database does not exist or cannot be opened. Any write
+    /// through the returned handle fails with `SynapseError::Db`.
+    pub fn open_readonly(db_path: &str) -> Result<Self, SynapseError> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(DbHandle { conn })
+    }
+
+    /// Opens the existing database at `db_path` for reading and writing, without creating any
+    /// tables, for short-lived connections to a database another handle already set up.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the database does not exist or cannot be opened.
+    pub fn open_existing(db_path: &str) -> Result<Self, SynapseError> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.execute("PRAGMA foreign_keys = ON", []).ok();
+        Ok(DbHandle { conn })
+    }
+
+    /// Returns the path of the database file; empty for an in-memory database.
+    pub fn path(&self) -> String {
+        self.conn.path().unwrap_or_default().to_string()
+    }
+
+    /// Creates the tables used by the application if they do not exist yet.
+    fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS focus_sessions (
                 id TEXT PRIMARY KEY,
                 start_time INTEGER NOT NULL,
                 end_time INTEGER,
                 work_apps TEXT,
-                distraction_attempts INTEGER
+                distraction_attempts INTEGER,
+                is_synced INTEGER NOT NULL DEFAULT 0,
+                work_apps_truncated INTEGER NOT NULL DEFAULT 0,
+                manual INTEGER NOT NULL DEFAULT 0
             )",
             [],
-        )
-        .map_err(|e| SynapseError::Db(rusqlite::Error::ToSqlConversionFailure(Box::new(e))))?;
+        )?;
+        add_column_if_missing(conn, "focus_sessions", "is_synced", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(
+            conn,
+            "focus_sessions",
+            "work_apps_truncated",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        add_column_if_missing(conn, "focus_sessions", "project", "TEXT")?;
+        add_column_if_missing(conn, "focus_sessions", "manual", "INTEGER NOT NULL DEFAULT 0")?;
+        // Any local change to a session has to be pushed again
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS focus_sessions_resync AFTER UPDATE OF start_time, end_time, work_apps, distraction_attempts ON focus_sessions BEGIN UPDATE focus_sessions SET is_synced = 0 WHERE id = NEW.id; END",
+            [],
+        )?;
         conn.execute(
             "CREATE TABLE IF NOT EXISTS app_usage_events (
                 id TEXT PRIMARY KEY,
@@ -45,12 +121,132 @@ impl DbHandle {
                 start_time INTEGER,
                 end_time INTEGER,
                 duration_secs INTEGER,
+                matched_rule TEXT,
+                reason TEXT,
+                is_synced INTEGER NOT NULL DEFAULT 0,
                 FOREIGN KEY(session_id) REFERENCES focus_sessions(id)
             )",
             [],
-        )
-        .map_err(|e| SynapseError::Db(rusqlite::Error::ToSqlConversionFailure(Box::new(e))))?;
-        Ok(DbHandle { conn })
+        )?;
+        add_column_if_missing(conn, "app_usage_events", "matched_rule", "TEXT")?;
+        add_column_if_missing(conn, "app_usage_events", "reason", "TEXT")?;
+        add_column_if_missing(conn, "app_usage_events", "is_synced", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(conn, "app_usage_events", "raw_name", "TEXT")?;
+        // Older builds wrote free-form statuses; fold them into the canonical values
+        conn.execute(
+            "UPDATE app_usage_events SET is_synced = 0, status = CASE lower(trim(status)) WHEN 'active' THEN 'allowed' WHEN 'distraction' THEN 'blocked' ELSE lower(trim(status)) END WHERE status NOT IN ('allowed', 'blocked')",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS inflight_app (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                process_name TEXT NOT NULL,
+                start_time INTEGER NOT NULL,
+                saved_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS rule_matches (
+                rule TEXT PRIMARY KEY,
+                match_count INTEGER NOT NULL,
+                last_matched INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_names (
+                app_key TEXT PRIMARY KEY,
+                display_name TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS focus_goals (
+                goal_key TEXT PRIMARY KEY,
+                goal_secs INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_failures (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                error TEXT NOT NULL,
+                failed_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pending_session_updates (
+                session_id TEXT PRIMARY KEY,
+                queued_at INTEGER NOT NULL,
+                attempts INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS metrics_snapshots (
+                day TEXT PRIMARY KEY,
+                total_checks INTEGER NOT NULL,
+                blocked_count INTEGER NOT NULL,
+                app_frequency TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS outside_session_distractions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                app TEXT NOT NULL,
+                occurred_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                version INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        // Never downgrade: a newer build may already have migrated this file
+        conn.execute(
+            "INSERT INTO schema_version (id, version) VALUES (1, ?1) ON CONFLICT(id) DO UPDATE SET version = MAX(version, excluded.version)",
+            [SCHEMA_VERSION],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the schema version recorded in the database.
+    ///
+    /// Databases created before versioning have no version table and report 0. A value above
+    /// `SCHEMA_VERSION` means the file was written by a newer build.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn schema_version(&self) -> Result<i64, SynapseError> {
+        let has_table: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_table {
+            return Ok(0);
+        }
+        let version = self
+            .conn
+            .query_row("SELECT version FROM schema_version WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(version.unwrap_or(0))
     }
 
     /// Construct DbHandle with an in-memory SQLite database (for tests and integration).
@@ -60,6 +256,13 @@ impl DbHandle {
         }
     }
 
+    /// Construct DbHandle with an in-memory SQLite database and the full schema (for tests and integration).
+    pub fn test_in_memory_with_schema() -> Self {
+        let conn = Connection::open_in_memory().unwrap();
+        Self::create_schema(&conn).unwrap();
+        DbHandle { conn }
+    }
+
     /// Logs an app usage event to the database.
     ///
     /// # Arguments
@@ -86,19 +289,53 @@ impl DbHandle {
         Ok(())
     }
 
-    /// Inserts a new focus session into the database.
+    /// Inserts a new focus session under its own id.
     ///
-    /// # Arguments
-    /// * `start_time` - Session start time (seconds since epoch)
+    /// Idempotent: inserting a session whose id is already stored leaves the existing row
+    /// untouched, so a retried insert never duplicates a session. Returns whether a row was
+    /// written.
     ///
     /// # Errors
     /// Returns `SynapseError` if the insert fails.
-    pub fn insert_session(&self, start_time: i64) -> Result<Uuid, SynapseError> {
+    pub fn insert_session(&self, session: &FocusSession) -> Result<bool, SynapseError> {
+        let start_time = session.start_time.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES (?1, ?2, NULL, ?3, ?4)",
+            params![
+                session.id.to_string(),
+                start_time,
+                session.work_apps.join(","),
+                session.distraction_attempts
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Inserts a completed session entered by hand, e.g. for a meeting away from the computer.
+    /// The row is flagged `manual` so it can be told apart from tracked sessions.
+    ///
+    /// Callers validate the times; see `api::add_manual_session`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the insert fails.
+    pub fn insert_manual_session(
+        &self,
+        start_time: i64,
+        end_time: i64,
+        work_apps: &[String],
+        distraction_attempts: u32,
+    ) -> Result<Uuid, SynapseError> {
         let session_id = Uuid::new_v4();
         self.conn.execute(
-            "INSERT INTO focus_sessions (id, start_time, distraction_attempts) VALUES (?1, ?2, 0)",
-            params![session_id.to_string(), start_time],
-        ).map_err(|e| SynapseError::Db(rusqlite::Error::ToSqlConversionFailure(Box::new(e))))?;
+            "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts, manual) VALUES (?1, ?2, ?3, ?4, ?5, 1)",
+            params![
+                session_id.to_string(),
+                start_time,
+                end_time,
+                work_apps.join(","),
+                distraction_attempts
+            ],
+        )?;
         Ok(session_id)
     }
 
@@ -126,6 +363,40 @@ impl DbHandle {
         Ok(())
     }
 
+    /// Clears a session's end time, so a resumed session is open again.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the update fails.
+    pub fn reopen_session(&self, session_id: Uuid) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "UPDATE focus_sessions SET end_time = NULL WHERE id = ?1",
+            params![session_id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Sets the project a session belongs to and marks it for re-sync.
+    pub fn set_session_project(
+        &self,
+        session_id: Uuid,
+        project: Option<&str>,
+    ) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "UPDATE focus_sessions SET project = ?1, is_synced = 0 WHERE id = ?2",
+            params![project, session_id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Records how many work apps were left out of a session's stored `work_apps`.
+    pub fn set_work_apps_truncated(&self, session_id: Uuid, count: u32) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "UPDATE focus_sessions SET work_apps_truncated = ?1 WHERE id = ?2",
+            params![count, session_id.to_string()],
+        )?;
+        Ok(())
+    }
+
     /// Updates only the distraction attempts for a session.
     pub fn update_session_distractions(
         &self,
@@ -169,48 +440,136 @@ impl DbHandle {
     ///
     /// # Arguments
     /// * `process_name` - Name of the process
-    /// * `status` - Status of the app usage (e.g., "blocked", "active", "distraction")
+    /// * `status` - Whether the app was allowed or blocked
     /// * `session_id` - Associated session ID
     /// * `start_time` - When the app came into focus
+    /// * `matched_rule` - The blacklist rule that blocked the app, if any
+    /// * `raw_name` - The process name as the OS reported it, before any mapping
     ///
     /// # Errors
     /// Returns `SynapseError` if the insert fails.
+    #[allow(clippy::too_many_arguments)]
     pub fn insert_app_usage_event(
         &self,
         process_name: &str,
-        status: &str,
+        status: UsageStatus,
         session_id: Option<Uuid>,
         start_time: i64,
         end_time: i64,
         duration_secs: i64,
+        matched_rule: Option<&str>,
+        raw_name: Option<&str>,
     ) -> Result<Uuid, SynapseError> {
         let event_id = Uuid::new_v4();
         self.conn.execute(
-            "INSERT INTO app_usage_events (id, process_name, status, session_id, start_time, end_time, duration_secs) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![event_id.to_string(), process_name, status, session_id.map(|u| u.to_string()), start_time, end_time, duration_secs],
+            "INSERT INTO app_usage_events (id, process_name, status, session_id, start_time, end_time, duration_secs, matched_rule, raw_name) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![event_id.to_string(), process_name, status.as_str(), session_id.map(|u| u.to_string()), start_time, end_time, duration_secs, matched_rule, raw_name],
         ).map_err(|e| SynapseError::Db(rusqlite::Error::ToSqlConversionFailure(Box::new(e))))?;
         Ok(event_id)
     }
 
+    /// Returns each distinct stored process name with a raw name it was recorded under, for
+    /// events starting in `[start, end)`, ordered by both. Events stored without a raw name
+    /// are skipped.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn raw_process_names_between(
+        &self,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(String, String)>, SynapseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT process_name, raw_name FROM app_usage_events WHERE raw_name IS NOT NULL AND start_time >= ?1 AND start_time < ?2 ORDER BY process_name, raw_name",
+        )?;
+        let names = stmt
+            .query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(names)
+    }
+
+    /// Returns the recorded focus time per app for events starting in `[start, end)`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn focus_secs_by_app_between(
+        &self,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(String, i64)>, SynapseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT process_name, COALESCE(SUM(duration_secs), 0) FROM app_usage_events WHERE start_time >= ?1 AND start_time < ?2 GROUP BY process_name",
+        )?;
+        let usage = stmt
+            .query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(usage)
+    }
+
+    /// Attaches a user-provided `reason` to the most recent blocked event for `process_name`
+    /// in the given session.
+    ///
+    /// Returns `false` if there is no such event.
+    pub fn set_distraction_reason(
+        &self,
+        session_id: Uuid,
+        process_name: &str,
+        reason: &str,
+    ) -> Result<bool, SynapseError> {
+        let updated = self.conn.execute(
+            "UPDATE app_usage_events SET reason = ?3 WHERE rowid = (SELECT rowid FROM app_usage_events WHERE session_id = ?1 AND process_name = ?2 AND status = 'blocked' ORDER BY start_time DESC, rowid DESC LIMIT 1)",
+            params![session_id.to_string(), process_name, reason],
+        )?;
+        Ok(updated > 0)
+    }
+
     pub fn get_app_usage_events_for_session(
         &self,
         session_id: Uuid,
     ) -> Result<Vec<AppUsageEvent>, SynapseError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT process_name, status, session_id, start_time, end_time, duration_secs FROM app_usage_events WHERE session_id = ?1"
-        )?;
+        self.query_session_events(session_id, false)
+    }
+
+    /// Returns the session's events that haven't been pushed to Supabase yet.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn get_unsynced_app_usage_events_for_session(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Vec<AppUsageEvent>, SynapseError> {
+        self.query_session_events(session_id, true)
+    }
+
+    fn query_session_events(
+        &self,
+        session_id: Uuid,
+        unsynced_only: bool,
+    ) -> Result<Vec<AppUsageEvent>, SynapseError> {
+        let mut sql = "SELECT id, process_name, status, session_id, start_time, end_time, duration_secs, matched_rule, reason FROM app_usage_events WHERE session_id = ?1".to_string();
+        if unsynced_only {
+            sql.push_str(" AND is_synced = 0");
+        }
+        let mut stmt = self.conn.prepare(&sql)?;
         let rows = stmt.query_map([session_id.to_string()], |row| {
             Ok(AppUsageEvent {
-                id: Uuid::new_v4(), // Dummy value for test, replace with actual if available
-                process_name: row.get(0)?,
-                status: row.get(1)?,
+                // The real id, so the event can be marked synced after a push
+                id: row
+                    .get(0)
+                    .ok()
+                    .and_then(|s: String| Uuid::parse_str(&s).ok())
+                    .unwrap_or_else(Uuid::new_v4),
+                process_name: row.get(1)?,
+                status: row.get(2)?,
                 session_id: row
-                    .get(2)
+                    .get(3)
                     .ok()
                     .and_then(|s: String| Uuid::parse_str(&s).ok()),
-                start_time: row.get(3)?,
-                end_time: row.get(4)?,
-                duration_secs: row.get(5)?,
+                start_time: row.get(4)?,
+                end_time: row.get(5)?,
+                duration_secs: row.get(6)?,
+                matched_rule: row.get(7)?,
+                reason: row.get(8)?,
             })
         })?;
         let mut events = Vec::new();
@@ -220,6 +579,489 @@ impl DbHandle {
         Ok(events)
     }
 
+    /// Returns the session's events with their times also formatted in the local timezone.
+    pub fn get_app_usage_events_with_local_time(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Vec<LocalTimeEvent>, SynapseError> {
+        self.get_app_usage_events_in_timezone(session_id, &Local)
+    }
+
+    /// Returns the session's events with their times also formatted in `tz`.
+    pub fn get_app_usage_events_in_timezone<Tz>(
+        &self,
+        session_id: Uuid,
+        tz: &Tz,
+    ) -> Result<Vec<LocalTimeEvent>, SynapseError>
+    where
+        Tz: TimeZone,
+        Tz::Offset: std::fmt::Display,
+    {
+        Ok(self
+            .get_app_usage_events_for_session(session_id)?
+            .into_iter()
+            .map(|event| LocalTimeEvent {
+                start_local: format_epoch_in(event.start_time, tz),
+                end_local: format_epoch_in(event.end_time, tz),
+                event,
+            })
+            .collect())
+    }
+
+    /// Deletes the sessions starting in `[start, end)` together with their app usage events.
+    ///
+    /// Returns the number of sessions deleted.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the delete fails; nothing is deleted in that case.
+    pub fn delete_sessions_between(&self, start: i64, end: i64) -> Result<usize, SynapseError> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM app_usage_events WHERE session_id IN (SELECT id FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2)",
+            [start, end],
+        )?;
+        let deleted = tx.execute(
+            "DELETE FROM focus_sessions WHERE start_time >= ?1 AND start_time < ?2",
+            [start, end],
+        )?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Rebuilds the database file to reclaim the space left by deleted rows.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Other` if a transaction is open on this connection, since SQLite
+    /// cannot vacuum inside one, or `SynapseError::Db` if the vacuum fails.
+    pub fn vacuum(&self) -> Result<(), SynapseError> {
+        if !self.conn.is_autocommit() {
+            return Err(SynapseError::Other(
+                "cannot vacuum the database while a transaction is open".to_string(),
+            ));
+        }
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Vacuums the database if it has not been vacuumed in the last `VACUUM_INTERVAL_SECS`,
+    /// recording `now` as the last vacuum time. Returns whether a vacuum ran.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the vacuum or the settings update fails.
+    pub fn vacuum_if_due(&self, now: i64) -> Result<bool, SynapseError> {
+        let last: Option<i64> = self.get_setting_as("last_vacuum_at")?;
+        if last.is_some_and(|last| now - last < VACUUM_INTERVAL_SECS) {
+            return Ok(false);
+        }
+        self.vacuum()?;
+        self.set_setting_as("last_vacuum_at", &now)?;
+        Ok(true)
+    }
+
+    /// Returns the number of unused pages in the database file, which a vacuum reclaims.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn free_page_count(&self) -> Result<i64, SynapseError> {
+        Ok(self
+            .conn
+            .query_row("PRAGMA freelist_count", [], |row| row.get(0))?)
+    }
+
+    /// Records the display names shown for app usage keys, replacing earlier names.
+    pub fn set_app_display_names(&self, names: &[(String, String)]) -> Result<(), SynapseError> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (app_key, display_name) in names {
+            tx.execute(
+                "INSERT INTO app_names (app_key, display_name) VALUES (?1, ?2) ON CONFLICT(app_key) DO UPDATE SET display_name = excluded.display_name",
+                params![app_key, display_name],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Marks a session as pushed to Supabase and drops any update queued for it. Later
+    /// local changes clear the flag again.
+    pub fn mark_session_synced(&self, session_id: Uuid) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "UPDATE focus_sessions SET is_synced = 1 WHERE id = ?1",
+            [session_id.to_string()],
+        )?;
+        self.conn.execute(
+            "DELETE FROM pending_session_updates WHERE session_id = ?1",
+            [session_id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Records that syncing an ended session to Supabase failed, and queues the session
+    /// (its events and its update) to be retried until [`mark_session_synced`](Self::mark_session_synced) confirms it.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the failure cannot be stored.
+    pub fn queue_session_update(
+        &self,
+        session_id: Uuid,
+        error: &str,
+        failed_at: i64,
+    ) -> Result<(), SynapseError> {
+        self.record_sync_failure(session_id, error, failed_at)?;
+        self.conn.execute(
+            "INSERT INTO pending_session_updates (session_id, queued_at, attempts) VALUES (?1, ?2, 1) ON CONFLICT(session_id) DO UPDATE SET attempts = attempts + 1",
+            params![session_id.to_string(), failed_at],
+        )?;
+        Ok(())
+    }
+
+    /// Queues an ended session that wasn't pushed because syncing is paused, so the retry
+    /// after syncing resumes uploads it. Unlike a failure this counts no attempt, and a
+    /// session already queued keeps its place.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the insert fails.
+    pub fn defer_session_sync(&self, session_id: Uuid, queued_at: i64) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "INSERT INTO pending_session_updates (session_id, queued_at, attempts) VALUES (?1, ?2, 0) ON CONFLICT(session_id) DO NOTHING",
+            params![session_id.to_string(), queued_at],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the sessions whose update is queued for a retry, oldest first, as stored now.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn pending_session_updates(&self) -> Result<Vec<FocusSession>, SynapseError> {
+        self.query_pending_sessions("", params![])
+    }
+
+    /// Returns the queued sessions due for a retry at `now`, oldest first. After each failed
+    /// attempt a session waits `SESSION_RETRY_BASE_SECS`, doubling per attempt up to
+    /// `SESSION_RETRY_MAX_SECS`, and after `SESSION_RETRY_MAX_ATTEMPTS` it is left queued
+    /// but no longer retried.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn due_session_updates(&self, now: i64) -> Result<Vec<FocusSession>, SynapseError> {
+        self.query_pending_sessions(
+            "WHERE p.attempts < ?2 AND (p.attempts = 0 OR COALESCE((SELECT MAX(f.failed_at) FROM sync_failures f WHERE f.session_id = p.session_id), p.queued_at) + MIN(?3 << MIN(p.attempts - 1, 30), ?4) <= ?1)",
+            params![now, SESSION_RETRY_MAX_ATTEMPTS, SESSION_RETRY_BASE_SECS, SESSION_RETRY_MAX_SECS],
+        )
+    }
+
+    fn query_pending_sessions(
+        &self,
+        filter: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<FocusSession>, SynapseError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT s.id, s.start_time, s.end_time, s.work_apps, s.distraction_attempts, s.project FROM pending_session_updates p JOIN focus_sessions s ON s.id = p.session_id {} ORDER BY p.queued_at, p.session_id",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            let id: String = row.get(0)?;
+            let start_time: i64 = row.get(1)?;
+            let end_time: Option<i64> = row.get(2)?;
+            let work_apps: Option<String> = row.get(3)?;
+            let distraction_attempts: Option<u32> = row.get(4)?;
+            let project: Option<String> = row.get(5)?;
+            Ok((id, start_time, end_time, work_apps, distraction_attempts, project))
+        })?;
+        let mut sessions = Vec::new();
+        for row in rows {
+            let (id, start_time, end_time, work_apps, distraction_attempts, project) = row?;
+            let Ok(id) = Uuid::parse_str(&id) else {
+                continue;
+            };
+            let at = |secs: i64| UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64);
+            sessions.push(FocusSession {
+                id,
+                start_time: at(start_time),
+                end_time: end_time.map(at),
+                work_apps: work_apps
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|app| !app.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                distraction_attempts: distraction_attempts.unwrap_or(0),
+                project,
+            });
+        }
+        Ok(sessions)
+    }
+
+    /// Records that pushing a session to Supabase failed with `error` at `failed_at`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the insert fails.
+    pub fn record_sync_failure(
+        &self,
+        session_id: Uuid,
+        error: &str,
+        failed_at: i64,
+    ) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "INSERT INTO sync_failures (session_id, error, failed_at) VALUES (?1, ?2, ?3)",
+            params![session_id.to_string(), error, failed_at],
+        )?;
+        Ok(())
+    }
+
+    /// Marks app usage events as synced.
+    pub fn mark_events_synced(&self, event_ids: &[Uuid]) -> Result<(), SynapseError> {
+        let mut stmt = self
+            .conn
+            .prepare("UPDATE app_usage_events SET is_synced = 1 WHERE id = ?1")?;
+        for id in event_ids {
+            stmt.execute([id.to_string()])?;
+        }
+        Ok(())
+    }
+
+    /// Saves the app currently in focus so its duration can bridge a restart.
+    ///
+    /// # Arguments
+    /// * `process_name` - The app in focus
+    /// * `start_time` - When it came into focus (seconds since epoch)
+    /// * `saved_at` - When the state was saved (seconds since epoch)
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the write fails.
+    pub fn save_inflight_app(
+        &self,
+        process_name: &str,
+        start_time: i64,
+        saved_at: i64,
+    ) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO inflight_app (id, process_name, start_time, saved_at) VALUES (1, ?1, ?2, ?3)",
+            params![process_name, start_time, saved_at],
+        )?;
+        Ok(())
+    }
+
+    /// Reads and clears the saved in-flight app, returning `(process_name, start_time, saved_at)`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn take_inflight_app(&self) -> Result<Option<(String, i64, i64)>, SynapseError> {
+        let saved = self
+            .conn
+            .query_row(
+                "SELECT process_name, start_time, saved_at FROM inflight_app WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        self.conn.execute("DELETE FROM inflight_app", [])?;
+        Ok(saved)
+    }
+
+    /// Sets the focus goal (in seconds) for a date or weekday, replacing any previous goal.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the write fails.
+    pub fn set_goal(&self, key: GoalKey, goal_secs: i64) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO focus_goals (goal_key, goal_secs) VALUES (?1, ?2)",
+            params![key.storage_key(), goal_secs],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the explicit goal for `date`: a date-specific goal wins over a weekday goal.
+    ///
+    /// Returns `None` when neither is set, so callers can apply their own default.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn get_goal_for_day(&self, date: NaiveDate) -> Result<Option<i64>, SynapseError> {
+        for key in [GoalKey::Date(date), GoalKey::Weekday(date.weekday())] {
+            let goal = self
+                .conn
+                .query_row(
+                    "SELECT goal_secs FROM focus_goals WHERE goal_key = ?1",
+                    [key.storage_key()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if goal.is_some() {
+                return Ok(goal);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the JSON-encoded value stored under `key`, or `None` if it was never set.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the read fails.
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, SynapseError> {
+        let value = self
+            .conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(value)
+    }
+
+    /// Stores a JSON-encoded `value` under `key`, replacing any previous value.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Serde` if `value` is not valid JSON, or `SynapseError` if the
+    /// write fails.
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), SynapseError> {
+        serde_json::from_str::<serde_json::Value>(value)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the setting under `key` decoded as `T`, or `None` if it was never set.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Serde` if the stored value does not decode as `T`.
+    pub fn get_setting_as<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, SynapseError> {
+        match self.get_setting(key)? {
+            Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores `value` JSON-encoded under `key`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if encoding or the write fails.
+    pub fn set_setting_as<T: Serialize>(&self, key: &str, value: &T) -> Result<(), SynapseError> {
+        self.set_setting(key, &serde_json::to_string(value)?)
+    }
+
+    /// Stores the metrics counters for the local `day`, replacing any earlier snapshot of it.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if encoding or the upsert fails.
+    pub fn save_metrics_snapshot(
+        &self,
+        day: NaiveDate,
+        snapshot: &MetricsSnapshot,
+    ) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "INSERT INTO metrics_snapshots (day, total_checks, blocked_count, app_frequency) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(day) DO UPDATE SET total_checks = excluded.total_checks, blocked_count = excluded.blocked_count, app_frequency = excluded.app_frequency",
+            params![
+                day.to_string(),
+                snapshot.total_checks as i64,
+                snapshot.blocked_count as i64,
+                serde_json::to_string(&snapshot.app_frequency)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the metrics counters stored for the local `day`, if any.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails or the stored frequencies don't decode.
+    pub fn load_metrics_snapshot(
+        &self,
+        day: NaiveDate,
+    ) -> Result<Option<MetricsSnapshot>, SynapseError> {
+        let row: Option<(i64, i64, String)> = self
+            .conn
+            .query_row(
+                "SELECT total_checks, blocked_count, app_frequency FROM metrics_snapshots WHERE day = ?1",
+                [day.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        row.map(|(total_checks, blocked_count, app_frequency)| {
+            Ok(MetricsSnapshot {
+                total_checks: total_checks as u64,
+                blocked_count: blocked_count as u64,
+                app_frequency: serde_json::from_str(&app_frequency)?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Records a blocked app opened at `at` (seconds since epoch) while no session was running.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the insert fails.
+    pub fn record_outside_session_distraction(&self, app: &str, at: i64) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "INSERT INTO outside_session_distractions (app, occurred_at) VALUES (?1, ?2)",
+            params![app, at],
+        )?;
+        Ok(())
+    }
+
+    /// Counts distractions recorded outside sessions in `[start, end)`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn outside_session_distractions_between(&self, start: i64, end: i64) -> Result<i64, SynapseError> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM outside_session_distractions WHERE occurred_at >= ?1 AND occurred_at < ?2",
+            [start, end],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Records that an app rule matched an observed process at `at` (seconds since epoch).
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the upsert fails.
+    pub fn record_rule_match(&self, rule: &str, at: i64) -> Result<(), SynapseError> {
+        self.conn.execute(
+            "INSERT INTO rule_matches (rule, match_count, last_matched) VALUES (?1, 1, ?2)
+             ON CONFLICT(rule) DO UPDATE SET match_count = match_count + 1, last_matched = MAX(last_matched, excluded.last_matched)",
+            params![rule, at],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the rules that matched an observed process at or after `since`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn rules_matched_since(&self, since: i64) -> Result<Vec<String>, SynapseError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT rule FROM rule_matches WHERE last_matched >= ?1")?;
+        let rules = stmt
+            .query_map([since], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(rules)
+    }
+
+    /// Counts events starting in `[start, end)` per stored status, most frequent first.
+    /// Statuses are returned as stored, so non-canonical values show up as their own rows.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the query fails.
+    pub fn count_events_by_status(
+        &self,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(String, i64)>, SynapseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT status, COUNT(*) FROM app_usage_events WHERE start_time >= ?1 AND start_time < ?2 GROUP BY status ORDER BY COUNT(*) DESC, status",
+        )?;
+        let counts = stmt
+            .query_map([start, end], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, i64)>, _>>()?;
+        Ok(counts)
+    }
+
     pub fn execute_sql(
         &self,
         sql: &str,
@@ -242,6 +1084,92 @@ impl DbConn for DbHandle {
     }
 }
 
+/// Adds a column to a table created by an older version of the schema.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Marks a session as synced from async code, on a fresh connection to the existing database
+/// at `db_path`. Failures are only logged: the session stays pending and is reported as such.
+pub async fn mark_session_synced_async(db_path: String, session_id: Uuid) {
+    let result = tokio::task::spawn_blocking(move || {
+        DbHandle::open_existing(&db_path)?.mark_session_synced(session_id)
+    })
+    .await;
+    if let Ok(Err(e)) = result {
+        eprintln!("[Supabase] Failed to mark session {} synced: {}", session_id, e);
+    }
+}
+
+/// Records a failed session push from async code. See [`mark_session_synced_async`].
+pub async fn record_sync_failure_async(db_path: String, session_id: Uuid, error: String) {
+    let failed_at = Local::now().timestamp();
+    let result = tokio::task::spawn_blocking(move || {
+        DbHandle::open_existing(&db_path)?.record_sync_failure(session_id, &error, failed_at)
+    })
+    .await;
+    if let Ok(Err(e)) = result {
+        eprintln!("[Supabase] Failed to record sync failure for {}: {}", session_id, e);
+    }
+}
+
+/// Queues a failed session update from async code. See [`mark_session_synced_async`].
+pub async fn queue_session_update_async(db_path: String, session_id: Uuid, error: String) {
+    let failed_at = Local::now().timestamp();
+    let result = tokio::task::spawn_blocking(move || {
+        DbHandle::open_existing(&db_path)?.queue_session_update(session_id, &error, failed_at)
+    })
+    .await;
+    if let Ok(Err(e)) = result {
+        eprintln!("[Supabase] Failed to queue update for session {}: {}", session_id, e);
+    }
+}
+
+/// Marks app usage events as synced from async code. See [`mark_session_synced_async`].
+pub async fn mark_events_synced_async(db_path: String, event_ids: Vec<Uuid>) {
+    let result = tokio::task::spawn_blocking(move || {
+        DbHandle::open_existing(&db_path)?.mark_events_synced(&event_ids)
+    })
+    .await;
+    if let Ok(Err(e)) = result {
+        eprintln!("[Supabase] Failed to mark events synced: {}", e);
+    }
+}
+
+/// Formats epoch seconds as an ISO-8601 timestamp in the local timezone.
+pub fn format_local_time(epoch_secs: i64) -> String {
+    format_epoch_in(epoch_secs, &Local)
+}
+
+/// Formats epoch seconds as an ISO-8601 timestamp with `tz`'s offset.
+pub fn format_epoch_in<Tz>(epoch_secs: i64, tz: &Tz) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    match tz.timestamp_opt(epoch_secs, 0).single() {
+        Some(time) => time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        None => epoch_secs.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +1181,13 @@ mod tests {
         }
     }
 
+    fn session_at(start_secs: u64) -> FocusSession {
+        FocusSession::new(
+            UNIX_EPOCH + std::time::Duration::from_secs(start_secs),
+            vec!["code".to_string()],
+        )
+    }
+
     #[test]
     fn creates_tables_and_inserts_session() {
         let db = db_in_memory();
@@ -268,8 +1203,32 @@ mod tests {
                 [],
             )
             .unwrap();
-        let id = db.insert_session(12345).unwrap();
-        assert_ne!(id, Uuid::nil());
+        let session = session_at(12345);
+        assert!(db.insert_session(&session).unwrap());
+        let count: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM focus_sessions WHERE id = ?1", [session.id.to_string()], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn inserting_the_same_session_twice_keeps_one_row() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let mut session = session_at(12345);
+        assert!(db.insert_session(&session).unwrap());
+        // A retry, even with state that moved on in memory, leaves the stored row alone
+        session.distraction_attempts = 4;
+        assert!(!db.insert_session(&session).unwrap());
+        let (count, distractions): (i64, i64) = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*), MAX(distraction_attempts) FROM focus_sessions WHERE id = ?1",
+                [session.id.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((count, distractions), (1, 0));
     }
 
     #[test]
@@ -324,7 +1283,9 @@ mod tests {
                 [],
             )
             .unwrap();
-        let id = db.insert_session(12345).unwrap();
+        let session = session_at(12345);
+        db.insert_session(&session).unwrap();
+        let id = session.id;
         db.update_session(id, 54321, "notepad.exe,word.exe", 2)
             .unwrap();
         let mut stmt = db
@@ -358,11 +1319,315 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn inflight_app_save_and_take() {
+        let db = DbHandle::test_in_memory_with_schema();
+        assert!(db.take_inflight_app().unwrap().is_none());
+        db.save_inflight_app("code.exe", 100, 150).unwrap();
+        db.save_inflight_app("word.exe", 200, 250).unwrap();
+        assert_eq!(
+            db.take_inflight_app().unwrap(),
+            Some(("word.exe".to_string(), 200, 250))
+        );
+        // Taking clears the saved state
+        assert!(db.take_inflight_app().unwrap().is_none());
+    }
+
+    #[test]
+    fn events_carry_local_time_matching_epoch() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let session = session_at(1_700_000_000);
+        db.insert_session(&session).unwrap();
+        let session_id = session.id;
+        db.insert_app_usage_event("code.exe", UsageStatus::Allowed, Some(session_id), 1_700_000_000, 1_700_000_600, 600, None, None)
+            .unwrap();
+        let ist = chrono::FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+
+        let events = db.get_app_usage_events_in_timezone(session_id, &ist).unwrap();
+        assert_eq!(events.len(), 1);
+        // 1_700_000_000 is 2023-11-14T22:13:20Z
+        assert_eq!(events[0].start_local, "2023-11-15T03:43:20+05:30");
+        assert_eq!(events[0].end_local, "2023-11-15T03:53:20+05:30");
+        assert_eq!(events[0].event.start_time, 1_700_000_000);
+        assert_eq!(format_epoch_in(1_700_000_000, &chrono::Utc), "2023-11-14T22:13:20+00:00");
+    }
+
+    #[test]
+    fn events_store_canonical_status_and_count_by_status() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let session = session_at(100);
+        db.insert_session(&session).unwrap();
+        let session_id = session.id;
+        for (status, start) in [
+            (UsageStatus::Blocked, 100),
+            (UsageStatus::Allowed, 110),
+            (UsageStatus::Blocked, 120),
+            (UsageStatus::Blocked, 500),
+        ] {
+            db.insert_app_usage_event("app", status, Some(session_id), start, start + 5, 5, None, None)
+                .unwrap();
+        }
+        let events = db.get_app_usage_events_for_session(session_id).unwrap();
+        assert_eq!(events[0].status, "blocked");
+        assert_eq!(events[1].status, "allowed");
+
+        assert_eq!(
+            db.count_events_by_status(100, 200).unwrap(),
+            vec![("blocked".to_string(), 2), ("allowed".to_string(), 1)]
+        );
+        assert!(db.count_events_by_status(200, 300).unwrap().is_empty());
+    }
+
+    #[test]
+    fn schema_version_is_latest_after_migration_and_zero_before() {
+        assert_eq!(DbHandle::test_in_memory().schema_version().unwrap(), 0);
+
+        let mut db = DbHandle::test_in_memory_with_schema();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        // Re-running the schema keeps the version
+        DbHandle::create_schema(db.test_conn()).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn vacuum_reclaims_free_pages() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let value = "x".repeat(1000);
+        for i in 0..500 {
+            db.set_setting_as(&format!("key{}", i), &value).unwrap();
+        }
+        db.conn.execute("DELETE FROM settings", []).unwrap();
+        let before = db.free_page_count().unwrap();
+        assert!(before > 0);
+
+        db.vacuum().unwrap();
+        assert!(db.free_page_count().unwrap() < before);
+
+        // Not inside a transaction
+        let tx = db.conn.unchecked_transaction().unwrap();
+        assert!(matches!(db.vacuum(), Err(SynapseError::Other(_))));
+        drop(tx);
+    }
+
+    #[test]
+    fn vacuum_if_due_runs_at_most_once_per_interval() {
+        let db = DbHandle::test_in_memory_with_schema();
+        assert!(db.vacuum_if_due(1_700_000_000).unwrap());
+        assert!(!db.vacuum_if_due(1_700_000_000 + VACUUM_INTERVAL_SECS - 1).unwrap());
+        assert!(db.vacuum_if_due(1_700_000_000 + VACUUM_INTERVAL_SECS).unwrap());
+    }
+
+    #[test]
+    fn schema_normalizes_legacy_statuses() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let legacy = [("a", "BLOCKED "), ("b", "distraction"), ("c", "Active"), ("d", "allowed")];
+        for (id, status) in legacy {
+            db.conn
+                .execute(
+                    "INSERT INTO app_usage_events (id, process_name, status, start_time) VALUES (?1, 'app', ?2, 100)",
+                    params![id, status],
+                )
+                .unwrap();
+        }
+        DbHandle::create_schema(&db.conn).unwrap();
+        assert_eq!(
+            db.count_events_by_status(0, 1000).unwrap(),
+            vec![("allowed".to_string(), 2), ("blocked".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn settings_round_trip_and_overwrite() {
+        let db = DbHandle::test_in_memory_with_schema();
+        assert_eq!(db.get_setting("theme").unwrap(), None);
+
+        db.set_setting("theme", "\"dark\"").unwrap();
+        assert_eq!(db.get_setting("theme").unwrap().as_deref(), Some("\"dark\""));
+        db.set_setting("theme", "\"light\"").unwrap();
+        assert_eq!(db.get_setting_as::<String>("theme").unwrap().as_deref(), Some("light"));
+
+        db.set_setting_as("break_reminders", &true).unwrap();
+        assert_eq!(db.get_setting_as::<bool>("break_reminders").unwrap(), Some(true));
+        db.set_setting_as("daily_goal_secs", &7200i64).unwrap();
+        assert_eq!(db.get_setting_as::<i64>("daily_goal_secs").unwrap(), Some(7200));
+        assert_eq!(db.get_setting_as::<i64>("missing").unwrap(), None);
+
+        // Values must be JSON, and must decode as the requested type
+        assert!(matches!(db.set_setting("theme", "dark"), Err(SynapseError::Serde(_))));
+        assert!(db.get_setting_as::<bool>("theme").is_err());
+    }
+
+    #[test]
+    fn date_goal_overrides_weekday_goal() {
+        let db = DbHandle::test_in_memory_with_schema();
+        // 2024-01-01 and 2024-01-08 are Mondays
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let next_monday = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+        assert_eq!(db.get_goal_for_day(monday).unwrap(), None);
+
+        db.set_goal(GoalKey::Weekday(chrono::Weekday::Mon), 3600).unwrap();
+        db.set_goal(GoalKey::Date(next_monday), 600).unwrap();
+        assert_eq!(db.get_goal_for_day(monday).unwrap(), Some(3600));
+        assert_eq!(db.get_goal_for_day(next_monday).unwrap(), Some(600));
+
+        // Setting again replaces the goal
+        db.set_goal(GoalKey::Weekday(chrono::Weekday::Mon), 7200).unwrap();
+        assert_eq!(db.get_goal_for_day(monday).unwrap(), Some(7200));
+        assert_eq!(db.get_goal_for_day(monday.succ_opt().unwrap()).unwrap(), None);
+    }
+
+    #[test]
+    fn schema_adds_matched_rule_to_existing_table() {
+        let db = db_in_memory();
+        db.conn
+            .execute(
+                "CREATE TABLE app_usage_events (
+                id TEXT PRIMARY KEY,
+                process_name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                session_id TEXT,
+                start_time INTEGER,
+                end_time INTEGER,
+                duration_secs INTEGER
+            )",
+                [],
+            )
+            .unwrap();
+        DbHandle::create_schema(&db.conn).unwrap();
+        let session_id = Uuid::new_v4();
+        db.insert_app_usage_event(
+            "chrome.exe",
+            UsageStatus::Blocked,
+            Some(session_id),
+            100,
+            110,
+            10,
+            Some("chrome.exe"),
+            None,
+        )
+        .unwrap();
+        let events = db.get_app_usage_events_for_session(session_id).unwrap();
+        assert_eq!(events[0].matched_rule.as_deref(), Some("chrome.exe"));
+    }
+
+    #[test]
+    fn usage_events_keep_the_raw_process_name() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let session = session_at(100);
+        db.insert_session(&session).unwrap();
+        let session_id = session.id;
+        for (start, raw) in [(100, Some("C:\\Tools\\Code.exe")), (200, Some("Code.exe")), (300, None)] {
+            db.insert_app_usage_event("code.exe", UsageStatus::Allowed, Some(session_id), start, start + 50, 50, None, raw)
+                .unwrap();
+        }
+        let events = db.get_app_usage_events_for_session(session_id).unwrap();
+        assert!(events.iter().all(|event| event.process_name == "code.exe"));
+        assert_eq!(
+            db.raw_process_names_between(0, 1000).unwrap(),
+            vec![
+                ("code.exe".to_string(), "C:\\Tools\\Code.exe".to_string()),
+                ("code.exe".to_string(), "Code.exe".to_string()),
+            ]
+        );
+        assert_eq!(db.raw_process_names_between(150, 1000).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn queued_sessions_back_off_and_stop_after_max_attempts() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let session = session_at(100);
+        db.insert_session(&session).unwrap();
+        let due = |now: i64| db.due_session_updates(now).unwrap().len();
+
+        // A session deferred while sync was paused is due at once
+        db.defer_session_sync(session.id, 1_000).unwrap();
+        assert_eq!(due(1_000), 1);
+
+        // Each failure doubles the wait before the next retry
+        db.queue_session_update(session.id, "boom", 1_000).unwrap();
+        assert_eq!(due(1_000 + SESSION_RETRY_BASE_SECS - 1), 0);
+        assert_eq!(due(1_000 + SESSION_RETRY_BASE_SECS), 1);
+        db.queue_session_update(session.id, "boom", 2_000).unwrap();
+        assert_eq!(due(2_000 + SESSION_RETRY_BASE_SECS), 0);
+        assert_eq!(due(2_000 + 2 * SESSION_RETRY_BASE_SECS), 1);
+
+        // After the last attempt it stays queued but is no longer retried
+        for _ in 2..SESSION_RETRY_MAX_ATTEMPTS {
+            db.queue_session_update(session.id, "boom", 3_000).unwrap();
+        }
+        assert_eq!(due(i64::MAX / 2), 0);
+        assert_eq!(db.pending_session_updates().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn readonly_handle_reads_but_rejects_writes() {
+        let path = std::env::temp_dir().join(format!("synapse_readonly_{}.db", Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        DbHandle::open(path).unwrap().insert_session(&session_at(12345)).unwrap();
+
+        let db = DbHandle::open_readonly(path).unwrap();
+        let count: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM focus_sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(matches!(db.insert_session(&session_at(67890)), Err(SynapseError::Db(_))));
+        drop(db);
+        // Nothing is created for a missing database
+        let _ = std::fs::remove_file(path);
+        assert!(DbHandle::open_readonly(path).is_err());
+        assert!(!std::path::Path::new(path).exists());
+    }
+
+    #[tokio::test]
+    async fn async_sync_updates_reuse_the_handle_database() {
+        let path = std::env::temp_dir().join(format!("synapse_existing_{}.db", Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let db = DbHandle::open(path).unwrap();
+        let session = session_at(12345);
+        db.insert_session(&session).unwrap();
+        assert_eq!(db.path(), path);
+
+        mark_session_synced_async(db.path(), session.id).await;
+        let synced: i64 = db
+            .conn
+            .query_row("SELECT is_synced FROM focus_sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(synced, 1);
+        drop(db);
+        // Nothing is created for a missing database
+        let _ = std::fs::remove_file(path);
+        assert!(DbHandle::open_existing(path).is_err());
+        assert!(!std::path::Path::new(path).exists());
+    }
+
+    #[test]
+    fn reopen_session_clears_only_its_end_time() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (reopened, other) = (session_at(100), session_at(200));
+        for session in [&reopened, &other] {
+            db.insert_session(session).unwrap();
+            db.update_session(session.id, 300, "code", 0).unwrap();
+        }
+        db.reopen_session(reopened.id).unwrap();
+        let end_time = |id: Uuid| -> Option<i64> {
+            db.conn
+                .query_row(
+                    "SELECT end_time FROM focus_sessions WHERE id = ?1",
+                    [id.to_string()],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(end_time(reopened.id), None);
+        assert_eq!(end_time(other.id), Some(300));
+    }
+
     #[test]
     fn insert_session_invalid_table() {
         let db = db_in_memory();
         // Do not create the table, should error
-        let result = db.insert_session(12345);
+        let result = db.insert_session(&session_at(12345));
         assert!(result.is_err());
     }
 
@@ -19,6 +19,10 @@ pub enum SupabaseError {
     Config(String),
     #[error("API error: {0}")]
     Api(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Unreachable: {0}")]
+    Unreachable(String),
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -1,37 +1,39 @@
 //! Graceful shutdown module: handles Ctrl-C signal for a clean application exit.
 
 use crate::logger::log_error;
-use crate::session::SessionManager;
+use crate::session::{FocusSession, SessionManager};
 use ctrlc;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, Sender},
     Arc, Mutex,
 };
 
 /// Installs a Ctrl-C handler to gracefully shut down the application.
 ///
-/// On Ctrl-C, it sets a shutdown flag and ends any active session.
+/// On Ctrl-C, it sets a shutdown flag and ends any active session. The ended session is
+/// sent on the returned channel, so the main loop can sync it once it stops.
 ///
 /// # Note
 /// This function will only set the handler if one hasn't been set already.
 /// Multiple calls will be ignored to prevent "MultipleHandlers" errors.
-pub fn install(session_mgr: Arc<Mutex<SessionManager>>, shutdown_flag: Arc<AtomicBool>) {
+pub fn install(
+    session_mgr: Arc<Mutex<SessionManager>>,
+    shutdown_flag: Arc<AtomicBool>,
+) -> Receiver<FocusSession> {
     // Use a static flag to track if we've already set a handler
     static mut HANDLER_SET: bool = false;
 
+    let (ended_tx, ended_rx) = mpsc::channel();
     unsafe {
         if HANDLER_SET {
             // Handler already set, skip
-            return;
+            return ended_rx;
         }
 
         match ctrlc::set_handler(move || {
             shutdown_flag.store(true, Ordering::SeqCst);
-            if let Ok(mut mgr) = session_mgr.lock() {
-                if let Err(e) = mgr.end_active_session() {
-                    log_error(&e);
-                }
-            }
+            end_for_shutdown(&session_mgr, &ended_tx);
         }) {
             Ok(_) => {
                 HANDLER_SET = true;
@@ -47,6 +49,25 @@ pub fn install(session_mgr: Arc<Mutex<SessionManager>>, shutdown_flag: Arc<Atomi
             }
         }
     }
+    ended_rx
+}
+
+/// Ends any active session and saves the in-flight app, sending the ended session on
+/// `ended_tx`.
+fn end_for_shutdown(session_mgr: &Mutex<SessionManager>, ended_tx: &Sender<FocusSession>) {
+    if let Ok(mut mgr) = session_mgr.lock() {
+        match mgr.end_active_session() {
+            // The receiver is gone only once the main loop has exited
+            Ok(Some(session)) => {
+                let _ = ended_tx.send(session);
+            }
+            Ok(None) => {}
+            Err(e) => log_error(&e),
+        }
+        if let Err(e) = mgr.save_inflight_app() {
+            log_error(&e);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -75,4 +96,24 @@ mod tests {
         assert!(shutdown_flag.load(Ordering::SeqCst));
         // No panic means cleanup logic is safe
     }
+
+    #[test]
+    fn test_ended_session_is_forwarded_for_sync() {
+        let rules = AppRules::test_with_rules(vec!["code".to_string()], vec![]);
+        let db = DbHandle::test_in_memory_with_schema();
+        let mgr = Mutex::new(SessionManager::new(rules, db, None, None));
+        mgr.lock()
+            .unwrap()
+            .poll_snapshot(Some("code".to_string()), vec!["code".to_string()])
+            .unwrap();
+        let (ended_tx, ended_rx) = mpsc::channel();
+
+        end_for_shutdown(&mgr, &ended_tx);
+        let session = ended_rx.try_recv().expect("ended session forwarded");
+        assert!(session.end_time.is_some());
+        assert!(mgr.lock().unwrap().current_session().is_none());
+        // Nothing is left to end a second time
+        end_for_shutdown(&mgr, &ended_tx);
+        assert!(ended_rx.try_recv().is_err());
+    }
 }
@@ -0,0 +1,236 @@
+//! Hooks module: runs user-configured shell commands when focus sessions start and end.
+
+use crate::config::Config;
+use crate::constants::HOOK_TIMEOUT_SECS;
+use crate::error::SynapseError;
+use crate::logger::log_error_with_context;
+use crate::session::{SessionEvent, SessionManager};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Runs a hook command with the given environment variables; replaceable in tests.
+pub type CommandRunner = Arc<dyn Fn(&str, &[(String, String)]) + Send + Sync>;
+
+/// Commands run on session start and end, with the session's details in `SYNAPSE_*`
+/// environment variables.
+#[derive(Clone)]
+pub struct SessionHooks {
+    on_start: Option<String>,
+    on_end: Option<String>,
+    runner: CommandRunner,
+}
+
+impl SessionHooks {
+    /// Creates hooks for the configured `on_session_start_cmd` and `on_session_end_cmd`,
+    /// run in the background through the system shell.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            on_start: config.on_session_start_cmd.clone(),
+            on_end: config.on_session_end_cmd.clone(),
+            runner: Arc::new(spawn_hook),
+        }
+    }
+
+    /// Replaces how hook commands are run (for tests).
+    pub fn with_runner(mut self, runner: CommandRunner) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    /// Returns true if no hook command is configured.
+    pub fn is_empty(&self) -> bool {
+        self.on_start.is_none() && self.on_end.is_none()
+    }
+
+    /// Runs the hook matching `event`, if one is configured.
+    pub fn handle(&self, event: &SessionEvent) {
+        let (command, env) = match event {
+            SessionEvent::SessionStarted {
+                session_id,
+                start_time,
+                work_apps,
+            } => (
+                &self.on_start,
+                vec![
+                    ("SYNAPSE_EVENT", "session_started".to_string()),
+                    ("SYNAPSE_SESSION_ID", session_id.to_string()),
+                    ("SYNAPSE_START_TIME", start_time.to_string()),
+                    ("SYNAPSE_WORK_APPS", work_apps.join(",")),
+                ],
+            ),
+            SessionEvent::SessionEnded {
+                session_id,
+                end_time,
+                distraction_attempts,
+                reason,
+            } => (
+                &self.on_end,
+                vec![
+                    ("SYNAPSE_EVENT", "session_ended".to_string()),
+                    ("SYNAPSE_SESSION_ID", session_id.to_string()),
+                    ("SYNAPSE_END_TIME", end_time.to_string()),
+                    ("SYNAPSE_DISTRACTION_ATTEMPTS", distraction_attempts.to_string()),
+                    ("SYNAPSE_END_REASON", reason.clone()),
+                ],
+            ),
+            _ => return,
+        };
+        if let Some(command) = command {
+            let env: Vec<(String, String)> =
+                env.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+            (self.runner)(command, &env);
+        }
+    }
+
+    /// Runs these hooks from `mgr`'s session events, alongside any other observer.
+    /// Does nothing if no hook is configured.
+    pub fn attach(self, mgr: &mut SessionManager) {
+        if self.is_empty() {
+            return;
+        }
+        mgr.add_event_observer(Box::new(move |event: &SessionEvent| self.handle(event)));
+    }
+}
+
+/// Runs `command` through the system shell on a background thread, killing it after
+/// `HOOK_TIMEOUT_SECS`. Failures and non-zero exits are logged.
+fn spawn_hook(command: &str, env: &[(String, String)]) {
+    let command = command.to_string();
+    let env = env.to_vec();
+    std::thread::spawn(move || {
+        if let Err(e) = run_hook(&command, &env, Duration::from_secs(HOOK_TIMEOUT_SECS)) {
+            log_error_with_context(&format!("Running session hook '{}'", command), &e);
+        }
+    });
+}
+
+fn run_hook(command: &str, env: &[(String, String)], timeout: Duration) -> Result<(), SynapseError> {
+    #[cfg(target_os = "windows")]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+    let mut child = shell
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| SynapseError::Platform(format!("Failed to start hook: {}", e)))?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => {
+                return Err(SynapseError::Platform(format!("Hook exited with {}", status)))
+            }
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(SynapseError::Platform(format!(
+                    "Hook timed out after {}s",
+                    timeout.as_secs()
+                )));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(100)),
+            Err(e) => {
+                return Err(SynapseError::Platform(format!("Failed to wait for hook: {}", e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apprules::AppRules;
+    use crate::db::DbHandle;
+    use std::sync::Mutex;
+
+    type Calls = Arc<Mutex<Vec<(String, Vec<(String, String)>)>>>;
+
+    fn recording_hooks(start: Option<&str>, end: Option<&str>) -> (SessionHooks, Calls) {
+        let config = Config {
+            on_session_start_cmd: start.map(str::to_string),
+            on_session_end_cmd: end.map(str::to_string),
+            ..Config::default()
+        };
+        let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+        let recorder = calls.clone();
+        let hooks = SessionHooks::from_config(&config).with_runner(Arc::new(move |command, env| {
+            recorder
+                .lock()
+                .unwrap()
+                .push((command.to_string(), env.to_vec()));
+        }));
+        (hooks, calls)
+    }
+
+    fn env_value<'a>(env: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        env.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn configured_commands_fire_on_session_start_and_end() {
+        let rules = AppRules::test_with_rules(vec!["code".to_string()], vec![]);
+        let mut mgr = SessionManager::new(rules, DbHandle::test_in_memory_with_schema(), None, None);
+        let (hooks, calls) = recording_hooks(Some("lights on"), Some("lights off"));
+        hooks.attach(&mut mgr);
+
+        mgr.poll_snapshot(Some("code".to_string()), vec!["code".to_string()])
+            .unwrap();
+        let session_id = mgr.current_session().unwrap().id.to_string();
+        mgr.poll_snapshot(None, vec![]).unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        let (command, env) = &calls[0];
+        assert_eq!(command, "lights on");
+        assert_eq!(env_value(env, "SYNAPSE_EVENT"), Some("session_started"));
+        assert_eq!(env_value(env, "SYNAPSE_SESSION_ID"), Some(session_id.as_str()));
+        assert_eq!(env_value(env, "SYNAPSE_WORK_APPS"), Some("code"));
+        let (command, env) = &calls[1];
+        assert_eq!(command, "lights off");
+        assert_eq!(env_value(env, "SYNAPSE_EVENT"), Some("session_ended"));
+        assert_eq!(env_value(env, "SYNAPSE_SESSION_ID"), Some(session_id.as_str()));
+        assert_eq!(env_value(env, "SYNAPSE_DISTRACTION_ATTEMPTS"), Some("0"));
+    }
+
+    #[test]
+    fn hooks_share_the_observer_and_unset_hooks_do_nothing() {
+        let rules = AppRules::test_with_rules(vec!["code".to_string()], vec![]);
+        let mut mgr = SessionManager::new(rules, DbHandle::test_in_memory_with_schema(), None, None);
+        let events = Arc::new(Mutex::new(0));
+        let counter = events.clone();
+        mgr.set_on_event(Some(Box::new(move |_| *counter.lock().unwrap() += 1)));
+        let (hooks, calls) = recording_hooks(None, Some("notify-send done"));
+        hooks.attach(&mut mgr);
+
+        mgr.poll_snapshot(Some("code".to_string()), vec!["code".to_string()])
+            .unwrap();
+        mgr.poll_snapshot(None, vec![]).unwrap();
+
+        assert_eq!(*events.lock().unwrap(), 2);
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "notify-send done");
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn failing_and_slow_hooks_are_reported() {
+        let env = vec![("SYNAPSE_EVENT".to_string(), "session_ended".to_string())];
+        assert!(run_hook("test \"$SYNAPSE_EVENT\" = session_ended", &env, Duration::from_secs(5)).is_ok());
+        assert!(run_hook("exit 3", &env, Duration::from_secs(5)).is_err());
+        let slow = run_hook("sleep 5", &env, Duration::from_millis(200));
+        assert!(matches!(slow, Err(SynapseError::Platform(msg)) if msg.contains("timed out")));
+    }
+}
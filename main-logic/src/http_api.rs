@@ -0,0 +1,104 @@
+//! HTTP API module: read-only JSON endpoints over the `api` functions, for running Synapse
+//! headless and building your own dashboard.
+//!
+//! Built only with the `http-api` feature. Endpoints:
+//! - `GET /stats/today`: today's focus time, distractions and session count
+//! - `GET /sessions/recent?limit=N`: the most recent sessions, newest first
+//! - `GET /session/current`: the running session, or `null`
+
+use crate::api;
+use crate::async_db::AsyncDbHandle;
+use crate::constants::{DEFAULT_RECENT_SESSIONS_LIMIT, MAX_RECENT_SESSIONS_LIMIT};
+use crate::error::SynapseError;
+use crate::session::{FocusSession, SessionManager};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Shared state of the HTTP API.
+#[derive(Clone)]
+pub struct HttpApiState {
+    db: AsyncDbHandle,
+    session_mgr: Arc<Mutex<SessionManager>>,
+}
+
+impl HttpApiState {
+    /// Creates the state from the database and the backend's live session manager.
+    pub fn new(db: AsyncDbHandle, session_mgr: Arc<Mutex<SessionManager>>) -> Self {
+        Self { db, session_mgr }
+    }
+}
+
+/// A `SynapseError` rendered as a 500 response with a JSON `error` message.
+struct ApiError(SynapseError);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.0.to_string() }));
+        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+    }
+}
+
+impl From<SynapseError> for ApiError {
+    fn from(e: SynapseError) -> Self {
+        Self(e)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentQuery {
+    limit: Option<u32>,
+}
+
+async fn stats_today(
+    State(state): State<HttpApiState>,
+) -> Result<Json<api::TodayStats>, ApiError> {
+    Ok(Json(state.db.read(api::today_stats).await?))
+}
+
+async fn recent_sessions(
+    State(state): State<HttpApiState>,
+    Query(query): Query<RecentQuery>,
+) -> Result<Json<Vec<api::SessionRecord>>, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_RECENT_SESSIONS_LIMIT)
+        .min(MAX_RECENT_SESSIONS_LIMIT);
+    let sessions = state.db.read(move |db| api::recent_sessions(db, limit)).await?;
+    Ok(Json(sessions))
+}
+
+async fn current_session(State(state): State<HttpApiState>) -> Json<Option<FocusSession>> {
+    Json(state.session_mgr.lock().unwrap().current_session().cloned())
+}
+
+/// Builds the router with every endpoint.
+pub fn router(state: HttpApiState) -> Router {
+    Router::new()
+        .route("/stats/today", get(stats_today))
+        .route("/sessions/recent", get(recent_sessions))
+        .route("/session/current", get(current_session))
+        .with_state(state)
+}
+
+/// Binds `addr` and serves the API on a background task.
+///
+/// Returns the bound address, which differs from `addr` when its port is 0.
+///
+/// # Errors
+/// Returns `SynapseError::Io` if the address cannot be bound.
+pub async fn start(addr: SocketAddr, state: HttpApiState) -> Result<SocketAddr, SynapseError> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router(state)).await {
+            eprintln!("HTTP API server stopped: {}", e);
+        }
+    });
+    Ok(local_addr)
+}
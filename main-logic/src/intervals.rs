@@ -0,0 +1,111 @@
+//! Intervals module: arithmetic over half-open `[start, end)` time intervals in epoch seconds,
+//! shared by the time-based APIs so overlapping records are never counted twice.
+
+/// Sorts and merges intervals that overlap or touch, dropping empty and inverted ones.
+pub fn merge_overlapping(mut intervals: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    intervals.retain(|&(start, end)| end > start);
+    intervals.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Returns the total time covered by `intervals`, counting overlaps once.
+pub fn total_covered(intervals: &[(i64, i64)]) -> i64 {
+    merge_overlapping(intervals.to_vec())
+        .iter()
+        .map(|(start, end)| end - start)
+        .sum()
+}
+
+/// Splits the covered time into buckets of `bucket_secs` aligned to `origin`.
+///
+/// Returns `(bucket_start, covered_secs)` for every bucket with coverage, in order.
+/// Overlaps are counted once. Returns nothing if `bucket_secs` is not positive.
+pub fn split_by_buckets(
+    intervals: &[(i64, i64)],
+    origin: i64,
+    bucket_secs: i64,
+) -> Vec<(i64, i64)> {
+    if bucket_secs <= 0 {
+        return Vec::new();
+    }
+    let mut buckets: Vec<(i64, i64)> = Vec::new();
+    for (start, end) in merge_overlapping(intervals.to_vec()) {
+        let mut cursor = start;
+        while cursor < end {
+            let bucket_start = origin + (cursor - origin).div_euclid(bucket_secs) * bucket_secs;
+            let piece_end = end.min(bucket_start + bucket_secs);
+            match buckets.last_mut() {
+                Some(last) if last.0 == bucket_start => last.1 += piece_end - cursor,
+                _ => buckets.push((bucket_start, piece_end - cursor)),
+            }
+            cursor = piece_end;
+        }
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_overlapping_and_unsorted() {
+        assert_eq!(
+            merge_overlapping(vec![(50, 80), (0, 10), (5, 20), (70, 100)]),
+            vec![(0, 20), (50, 100)]
+        );
+    }
+
+    #[test]
+    fn merges_adjacent() {
+        assert_eq!(merge_overlapping(vec![(10, 20), (0, 10), (20, 25)]), vec![(0, 25)]);
+        assert_eq!(total_covered(&[(0, 10), (10, 20)]), 20);
+    }
+
+    #[test]
+    fn nested_intervals_count_once() {
+        assert_eq!(merge_overlapping(vec![(0, 100), (10, 20), (30, 40)]), vec![(0, 100)]);
+        assert_eq!(total_covered(&[(0, 100), (10, 20), (0, 100)]), 100);
+    }
+
+    #[test]
+    fn drops_zero_length_and_inverted() {
+        assert_eq!(merge_overlapping(vec![(5, 5), (9, 3), (0, 2)]), vec![(0, 2)]);
+        assert_eq!(total_covered(&[(5, 5)]), 0);
+        assert!(merge_overlapping(Vec::new()).is_empty());
+        // A zero-length interval touching another does not extend it
+        assert_eq!(merge_overlapping(vec![(0, 10), (10, 10)]), vec![(0, 10)]);
+    }
+
+    #[test]
+    fn splits_into_aligned_buckets() {
+        // Crosses a bucket boundary and overlaps another interval
+        assert_eq!(
+            split_by_buckets(&[(50, 130), (60, 70), (250, 260)], 0, 100),
+            vec![(0, 50), (100, 30), (200, 10)]
+        );
+        // Spans several whole buckets, aligned to a non-zero origin
+        assert_eq!(
+            split_by_buckets(&[(1005, 1305)], 1000, 100),
+            vec![(1000, 95), (1100, 100), (1200, 100), (1300, 5)]
+        );
+        // Intervals before the origin land in earlier buckets
+        assert_eq!(split_by_buckets(&[(-30, 10)], 0, 60), vec![(-60, 30), (0, 10)]);
+    }
+
+    #[test]
+    fn bucket_totals_match_coverage() {
+        let intervals = [(0, 3600), (1800, 5400), (7000, 7000), (9000, 9001)];
+        let buckets = split_by_buckets(&intervals, 0, 900);
+        let bucketed: i64 = buckets.iter().map(|(_, secs)| secs).sum();
+        assert_eq!(bucketed, total_covered(&intervals));
+        assert!(split_by_buckets(&intervals, 0, 0).is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+//! Live socket module: streams session events as JSON lines to local TCP clients.
+//!
+//! Built only with the `live-socket` feature. Each connected client receives one line per
+//! [`SessionEvent`]. Client sockets are nonblocking, so a broadcast never waits on a client:
+//! one that stops reading is dropped once its socket buffer is full, as is one that
+//! disconnects.
+
+use crate::error::SynapseError;
+use crate::logger::log_error_with_context;
+use crate::metrics::Metrics;
+use crate::session::{SessionEvent, SessionManager};
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A listening socket that broadcasts session events to every connected client.
+pub struct LiveSocket {
+    local_addr: SocketAddr,
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl LiveSocket {
+    /// Binds `addr` and accepts clients on a background thread.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Io` if the address cannot be bound.
+    pub fn bind(addr: SocketAddr) -> Result<Self, SynapseError> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let clients: Arc<Mutex<Vec<TcpStream>>> = Arc::new(Mutex::new(Vec::new()));
+        let accepted = clients.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    // A client that stops reading must not stall the poll loop
+                    Ok(stream) => match stream.set_nonblocking(true) {
+                        Ok(()) => accepted.lock().unwrap().push(stream),
+                        Err(e) => eprintln!("Live socket failed to configure a client: {}", e),
+                    },
+                    Err(e) => eprintln!("Live socket failed to accept a client: {}", e),
+                }
+            }
+        });
+        Ok(Self {
+            local_addr,
+            clients,
+        })
+    }
+
+    /// Returns the address the socket is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Writes `event` as one JSON line to every client, dropping clients that fail or whose
+    /// socket buffer is full.
+    pub fn broadcast(&self, event: &SessionEvent) {
+        let mut line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Failed to serialize live event: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+        self.clients
+            .lock()
+            .unwrap()
+            .retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+    }
+
+    /// Broadcasts a summary of `metrics`.
+    pub fn broadcast_summary(&self, metrics: &Metrics) {
+        self.broadcast(&SessionEvent::MetricsSummary {
+            total_checks: metrics.total_checks,
+            blocked_count: metrics.blocked_count,
+            session_active: metrics.current_session_active,
+        });
+    }
+}
+
+/// Binds the live socket at `addr` and feeds it from `mgr`'s event observer.
+///
+/// Returns `None`, after logging, if the socket cannot be bound; the backend runs without it.
+pub fn attach(mgr: &mut SessionManager, addr: SocketAddr) -> Option<Arc<LiveSocket>> {
+    match LiveSocket::bind(addr) {
+        Ok(socket) => {
+            println!("Live event socket listening on {}", socket.local_addr());
+            let socket = Arc::new(socket);
+            let observer = socket.clone();
+            mgr.add_event_observer(Box::new(move |event: &SessionEvent| {
+                observer.broadcast(event)
+            }));
+            Some(socket)
+        }
+        Err(e) => {
+            log_error_with_context("Binding live event socket", &e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apprules::AppRules;
+    use crate::db::DbHandle;
+    use std::io::{BufRead, BufReader};
+    use std::net::Ipv4Addr;
+    use std::time::{Duration, Instant};
+
+    fn wait_for_clients(socket: &LiveSocket, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while socket.clients.lock().unwrap().len() < count {
+            assert!(Instant::now() < deadline, "client was never accepted");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn client_receives_session_start() {
+        let rules = AppRules::test_with_rules(vec!["code".to_string()], vec![]);
+        let mut mgr =
+            SessionManager::new(rules, DbHandle::test_in_memory_with_schema(), None, None);
+        let socket = attach(&mut mgr, SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).unwrap();
+        assert!(socket.local_addr().ip().is_loopback());
+
+        let client = TcpStream::connect(socket.local_addr()).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        // Wait for the accept thread to register the client
+        wait_for_clients(&socket, 1);
+
+        mgr.poll_snapshot(Some("code".to_string()), vec!["code".to_string()])
+            .unwrap();
+        let session_id = mgr.current_session().unwrap().id;
+
+        let mut line = String::new();
+        BufReader::new(client).read_line(&mut line).unwrap();
+        let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(event["event"], "session_started");
+        assert_eq!(event["session_id"], session_id.to_string());
+        assert_eq!(event["work_apps"], serde_json::json!(["code"]));
+    }
+
+    #[test]
+    fn client_that_stops_reading_is_dropped_without_blocking() {
+        let socket = LiveSocket::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).unwrap();
+        let _stalled = TcpStream::connect(socket.local_addr()).unwrap();
+        wait_for_clients(&socket, 1);
+
+        let event = SessionEvent::RulesReloadFailed {
+            error: "x".repeat(64 * 1024),
+        };
+        // Each broadcast returns at once; the client goes when its buffer fills up
+        for _ in 0..10_000 {
+            socket.broadcast(&event);
+            if socket.clients.lock().unwrap().is_empty() {
+                return;
+            }
+        }
+        panic!("a client that never reads was not dropped");
+    }
+}
@@ -1,10 +1,153 @@
 //! Logger module: handles logging of events and errors to file and database.
+//!
+//! Structured `tracing` events are written to the same log file by [`FileLogLayer`].
+//! Once the file reaches [`LOG_MAX_BYTES`] it is rotated to `<log file>.1`, shifting older
+//! archives up, and only the newest few archives are kept.
 
+use std::fmt::Write as _;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use crate::constants::{DEFAULT_LOG_KEEP_COUNT, DEFAULT_LOG_PATH, LOG_MAX_BYTES};
 use crate::db::DbHandle;
 use crate::error::SynapseError;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
 use uuid::Uuid;
 
+/// Fallback log file shared by the plain-text logger and [`FileLogLayer`]; `None` until
+/// [`set_log_path`] is called, meaning [`DEFAULT_LOG_PATH`].
+static LOG_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Number of rotated archives kept beside the log file.
+static LOG_KEEP_COUNT: AtomicUsize = AtomicUsize::new(DEFAULT_LOG_KEEP_COUNT);
+
+/// Sets how many rotated log archives are kept; older ones are deleted on the next rotation.
+pub fn set_log_keep_count(count: usize) {
+    LOG_KEEP_COUNT.store(count, Ordering::Relaxed);
+}
+
+/// Sets the log file written from now on. Call it before [`init_tracing`], which keeps
+/// writing to the path set when it was called.
+pub fn set_log_path(path: impl Into<PathBuf>) {
+    *LOG_PATH.write().unwrap() = Some(path.into());
+}
+
+/// Returns the log file currently written to.
+pub fn log_path() -> PathBuf {
+    LOG_PATH
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_LOG_PATH))
+}
+
+/// Returns the path of the `n`th archive of `path`, e.g. `synapse.log.2`.
+fn archive_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Rotates `path` if it has reached `max_bytes`, keeping at most `keep` archives.
+///
+/// The file becomes `<path>.1` and existing archives shift up by one; archives numbered above
+/// `keep`, including leftovers from a larger earlier setting, are deleted. With `keep` at 0 the
+/// file is simply removed. Returns whether a rotation happened.
+///
+/// # Errors
+/// Returns an I/O error if an archive cannot be renamed or deleted.
+pub fn rotate_if_needed(path: &Path, max_bytes: u64, keep: usize) -> std::io::Result<bool> {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.len() >= max_bytes => {}
+        _ => return Ok(false),
+    }
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.", path.file_name().unwrap_or_default().to_string_lossy());
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let name = entry.file_name();
+        let n = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(&prefix))
+            .and_then(|n| n.parse::<usize>().ok());
+        // These would land beyond `keep` after shifting
+        if n.is_some_and(|n| n >= keep) {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    if keep == 0 {
+        std::fs::remove_file(path)?;
+        return Ok(true);
+    }
+    for n in (1..keep).rev() {
+        let from = archive_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, archive_path(path, n + 1))?;
+        }
+    }
+    std::fs::rename(path, archive_path(path, 1))?;
+    Ok(true)
+}
+
+/// Appends `entry` to the log file at `path`, rotating it first if it is full.
+fn append_to_log(path: &Path, entry: &str) -> std::io::Result<()> {
+    if let Err(e) = rotate_if_needed(path, LOG_MAX_BYTES, LOG_KEEP_COUNT.load(Ordering::Relaxed)) {
+        eprintln!("Failed to rotate {}: {}", path.display(), e);
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(entry.as_bytes())
+}
+
+/// A `tracing` layer that appends each event, with its fields, to a log file.
+pub struct FileLogLayer {
+    path: PathBuf,
+}
+
+impl FileLogLayer {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for FileLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let mut line = format!("[{}] {}:", meta.level(), meta.target());
+        event.record(&mut LineVisitor(&mut line));
+        line.push('\n');
+        let _ = append_to_log(&self.path, &line);
+    }
+}
+
+/// Formats event fields as ` message key=value ...`.
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Installs a global `tracing` subscriber that writes to the log file set by [`set_log_path`].
+///
+/// Does nothing if the embedding application already installed its own subscriber.
+pub fn init_tracing() {
+    let subscriber = tracing_subscriber::registry().with(FileLogLayer::new(log_path()));
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
+
 /// Logs an app usage event to the database (if available) and to the fallback log file.
 ///
 /// # Arguments
@@ -38,13 +181,9 @@ pub fn log_event(db_handle: Option<&DbHandle>, process: &str, blocked: bool, _di
     // Fallback: also log to file as before
     let status = if blocked { "BLOCKED" } else { "ALLOWED" };
     let entry = format!("{} -> {}\n", status, process);
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("synapse.log")
-        .map_err(|e| SynapseError::Io(std::io::Error::new(e.kind(), format!("Failed to open synapse.log: {}", e))))?;
-    file.write_all(entry.as_bytes())
-        .map_err(|e| SynapseError::Io(std::io::Error::new(e.kind(), format!("Failed to write to synapse.log: {}", e))))?;
+    let path = log_path();
+    append_to_log(&path, &entry)
+        .map_err(|e| SynapseError::Io(std::io::Error::new(e.kind(), format!("Failed to write to {}: {}", path.display(), e))))?;
     Ok(())
 }
 
@@ -54,25 +193,13 @@ pub fn log_event(db_handle: Option<&DbHandle>, process: &str, blocked: bool, _di
 /// * `err` - The error to log
 pub fn log_error(err: &SynapseError) {
     let entry = format!("[ERROR] {}\n", err);
-    if let Ok(mut file) = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("synapse.log")
-    {
-        let _ = file.write_all(entry.as_bytes());
-    }
+    let _ = append_to_log(&log_path(), &entry);
     eprintln!("{}", entry);
 }
 
 pub fn log_error_with_context(context: &str, err: &crate::error::SynapseError) {
     let entry = format!("[ERROR] {}: {}\n", context, err);
-    if let Ok(mut file) = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("synapse.log")
-    {
-        let _ = file.write_all(entry.as_bytes());
-    }
+    let _ = append_to_log(&log_path(), &entry);
     eprintln!("{}", entry);
 }
 
@@ -129,6 +256,31 @@ mod tests {
         assert_eq!(name, process);
     }
 
+    #[test]
+    fn rotation_keeps_only_the_newest_archives() {
+        let dir = std::env::temp_dir().join(format!("synapse_logs_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("synapse.log");
+        // A leftover from a larger keep count is cleaned up too
+        fs::write(archive_path(&path, 7), "stale").unwrap();
+
+        for round in 0..5 {
+            fs::write(&path, format!("round {}", round)).unwrap();
+            assert!(rotate_if_needed(&path, 1, 2).unwrap());
+        }
+        assert!(!rotate_if_needed(&path, 1, 2).unwrap());
+
+        let mut archives: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        archives.sort();
+        assert_eq!(archives, vec!["synapse.log.1", "synapse.log.2"]);
+        assert_eq!(fs::read_to_string(archive_path(&path, 1)).unwrap(), "round 4");
+        assert_eq!(fs::read_to_string(archive_path(&path, 2)).unwrap(), "round 3");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn log_event_file_error() {
         // Simulate file error by using an invalid path (readonly dir, etc.)
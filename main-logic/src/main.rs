@@ -1,21 +1,29 @@
 //! Main application entry point and logic loop.
+mod api;
 mod apprules;
+#[cfg(feature = "http-api")]
+mod async_db;
+mod config;
 mod constants;
 mod db;
 mod error;
 mod graceful_shutdown;
+mod hooks;
+#[cfg(feature = "http-api")]
+mod http_api;
+#[cfg(feature = "live-socket")]
+mod live_socket;
 mod logger;
 mod metrics;
 mod platform;
+mod pomodoro;
 mod session;
 mod sync;
 mod types;
-use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::mpsc::channel;
+mod watcher;
 
 use apprules::AppRules;
-use constants::MAIN_LOOP_SLEEP_MS;
+use config::{Config, LiveConfig};
 use db::DbHandle;
 use logger::{log_error, log_error_with_context};
 use metrics::Metrics;
@@ -25,17 +33,30 @@ use std::sync::{
     Arc, Mutex,
 };
 use std::thread;
-use std::time::Duration;
-use sync::{SupabaseSync, SyncStatus};
+use sync::{finalize_and_sync_session, retry_queued_session_updates, SupabaseSync, SyncStatus};
+use watcher::spawn_apprules_watcher;
 
 #[tokio::main]
 async fn main() {
+    let config = Config::load().unwrap_or_else(|e| {
+        log_error_with_context("Loading config, using defaults", &e);
+        Config::default()
+    });
+    logger::set_log_path(&config.log_path);
+    logger::set_log_keep_count(config.log_keep_count);
+    logger::init_tracing();
     // Check Supabase connection at startup
     match SupabaseSync::from_env(false) {
         Ok(_) => println!("Supabase connection established!"),
         Err(e) => println!("Supabase connection failed: {}", e),
     }
-    let apprules = match AppRules::new() {
+    // One rules file for loading, watching and the whitelist action
+    let rules_path = AppRules::path_from_env("apprules.json");
+    // Rules from before apprules.json replaced blacklist.json are folded in once
+    if let Err(e) = AppRules::migrate_legacy_blacklist_at(&rules_path.with_file_name("blacklist.json"), &rules_path) {
+        log_error_with_context("Migrating legacy blacklist.json", &e);
+    }
+    let apprules = match AppRules::load(&rules_path) {
         Ok(rules) => rules,
         Err(e) => {
             log_error_with_context("Initializing AppRules", &e);
@@ -50,10 +71,27 @@ async fn main() {
             return;
         }
     };
-    let supabase_sync = SupabaseSync::from_env(false).ok();
-    let sync_status = Arc::new(Mutex::new(SyncStatus::new()));
-    // Set up a Tokio runtime for async tasks
-    // let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime"); // This line is removed as per edit hint
+    // Reclaim space freed by deletions at most once a week
+    if let Err(e) = db_handle.vacuum_if_due(chrono::Local::now().timestamp()) {
+        log_error_with_context("Vacuuming database", &e);
+    }
+    let supabase_sync = SupabaseSync::from_env(false)
+        .ok()
+        .map(|sync| {
+            sync.with_max_concurrent(config.sync_max_concurrency)
+                .with_fields(config.sync_fields.clone())
+        });
+    let sync_status = Arc::new(Mutex::new(
+        SyncStatus::new().with_failure_threshold(config.sync_failure_threshold),
+    ));
+    // Syncing uses a connection of its own, so the manager lock isn't held across network calls
+    let sync_db = match DbHandle::new() {
+        Ok(db) => db,
+        Err(e) => {
+            log_error_with_context("Opening sync database connection", &e);
+            return;
+        }
+    };
 
     println!(
         "Constructing SessionManager with supabase_sync: {}",
@@ -65,145 +103,124 @@ async fn main() {
         supabase_sync.clone(),
         None,
     )));
+    session_mgr.lock().unwrap().apply_config(&config);
+    crate::hooks::SessionHooks::from_config(&config).attach(&mut session_mgr.lock().unwrap());
+    #[cfg(feature = "live-socket")]
+    let live_socket = crate::live_socket::attach(
+        &mut session_mgr.lock().unwrap(),
+        config.live_socket_addr,
+    );
+    #[cfg(feature = "http-api")]
+    {
+        let state = http_api::HttpApiState::new(
+            async_db::AsyncDbHandle::from_env(),
+            session_mgr.clone(),
+        );
+        match http_api::start(config.http_api_addr, state).await {
+            Ok(addr) => println!("HTTP API listening on http://{}", addr),
+            Err(e) => log_error_with_context("Starting HTTP API", &e),
+        }
+    }
+    // Only Windows can list installed apps; elsewhere every foreground app is recognized
+    #[cfg(target_os = "windows")]
+    if let Err(e) = session_mgr
+        .lock()
+        .unwrap()
+        .register_installed_apps(api::get_installed_apps_api())
+    {
+        log_error_with_context("Registering installed apps", &e);
+    }
+    if let Err(e) = session_mgr.lock().unwrap().restore_inflight_app() {
+        log_error_with_context("Restoring in-flight app", &e);
+    }
     let shutdown_flag = Arc::new(AtomicBool::new(false));
 
-    // --- File watcher for apprules.json ---
+    // --- Live reload of synapse.toml ---
+    let live_config = LiveConfig::new(config);
     {
         let session_mgr = session_mgr.clone();
-        let shutdown_flag = shutdown_flag.clone();
-        thread::spawn(move || {
-            let (tx, rx) = channel();
-            let path_str =
-                std::env::var("APPRULES_PATH").unwrap_or_else(|_| "apprules.json".to_string());
-            let path = Path::new(&path_str);
-            let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())
-                .expect("Failed to create watcher");
-            watcher
-                .watch(path, RecursiveMode::NonRecursive)
-                .expect("Failed to watch apprules.json");
-            while !shutdown_flag.load(Ordering::SeqCst) {
-                if let Ok(event) = rx.recv_timeout(Duration::from_secs(1)) {
-                    match event {
-                        Ok(Event {
-                            kind: EventKind::Modify(_),
-                            ..
-                        }) => {
-                            log::info!("[Watcher] Detected apprules.json change, reloading...");
-                            match AppRules::new() {
-                                Ok(new_rules) => {
-                                    let mut mgr = session_mgr.lock().unwrap();
-                                    mgr.set_apprules(new_rules);
-                                    log::info!("[Watcher] AppRules reloaded successfully.");
-                                }
-                                Err(e) => {
-                                    log::error!("[Watcher] Failed to reload AppRules: {}", e);
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
+        config::watch_config_file(live_config.clone(), shutdown_flag.clone(), move |c| {
+            session_mgr.lock().unwrap().apply_config(c)
         });
     }
 
-    graceful_shutdown::install(session_mgr.clone(), shutdown_flag.clone());
+    // --- File watcher for apprules.json ---
+    {
+        spawn_apprules_watcher(rules_path.clone(), session_mgr.clone(), shutdown_flag.clone());
+    }
 
-    // Set up Supabase sync (optional, can be disabled if env not set)
-    // let supabase_sync = SupabaseSync::from_env(false).ok(); // This line is removed as per edit hint
-    // let sync_status = Arc::new(Mutex::new(SyncStatus::new())); // This line is removed as per edit hint
-    // Set up a Tokio runtime for async tasks // This line is removed as per edit hint
-    // let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime"); // This line is removed as per edit hint
+    let shutdown_ended = graceful_shutdown::install(session_mgr.clone(), shutdown_flag.clone());
 
     while !shutdown_flag.load(Ordering::SeqCst) {
-        let mut mgr = session_mgr.lock().unwrap();
-        let poll_result = match mgr.poll() {
-            Ok(ended_session) => ended_session,
-            Err(e) => {
-                log_error_with_context("Polling session manager", &e);
-                None
+        // The lock is released before syncing and sleeping so embedders can query live state
+        let (poll_result, summary_due) = {
+            let mut mgr = session_mgr.lock().unwrap();
+            let poll_result = match mgr.poll() {
+                Ok(ended_sessions) => ended_sessions,
+                Err(e) => {
+                    log_error_with_context("Polling session manager", &e);
+                    Vec::new()
+                }
+            };
+            metrics.update_from_session(&mgr);
+            let summary_due = metrics.should_log_summary();
+            if summary_due {
+                // Saved with each summary so counters cover the whole day across restarts
+                let today = chrono::Local::now().date_naive();
+                if let Err(e) = metrics.persist(mgr.db_handle(), today) {
+                    log_error_with_context("Saving metrics snapshot", &e);
+                }
+                #[cfg(feature = "live-socket")]
+                if let Some(socket) = &live_socket {
+                    socket.broadcast_summary(&metrics);
+                }
+                if let Err(e) = metrics.log_summary() {
+                    log_error_with_context("Logging metrics summary", &e);
+                }
             }
+            (poll_result, summary_due)
         };
-        metrics.update_from_session(&mgr);
-        if metrics.should_log_summary() {
-            if let Err(e) = metrics.log_summary() {
-                log_error_with_context("Logging metrics summary", &e);
-            }
-        }
-        // If a session just ended, push it to Supabase
-        if let (Some(sync), Some(session)) = (&supabase_sync, poll_result) {
-            match serde_json::to_string_pretty(&session) {
-                Ok(json) => println!("[DEBUG] Pushing session to Supabase: {}", json),
-                Err(e) => eprintln!("[DEBUG] Failed to serialize session: {}", e),
+        if let Some(sync) = &supabase_sync {
+            // Failed session syncs are retried on the summary's schedule
+            if summary_due {
+                if let Err(e) = retry_queued_session_updates(&sync_db, sync, Some(&sync_status)).await {
+                    log_error_with_context("Retrying session updates", &e);
+                }
             }
-            let status = sync_status.clone();
-            let sync = sync.clone();
-            // Await the async push
-            // REMOVE: push_focus_session_with_status at session end
-            // Only update app usage events here
-            // Push app usage events for this
-            let db_handle = mgr.db_handle();
-            if let Some(sid) = mgr.session_id().map(|id| id.0) {
-                match db_handle.get_app_usage_events_for_session(sid) {
-                    Ok(events) => {
-                        if !events.is_empty() {
-                            match sync.push_app_usage_events(&events).await {
-                                Ok(_) => {
-                                    println!("[Supabase] App usage events pushed successfully!")
-                                }
-                                Err(e) => {
-                                    eprintln!("[Supabase] App usage events sync failed: {}", e)
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => eprintln!("[Supabase] Failed to fetch app usage events: {}", e),
+            // Push every session that just ended to Supabase with its events
+            for session in poll_result {
+                if let Err(e) =
+                    finalize_and_sync_session(&session, &sync_db, sync, Some(&sync_status)).await
+                {
+                    log_error_with_context("Syncing ended session", &e);
                 }
             }
-            // --- NEW: Always update session in Supabase when it ends ---
-            let sync = sync.clone();
-            let session_clone = session.clone();
-            tokio::spawn(async move {
-                let _ = sync.update_focus_session(&session_clone).await;
-            });
         }
-        thread::sleep(Duration::from_millis(MAIN_LOOP_SLEEP_MS));
+        thread::sleep(live_config.get().poll_sleep());
     }
-    // After loop: ensure session is ended and logged
-    let mut mgr = session_mgr.lock().unwrap();
-    println!("[Main] Calling end_active_session");
-    match mgr.end_active_session() {
-        Ok(Some(session)) => {
-            match serde_json::to_string_pretty(&session) {
-                Ok(json) => println!("[DEBUG] Pushing session to Supabase: {}", json),
-                Err(e) => eprintln!("[DEBUG] Failed to serialize session: {}", e),
-            }
-            if let Some(sync) = &supabase_sync {
-                // REMOVE: push_focus_session_with_status at session end
-                // Only update app usage events here
-                let status = sync_status.clone();
-                // Push app usage events for this session
-                let db_handle = mgr.db_handle();
-                if let Some(sid) = mgr.session_id().map(|id| id.0) {
-                    match db_handle.get_app_usage_events_for_session(sid) {
-                        Ok(events) => {
-                            if !events.is_empty() {
-                                match sync.push_app_usage_events(&events).await {
-                                    Ok(_) => {
-                                        println!("[Supabase] App usage events pushed successfully!")
-                                    }
-                                    Err(e) => {
-                                        eprintln!("[Supabase] App usage events sync failed: {}", e)
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => eprintln!("[Supabase] Failed to fetch app usage events: {}", e),
-                    }
-                }
+    // After loop: ensure session is ended and logged, along with any the Ctrl-C handler ended
+    let ended = {
+        let mut mgr = session_mgr.lock().unwrap();
+        let mut ended: Vec<_> = shutdown_ended.try_iter().collect();
+        println!("[Main] Calling end_active_session");
+        ended.extend(mgr.end_active_session().unwrap_or_else(|e| {
+            log_error_with_context("Ending active session", &e);
+            None
+        }));
+        // The session end flushed the in-flight interval, so this saves the app
+        // with a start of "now" and a restart doesn't count that interval twice
+        if let Err(e) = mgr.save_inflight_app() {
+            log_error_with_context("Saving in-flight app", &e);
+        }
+        ended
+    };
+    if let Some(sync) = &supabase_sync {
+        for session in ended {
+            if let Err(e) =
+                finalize_and_sync_session(&session, &sync_db, sync, Some(&sync_status)).await
+            {
+                log_error_with_context("Syncing ended session", &e);
             }
         }
-        Ok(None) => {}
-        Err(e) => log_error_with_context("Ending active session", &e),
     }
 }
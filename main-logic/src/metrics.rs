@@ -1,10 +1,15 @@
 //! Metrics module: tracks and summarizes app usage, blocked events, and session statistics.
 
-use crate::constants::SUMMARY_INTERVAL_SECS;
+use crate::api;
+use crate::constants::{POLL_TIMING_WINDOW, SUMMARY_INTERVAL_SECS};
+use crate::db::DbHandle;
 use crate::error::SynapseError;
 use crate::session::SessionManager;
-use std::collections::HashMap;
-use std::time::Instant;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
 
 /// Tracks metrics for app usage and focus sessions.
 pub struct Metrics {
@@ -16,6 +21,18 @@ pub struct Metrics {
     pub app_frequency: HashMap<String, u64>,
     /// Time of the last summary log.
     pub last_summary: Instant,
+    /// Whether a focus session was active at the last update.
+    pub current_session_active: bool,
+    /// The local day the counters are persisted under, once they have been.
+    pub day: Option<NaiveDate>,
+}
+
+/// The persisted counters of a [`Metrics`] for one local day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub total_checks: u64,
+    pub blocked_count: u64,
+    pub app_frequency: HashMap<String, u64>,
 }
 
 impl Metrics {
@@ -26,7 +43,68 @@ impl Metrics {
             blocked_count: 0,
             app_frequency: HashMap::new(),
             last_summary: Instant::now(),
+            current_session_active: false,
+            day: None,
+        }
+    }
+
+    /// Creates a tracker that continues from the counters stored for `day`, so a summary
+    /// covers the whole day across restarts.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the snapshot cannot be read.
+    pub fn load(db: &DbHandle, day: NaiveDate) -> Result<Self, SynapseError> {
+        let mut metrics = Self::new();
+        if let Some(stored) = db.load_metrics_snapshot(day)? {
+            metrics.accumulate(stored);
         }
+        metrics.day = Some(day);
+        Ok(metrics)
+    }
+
+    /// Returns the counters to persist.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            total_checks: self.total_checks,
+            blocked_count: self.blocked_count,
+            app_frequency: self.app_frequency.clone(),
+        }
+    }
+
+    /// Adds `snapshot`'s counters onto the current ones.
+    fn accumulate(&mut self, snapshot: MetricsSnapshot) {
+        self.total_checks += snapshot.total_checks;
+        self.blocked_count += snapshot.blocked_count;
+        for (app, count) in snapshot.app_frequency {
+            *self.app_frequency.entry(app).or_insert(0) += count;
+        }
+    }
+
+    /// Saves the counters under `today`. Called periodically rather than per check.
+    ///
+    /// A tracker not yet persisted first adds on what earlier runs stored for `today`. When
+    /// the day has changed, the previous day's final counters are saved and the counters
+    /// start again from zero.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if a snapshot cannot be read or written.
+    pub fn persist(&mut self, db: &DbHandle, today: NaiveDate) -> Result<(), SynapseError> {
+        match self.day {
+            Some(day) if day == today => {}
+            Some(day) => {
+                db.save_metrics_snapshot(day, &self.snapshot())?;
+                self.total_checks = 0;
+                self.blocked_count = 0;
+                self.app_frequency.clear();
+            }
+            None => {
+                if let Some(stored) = db.load_metrics_snapshot(today)? {
+                    self.accumulate(stored);
+                }
+            }
+        }
+        self.day = Some(today);
+        db.save_metrics_snapshot(today, &self.snapshot())
     }
 
     /// Updates metrics for a single app check.
@@ -55,6 +133,63 @@ impl Metrics {
                 *self.app_frequency.entry(app.clone()).or_insert(0) += 1;
             }
         }
+        self.current_session_active = session_mgr.current_session().is_some();
+    }
+
+    /// Renders today's stats and the in-memory counters in the Prometheus text exposition format.
+    ///
+    /// Gauges whose database query fails are omitted rather than reported as zero.
+    pub fn prometheus_text(&self, db: &DbHandle) -> String {
+        let mut out = String::new();
+        if let Ok(v) = api::total_focus_time_today(db) {
+            write_metric(
+                &mut out,
+                "synapse_total_focus_seconds_today",
+                "gauge",
+                "Total focus time today in seconds.",
+                v,
+            );
+        }
+        if let Ok(v) = api::total_distractions_today(db) {
+            write_metric(
+                &mut out,
+                "synapse_distractions_today",
+                "gauge",
+                "Distraction attempts today.",
+                v,
+            );
+        }
+        if let Ok(v) = api::total_focus_sessions_today(db) {
+            write_metric(
+                &mut out,
+                "synapse_sessions_today",
+                "gauge",
+                "Focus sessions started today.",
+                v,
+            );
+        }
+        write_metric(
+            &mut out,
+            "synapse_current_session_active",
+            "gauge",
+            "1 if a focus session is currently active, 0 otherwise.",
+            i64::from(self.current_session_active),
+        );
+        write_metric(
+            &mut out,
+            "synapse_checks_total",
+            "counter",
+            "App checks performed since startup.",
+            self.total_checks as i64,
+        );
+        write_metric(
+            &mut out,
+            "synapse_blocked_detections_total",
+            "counter",
+            "Blocked app detections since startup.",
+            self.blocked_count as i64,
+        );
+        out
     }
 
     /// Returns true if it is time to log a summary (every 60 seconds).
@@ -87,6 +222,88 @@ impl Metrics {
     }
 }
 
+/// How long each phase of one `poll()` took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PollTiming {
+    /// Enumerating the running processes.
+    pub processes: Duration,
+    /// Resolving the foreground app and, when projects are tracked, its window title.
+    pub foreground: Duration,
+    /// Updating the session and logging events.
+    pub processing: Duration,
+}
+
+impl PollTiming {
+    /// Returns the duration of the whole poll.
+    pub fn total(&self) -> Duration {
+        self.processes + self.foreground + self.processing
+    }
+}
+
+/// Average and longest duration of one poll phase, in microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PhaseTiming {
+    pub avg_micros: u64,
+    pub max_micros: u64,
+}
+
+/// Poll durations over the last [`POLL_TIMING_WINDOW`] polls, overall and per phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PollTimingStats {
+    /// Number of polls the figures cover.
+    pub polls: usize,
+    pub total: PhaseTiming,
+    pub processes: PhaseTiming,
+    pub foreground: PhaseTiming,
+    pub processing: PhaseTiming,
+}
+
+/// Keeps the timings of the most recent polls.
+#[derive(Debug, Clone, Default)]
+pub struct PollTimings {
+    recent: VecDeque<PollTiming>,
+}
+
+impl PollTimings {
+    /// Records one poll, dropping the oldest once [`POLL_TIMING_WINDOW`] are kept.
+    pub fn record(&mut self, timing: PollTiming) {
+        if self.recent.len() == POLL_TIMING_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(timing);
+    }
+
+    /// Returns the moving average and maximum of the recorded polls.
+    pub fn stats(&self) -> PollTimingStats {
+        let phase = |duration: fn(&PollTiming) -> Duration| {
+            let micros: Vec<u64> = self
+                .recent
+                .iter()
+                .map(|timing| duration(timing).as_micros() as u64)
+                .collect();
+            PhaseTiming {
+                avg_micros: micros.iter().sum::<u64>() / micros.len().max(1) as u64,
+                max_micros: micros.iter().copied().max().unwrap_or(0),
+            }
+        };
+        PollTimingStats {
+            polls: self.recent.len(),
+            total: phase(PollTiming::total),
+            processes: phase(|timing| timing.processes),
+            foreground: phase(|timing| timing.foreground),
+            processing: phase(|timing| timing.processing),
+        }
+    }
+}
+
+/// Appends one metric with its `# HELP` and `# TYPE` lines.
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: i64) {
+    // Writing to a String cannot fail
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,6 +372,119 @@ mod tests {
         assert!(after > before);
     }
 
+    fn parse_prometheus(text: &str) -> HashMap<String, i64> {
+        text.lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once(' '))
+            .map(|(name, value)| (name.to_string(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_prometheus_text_matches_db() {
+        let db = crate::db::DbHandle::test_in_memory_with_schema();
+        let now = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        db.execute_sql(
+            "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES ('a', ?1, ?2, 'code', 2)",
+            &[&(now - 10), &now],
+        )
+        .unwrap();
+        let mut metrics = setup_metrics();
+        metrics.update("code", false);
+        metrics.update("chrome", true);
+
+        let text = metrics.prometheus_text(&db);
+        for name in [
+            "synapse_total_focus_seconds_today",
+            "synapse_distractions_today",
+            "synapse_sessions_today",
+            "synapse_current_session_active",
+        ] {
+            assert!(text.contains(&format!("# HELP {} ", name)));
+            assert!(text.contains(&format!("# TYPE {} gauge", name)));
+        }
+        let values = parse_prometheus(&text);
+        assert_eq!(
+            values["synapse_total_focus_seconds_today"],
+            crate::api::total_focus_time_today(&db).unwrap()
+        );
+        assert_eq!(
+            values["synapse_distractions_today"],
+            crate::api::total_distractions_today(&db).unwrap()
+        );
+        assert_eq!(
+            values["synapse_sessions_today"],
+            crate::api::total_focus_sessions_today(&db).unwrap()
+        );
+        assert_eq!(values["synapse_current_session_active"], 0);
+        assert_eq!(values["synapse_checks_total"], 2);
+        assert_eq!(values["synapse_blocked_detections_total"], 1);
+    }
+
+    #[test]
+    fn test_persisted_counters_survive_restart_and_accumulate() {
+        let db = crate::db::DbHandle::test_in_memory_with_schema();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let mut first_run = setup_metrics();
+        first_run.update("code", false);
+        first_run.update("steam", true);
+        first_run.persist(&db, day).unwrap();
+
+        let mut second_run = Metrics::load(&db, day).unwrap();
+        assert_eq!(second_run.snapshot(), first_run.snapshot());
+        second_run.update("code", false);
+        second_run.persist(&db, day).unwrap();
+
+        // A tracker started without loading still adds onto the stored day
+        let mut third_run = setup_metrics();
+        third_run.update("steam", true);
+        third_run.persist(&db, day).unwrap();
+        let stored = db.load_metrics_snapshot(day).unwrap().unwrap();
+        assert_eq!((stored.total_checks, stored.blocked_count), (4, 2));
+        assert_eq!(stored.app_frequency["code"], 2);
+        assert_eq!(stored.app_frequency["steam"], 2);
+
+        // The next day starts from zero and leaves the previous day as it was
+        let next_day = day.succ_opt().unwrap();
+        third_run.update("code", false);
+        third_run.persist(&db, next_day).unwrap();
+        assert_eq!(third_run.total_checks, 0);
+        assert_eq!(db.load_metrics_snapshot(day).unwrap().unwrap().total_checks, 5);
+        assert_eq!(db.load_metrics_snapshot(next_day).unwrap().unwrap().total_checks, 0);
+    }
+
+    #[test]
+    fn test_poll_timing_stats_average_and_max() {
+        let timing = |processes: u64, foreground: u64, processing: u64| PollTiming {
+            processes: Duration::from_millis(processes),
+            foreground: Duration::from_millis(foreground),
+            processing: Duration::from_millis(processing),
+        };
+        let mut timings = PollTimings::default();
+        assert_eq!(timings.stats(), PollTimingStats::default());
+
+        timings.record(timing(10, 2, 3));
+        timings.record(timing(30, 4, 1));
+        timings.record(timing(20, 6, 2));
+        let stats = timings.stats();
+        assert_eq!(stats.polls, 3);
+        assert_eq!(stats.processes, PhaseTiming { avg_micros: 20_000, max_micros: 30_000 });
+        assert_eq!(stats.foreground, PhaseTiming { avg_micros: 4_000, max_micros: 6_000 });
+        assert_eq!(stats.processing, PhaseTiming { avg_micros: 2_000, max_micros: 3_000 });
+        assert_eq!(stats.total, PhaseTiming { avg_micros: 26_000, max_micros: 35_000 });
+
+        // Only the most recent polls count
+        for _ in 0..POLL_TIMING_WINDOW {
+            timings.record(timing(1, 1, 1));
+        }
+        let stats = timings.stats();
+        assert_eq!(stats.polls, POLL_TIMING_WINDOW);
+        assert_eq!(stats.total, PhaseTiming { avg_micros: 3_000, max_micros: 3_000 });
+    }
+
     #[test]
     fn test_log_summary_with_no_data() {
         let mut metrics = setup_metrics();
@@ -0,0 +1,110 @@
+//! Installed-apps cache: keeps the (app_name, exe_name) list between scans, since scanning
+//! the registry is slow, and lets callers force a rescan after something is installed.
+
+use crate::constants::INSTALLED_APPS_CACHE_TTL_SECS;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Produces the current list of installed (app_name, exe_name) tuples.
+pub type InstalledAppsSource = Box<dyn Fn() -> Vec<(String, String)> + Send + Sync>;
+
+/// When a scan ran and the apps it found.
+type Snapshot = (Instant, Vec<(String, String)>);
+
+/// A list of installed apps, rescanned from its source once it is older than the TTL.
+pub struct InstalledAppsCache {
+    source: InstalledAppsSource,
+    ttl: Duration,
+    entries: Mutex<Option<Snapshot>>,
+}
+
+impl InstalledAppsCache {
+    /// Creates an empty cache that scans `source` on first use and after `ttl`.
+    pub fn new(source: InstalledAppsSource, ttl: Duration) -> Self {
+        Self {
+            source,
+            ttl,
+            entries: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cached apps, rescanning first if the cache is empty or stale.
+    pub fn get(&self) -> Vec<(String, String)> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.as_ref() {
+            Some((scanned_at, apps)) if scanned_at.elapsed() < self.ttl => apps.clone(),
+            _ => {
+                let apps = (self.source)();
+                *entries = Some((Instant::now(), apps.clone()));
+                apps
+            }
+        }
+    }
+
+    /// Rescans the source now, regardless of the TTL, and returns the number of apps found.
+    pub fn refresh(&self) -> usize {
+        let apps = (self.source)();
+        let count = apps.len();
+        *self.entries.lock().unwrap() = Some((Instant::now(), apps));
+        count
+    }
+}
+
+fn cache() -> &'static InstalledAppsCache {
+    static CACHE: OnceLock<InstalledAppsCache> = OnceLock::new();
+    CACHE.get_or_init(|| {
+        InstalledAppsCache::new(
+            Box::new(scan),
+            Duration::from_secs(INSTALLED_APPS_CACHE_TTL_SECS),
+        )
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn scan() -> Vec<(String, String)> {
+    super::windows::scan_installed_apps()
+}
+
+/// Only Windows can list installed apps.
+#[cfg(not(target_os = "windows"))]
+fn scan() -> Vec<(String, String)> {
+    Vec::new()
+}
+
+/// Returns the installed (app_name, exe_name) tuples from the process-wide cache.
+pub fn installed_apps() -> Vec<(String, String)> {
+    cache().get()
+}
+
+/// Rescans installed apps immediately and returns how many were found.
+pub fn refresh_installed_apps_cache() -> usize {
+    cache().refresh()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn app(name: &str, exe: &str) -> (String, String) {
+        (name.to_string(), exe.to_string())
+    }
+
+    #[test]
+    fn refresh_picks_up_newly_installed_apps() {
+        let installed = Arc::new(Mutex::new(vec![app("Visual Studio Code", "Code.exe")]));
+        let source = installed.clone();
+        let cache = InstalledAppsCache::new(
+            Box::new(move || source.lock().unwrap().clone()),
+            Duration::from_secs(3600),
+        );
+        assert_eq!(cache.get(), vec![app("Visual Studio Code", "Code.exe")]);
+
+        installed.lock().unwrap().push(app("Obsidian", "Obsidian.exe"));
+        // Still within the TTL: the cached list is served
+        assert_eq!(cache.get().len(), 1);
+
+        assert_eq!(cache.refresh(), 2);
+        assert!(cache.get().contains(&app("Obsidian", "Obsidian.exe")));
+    }
+}
@@ -1,37 +1,291 @@
 //! Linux platform module: provides process and popup utilities for Linux OS.
 
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::config::PopupSound;
 use crate::error::SynapseError;
 
-/// Gets the name of the foreground process on Linux.
+/// Freedesktop sound theme file played for [`PopupSound::System`].
+const SYSTEM_POPUP_SOUND: &str = "/usr/share/sounds/freedesktop/stereo/dialog-warning.oga";
+
+/// Window types that never count as the foreground application (dialogs, tooltips, etc.).
+const TRANSIENT_WINDOW_TYPES: &[&str] = &[
+    "_NET_WM_WINDOW_TYPE_DIALOG",
+    "_NET_WM_WINDOW_TYPE_UTILITY",
+    "_NET_WM_WINDOW_TYPE_SPLASH",
+    "_NET_WM_WINDOW_TYPE_TOOLTIP",
+    "_NET_WM_WINDOW_TYPE_NOTIFICATION",
+    "_NET_WM_WINDOW_TYPE_POPUP_MENU",
+    "_NET_WM_WINDOW_TYPE_DROPDOWN_MENU",
+];
+
+/// Window types that are never picked as the topmost application window (panels, desktop).
+const BACKGROUND_WINDOW_TYPES: &[&str] = &["_NET_WM_WINDOW_TYPE_DOCK", "_NET_WM_WINDOW_TYPE_DESKTOP"];
+
+/// `_NET_WM_DESKTOP` value of windows shown on every desktop.
+const ALL_DESKTOPS: u32 = 0xFFFF_FFFF;
+
+/// The last foreground app that came from a real application window.
+static LAST_APP_WINDOW: Mutex<Option<String>> = Mutex::new(None);
+
+/// Properties of a window as reported by `xprop`.
+#[derive(Debug, Default, PartialEq)]
+struct WindowProps {
+    pid: Option<u32>,
+    window_types: Vec<String>,
+    wm_class: Vec<String>,
+    desktop: Option<u32>,
+    states: Vec<String>,
+}
+
+/// Which window counts as the foreground, from `SYNAPSE_FOREGROUND_WINDOW`.
 ///
-/// # Errors
-/// Returns `SynapseError` if the process name cannot be determined.
-pub fn get_foreground_process_name() -> Result<Option<String>, SynapseError> {
-    // Try to get the active window's PID using xprop and xdotool
-    let window_id = Command::new("xprop")
+/// With several monitors, only one window holds keyboard focus at a time; that is the
+/// foreground window. Under focus-follows-mouse, focus (and so `_NET_ACTIVE_WINDOW`) moves with
+/// the pointer, which `Topmost` avoids by using the stacking order instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ForegroundWindowSource {
+    /// The focused window (`_NET_ACTIVE_WINDOW`), or the topmost window when none is focused.
+    #[default]
+    Active,
+    /// The most recently raised application window on the current desktop, across all
+    /// monitors, regardless of focus or pointer position.
+    Topmost,
+}
+
+/// Reads `SYNAPSE_FOREGROUND_WINDOW` (`active` or `topmost`); unset or unknown values mean `active`.
+fn foreground_window_source() -> ForegroundWindowSource {
+    match std::env::var("SYNAPSE_FOREGROUND_WINDOW")
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "topmost" => ForegroundWindowSource::Topmost,
+        _ => ForegroundWindowSource::Active,
+    }
+}
+
+/// Window-manager state of the root window, as reported by `xprop -root`.
+#[derive(Debug, Default, PartialEq)]
+struct RootWindowProps {
+    active: Option<String>,
+    /// Managed windows, bottom-most first.
+    stacking: Vec<String>,
+    current_desktop: Option<u32>,
+}
+
+/// Parses `xprop -root _NET_ACTIVE_WINDOW _NET_CLIENT_LIST_STACKING _NET_CURRENT_DESKTOP` output.
+fn parse_root_props(xprop_output: &str) -> RootWindowProps {
+    let mut root = RootWindowProps::default();
+    for line in xprop_output.lines() {
+        let value = match line.split_once(" # ").or_else(|| line.split_once(" = ")) {
+            Some((_, value)) => value.trim(),
+            None => continue,
+        };
+        let ids = || {
+            value
+                .split(',')
+                .map(|id| id.trim().to_string())
+                .filter(|id| id.starts_with("0x") && id != "0x0")
+        };
+        if line.starts_with("_NET_ACTIVE_WINDOW") {
+            root.active = ids().next();
+        } else if line.starts_with("_NET_CLIENT_LIST_STACKING") {
+            root.stacking = ids().collect();
+        } else if line.starts_with("_NET_CURRENT_DESKTOP") {
+            root.current_desktop = value.parse().ok();
+        }
+    }
+    root
+}
+
+/// Returns true if the window can be picked as the topmost application window on `desktop`.
+fn is_topmost_candidate(props: &WindowProps, desktop: Option<u32>, excluded_classes: &[String]) -> bool {
+    let on_desktop = match (props.desktop, desktop) {
+        (Some(window), Some(current)) => window == current || window == ALL_DESKTOPS,
+        _ => true,
+    };
+    on_desktop
+        && !is_excluded_window(props, excluded_classes)
+        && !props
+            .window_types
+            .iter()
+            .any(|t| BACKGROUND_WINDOW_TYPES.contains(&t.as_str()))
+        && !props.states.iter().any(|s| s == "_NET_WM_STATE_HIDDEN")
+}
+
+/// Picks the foreground window and its properties.
+///
+/// Under `Active`, the focused window is used as is (an excluded one is resolved by the caller).
+/// Otherwise, or when nothing is focused, the topmost visible application window on the current
+/// desktop is used, so the result depends on the stacking order only.
+fn select_foreground_window(
+    source: ForegroundWindowSource,
+    root: &RootWindowProps,
+    props_of: impl Fn(&str) -> Option<WindowProps>,
+    excluded_classes: &[String],
+) -> Option<(String, WindowProps)> {
+    if source == ForegroundWindowSource::Active {
+        if let Some(active) = &root.active {
+            return props_of(active).map(|props| (active.clone(), props));
+        }
+    }
+    root.stacking.iter().rev().find_map(|id| {
+        let props = props_of(id)?;
+        is_topmost_candidate(&props, root.current_desktop, excluded_classes)
+            .then(|| (id.clone(), props))
+    })
+}
+
+/// Parses `xprop -id <window> _NET_WM_PID _NET_WM_WINDOW_TYPE WM_CLASS _NET_WM_DESKTOP
+/// _NET_WM_STATE` output.
+fn parse_window_props(xprop_output: &str) -> WindowProps {
+    let mut props = WindowProps::default();
+    for line in xprop_output.lines() {
+        let Some((key, value)) = line.split_once(" = ") else {
+            continue;
+        };
+        let values = value
+            .split(',')
+            .map(|v| v.trim().trim_matches('"').to_string())
+            .filter(|v| !v.is_empty());
+        if key.starts_with("_NET_WM_PID") {
+            props.pid = value.trim().parse::<u32>().ok();
+        } else if key.starts_with("_NET_WM_WINDOW_TYPE") {
+            props.window_types = values.collect();
+        } else if key.starts_with("WM_CLASS") {
+            props.wm_class = values.collect();
+        } else if key.starts_with("_NET_WM_DESKTOP") {
+            props.desktop = value.trim().parse::<u32>().ok();
+        } else if key.starts_with("_NET_WM_STATE") {
+            props.states = values.collect();
+        }
+    }
+    props
+}
+
+/// Returns true if the window is transient (dialog/utility/splash/...) or has an excluded class.
+fn is_excluded_window(props: &WindowProps, excluded_classes: &[String]) -> bool {
+    props
+        .window_types
+        .iter()
+        .any(|t| TRANSIENT_WINDOW_TYPES.contains(&t.as_str()))
+        || props
+            .wm_class
+            .iter()
+            .any(|c| excluded_classes.iter().any(|e| e.eq_ignore_ascii_case(c)))
+}
+
+/// Window classes to ignore, from the comma-separated `SYNAPSE_EXCLUDED_WM_CLASSES` variable.
+fn excluded_window_classes() -> Vec<String> {
+    std::env::var("SYNAPSE_EXCLUDED_WM_CLASSES")
+        .map(|v| {
+            v.split(',')
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the active window, stacking order and current desktop from the root window.
+fn root_window_props() -> Result<RootWindowProps, SynapseError> {
+    let output = Command::new("xprop")
         .arg("-root")
         .arg("_NET_ACTIVE_WINDOW")
+        .arg("_NET_CLIENT_LIST_STACKING")
+        .arg("_NET_CURRENT_DESKTOP")
         .output()
         .map_err(|e| SynapseError::Platform(format!("xprop failed: {}", e)))?
         .stdout;
-    let s = String::from_utf8_lossy(&window_id);
-    let id = s.split_whitespace().last().map(|w| w.trim().to_string());
-    let window_id = match id {
-        Some(id) if id != "0x0" => id,
-        _ => return Ok(None),
+    Ok(parse_root_props(&String::from_utf8_lossy(&output)))
+}
+
+/// Reads the properties of window `window_id`.
+fn window_props(window_id: &str) -> Result<WindowProps, SynapseError> {
+    let output = Command::new("xprop")
+        .arg("-id")
+        .arg(window_id)
+        .arg("_NET_WM_PID")
+        .arg("_NET_WM_WINDOW_TYPE")
+        .arg("WM_CLASS")
+        .arg("_NET_WM_DESKTOP")
+        .arg("_NET_WM_STATE")
+        .output()
+        .map_err(|e| SynapseError::Platform(format!("xprop failed: {}", e)))?
+        .stdout;
+    Ok(parse_window_props(&String::from_utf8_lossy(&output)))
+}
+
+/// Returns the X window id and properties of the foreground window (see
+/// [`ForegroundWindowSource`]), or `None` if there is none.
+fn foreground_window() -> Result<Option<(String, WindowProps)>, SynapseError> {
+    let root = root_window_props()?;
+    Ok(select_foreground_window(
+        foreground_window_source(),
+        &root,
+        |id| window_props(id).ok(),
+        &excluded_window_classes(),
+    ))
+}
+
+/// Parses `xprop -id <window> _NET_WM_NAME WM_NAME` output, preferring the UTF-8 name.
+fn parse_window_title(xprop_output: &str) -> Option<String> {
+    let title_for = |prefix: &str| {
+        xprop_output.lines().find_map(|line| {
+            let (key, value) = line.split_once(" = ")?;
+            if !key.starts_with(prefix) {
+                return None;
+            }
+            let value = value.trim();
+            let quoted = value.strip_prefix('"')?.strip_suffix('"')?;
+            Some(quoted.replace("\\\"", "\"").replace("\\\\", "\\"))
+        })
+    };
+    title_for("_NET_WM_NAME")
+        .or_else(|| title_for("WM_NAME"))
+        .filter(|title| !title.is_empty())
+}
+
+/// Gets the title of the foreground window on Linux.
+///
+/// # Errors
+/// Returns `SynapseError` if `xprop` cannot be run.
+pub fn get_foreground_window_title() -> Result<Option<String>, SynapseError> {
+    let Some((window_id, _)) = foreground_window()? else {
+        return Ok(None);
     };
-    let pid_out = Command::new("xprop")
+    let output = Command::new("xprop")
         .arg("-id")
         .arg(&window_id)
-        .arg("_NET_WM_PID")
+        .arg("_NET_WM_NAME")
+        .arg("WM_NAME")
         .output()
         .map_err(|e| SynapseError::Platform(format!("xprop failed: {}", e)))?
         .stdout;
-    let s = String::from_utf8_lossy(&pid_out);
-    let pid = s.split_whitespace().last().and_then(|w| w.parse::<u32>().ok());
-    let pid = match pid {
+    Ok(parse_window_title(&String::from_utf8_lossy(&output)))
+}
+
+/// Resolves the executable name behind the foreground window.
+///
+/// Transient windows (dialogs, utilities, splash screens, tooltips) and excluded window classes
+/// are skipped in favour of the last real application window, so a file-save dialog does not
+/// register as a different app.
+///
+/// # Errors
+/// Returns `SynapseError` if the process name cannot be determined.
+pub fn raw_foreground_exe_name() -> Result<Option<String>, SynapseError> {
+    let Some((_, props)) = foreground_window()? else {
+        return Ok(None);
+    };
+    if is_excluded_window(&props, &excluded_window_classes()) {
+        return Ok(LAST_APP_WINDOW.lock().unwrap().clone());
+    }
+    let pid = match props.pid {
         Some(pid) => pid,
         None => return Ok(None),
     };
@@ -40,27 +294,100 @@ pub fn get_foreground_process_name() -> Result<Option<String>, SynapseError> {
         .map_err(|e| SynapseError::Platform(format!("Failed to read comm file: {}", e)))?
         .trim()
         .to_lowercase();
+    *LAST_APP_WINDOW.lock().unwrap() = Some(name.clone());
     Ok(Some(name))
 }
 
+/// Gets the name of the foreground process on Linux.
+///
+/// # Errors
+/// Returns `SynapseError` if the process name cannot be determined.
+pub fn get_foreground_process_name() -> Result<Option<String>, SynapseError> {
+    raw_foreground_exe_name()
+}
+
 /// Lists all running process names on Linux.
 ///
 /// # Errors
 /// Returns `SynapseError` if the process list cannot be retrieved.
 pub fn list_running_process_names() -> Result<Vec<String>, SynapseError> {
-    let mut names = Vec::new();
+    Ok(list_processes()?.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Lists running processes as `(pid, lowercased name)` pairs.
+///
+/// # Errors
+/// Returns `SynapseError` if the process list cannot be retrieved.
+pub fn list_processes() -> Result<Vec<(u32, String)>, SynapseError> {
+    let mut processes = Vec::new();
     for entry in fs::read_dir("/proc").map_err(|e| SynapseError::Platform(format!("Failed to read /proc: {}", e)))? {
         let entry = entry.map_err(|e| SynapseError::Platform(format!("Failed to read /proc entry: {}", e)))?;
         if let Ok(file_name) = entry.file_name().into_string() {
             if let Ok(pid) = file_name.parse::<u32>() {
                 let comm_path = format!("/proc/{}/comm", pid);
                 if let Ok(name) = fs::read_to_string(comm_path) {
-                    names.push(name.trim().to_lowercase());
+                    processes.push((pid, name.trim().to_lowercase()));
                 }
             }
         }
     }
-    Ok(names)
+    Ok(processes)
+}
+
+/// Asks process `pid` to exit with SIGTERM and returns right away. A detached thread sends
+/// SIGKILL if it is still running after a short grace period, so callers such as the poll
+/// loop never wait on the process. The pid may be reused once the process exits, so SIGKILL
+/// is only sent while `/proc/<pid>/stat` still reports the start time seen before SIGTERM.
+///
+/// # Errors
+/// Returns `SynapseError` if SIGTERM cannot be sent, e.g. the process belongs to another user.
+pub fn terminate_process(pid: u32) -> Result<(), SynapseError> {
+    let start_time = process_start_time(pid);
+    send_signal(pid, "-TERM")?;
+    let Some(start_time) = start_time else {
+        return Ok(());
+    };
+    std::thread::spawn(move || {
+        for _ in 0..20 {
+            if process_start_time(pid) != Some(start_time) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        if process_start_time(pid) != Some(start_time) {
+            return;
+        }
+        if let Err(e) = send_signal(pid, "-KILL") {
+            eprintln!("Failed to force-close process {}: {}", pid, e);
+        }
+    });
+    Ok(())
+}
+
+/// Reads when process `pid` started, in clock ticks since boot, or `None` if it is gone.
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_start_time(&stat)
+}
+
+/// Parses `starttime`, field 22 of `/proc/<pid>/stat`. The command name in field 2 may hold
+/// spaces or parentheses, so fields are counted from the last `)`.
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    after_comm.split_whitespace().nth(19)?.parse().ok()
+}
+
+fn send_signal(pid: u32, signal: &str) -> Result<(), SynapseError> {
+    let status = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| SynapseError::Platform(format!("Failed to run kill: {}", e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(SynapseError::Platform(format!("kill {} {} failed", signal, pid)))
+    }
 }
 
 /// Shows a popup warning for a distraction app on Linux.
@@ -82,10 +409,154 @@ pub fn show_distraction_popup(app_name: &str) -> Result<(), SynapseError> {
     Ok(())
 }
 
+/// Plays `sound` in the background with `paplay`, or `aplay` if PulseAudio is unavailable.
+///
+/// # Errors
+/// Returns `SynapseError` if neither player can be started.
+pub fn play_popup_sound(sound: &PopupSound) -> Result<(), SynapseError> {
+    let path = match sound {
+        PopupSound::None => return Ok(()),
+        PopupSound::System => Path::new(SYSTEM_POPUP_SOUND),
+        PopupSound::Custom(path) => path.as_path(),
+    };
+    for player in ["paplay", "aplay"] {
+        let spawned = Command::new(player)
+            .arg(path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Ok(mut child) = spawned {
+            // Reap the player once it finishes without blocking the poll loop
+            std::thread::spawn(move || child.wait());
+            return Ok(());
+        }
+    }
+    Err(SynapseError::Platform(format!(
+        "no audio player (paplay, aplay) could play {}",
+        path.display()
+    )))
+}
+
+/// Shows a notification nudging the user to start focusing after `idle` without a session.
+///
+/// # Errors
+/// Never fails; a missing `notify-send` is only reported on stdout.
+pub fn show_focus_reminder(idle: Duration) -> Result<(), SynapseError> {
+    let result = Command::new("notify-send")
+        .arg("Time to focus?")
+        .arg(format!("No focus session for {} minutes.", idle.as_secs() / 60))
+        .output();
+    if result.is_err() {
+        println!("(Warning: notify-send failed, no reminder shown)");
+    }
+    Ok(())
+}
+
+/// Shows a notification that the session was ended after `limit` distractions.
+///
+/// # Errors
+/// Never fails; a missing `notify-send` is only reported on stdout.
+pub fn show_distraction_limit_reached(limit: u32) -> Result<(), SynapseError> {
+    let result = Command::new("notify-send")
+        .arg("Focus session ended")
+        .arg(format!("You reached your limit of {} distractions.", limit))
+        .output();
+    if result.is_err() {
+        println!("(Warning: notify-send failed, no distraction limit notice shown)");
+    }
+    Ok(())
+}
+
+/// Shows a notification that the rules file was not applied because of `error`.
+///
+/// # Errors
+/// Never fails; a missing `notify-send` is only reported on stdout.
+pub fn show_rules_reload_failed(error: &str) -> Result<(), SynapseError> {
+    let result = Command::new("notify-send")
+        .arg("App rules not applied")
+        .arg(format!("apprules.json has an error, keeping the previous rules: {}", error))
+        .output();
+    if result.is_err() {
+        println!("(Warning: notify-send failed, no rules error shown)");
+    }
+    Ok(())
+}
+
+/// Returns whether the notification daemon is in do-not-disturb mode, checking dunst
+/// (`dunstctl is-paused`) and then GNOME (`show-banners`). Returns `false` when neither
+/// can be queried.
+///
+/// # Errors
+/// Never fails today; the `Result` matches the other platforms.
+pub fn is_system_dnd() -> Result<bool, SynapseError> {
+    if let Some(paused) = command_stdout("dunstctl", &["is-paused"]) {
+        return Ok(paused.trim() == "true");
+    }
+    if let Some(banners) = command_stdout(
+        "gsettings",
+        &["get", "org.gnome.desktop.notifications", "show-banners"],
+    ) {
+        return Ok(gnome_banners_hidden(&banners));
+    }
+    Ok(false)
+}
+
+/// Returns how long the system has been suspended since this was first called.
+///
+/// `/proc/uptime` keeps counting while suspended but the monotonic clock behind `Instant`
+/// does not, so the difference in how far each moved is the time spent asleep.
+/// Returns `None` if `/proc/uptime` can't be read.
+pub fn time_suspended() -> Option<Duration> {
+    static BASELINE: OnceLock<(Instant, Duration)> = OnceLock::new();
+    let uptime = boot_uptime()?;
+    let (started, uptime_at_start) = *BASELINE.get_or_init(|| (Instant::now(), uptime));
+    Some(
+        uptime
+            .saturating_sub(uptime_at_start)
+            .saturating_sub(started.elapsed()),
+    )
+}
+
+/// Reads the time since boot, including time suspended, from `/proc/uptime`.
+fn boot_uptime() -> Option<Duration> {
+    let uptime = fs::read_to_string("/proc/uptime").ok()?;
+    let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// Runs `program` and returns its stdout, or `None` if it is missing or fails.
+fn command_stdout(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// GNOME hides notification banners in do-not-disturb mode.
+fn gnome_banners_hidden(show_banners: &str) -> bool {
+    show_banners.trim() == "false"
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn parses_window_title_preferring_utf8_name() {
+        let output = "_NET_WM_NAME(UTF8_STRING) = \"main.rs \\\"draft\\\" - synapse - Code\"\nWM_NAME(STRING) = \"fallback\"\n";
+        assert_eq!(
+            parse_window_title(output).as_deref(),
+            Some("main.rs \"draft\" - synapse - Code")
+        );
+        assert_eq!(
+            parse_window_title("_NET_WM_NAME:  not found.\nWM_NAME(STRING) = \"xterm\"\n")
+                .as_deref(),
+            Some("xterm")
+        );
+        assert_eq!(parse_window_title("WM_NAME(STRING) = \"\"\n"), None);
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_get_foreground_process_name_handles_no_window() {
@@ -111,6 +582,153 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_terminate_process_returns_before_escalating_to_sigkill() {
+        // Ignores SIGTERM, so only the SIGKILL escalation ends it
+        let mut child = Command::new("sh")
+            .args(["-c", "trap '' TERM; exec sleep 30"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let started = Instant::now();
+        terminate_process(child.id()).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while child.try_wait().unwrap().is_none() {
+            assert!(Instant::now() < deadline, "process was never killed");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn test_parse_start_time_skips_command_names_with_spaces() {
+        let stat = "4242 (Web (Content) x) S 1 4242 4242 0 -1 4194560 100 0 0 0 5 3 0 0 20 0 12 0 987654 1000 50";
+        assert_eq!(parse_start_time(stat), Some(987654));
+        assert_eq!(parse_start_time("4242 (truncated"), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_process_start_time_is_stable_for_a_running_process() {
+        let pid = std::process::id();
+        assert!(process_start_time(pid).is_some());
+        assert_eq!(process_start_time(pid), process_start_time(pid));
+    }
+
+    const DIALOG_FIXTURE: &str = "_NET_WM_PID(CARDINAL) = 4242
+_NET_WM_WINDOW_TYPE(ATOM) = _NET_WM_WINDOW_TYPE_DIALOG
+WM_CLASS(STRING) = \"gtk-file-chooser\", \"Gedit\"
+";
+
+    const NORMAL_FIXTURE: &str = "_NET_WM_PID(CARDINAL) = 1337
+_NET_WM_WINDOW_TYPE(ATOM) = _NET_WM_WINDOW_TYPE_NORMAL
+WM_CLASS(STRING) = \"code\", \"Code\"
+";
+
+    #[test]
+    fn test_parse_window_props_reads_pid_type_and_class() {
+        let props = parse_window_props(NORMAL_FIXTURE);
+        assert_eq!(props.pid, Some(1337));
+        assert_eq!(props.window_types, vec!["_NET_WM_WINDOW_TYPE_NORMAL"]);
+        assert_eq!(props.wm_class, vec!["code", "Code"]);
+    }
+
+    #[test]
+    fn test_parse_window_props_handles_missing_properties() {
+        let output = "_NET_WM_PID:  not found.
+_NET_WM_WINDOW_TYPE:  not found.
+WM_CLASS:  not found.
+";
+        assert_eq!(parse_window_props(output), WindowProps::default());
+    }
+
+    #[test]
+    fn test_transient_window_types_are_excluded() {
+        assert!(is_excluded_window(&parse_window_props(DIALOG_FIXTURE), &[]));
+        for window_type in ["_NET_WM_WINDOW_TYPE_UTILITY", "_NET_WM_WINDOW_TYPE_SPLASH"] {
+            let output = format!("_NET_WM_WINDOW_TYPE(ATOM) = {}\n", window_type);
+            assert!(is_excluded_window(&parse_window_props(&output), &[]));
+        }
+        assert!(!is_excluded_window(&parse_window_props(NORMAL_FIXTURE), &[]));
+    }
+
+    #[test]
+    fn test_excluded_window_classes_are_skipped() {
+        let props = parse_window_props(NORMAL_FIXTURE);
+        assert!(is_excluded_window(&props, &["CODE".to_string()]));
+        assert!(!is_excluded_window(&props, &["firefox".to_string()]));
+    }
+
+    #[test]
+    fn test_gnome_dnd_is_read_from_show_banners() {
+        assert!(gnome_banners_hidden("false\n"));
+        assert!(!gnome_banners_hidden("true\n"));
+        assert!(!gnome_banners_hidden(""));
+    }
+
+    const ROOT_FIXTURE: &str = "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+_NET_CLIENT_LIST_STACKING(WINDOW): window id # 0x1000001, 0x2c00004, 0x3a00007, 0x4e00002, 0x5000003
+_NET_CURRENT_DESKTOP(CARDINAL) = 1
+";
+
+    /// Desktop 1 spans two monitors with an editor and a browser; above them are a hidden
+    /// player and a window on desktop 0, and the desktop window is at the bottom.
+    fn multi_window_props(id: &str) -> Option<WindowProps> {
+        let output = match id {
+            "0x1000001" => "_NET_WM_PID(CARDINAL) = 10\n_NET_WM_WINDOW_TYPE(ATOM) = _NET_WM_WINDOW_TYPE_DESKTOP\n_NET_WM_DESKTOP(CARDINAL) = 4294967295\n",
+            "0x2c00004" => "_NET_WM_PID(CARDINAL) = 20\nWM_CLASS(STRING) = \"code\", \"Code\"\n_NET_WM_DESKTOP(CARDINAL) = 1\n",
+            "0x3a00007" => "_NET_WM_PID(CARDINAL) = 30\nWM_CLASS(STRING) = \"firefox\", \"Firefox\"\n_NET_WM_DESKTOP(CARDINAL) = 1\n",
+            "0x4e00002" => "_NET_WM_PID(CARDINAL) = 40\nWM_CLASS(STRING) = \"vlc\", \"vlc\"\n_NET_WM_DESKTOP(CARDINAL) = 1\n_NET_WM_STATE(ATOM) = _NET_WM_STATE_HIDDEN\n",
+            "0x5000003" => "_NET_WM_PID(CARDINAL) = 50\nWM_CLASS(STRING) = \"slack\", \"Slack\"\n_NET_WM_DESKTOP(CARDINAL) = 0\n",
+            _ => return None,
+        };
+        Some(parse_window_props(output))
+    }
+
+    #[test]
+    fn test_parse_root_props_reads_active_stacking_and_desktop() {
+        let root = parse_root_props(ROOT_FIXTURE);
+        assert_eq!(root.active.as_deref(), Some("0x3a00007"));
+        assert_eq!(root.stacking.len(), 5);
+        assert_eq!(root.stacking[0], "0x1000001");
+        assert_eq!(root.current_desktop, Some(1));
+
+        let unfocused = parse_root_props("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n_NET_CLIENT_LIST_STACKING:  not found.\n");
+        assert_eq!(unfocused, RootWindowProps::default());
+    }
+
+    #[test]
+    fn test_foreground_selection_is_deterministic_across_monitors() {
+        let root = parse_root_props(ROOT_FIXTURE);
+        let pid_of = |source, root: &RootWindowProps| {
+            select_foreground_window(source, root, multi_window_props, &[]).and_then(|(_, p)| p.pid)
+        };
+        // The focused window wins by default
+        assert_eq!(pid_of(ForegroundWindowSource::Active, &root), Some(30));
+
+        // Topmost skips hidden windows, other desktops and the desktop window, ignoring focus
+        let mut focus_on_editor = parse_root_props(ROOT_FIXTURE);
+        focus_on_editor.active = Some("0x2c00004".to_string());
+        assert_eq!(pid_of(ForegroundWindowSource::Topmost, &root), Some(30));
+        assert_eq!(pid_of(ForegroundWindowSource::Topmost, &focus_on_editor), Some(30));
+        assert_eq!(pid_of(ForegroundWindowSource::Active, &focus_on_editor), Some(20));
+
+        // Without a focused window (e.g. the pointer is over an empty monitor) the topmost is used
+        let mut unfocused = parse_root_props(ROOT_FIXTURE);
+        unfocused.active = None;
+        assert_eq!(pid_of(ForegroundWindowSource::Active, &unfocused), Some(30));
+
+        // Excluded classes are skipped too
+        let excluded = ["Firefox".to_string()];
+        let picked = select_foreground_window(ForegroundWindowSource::Topmost, &root, multi_window_props, &excluded);
+        assert_eq!(picked.and_then(|(_, p)| p.pid), Some(20));
+
+        let empty = RootWindowProps::default();
+        assert_eq!(pid_of(ForegroundWindowSource::Active, &empty), None);
+    }
+
     #[test]
     fn test_non_linux_functions_do_not_panic() {
         // On non-Linux, these functions should not panic if called (should not be available)
@@ -1,14 +1,24 @@
 //! Platform abstraction module: re-exports platform-specific process and popup utilities for the current OS.
 
+pub mod installed_apps;
+pub use installed_apps::installed_apps;
+
 #[cfg(target_os = "windows")]
 mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::{
-    get_foreground_process_name, kill_process_by_name, list_running_process_names,
-    show_distraction_popup,
+    get_foreground_process_name, get_foreground_window_title, is_system_dnd, kill_process_by_name,
+    list_processes, list_running_process_names, play_popup_sound, show_distraction_limit_reached,
+    show_distraction_popup, show_focus_reminder, show_rules_reload_failed, terminate_process,
+    time_suspended,
 };
 
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "linux")]
-pub use linux::{get_foreground_process_name, list_running_process_names, show_distraction_popup};
+pub use linux::{
+    get_foreground_process_name, get_foreground_window_title, is_system_dnd,
+    list_processes, list_running_process_names, play_popup_sound, show_distraction_limit_reached,
+    show_distraction_popup, show_focus_reminder, show_rules_reload_failed, terminate_process,
+    time_suspended,
+};
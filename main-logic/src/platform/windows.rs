@@ -2,17 +2,29 @@
 
 use windows::{
     core::PCSTR,
-    Win32::Foundation::CloseHandle,
+    Win32::Foundation::{CloseHandle, HMODULE},
+    Win32::Media::Audio::{PlaySoundA, SND_ASYNC, SND_FILENAME, SND_NODEFAULT},
     Win32::System::Diagnostics::ToolHelp::*,
+    Win32::System::SystemInformation::GetTickCount64,
     Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE},
+    Win32::System::WindowsProgramming::QueryUnbiasedInterruptTime,
+    Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME,
+        QUNS_RUNNING_D3D_FULL_SCREEN,
+    },
     Win32::UI::WindowsAndMessaging::*,
 };
 
+use crate::config::PopupSound;
 use crate::error::SynapseError;
 use std::ffi::{CStr, CString};
+use std::time::Duration;
 
 /// Gets the name of the foreground process on Windows.
 ///
+/// Windows has a single foreground window across all monitors (the one receiving keyboard
+/// input), so the result does not depend on which monitor the pointer is on.
+///
 /// # Errors
 /// Returns `SynapseError` if the process name cannot be determined.
 pub fn get_foreground_process_name() -> Result<Option<String>, SynapseError> {
@@ -51,12 +63,113 @@ pub fn get_foreground_process_name() -> Result<Option<String>, SynapseError> {
     Ok(None)
 }
 
+/// Gets the title of the foreground window on Windows.
+///
+/// # Errors
+/// Returns `SynapseError` if the title cannot be read.
+pub fn get_foreground_window_title() -> Result<Option<String>, SynapseError> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return Ok(None);
+        }
+        let len = GetWindowTextLengthW(hwnd);
+        if len <= 0 {
+            return Ok(None);
+        }
+        let mut buf = vec![0u16; len as usize + 1];
+        let copied = GetWindowTextW(hwnd, &mut buf);
+        if copied <= 0 {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf16_lossy(&buf[..copied as usize])))
+    }
+}
+
+/// Scans the Windows registry for installed (app_name, exe_name) tuples.
+pub fn scan_installed_apps() -> Vec<(String, String)> {
+    use std::path::Path;
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    fn extract_exe_name(path: &str) -> Option<String> {
+        // Strip quotes/arguments/comma and extract only filename ending in .exe
+        path.split(|c| c == ',' || c == ' ' || c == '\"')
+            .find(|s| s.to_lowercase().ends_with(".exe"))
+            .and_then(|p| {
+                Path::new(p)
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+            })
+    }
+
+    let mut apps = Vec::new();
+
+    let uninstall_paths = [
+        (
+            RegKey::predef(HKEY_LOCAL_MACHINE),
+            r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+        ),
+        (
+            RegKey::predef(HKEY_LOCAL_MACHINE),
+            r"SOFTWARE\Wow6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+        ),
+        (
+            RegKey::predef(HKEY_CURRENT_USER),
+            r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+        ),
+    ];
+
+    for (hive, path) in uninstall_paths.iter() {
+        if let Ok(uninstall) = hive.open_subkey(path) {
+            for item in uninstall.enum_keys().flatten() {
+                if let Ok(subkey) = uninstall.open_subkey(&item) {
+                    let display_name: Result<String, _> = subkey.get_value("DisplayName");
+                    let is_system_component =
+                        subkey.get_value::<u32, _>("SystemComponent").unwrap_or(0) == 1;
+
+                    if let Ok(name) = display_name {
+                        if is_system_component || name.trim().is_empty() {
+                            continue;
+                        }
+
+                        // Try DisplayIcon first, fallback to UninstallString
+                        let exe_source = subkey
+                            .get_value::<String, _>("DisplayIcon")
+                            .ok()
+                            .or_else(|| subkey.get_value("UninstallString").ok());
+
+                        if let Some(source) = exe_source {
+                            if let Some(exe_name) = extract_exe_name(&source) {
+                                apps.push((name.trim().to_string(), exe_name));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Sort and deduplicate by app name
+    apps.sort_by(|a, b| a.0.cmp(&b.0));
+    apps.dedup_by(|a, b| a.0 == b.0);
+    apps
+}
+
 /// Lists all running process names on Windows.
 ///
 /// # Errors
 /// Returns `SynapseError` if the process list cannot be retrieved.
 pub fn list_running_process_names() -> Result<Vec<String>, SynapseError> {
-    let mut names = Vec::new();
+    Ok(list_processes()?.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Lists running processes as `(pid, lowercased executable name)` pairs.
+///
+/// # Errors
+/// Returns `SynapseError` if the process list cannot be retrieved.
+pub fn list_processes() -> Result<Vec<(u32, String)>, SynapseError> {
+    let mut processes = Vec::new();
     unsafe {
         let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
             .map_err(|e| SynapseError::Platform(format!("Snapshot failed: {:?}", e)))?;
@@ -71,14 +184,32 @@ pub fn list_running_process_names() -> Result<Vec<String>, SynapseError> {
                     .to_string_lossy()
                     .into_owned()
                     .to_lowercase();
-                names.push(name);
+                processes.push((entry.th32ProcessID, name));
                 if Process32Next(snapshot, &mut entry).is_err() {
                     break;
                 }
             }
         }
+        let _ = CloseHandle(snapshot);
+    }
+    Ok(processes)
+}
+
+/// Terminates process `pid`.
+///
+/// # Errors
+/// Returns `SynapseError` if the process cannot be opened or terminated.
+pub fn terminate_process(pid: u32) -> Result<(), SynapseError> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, false, pid).map_err(|e| {
+            SynapseError::Platform(format!("OpenProcess({}) failed: {:?}", pid, e))
+        })?;
+        let result = TerminateProcess(handle, 1).map_err(|e| {
+            SynapseError::Platform(format!("TerminateProcess({}) failed: {:?}", pid, e))
+        });
+        let _ = CloseHandle(handle);
+        result
     }
-    Ok(names)
 }
 
 /// Shows a popup warning for a distraction app on Windows.
@@ -104,6 +235,115 @@ pub fn show_distraction_popup(app_name: &str) -> Result<(), SynapseError> {
     Ok(())
 }
 
+/// Plays `sound` asynchronously: the warning beep for the system sound, `PlaySound` for a file.
+///
+/// # Errors
+/// Returns `SynapseError` if the sound cannot be played.
+pub fn play_popup_sound(sound: &PopupSound) -> Result<(), SynapseError> {
+    match sound {
+        PopupSound::None => Ok(()),
+        PopupSound::System => unsafe { MessageBeep(MB_ICONWARNING) }
+            .map_err(|e| SynapseError::Platform(format!("MessageBeep failed: {:?}", e))),
+        PopupSound::Custom(path) => {
+            let path = CString::new(path.to_string_lossy().as_bytes())
+                .map_err(|e| SynapseError::Platform(format!("CString failed: {}", e)))?;
+            let played = unsafe {
+                PlaySoundA(
+                    PCSTR(path.as_ptr() as *const u8),
+                    HMODULE(0),
+                    SND_FILENAME | SND_ASYNC | SND_NODEFAULT,
+                )
+            };
+            if played.as_bool() {
+                Ok(())
+            } else {
+                Err(SynapseError::Platform(format!(
+                    "PlaySound failed for {}",
+                    path.to_string_lossy()
+                )))
+            }
+        }
+    }
+}
+
+/// Shows a message box nudging the user to start focusing after `idle` without a session.
+///
+/// # Errors
+/// Returns `SynapseError` if the message cannot be built.
+pub fn show_focus_reminder(idle: Duration) -> Result<(), SynapseError> {
+    unsafe {
+        let title = CString::new("Time to focus?")
+            .map_err(|e| SynapseError::Platform(format!("CString failed: {}", e)))?;
+        let message = CString::new(format!("No focus session for {} minutes.", idle.as_secs() / 60))
+            .map_err(|e| SynapseError::Platform(format!("CString failed: {}", e)))?;
+        MessageBoxA(
+            None,
+            PCSTR(message.as_ptr() as *const u8),
+            PCSTR(title.as_ptr() as *const u8),
+            MB_OK | MB_ICONINFORMATION | MB_TOPMOST,
+        );
+    }
+    Ok(())
+}
+
+/// Shows a message box saying the session was ended after `limit` distractions.
+///
+/// # Errors
+/// Returns `SynapseError` if the message cannot be built.
+pub fn show_distraction_limit_reached(limit: u32) -> Result<(), SynapseError> {
+    unsafe {
+        let title = CString::new("Focus session ended")
+            .map_err(|e| SynapseError::Platform(format!("CString failed: {}", e)))?;
+        let message = CString::new(format!("You reached your limit of {} distractions.", limit))
+            .map_err(|e| SynapseError::Platform(format!("CString failed: {}", e)))?;
+        MessageBoxA(
+            None,
+            PCSTR(message.as_ptr() as *const u8),
+            PCSTR(title.as_ptr() as *const u8),
+            MB_OK | MB_ICONINFORMATION | MB_TOPMOST,
+        );
+    }
+    Ok(())
+}
+
+/// Shows a message box saying the rules file was not applied because of `error`.
+///
+/// # Errors
+/// Returns `SynapseError` if the message cannot be built.
+pub fn show_rules_reload_failed(error: &str) -> Result<(), SynapseError> {
+    unsafe {
+        let title = CString::new("App rules not applied")
+            .map_err(|e| SynapseError::Platform(format!("CString failed: {}", e)))?;
+        let message = CString::new(format!(
+            "apprules.json has an error, keeping the previous rules:\n{}",
+            error
+        ))
+        .map_err(|e| SynapseError::Platform(format!("CString failed: {}", e)))?;
+        MessageBoxA(
+            None,
+            PCSTR(message.as_ptr() as *const u8),
+            PCSTR(title.as_ptr() as *const u8),
+            MB_OK | MB_ICONWARNING | MB_TOPMOST,
+        );
+    }
+    Ok(())
+}
+
+/// Returns whether Windows is holding back notifications: Focus Assist (quiet time),
+/// presentation mode, or a full-screen app or game.
+///
+/// # Errors
+/// Returns `SynapseError` if the notification state cannot be queried.
+pub fn is_system_dnd() -> Result<bool, SynapseError> {
+    let state = unsafe { SHQueryUserNotificationState() }.map_err(|e| {
+        SynapseError::Platform(format!("SHQueryUserNotificationState failed: {:?}", e))
+    })?;
+    Ok(matches!(
+        state,
+        QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_PRESENTATION_MODE | QUNS_QUIET_TIME
+    ))
+}
+
 /// Kills a process by name.
 ///
 /// # Arguments
@@ -158,6 +398,20 @@ pub fn kill_process_by_name(process_name: &str) -> Result<(), SynapseError> {
     }
 }
 
+/// Returns how long the system has been suspended since boot.
+///
+/// The tick count keeps running while the system sleeps but the unbiased interrupt time
+/// does not, so their difference is the total time spent asleep.
+pub fn time_suspended() -> Option<Duration> {
+    let mut unbiased = 0u64;
+    if !unsafe { QueryUnbiasedInterruptTime(&mut unbiased) }.as_bool() {
+        return None;
+    }
+    let ticks = unsafe { GetTickCount64() };
+    // The interrupt time is in 100ns units, the tick count in milliseconds
+    Some(Duration::from_millis(ticks).saturating_sub(Duration::from_nanos(unbiased * 100)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,3 +448,4 @@ mod tests {
         assert!(true);
     }
 }
+
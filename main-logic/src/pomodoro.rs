@@ -0,0 +1,128 @@
+//! Pomodoro module: fixed work/break cycles layered on top of focus sessions.
+
+use crate::error::SynapseError;
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+
+/// The phase a pomodoro is in at a given time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PomodoroPhase {
+    /// Focus time; counts toward sessions.
+    Work,
+    /// A pause between work phases; sessions are closed and distractions are not interrupted.
+    Break,
+    /// All cycles are done.
+    Finished,
+}
+
+/// A pomodoro schedule: `cycles` work phases separated by breaks, starting at `started_at`.
+#[derive(Debug, Clone)]
+pub struct PomodoroMode {
+    work: Duration,
+    brk: Duration,
+    cycles: u32,
+    /// From the start to the end of the last work phase.
+    total: Duration,
+    started_at: SystemTime,
+}
+
+/// Converts a number of minutes, as entered by the user, into a `Duration`.
+///
+/// # Errors
+/// Returns `SynapseError::Config` if `minutes` is too large to count in seconds.
+pub fn minutes(minutes: u64) -> Result<Duration, SynapseError> {
+    minutes
+        .checked_mul(60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| SynapseError::Config(format!("{} minutes is too long", minutes)))
+}
+
+impl PomodoroMode {
+    /// Creates a pomodoro schedule starting at `started_at`.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if the whole schedule is too long to represent.
+    pub fn new(
+        work: Duration,
+        brk: Duration,
+        cycles: u32,
+        started_at: SystemTime,
+    ) -> Result<Self, SynapseError> {
+        let total = work
+            .checked_add(brk)
+            .and_then(|period| period.checked_mul(cycles))
+            .map(|all| all.saturating_sub(brk))
+            .ok_or_else(|| {
+                SynapseError::Config(format!(
+                    "pomodoro of {} cycles of {:?} work and {:?} break is too long",
+                    cycles, work, brk
+                ))
+            })?;
+        Ok(Self {
+            work,
+            brk,
+            cycles,
+            total,
+            started_at,
+        })
+    }
+
+    /// Returns the phase at `now` and the zero-based cycle it belongs to.
+    ///
+    /// There is no break after the last work phase; the pomodoro finishes instead,
+    /// reporting `cycles` as its cycle.
+    pub fn phase_at(&self, now: SystemTime) -> (PomodoroPhase, u32) {
+        let elapsed = now.duration_since(self.started_at).unwrap_or_default();
+        let period = self.work + self.brk;
+        if self.cycles == 0 || elapsed >= self.total {
+            return (PomodoroPhase::Finished, self.cycles);
+        }
+        let cycle = (elapsed.as_nanos() / period.as_nanos()) as u32;
+        let into_cycle = elapsed - period * cycle;
+        if into_cycle < self.work {
+            (PomodoroPhase::Work, cycle)
+        } else {
+            (PomodoroPhase::Break, cycle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_follow_the_schedule() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let pomodoro = PomodoroMode::new(
+            Duration::from_secs(25 * 60),
+            Duration::from_secs(5 * 60),
+            2,
+            start,
+        )
+        .unwrap();
+        let at = |secs: u64| pomodoro.phase_at(start + Duration::from_secs(secs));
+
+        assert_eq!(at(0), (PomodoroPhase::Work, 0));
+        assert_eq!(at(1499), (PomodoroPhase::Work, 0));
+        assert_eq!(at(1500), (PomodoroPhase::Break, 0));
+        assert_eq!(at(1799), (PomodoroPhase::Break, 0));
+        assert_eq!(at(1800), (PomodoroPhase::Work, 1));
+        assert_eq!(at(3299), (PomodoroPhase::Work, 1));
+        // No trailing break after the last work phase
+        assert_eq!(at(3300), (PomodoroPhase::Finished, 2));
+    }
+
+    #[test]
+    fn oversized_schedules_are_rejected() {
+        let start = SystemTime::UNIX_EPOCH;
+        let huge = Duration::from_secs(u64::MAX / 2);
+        assert!(matches!(
+            PomodoroMode::new(huge, huge, 4, start),
+            Err(SynapseError::Config(_))
+        ));
+        assert!(matches!(minutes(u64::MAX), Err(SynapseError::Config(_))));
+        assert_eq!(minutes(25).unwrap(), Duration::from_secs(1500));
+    }
+}
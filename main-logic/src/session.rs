@@ -1,17 +1,38 @@
 //! Session module: manages focus sessions, tracks app usage, and handles session state transitions.
 
 use crate::apprules::AppRules;
-use crate::db::DbHandle;
+use crate::config::{
+    CategoryBudgets, Config, EnforcementMode, KillBlockedApps, OverlapPrecedence, PopupSound,
+    SleepPolicy, TimezoneChangePolicy, UnknownAppPolicy, UsageKey,
+};
+use crate::constants::{
+    DEFAULT_MAX_WORK_APPS, DEFAULT_MIN_SLEEP_SECS, DEFAULT_POPUP_SUPPRESSION_SECS,
+    INFLIGHT_APP_MAX_AGE_SECS, RULE_MATCH_RECORD_INTERVAL_SECS,
+};
+use crate::db::{
+    mark_events_synced_async, mark_session_synced_async, queue_session_update_async,
+    record_sync_failure_async, DbHandle,
+};
 use crate::error::SynapseError;
 use crate::logger::log_event;
+use crate::metrics::{PollTiming, PollTimingStats, PollTimings};
 use crate::platform::{
-    get_foreground_process_name, list_running_process_names, show_distraction_popup,
+    get_foreground_process_name, get_foreground_window_title, is_system_dnd,
+    list_processes, list_running_process_names, play_popup_sound, show_distraction_popup,
+    show_distraction_limit_reached, show_focus_reminder, show_rules_reload_failed,
+    terminate_process, time_suspended,
 };
+use crate::pomodoro::{PomodoroMode, PomodoroPhase};
 use crate::sync::SupabaseSync;
 use crate::types::AppUsageEvent;
 use crate::types::SessionId;
+use crate::types::UsageStatus;
+use chrono::{DateTime, Local, NaiveDate};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 /// Represents a single focus session, including timing, apps used, and distraction attempts.
@@ -28,6 +49,9 @@ pub struct FocusSession {
     pub work_apps: Vec<String>,
     /// Number of distraction attempts during the session.
     pub distraction_attempts: u32,
+    /// Project taken from the work app's window title, if project partitioning is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
 }
 
 impl FocusSession {
@@ -39,6 +63,7 @@ impl FocusSession {
             end_time: None,
             work_apps,
             distraction_attempts: 0,
+            project: None,
         }
     }
     /// Returns the session start time.
@@ -57,6 +82,15 @@ impl FocusSession {
     pub fn distraction_attempts(&self) -> u32 {
         self.distraction_attempts
     }
+    /// Returns how long the session has lasted: up to its end if ended, or up to `now` if
+    /// still active. Clamped to zero if the clock is behind the start. Sessions are never
+    /// paused (a pomodoro break ends them), so no paused time is subtracted.
+    pub fn duration(&self, now: SystemTime) -> Duration {
+        self.end_time
+            .unwrap_or(now)
+            .duration_since(self.start_time)
+            .unwrap_or(Duration::ZERO)
+    }
 
     /// Increments the distraction attempts counter.
     pub fn increment_distraction_attempts(&mut self) {
@@ -64,7 +98,134 @@ impl FocusSession {
     }
 }
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Returns the file name of the running executable.
+fn current_process_name() -> Option<String> {
+    std::env::current_exe()
+        .ok()?
+        .file_name()?
+        .to_str()
+        .map(str::to_string)
+}
+
+/// Returns the local calendar date of `time`.
+fn local_date(time: SystemTime) -> NaiveDate {
+    DateTime::<Local>::from(time).date_naive()
+}
+
+/// Returns the UNIX timestamp of local midnight starting the day of `time`.
+fn local_day_start(time: SystemTime) -> i64 {
+    let midnight = local_date(time).and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    midnight
+        .and_local_timezone(Local)
+        .earliest()
+        .map_or_else(|| DateTime::<Local>::from(time).timestamp(), |start| start.timestamp())
+}
+
+/// Plays a resolved popup sound. Defaults to the platform player; replaceable for tests.
+pub type SoundPlayer = Box<dyn Fn(&PopupSound) -> Result<(), SynapseError> + Send + Sync>;
+
+/// Returns the system timezone's offset from UTC, in seconds, at `time`.
+fn local_utc_offset(time: SystemTime) -> i32 {
+    DateTime::<Local>::from(time).offset().local_minus_utc()
+}
+
+/// What a poll would do for a given foreground app and set of running processes, as
+/// reported by [`SessionManager::dry_run_poll`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PollOutcome {
+    /// The foreground app as it would be tracked (helpers folded, aliases applied), or `None`
+    /// if it would be ignored.
+    pub foreground: Option<String>,
+    /// Whether the foreground app counts as work.
+    pub is_work_app: bool,
+    /// Whether the foreground app counts as a distraction.
+    pub is_blocked: bool,
+    /// Whether a focus session would start (or be resumed).
+    pub would_start_session: bool,
+    /// Whether the running session would end because no work app is left.
+    pub would_end_session: bool,
+    /// Whether a distraction popup would be shown.
+    pub would_popup: bool,
+    /// Why a blocked foreground app would not get a popup, if it wouldn't.
+    pub popup_skipped: Option<String>,
+}
+
+/// A significant session-manager event, reported to the observer set with
+/// [`SessionManager::set_on_event`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// A focus session began.
+    SessionStarted {
+        session_id: Uuid,
+        start_time: i64,
+        work_apps: Vec<String>,
+    },
+    /// A focus session ended.
+    SessionEnded {
+        session_id: Uuid,
+        end_time: i64,
+        distraction_attempts: u32,
+        reason: String,
+    },
+    /// A blocked app came to the foreground during a session, or outside one (with the nil
+    /// session id) when out-of-session distractions are counted.
+    Distraction { session_id: Uuid, app: String },
+    /// A changed rules file could not be loaded; the previous rules stay in effect.
+    RulesReloadFailed { error: String },
+    /// An app category went over today's budget; reported once per category and day.
+    CategoryOverBudget {
+        category: String,
+        used_secs: i64,
+        budget_secs: u64,
+    },
+    /// Periodic metrics summary from the main loop.
+    MetricsSummary {
+        total_checks: u64,
+        blocked_count: u64,
+        session_active: bool,
+    },
+}
+
+/// Emits the structured "session ended" event.
+fn trace_session_end(session: &FocusSession, reason: &str) {
+    // Measured to the recorded end; a session without one counts as zero
+    let duration_secs = session.duration(session.start_time).as_secs();
+    tracing::info!(
+        session_id = %session.id,
+        duration_secs,
+        distraction_attempts = session.distraction_attempts,
+        reason,
+        "session ended"
+    );
+}
+
+/// Source of the current time for a [`SessionManager`]; replaceable in tests.
+pub type Clock = Arc<dyn Fn() -> SystemTime + Send + Sync>;
+
+/// Reports whether the OS is in do-not-disturb mode; replaceable in tests.
+pub type DndCheck = Arc<dyn Fn() -> Result<bool, SynapseError> + Send + Sync>;
+
+/// Returns the local timezone's offset from UTC, in seconds, at a given time; replaceable in tests.
+pub type UtcOffsetSource = Arc<dyn Fn(SystemTime) -> i32 + Send + Sync>;
+
+/// Returns the total time the system has spent suspended, or `None` if the OS can't tell;
+/// replaceable in tests.
+pub type SuspendedTimeSource = Arc<dyn Fn() -> Option<Duration> + Send + Sync>;
+
+/// Lists running processes as `(pid, name)` pairs; replaceable in tests.
+pub type ProcessLister = Arc<dyn Fn() -> Result<Vec<(u32, String)>, SynapseError> + Send + Sync>;
+
+/// Terminates a process by pid; replaceable in tests.
+pub type ProcessKiller = Arc<dyn Fn(u32) -> Result<(), SynapseError> + Send + Sync>;
+
+/// Observer called with each [`SessionEvent`].
+pub type EventCallback = Box<dyn Fn(&SessionEvent) + Send + Sync>;
+
+/// Called with the error when reloading the app rules fails.
+pub type RulesReloadFailedCallback = Box<dyn Fn(&SynapseError) + Send + Sync>;
 
 /// Manages the current focus session, tracks app usage, and interacts with the database.
 pub struct SessionManager {
@@ -74,6 +235,14 @@ pub struct SessionManager {
     current_session: Option<FocusSession>,
     /// The last distraction app detected.
     last_distraction_app: Option<String>,
+    /// When a popup was last shown for each blocked app.
+    popups_shown: HashMap<String, SystemTime>,
+    /// How long after a popup the same app does not trigger another. Zero disables this.
+    popup_suppression_window: Duration,
+    /// Most popups shown per local day. Zero means no cap.
+    max_popups_per_day: u32,
+    /// The local day popups are being counted for and how many were shown on it.
+    popups_today: Option<(NaiveDate, u32)>,
     /// The last checked process name.
     last_checked_process: Option<String>,
     /// Whether the last checked process was blocked.
@@ -86,12 +255,120 @@ pub struct SessionManager {
     last_app: Option<String>,
     /// The last app start time.
     last_app_start: Option<std::time::SystemTime>,
-    /// The row ID of the last app usage event in the database.
-    last_app_event_id: Option<Uuid>,
+    /// The last app's name as the OS reported it, before helper folding and aliasing.
+    last_app_raw: Option<String>,
+    /// The foreground name as the OS reported it at the current poll.
+    foreground_raw: Option<String>,
     supabase_sync: Option<SupabaseSync>,
     on_distraction: Option<Box<dyn Fn(&str) + Send + Sync>>,
     /// Temporary allowances for blocked apps (App Name -> Allowed Until).
     temporary_allowances: HashMap<String, SystemTime>,
+    /// Whether distractions show a popup or are only recorded.
+    enforcement_mode: EnforcementMode,
+    /// How long after ending a session it can still be resumed. Zero disables resuming.
+    resume_window: Duration,
+    /// The most recently ended session and its database ID, kept for `resume_window`.
+    recently_ended: Option<(FocusSession, SessionId)>,
+    /// Sessions longer than this are split into a new session. Zero disables splitting.
+    max_session_length: Duration,
+    /// Sessions end once this many distractions are counted. Zero disables the limit.
+    max_distractions_per_session: u32,
+    /// Returns the current time.
+    clock: Clock,
+    /// Whether the OS is in do-not-disturb mode, in which popups are skipped.
+    dnd_check: DndCheck,
+    /// Returns the local timezone's UTC offset.
+    utc_offset: UtcOffsetSource,
+    /// The UTC offset seen at the previous poll, to detect timezone changes.
+    last_utc_offset: Option<i32>,
+    /// What a timezone change does to the running session.
+    timezone_change_policy: TimezoneChangePolicy,
+    /// Returns the total time the system has been suspended.
+    time_suspended: SuspendedTimeSource,
+    /// When the previous poll ran and the suspended time it saw, to detect sleeps.
+    last_poll: Option<(SystemTime, Option<Duration>)>,
+    /// What a system sleep does to the running session.
+    sleep_policy: SleepPolicy,
+    /// Sleeps shorter than this are ignored. Zero ignores all sleeps.
+    min_sleep: Duration,
+    /// Set after a sleep ended the session under `SleepPolicy::EndSession`, or the distraction
+    /// limit ended it, until a work app is in the foreground again.
+    awaiting_work_app: bool,
+    /// Synapse's own process name, never tracked as the foreground app.
+    self_process_name: Option<String>,
+    /// A reason given for the blocked app still in focus, attached once its event is recorded.
+    pending_reason: Option<(String, String)>,
+    /// When each app rule's match was last persisted, to throttle writes.
+    rule_matches_recorded: HashMap<String, SystemTime>,
+    /// Which rule applies to an app in both the whitelist and the blacklist.
+    overlap_precedence: OverlapPrecedence,
+    /// Lowercased executable names of installed apps. `None` when no list is available,
+    /// in which case every app counts as recognized.
+    known_apps: Option<HashSet<String>>,
+    /// How foreground apps missing from `known_apps` are handled.
+    unknown_app_policy: UnknownAppPolicy,
+    /// Which identifier app usage events are stored under.
+    usage_key: UsageKey,
+    /// Maximum distinct work apps stored per session.
+    max_work_apps: usize,
+    /// Seconds of focus per normalized app name in the current session, used to pick which
+    /// work apps to keep.
+    app_focus_secs: HashMap<String, i64>,
+    /// Minimum percentage of a session a work app must be in the foreground to be kept.
+    min_work_app_focus_pct: u8,
+    /// The running pomodoro, if any.
+    pomodoro: Option<PomodoroMode>,
+    /// The pomodoro phase and cycle seen at the last poll.
+    pomodoro_phase: Option<(PomodoroPhase, u32)>,
+    /// Called with the new phase and cycle at each pomodoro transition.
+    on_pomodoro_phase: Option<Box<dyn Fn(PomodoroPhase, u32) + Send + Sync>>,
+    /// Observer called with each session start, end and distraction.
+    on_event: Option<EventCallback>,
+    /// Extracts the project from a work app's window title; `None` disables partitioning.
+    project_pattern: Option<Regex>,
+    /// How long without a session before a focus reminder is shown. Zero disables reminders.
+    focus_reminder_after: Duration,
+    /// When the current stretch without a session began.
+    no_session_since: Option<SystemTime>,
+    /// Whether the reminder for the current stretch without a session was already shown.
+    focus_reminder_shown: bool,
+    /// Called with the time since the last session when a focus reminder is due; without it
+    /// a platform notification is shown.
+    on_focus_reminder: Option<Box<dyn Fn(Duration) + Send + Sync>>,
+    /// Called with the limit when a session is ended for reaching it; defaults to a
+    /// platform notification.
+    on_distraction_limit: Option<Box<dyn Fn(u32) + Send + Sync>>,
+    /// Called with the error when reloading the rules fails; without it a platform
+    /// notification is shown.
+    on_rules_reload_failed: Option<RulesReloadFailedCallback>,
+    /// Project most recently seen in a work app's window title during the current session.
+    current_project: Option<String>,
+    /// Daily focus-time budgets per app category.
+    category_budgets: CategoryBudgets,
+    /// Whether non-work apps in an over-budget category count as distractions.
+    block_over_budget_categories: bool,
+    /// Local day each over-budget category was last reported on.
+    over_budget_reported: HashMap<String, NaiveDate>,
+    /// Sound played with each distraction popup.
+    popup_sound: PopupSound,
+    /// Plays the popup sound.
+    sound_player: SoundPlayer,
+    /// Whether blocked apps may be terminated, and when.
+    kill_blocked_apps: KillBlockedApps,
+    /// Lists running processes when resolving an app to kill.
+    process_lister: ProcessLister,
+    /// Terminates a process.
+    process_killer: ProcessKiller,
+    /// Whether blocked apps opened while no session runs are recorded as distractions.
+    count_distractions_outside_sessions: bool,
+    /// The blocked app last recorded as a distraction outside a session, until it loses focus.
+    last_outside_session_distraction: Option<String>,
+    /// Whether apps that are neither whitelisted nor neutral count as distractions.
+    strict_mode: bool,
+    /// Whether usage events also store the process name as the OS reported it.
+    store_raw_process_names: bool,
+    /// How long recent calls to [`poll`](Self::poll) took.
+    poll_timings: PollTimings,
 }
 
 impl SessionManager {
@@ -106,63 +383,361 @@ impl SessionManager {
             apprules,
             current_session: None,
             last_distraction_app: None,
+            popups_shown: HashMap::new(),
+            popup_suppression_window: Duration::from_secs(DEFAULT_POPUP_SUPPRESSION_SECS),
+            max_popups_per_day: 0,
+            popups_today: None,
             last_checked_process: None,
             last_blocked: false,
             db_handle,
             session_id: None,
             last_app: None,
             last_app_start: None,
-            last_app_event_id: None,
+            last_app_raw: None,
+            foreground_raw: None,
             supabase_sync,
             on_distraction,
             temporary_allowances: HashMap::new(),
+            enforcement_mode: EnforcementMode::default(),
+            resume_window: Duration::ZERO,
+            recently_ended: None,
+            max_session_length: Duration::ZERO,
+            max_distractions_per_session: 0,
+            clock: Arc::new(SystemTime::now),
+            // Unit tests must not depend on the machine's do-not-disturb state
+            dnd_check: if cfg!(test) {
+                Arc::new(|| Ok(false))
+            } else {
+                Arc::new(is_system_dnd)
+            },
+            utc_offset: Arc::new(local_utc_offset),
+            last_utc_offset: None,
+            timezone_change_policy: TimezoneChangePolicy::default(),
+            time_suspended: Arc::new(time_suspended),
+            last_poll: None,
+            sleep_policy: SleepPolicy::default(),
+            min_sleep: Duration::from_secs(DEFAULT_MIN_SLEEP_SECS),
+            awaiting_work_app: false,
+            self_process_name: current_process_name(),
+            pending_reason: None,
+            rule_matches_recorded: HashMap::new(),
+            overlap_precedence: OverlapPrecedence::default(),
+            known_apps: None,
+            unknown_app_policy: UnknownAppPolicy::default(),
+            usage_key: UsageKey::default(),
+            max_work_apps: DEFAULT_MAX_WORK_APPS,
+            app_focus_secs: HashMap::new(),
+            min_work_app_focus_pct: 0,
+            pomodoro: None,
+            pomodoro_phase: None,
+            on_pomodoro_phase: None,
+            on_event: None,
+            focus_reminder_after: Duration::ZERO,
+            no_session_since: None,
+            focus_reminder_shown: false,
+            on_focus_reminder: None,
+            on_distraction_limit: None,
+            on_rules_reload_failed: None,
+            category_budgets: CategoryBudgets::default(),
+            block_over_budget_categories: false,
+            over_budget_reported: HashMap::new(),
+            popup_sound: PopupSound::default(),
+            sound_player: Box::new(play_popup_sound),
+            kill_blocked_apps: KillBlockedApps::default(),
+            process_lister: Arc::new(list_processes),
+            process_killer: Arc::new(terminate_process),
+            count_distractions_outside_sessions: false,
+            last_outside_session_distraction: None,
+            strict_mode: false,
+            store_raw_process_names: true,
+            poll_timings: PollTimings::default(),
+            project_pattern: None,
+            current_project: None,
         }
     }
 
+    /// Replaces the time source (for tests and simulations).
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    fn now(&self) -> SystemTime {
+        (self.clock)()
+    }
+
+    /// Replaces the do-not-disturb check (for tests and simulations).
+    pub fn set_dnd_check(&mut self, check: DndCheck) {
+        self.dnd_check = check;
+    }
+
+    /// Replaces the timezone offset lookup (for tests and simulations).
+    pub fn set_utc_offset_source(&mut self, source: UtcOffsetSource) {
+        self.utc_offset = source;
+    }
+
+    /// Replaces the suspended-time lookup used to detect sleeps (for tests and simulations).
+    pub fn set_time_suspended_source(&mut self, source: SuspendedTimeSource) {
+        self.time_suspended = source;
+    }
+
+    /// Sets what a system sleep of at least `min_sleep` does to the running session.
+    /// `Duration::ZERO` ignores sleeps.
+    pub fn set_sleep_policy(&mut self, policy: SleepPolicy, min_sleep: Duration) {
+        self.sleep_policy = policy;
+        self.min_sleep = min_sleep;
+    }
+
+    /// Sets whether blocked apps may be terminated.
+    pub fn set_kill_blocked_apps(&mut self, kill: KillBlockedApps) {
+        self.kill_blocked_apps = kill;
+    }
+
+    /// Sets whether blocked apps opened while no session runs are recorded as distractions.
+    pub fn set_count_distractions_outside_sessions(&mut self, count: bool) {
+        self.count_distractions_outside_sessions = count;
+    }
+
+    /// Sets whether apps that are neither whitelisted nor neutral count as distractions.
+    pub fn set_strict_mode(&mut self, strict: bool) {
+        self.strict_mode = strict;
+    }
+
+    /// Sets whether usage events also store the process name as the OS reported it.
+    pub fn set_store_raw_process_names(&mut self, store: bool) {
+        self.store_raw_process_names = store;
+    }
+
+    /// Replaces how processes are listed and terminated (for tests and simulations).
+    pub fn set_process_control(&mut self, lister: ProcessLister, killer: ProcessKiller) {
+        self.process_lister = lister;
+        self.process_killer = killer;
+    }
+
+    /// Sets the daily focus-time budgets per app category.
+    pub fn set_category_budgets(&mut self, budgets: CategoryBudgets) {
+        self.category_budgets = budgets;
+    }
+
+    /// Sets whether non-work apps in a category over today's budget count as distractions.
+    pub fn set_block_over_budget_categories(&mut self, block: bool) {
+        self.block_over_budget_categories = block;
+    }
+
+    /// Sets the sound played with each distraction popup.
+    pub fn set_popup_sound(&mut self, sound: PopupSound) {
+        self.popup_sound = sound;
+    }
+
+    /// Replaces the player used for popup sounds.
+    pub fn set_sound_player(&mut self, player: SoundPlayer) {
+        self.sound_player = player;
+    }
+
+    /// Sets what a change of the system timezone does to the running session.
+    pub fn set_timezone_change_policy(&mut self, policy: TimezoneChangePolicy) {
+        self.timezone_change_policy = policy;
+    }
+
+    /// Whether the OS is in do-not-disturb mode. A failed check counts as not in DND.
+    fn system_in_dnd(&self) -> bool {
+        (self.dnd_check)().unwrap_or_else(|e| {
+            eprintln!("Failed to query do-not-disturb state: {}", e);
+            false
+        })
+    }
+
     /// Polls the current foreground app, updates session state, logs events, and handles distractions.
+    /// Returns every session that ended during the poll, in the order they ended.
     ///
     /// # Errors
     /// Returns `SynapseError` if any platform or logging operation fails.
-    pub fn poll(&mut self) -> Result<Option<FocusSession>, SynapseError> {
+    pub fn poll(&mut self) -> Result<Vec<FocusSession>, SynapseError> {
+        let started = Instant::now();
         let running_processes = list_running_process_names().map_err(|e| {
             SynapseError::Platform(format!("Failed to list running processes: {}", e))
         })?;
-        let any_work_app_running = running_processes
-            .iter()
-            .any(|name| self.apprules.is_work_app(name));
+        let listed = Instant::now();
+        let foreground = get_foreground_process_name().map_err(|e| {
+            SynapseError::Platform(format!("Failed to get foreground process: {}", e))
+        })?;
+        // Titles are only needed to tell projects apart; a failed read just skips that
+        let title = if self.project_pattern.is_some() {
+            get_foreground_window_title().unwrap_or_else(|e| {
+                eprintln!("Failed to get foreground window title: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+        let resolved = Instant::now();
+        let result = self.poll_snapshot_with_title(foreground, title, running_processes);
+        self.poll_timings.record(PollTiming {
+            processes: listed - started,
+            foreground: resolved - listed,
+            processing: resolved.elapsed(),
+        });
+        result
+    }
+
+    /// Returns the average and longest duration of recent [`poll`](Self::poll) calls, in total
+    /// and per phase.
+    pub fn poll_timing_stats(&self) -> PollTimingStats {
+        self.poll_timings.stats()
+    }
+
+    /// Runs one poll iteration over an already-captured foreground app and process list.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if any logging or database operation fails.
+    pub fn poll_snapshot(
+        &mut self,
+        foreground: Option<String>,
+        running_processes: Vec<String>,
+    ) -> Result<Vec<FocusSession>, SynapseError> {
+        self.poll_snapshot_with_title(foreground, None, running_processes)
+    }
+
+    /// Like [`poll_snapshot`](Self::poll_snapshot), with the foreground window's title used
+    /// to tell projects apart.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if any logging or database operation fails.
+    pub fn poll_snapshot_with_title(
+        &mut self,
+        foreground: Option<String>,
+        title: Option<String>,
+        running_processes: Vec<String>,
+    ) -> Result<Vec<FocusSession>, SynapseError> {
+        let _span = tracing::debug_span!("poll", foreground = ?foreground).entered();
+        self.foreground_raw = foreground.clone();
+        // Helpers (crash handlers, renderers) count as the app that spawned them.
+        let foreground = foreground.map(|name| {
+            self.apprules
+                .helper_parent(&name, &running_processes)
+                .unwrap_or(name)
+        });
+        let running_processes = self.apprules.fold_helper_processes(&running_processes);
+        let foreground = foreground.filter(|name| {
+            self.unknown_app_policy != UnknownAppPolicy::Ignore || self.is_recognized(name)
+        });
+        let work_processes = self.work_candidates(&running_processes);
+        let work_condition_met = self.apprules.work_condition_met(&work_processes);
+        let slept = self.end_session_if_slept(&work_processes)?;
+        if self.awaiting_work_app {
+            self.awaiting_work_app = !foreground
+                .as_deref()
+                .is_some_and(|name| self.apprules.is_work_app(name));
+        }
+        self.record_rule_matches(foreground.as_deref(), &running_processes)?;
+        let paused = self.advance_pomodoro()?;
+        let switched = self.switch_project_if_changed(foreground.as_deref(), title.as_deref())?;
+        let moved = self.split_session_if_timezone_changed()?;
+        let split = self.split_session_if_too_long()?;
 
-        // NEW: Start session if any work app is running and no session is active
-        if any_work_app_running && self.current_session.is_none() {
-            self.start_new_session_if_needed(&running_processes)?;
+        // NEW: Start session if the work condition holds and no session is active
+        let may_start = !self.awaiting_work_app;
+        if work_condition_met && self.current_session.is_none() && may_start {
+            self.start_new_session_if_needed(&work_processes)?;
         }
 
-        if let Some(proc) = get_foreground_process_name().map_err(|e| {
-            SynapseError::Platform(format!("Failed to get foreground process: {}", e))
-        })? {
-            self.handle_foreground_process(proc, &running_processes, any_work_app_running)?;
-        } else {
-            self.handle_no_foreground_process();
+        match foreground {
+            Some(proc) if self.is_self_process(&proc) => self.handle_self_foreground()?,
+            Some(proc) => {
+                self.handle_foreground_process(proc, &work_processes, work_condition_met)?
+            }
+            None => self.handle_no_foreground_process(),
+        }
+
+        let limited = self.end_session_if_distraction_limit_reached()?;
+        let ended = self.check_and_end_session(work_condition_met)?;
+        self.remind_if_unfocused();
+        // A poll can close more than one session (a pomodoro break, then the work app closing)
+        Ok([slept, paused, switched, moved, split, limited, ended].into_iter().flatten().collect())
+    }
+
+    /// Evaluates what a poll with `foreground` in focus and `running_processes` running would
+    /// do under the current rules, without changing any state or touching the database.
+    ///
+    /// Category budgets are not considered, since they depend on recorded usage, and
+    /// timers (session length, pomodoro phases, sleeps) are taken as they are now.
+    pub fn dry_run_poll(&self, foreground: Option<String>, running_processes: &[String]) -> PollOutcome {
+        let foreground = foreground.map(|name| {
+            self.apprules
+                .helper_parent(&name, running_processes)
+                .unwrap_or(name)
+        });
+        let running_processes = self.apprules.fold_helper_processes(running_processes);
+        let foreground = foreground
+            .filter(|name| {
+                self.unknown_app_policy != UnknownAppPolicy::Ignore || self.is_recognized(name)
+            })
+            .filter(|name| !self.is_self_process(name))
+            .map(|name| self.apprules.normalize(&name));
+        let work_processes = self.work_candidates(&running_processes);
+        let work_condition_met = self.apprules.work_condition_met(&work_processes);
+
+        let mut outcome = PollOutcome {
+            foreground: foreground.clone(),
+            ..PollOutcome::default()
+        };
+        if let Some(proc_name) = &foreground {
+            (outcome.is_blocked, outcome.is_work_app) = self.rule_verdict(proc_name);
+            if outcome.is_blocked && self.is_snoozed(proc_name) {
+                outcome.is_blocked = false;
+            }
         }
 
-        self.check_and_end_session(any_work_app_running)
+        let session_active = self.current_session.is_some();
+        let awaiting_work = self.awaiting_work_app && !outcome.is_work_app;
+        outcome.would_start_session = !session_active
+            && work_condition_met
+            && !awaiting_work
+            && !self.in_pomodoro_break();
+        outcome.would_end_session = session_active && !work_condition_met;
+
+        if let Some(proc_name) = foreground.as_deref().filter(|_| outcome.is_blocked) {
+            let skipped = if !session_active && !outcome.would_start_session {
+                Some("no focus session")
+            } else if self.in_pomodoro_break() {
+                Some("pomodoro break")
+            } else if self.last_distraction_app.as_deref() == Some(proc_name) {
+                Some("already interrupted for this visit")
+            } else if self.enforcement_mode == EnforcementMode::Observe {
+                Some("observe mode")
+            } else if self.popup_recently_shown(proc_name) {
+                Some("popup shown recently")
+            } else if self.daily_popup_cap_reached() {
+                Some("daily popup cap reached")
+            } else if self.system_in_dnd() {
+                Some("do not disturb is on")
+            } else {
+                None
+            };
+            outcome.would_popup = skipped.is_none();
+            outcome.popup_skipped = skipped.map(str::to_string);
+        }
+        outcome
     }
 
     /// Ends the current active session, if any, and updates the database.
     ///
+    /// Returns the ended session; the caller pushes it to Supabase.
+    ///
     /// # Errors
     /// Returns `SynapseError` if updating the session fails.
     pub fn end_active_session(&mut self) -> Result<Option<FocusSession>, SynapseError> {
-        println!(
-            "[SessionManager] end_active_session: supabase_sync is_some: {}",
-            self.supabase_sync.is_some()
-        );
         // Finalize last app usage event if any
         self.finalize_last_app_usage_event()?;
         if let Some(mut session) = self.current_session.take() {
             println!("\n--- Focus session ended (graceful shutdown) ---");
             println!("Apps used: {:?}", session.work_apps());
-            let now = SystemTime::now();
+            let now = self.now();
             session.end_time = Some(now);
+            self.prune_unused_work_apps(&mut session);
+            let truncated = self.cap_work_apps(&mut session);
+            trace_session_end(&session, "shutdown");
+            self.emit_session_ended(&session, "shutdown");
+            self.current_project = None;
             if let Some(session_id) = self.session_id.take() {
                 let end_time = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
                 let work_apps_str = session.work_apps.join(",");
@@ -176,21 +751,7 @@ impl SessionManager {
                         &session_id.0.to_string(),
                     ],
                 )?;
-            }
-            // Supabase: update session at end
-            println!(
-                "[Supabase][update_focus_session] supabase_sync is_some: {}",
-                self.supabase_sync.is_some()
-            );
-            if let Some(sync) = &self.supabase_sync {
-                let session_clone = session.clone();
-                let sync = sync.clone();
-                println!("[Supabase][update_focus_session] About to update session in Supabase...");
-                let handle = std::thread::spawn(move || {
-                    let rt = tokio::runtime::Runtime::new().unwrap();
-                    let _ = rt.block_on(sync.update_focus_session(&session_clone));
-                });
-                let _ = handle.join(); // Wait for thread to finish so logs are printed
+                self.record_work_apps_truncated(&session, truncated)?;
             }
             Ok(Some(session))
         } else {
@@ -198,6 +759,38 @@ impl SessionManager {
         }
     }
 
+    /// Persists the app currently in focus so its duration can bridge a restart.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the state cannot be written.
+    pub fn save_inflight_app(&self) -> Result<(), SynapseError> {
+        if let (Some(app), Some(start)) = (&self.last_app, self.last_app_start) {
+            let start_secs = start.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            let now_secs = self.now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            self.db_handle.save_inflight_app(app, start_secs, now_secs)?;
+        }
+        Ok(())
+    }
+
+    /// Restores the in-flight app saved by a previous run, so the first poll can attribute
+    /// its full duration. Entries older than `INFLIGHT_APP_MAX_AGE_SECS` are discarded.
+    ///
+    /// Returns `true` if an app was restored.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the state cannot be read.
+    pub fn restore_inflight_app(&mut self) -> Result<bool, SynapseError> {
+        if let Some((app, start_secs, saved_at)) = self.db_handle.take_inflight_app()? {
+            let now_secs = self.now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            if now_secs - saved_at <= INFLIGHT_APP_MAX_AGE_SECS {
+                self.last_app = Some(app);
+                self.last_app_start = Some(UNIX_EPOCH + Duration::from_secs(start_secs.max(0) as u64));
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     /// Returns the last checked process name, if any.
     pub fn last_checked_process(&self) -> Option<&String> {
         self.last_checked_process.as_ref()
@@ -211,6 +804,14 @@ impl SessionManager {
         self.current_session.as_ref()
     }
 
+    /// Returns the distraction attempts of the active session, or 0 if no session is active.
+    pub fn current_session_distractions(&self) -> u32 {
+        self.current_session
+            .as_ref()
+            .map(|s| s.distraction_attempts())
+            .unwrap_or(0)
+    }
+
     /// Returns a reference to the database handle.
     pub fn db_handle(&self) -> &DbHandle {
         &self.db_handle
@@ -243,16 +844,393 @@ impl SessionManager {
         self.session_id = Some(id);
     }
 
+    /// Returns the application rules currently in effect.
+    pub fn apprules(&self) -> &AppRules {
+        &self.apprules
+    }
+
+    /// Sets whether distractions show a popup (`Enforce`) or are only recorded (`Observe`).
+    pub fn set_enforcement_mode(&mut self, mode: EnforcementMode) {
+        self.enforcement_mode = mode;
+    }
+
+    /// Sets how long after a session ends a reappearing work app resumes it instead of
+    /// starting a new session. `Duration::ZERO` disables resuming.
+    pub fn set_resume_window(&mut self, window: Duration) {
+        self.resume_window = window;
+    }
+
+    /// Applies the session-related settings from `config`.
+    pub fn apply_config(&mut self, config: &Config) {
+        self.set_enforcement_mode(config.enforcement_mode);
+        self.set_resume_window(Duration::from_secs(config.session_resume_secs));
+        self.set_max_session_length(Duration::from_secs(config.max_session_secs));
+        self.set_max_distractions_per_session(config.max_distractions_per_session);
+        if let Some(name) = &config.self_process_name {
+            self.set_self_process_name(Some(name.clone()));
+        }
+        self.set_overlap_precedence(config.overlap_precedence);
+        self.set_unknown_app_policy(config.unknown_app_policy);
+        self.set_usage_key(config.usage_key);
+        self.set_max_work_apps(config.max_work_apps);
+        self.set_popup_suppression_window(Duration::from_secs(config.popup_suppression_secs));
+        self.set_max_popups_per_day(config.max_popups_per_day);
+        self.set_timezone_change_policy(config.timezone_change_policy);
+        self.set_sleep_policy(config.sleep_policy, Duration::from_secs(config.min_sleep_secs));
+        self.set_focus_reminder_after(Duration::from_secs(config.focus_reminder_secs));
+        self.set_category_budgets(config.category_budgets.clone());
+        self.set_block_over_budget_categories(config.block_over_budget_categories);
+        self.set_popup_sound(config.popup_sound.clone());
+        self.set_kill_blocked_apps(config.kill_blocked_apps);
+        self.set_count_distractions_outside_sessions(config.count_distractions_outside_sessions);
+        self.set_strict_mode(config.strict_mode);
+        self.set_store_raw_process_names(config.store_raw_process_names);
+        self.set_min_work_app_focus_pct(config.min_work_app_focus_pct);
+        match config.project_title_regex() {
+            Ok(pattern) => self.set_project_pattern(pattern),
+            Err(e) => eprintln!("Ignoring project title pattern: {}", e),
+        }
+    }
+
+    /// Sets the maximum length of a session before it is split into a new one.
+    /// `Duration::ZERO` disables splitting.
+    pub fn set_max_session_length(&mut self, max: Duration) {
+        self.max_session_length = max;
+    }
+
+    /// Sets how many distractions end the current session. Zero disables the limit.
+    pub fn set_max_distractions_per_session(&mut self, limit: u32) {
+        self.max_distractions_per_session = limit;
+    }
+
+    /// Sets the process name treated as Synapse itself, which is ignored when it is in the
+    /// foreground. Defaults to the running executable's file name; `None` disables this.
+    pub fn set_self_process_name(&mut self, name: Option<String>) {
+        self.self_process_name = name;
+    }
+
+    /// Sets the regex that extracts the project from a work app's window title. Its first
+    /// capture group is the project, or the whole match if there is none. `None` turns
+    /// project partitioning off.
+    pub fn set_project_pattern(&mut self, pattern: Option<Regex>) {
+        self.project_pattern = pattern;
+    }
+
+    /// Sets which rule applies to an app in both the whitelist and the blacklist.
+    pub fn set_overlap_precedence(&mut self, precedence: OverlapPrecedence) {
+        self.overlap_precedence = precedence;
+    }
+
+    /// Sets the executable names of installed apps, used to recognize foreground apps.
+    /// `None` treats every app as recognized.
+    pub fn set_known_apps<I>(&mut self, apps: Option<I>)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.known_apps = apps.map(|apps| apps.into_iter().map(|a| a.to_lowercase()).collect());
+    }
+
+    /// Recognizes foreground apps from the installed `(display name, executable)` list and
+    /// records each display name against the app's usage key.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the display names cannot be stored.
+    pub fn register_installed_apps(
+        &mut self,
+        apps: Vec<(String, String)>,
+    ) -> Result<(), SynapseError> {
+        let names: Vec<(String, String)> = apps
+            .iter()
+            .map(|(display, exe)| (self.usage_key.key_for(exe), display.clone()))
+            .collect();
+        self.db_handle.set_app_display_names(&names)?;
+        self.set_known_apps(Some(apps.into_iter().map(|(_, exe)| exe)));
+        Ok(())
+    }
+
+    /// Sets which identifier app usage events are stored under.
+    pub fn set_usage_key(&mut self, key: UsageKey) {
+        self.usage_key = key;
+    }
+
+    /// Sets how foreground apps missing from the installed-apps list are handled.
+    pub fn set_unknown_app_policy(&mut self, policy: UnknownAppPolicy) {
+        self.unknown_app_policy = policy;
+    }
+
+    /// Sets the maximum number of distinct work apps stored per session.
+    pub fn set_max_work_apps(&mut self, max: usize) {
+        self.max_work_apps = max;
+    }
+
+    /// Sets the minimum percentage of a session a work app must spend in the foreground to
+    /// stay in the session's work apps when it ends. `0` keeps every running work app.
+    pub fn set_min_work_app_focus_pct(&mut self, pct: u8) {
+        self.min_work_app_focus_pct = pct.min(100);
+    }
+
+    /// Sets how long after a popup the same blocked app does not trigger another, even if it
+    /// lost and regained the foreground. `Duration::ZERO` shows a popup on every new visit.
+    pub fn set_popup_suppression_window(&mut self, window: Duration) {
+        self.popup_suppression_window = window;
+    }
+
+    /// Whether a popup for `proc_name` was shown within the suppression window.
+    fn popup_recently_shown(&self, proc_name: &str) -> bool {
+        let now = self.now();
+        self.popups_shown.get(proc_name).is_some_and(|shown| {
+            now.duration_since(*shown).unwrap_or_default() < self.popup_suppression_window
+        })
+    }
+
+    /// Plays the configured popup sound, if any. Failures are only logged.
+    fn play_popup_sound(&self) {
+        if let Some(sound) = self.popup_sound.resolved() {
+            if let Err(e) = (self.sound_player)(&sound) {
+                eprintln!("Failed to play popup sound: {}", e);
+            }
+        }
+    }
+
+    /// Records that a popup for `proc_name` was shown now, forgetting expired entries.
+    fn record_popup_shown(&mut self, proc_name: &str) {
+        let now = self.now();
+        let window = self.popup_suppression_window;
+        self.popups_shown
+            .retain(|_, shown| now.duration_since(*shown).unwrap_or_default() < window);
+        self.popups_shown.insert(proc_name.to_string(), now);
+        let today = local_date(now);
+        match &mut self.popups_today {
+            Some((day, count)) if *day == today => *count += 1,
+            popups_today => *popups_today = Some((today, 1)),
+        }
+    }
+
+    /// Caps how many popups are shown per local day; distractions past the cap are still
+    /// recorded. `0` removes the cap.
+    pub fn set_max_popups_per_day(&mut self, max: u32) {
+        self.max_popups_per_day = max;
+    }
+
+    /// Whether today's popups have reached the daily cap.
+    fn daily_popup_cap_reached(&self) -> bool {
+        let today = local_date(self.now());
+        self.max_popups_per_day > 0
+            && self
+                .popups_today
+                .is_some_and(|(day, count)| day == today && count >= self.max_popups_per_day)
+    }
+
+    /// Starts a pomodoro now: `cycles` work phases of `work`, separated by breaks of `brk`.
+    /// Only work phases count as focus time; breaks close the session and suppress popups.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` if `work` is zero, `cycles` is 0 or the schedule is
+    /// too long to represent.
+    pub fn start_pomodoro(
+        &mut self,
+        work: Duration,
+        brk: Duration,
+        cycles: u32,
+    ) -> Result<(), SynapseError> {
+        if work.is_zero() || cycles == 0 {
+            return Err(SynapseError::Config(
+                "pomodoro needs a non-zero work phase and at least one cycle".to_string(),
+            ));
+        }
+        self.pomodoro = Some(PomodoroMode::new(work, brk, cycles, self.now())?);
+        self.pomodoro_phase = None;
+        Ok(())
+    }
+
+    /// Sets the callback fired at each pomodoro phase transition.
+    pub fn set_on_pomodoro_phase(
+        &mut self,
+        callback: Option<Box<dyn Fn(PomodoroPhase, u32) + Send + Sync>>,
+    ) {
+        self.on_pomodoro_phase = callback;
+    }
+
+    /// Sets the observer called with each session start, end and distraction.
+    pub fn set_on_event(&mut self, observer: Option<EventCallback>) {
+        self.on_event = observer;
+    }
+
+    /// Adds an observer called after any already set, instead of replacing them.
+    pub fn add_event_observer(&mut self, observer: EventCallback) {
+        self.on_event = Some(match self.on_event.take() {
+            Some(existing) => Box::new(move |event: &SessionEvent| {
+                existing(event);
+                observer(event);
+            }),
+            None => observer,
+        });
+    }
+
+    /// Shows a reminder once no session has run for `after`; the next session re-arms it.
+    /// `Duration::ZERO` disables reminders.
+    pub fn set_focus_reminder_after(&mut self, after: Duration) {
+        self.focus_reminder_after = after;
+    }
+
+    /// Sets the callback fired with the time since the last session when a focus reminder
+    /// is due, replacing the platform notification.
+    pub fn set_on_focus_reminder(&mut self, callback: Option<Box<dyn Fn(Duration) + Send + Sync>>) {
+        self.on_focus_reminder = callback;
+    }
+
+    /// Sets the callback fired with the limit when a session is ended for reaching its
+    /// distraction limit, replacing the platform notification.
+    pub fn set_on_distraction_limit(&mut self, callback: Option<Box<dyn Fn(u32) + Send + Sync>>) {
+        self.on_distraction_limit = callback;
+    }
+
+    /// Sets the callback fired with the error when reloading the rules fails, replacing the
+    /// platform notification.
+    pub fn set_on_rules_reload_failed(&mut self, callback: Option<RulesReloadFailedCallback>) {
+        self.on_rules_reload_failed = callback;
+    }
+
+    /// Shows the focus reminder if no session has run for the reminder period.
+    /// Pomodoro breaks count as focus, so they never trigger it.
+    fn remind_if_unfocused(&mut self) {
+        let now = self.now();
+        if self.focus_reminder_after.is_zero()
+            || self.current_session.is_some()
+            || self.in_pomodoro_break()
+        {
+            self.no_session_since = None;
+            self.focus_reminder_shown = false;
+            return;
+        }
+        let since = *self.no_session_since.get_or_insert(now);
+        let idle = now.duration_since(since).unwrap_or_default();
+        if idle < self.focus_reminder_after || self.focus_reminder_shown {
+            return;
+        }
+        self.focus_reminder_shown = true;
+        println!("    No focus session for {}s: showing a reminder", idle.as_secs());
+        tracing::info!(idle_secs = idle.as_secs(), "focus reminder");
+        if let Some(callback) = &self.on_focus_reminder {
+            callback(idle);
+        } else if let Err(e) = show_focus_reminder(idle) {
+            eprintln!("Failed to show focus reminder: {}", e);
+        }
+    }
+
+    fn emit(&self, event: SessionEvent) {
+        if let Some(observer) = &self.on_event {
+            observer(&event);
+        }
+    }
+
+    fn emit_session_ended(&self, session: &FocusSession, reason: &str) {
+        let end_time = session
+            .end_time
+            .and_then(|end| end.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        self.emit(SessionEvent::SessionEnded {
+            session_id: session.id,
+            end_time,
+            distraction_attempts: session.distraction_attempts,
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Returns the current pomodoro phase and cycle, if a pomodoro is running.
+    pub fn pomodoro_phase(&self) -> Option<(PomodoroPhase, u32)> {
+        self.pomodoro_phase
+    }
+
+    fn in_pomodoro_break(&self) -> bool {
+        matches!(self.pomodoro_phase, Some((PomodoroPhase::Break, _)))
+    }
+
     /// Sets the application rules for the session manager.
+    ///
+    /// Apps the new rules whitelist stop counting as the current distraction and lose their
+    /// popup cooldown, so the next poll treats them as work.
     pub fn set_apprules(&mut self, apprules: crate::apprules::AppRules) {
         self.apprules = apprules;
+        self.forget_whitelisted_distractions();
+    }
+
+    fn forget_whitelisted_distractions(&mut self) {
+        if self
+            .last_distraction_app
+            .as_deref()
+            .is_some_and(|app| self.apprules.is_work_app(app))
+        {
+            self.last_distraction_app = None;
+            self.last_blocked = false;
+        }
+        let apprules = &self.apprules;
+        self.popups_shown.retain(|app, _| !apprules.is_work_app(app));
+    }
+
+    /// Applies freshly loaded rules, as the rules file watcher does after each change.
+    ///
+    /// A load error keeps the current rules and is reported to the rules-reload-failed
+    /// callback (or a platform notification) and the event observer. Returns whether the
+    /// new rules were applied.
+    pub fn reload_apprules(
+        &mut self,
+        loaded: Result<crate::apprules::AppRules, SynapseError>,
+    ) -> bool {
+        let result = loaded.map(|apprules| self.apprules = apprules);
+        self.finish_rules_reload(result)
+    }
+
+    /// Re-reads the rules file at `path` into the current rules in place, with the same
+    /// error handling as [`reload_apprules`](Self::reload_apprules). Returns whether the new
+    /// rules were applied.
+    pub fn reload_apprules_from_path(&mut self, path: &std::path::Path) -> bool {
+        let result = self.apprules.reload_from_path(path);
+        self.finish_rules_reload(result)
+    }
+
+    fn finish_rules_reload(&mut self, result: Result<(), SynapseError>) -> bool {
+        match result {
+            Ok(()) => {
+                self.forget_whitelisted_distractions();
+                let (whitelist, blacklist) = self.apprules.rule_count();
+                tracing::info!(whitelist, blacklist, "apprules reloaded");
+                true
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "apprules reload failed");
+                eprintln!("[SessionManager] Rules not reloaded, keeping previous rules: {}", e);
+                if let Some(callback) = &self.on_rules_reload_failed {
+                    callback(&e);
+                } else if let Err(err) = show_rules_reload_failed(&e.to_string()) {
+                    eprintln!("Failed to show rules reload error: {}", err);
+                }
+                self.emit(SessionEvent::RulesReloadFailed {
+                    error: e.to_string(),
+                });
+                false
+            }
+        }
+    }
+
+    /// Marks `app_name` as work: moves it to the whitelist, saves the rules to `rules_path`
+    /// and applies them right away. Backs the popup's "this is work" action.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the rules file cannot be written; the rules are then unchanged.
+    pub fn whitelist_app(&mut self, app_name: &str, rules_path: &Path) -> Result<(), SynapseError> {
+        let mut apprules = self.apprules.clone();
+        apprules.whitelist_app_at(app_name, rules_path)?;
+        println!("[SessionManager] Whitelisted app '{}'", app_name);
+        self.set_apprules(apprules);
+        Ok(())
     }
 
     // --- Private Helper Methods ---
 
     /// Snoozes a blocked app for a specified duration.
     pub fn snooze_app(&mut self, app_name: String, duration: std::time::Duration) {
-        let allowed_until = SystemTime::now() + duration;
+        let allowed_until = self.now() + duration;
         println!(
             "[SessionManager] Snoozing app '{}' until {:?}",
             app_name, allowed_until
@@ -261,19 +1239,52 @@ impl SessionManager {
             .insert(app_name.to_lowercase(), allowed_until);
     }
 
+    /// Records the user's `reason` for getting distracted by `app_name` in the current session.
+    ///
+    /// If the app is still in focus, the reason is attached when its event is recorded;
+    /// otherwise it is stored on the app's latest blocked event. Returns `false` if there is
+    /// no active session or no distraction to annotate.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the database update fails.
+    pub fn annotate_distraction(
+        &mut self,
+        app_name: &str,
+        reason: &str,
+    ) -> Result<bool, SynapseError> {
+        let reason = reason.trim();
+        let Some(session_id) = self.current_session.as_ref().map(|s| s.id) else {
+            return Ok(false);
+        };
+        if reason.is_empty() {
+            return Ok(false);
+        }
+        let app = self.apprules.normalize(app_name);
+        if self.last_app.as_deref() == Some(app.as_str()) && self.last_blocked {
+            self.pending_reason = Some((app, reason.to_string()));
+            return Ok(true);
+        }
+        self.db_handle
+            .set_distraction_reason(session_id, &self.usage_key.key_for(app_name), reason)
+    }
+
     fn handle_foreground_process(
         &mut self,
         proc_name: String,
         running_processes: &[String],
-        any_work_app_running: bool,
+        work_condition_met: bool,
     ) -> Result<(), SynapseError> {
-        let mut is_blocked = self.apprules.is_blocked(&proc_name);
-        let is_work_app = self.apprules.is_work_app(&proc_name);
+        // Canonicalize so aliased names log and group under one entry
+        let proc_name = self.apprules.normalize(&proc_name);
+        let (mut is_blocked, is_work_app) = self.rule_verdict(&proc_name);
+        if !is_blocked && !is_work_app && self.over_budget_distraction(&proc_name) {
+            is_blocked = true;
+        }
 
         // check temporary allowances
         if is_blocked {
             if let Some(allowed_until) = self.temporary_allowances.get(&proc_name.to_lowercase()) {
-                if SystemTime::now() < *allowed_until {
+                if self.now() < *allowed_until {
                     println!("    App '{}' is temporarily allowed (snoozed)", proc_name);
                     is_blocked = false;
                 } else {
@@ -287,7 +1298,7 @@ impl SessionManager {
         self.log_app_event(&proc_name, is_blocked)?;
         self.handle_distraction(&proc_name, is_blocked)?;
 
-        if any_work_app_running && is_work_app {
+        if work_condition_met && is_work_app {
             self.start_new_session_if_needed(running_processes)?;
         }
 
@@ -298,74 +1309,206 @@ impl SessionManager {
         Ok(())
     }
 
-    fn handle_no_foreground_process(&mut self) {
-        println!("Could not detect foreground app.");
-        self.last_checked_process = None;
-        self.last_blocked = false;
-        self.last_app = None;
-        self.last_app_start = None;
-        self.last_distraction_app = None;
-    }
+    /// Persists which rules match the observed processes, at most once per
+    /// [`RULE_MATCH_RECORD_INTERVAL_SECS`] for each rule.
+    fn record_rule_matches(
+        &mut self,
+        foreground: Option<&str>,
+        running_processes: &[String],
+    ) -> Result<(), SynapseError> {
+        let now = self.now();
+        let interval = Duration::from_secs(RULE_MATCH_RECORD_INTERVAL_SECS);
+        let mut matched: Vec<String> = Vec::new();
+        for name in running_processes.iter().map(String::as_str).chain(foreground) {
+            for rule in [self.apprules.work_rule(name), self.apprules.blocking_rule(name)]
+                .into_iter()
+                .flatten()
+            {
+                if !matched.iter().any(|m| m == rule) {
+                    matched.push(rule.to_string());
+                }
+            }
+        }
+        let now_secs = now.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        for rule in matched {
+            let due = match self.rule_matches_recorded.get(&rule) {
+                Some(last) => now.duration_since(*last).is_ok_and(|d| d >= interval),
+                None => true,
+            };
+            if due {
+                self.db_handle.record_rule_match(&rule, now_secs)?;
+                self.rule_matches_recorded.insert(rule, now);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the app is an installed app or is named by a rule.
+    fn is_recognized(&self, proc_name: &str) -> bool {
+        match &self.known_apps {
+            None => true,
+            Some(known) => {
+                known.contains(&self.apprules.normalize(proc_name).to_lowercase())
+                    || known.contains(&proc_name.to_lowercase())
+                    || self.apprules.is_work_app(proc_name)
+                    || self.apprules.is_blocked(proc_name)
+                    || self.apprules.is_neutral(proc_name)
+            }
+        }
+    }
+
+    fn is_unknown_distraction(&self, proc_name: &str) -> bool {
+        self.unknown_app_policy == UnknownAppPolicy::TreatAsDistraction
+            && !self.is_recognized(proc_name)
+    }
+
+    /// Returns true if strict mode blocks `proc_name` for being neither whitelisted nor neutral.
+    fn is_unlisted_distraction(&self, proc_name: &str) -> bool {
+        self.strict_mode
+            && !self.apprules.is_work_app(proc_name)
+            && !self.apprules.is_neutral(proc_name)
+    }
+
+    /// Returns whether `proc_name` counts as blocked and as a work app under the rules, the
+    /// overlap precedence, strict mode and the unknown-app policy.
+    fn rule_verdict(&self, proc_name: &str) -> (bool, bool) {
+        let mut is_blocked = self.apprules.is_blocked(proc_name)
+            || self.is_unknown_distraction(proc_name)
+            || self.is_unlisted_distraction(proc_name);
+        let mut is_work_app = self.apprules.is_work_app(proc_name);
+        if is_blocked && is_work_app {
+            match self.overlap_precedence {
+                OverlapPrecedence::BlacklistWins => is_work_app = false,
+                OverlapPrecedence::WhitelistWins => is_blocked = false,
+            }
+        }
+        (is_blocked, is_work_app)
+    }
+
+    /// Returns true if `proc_name` is snoozed until later than now.
+    fn is_snoozed(&self, proc_name: &str) -> bool {
+        self.temporary_allowances
+            .get(&proc_name.to_lowercase())
+            .is_some_and(|until| self.now() < *until)
+    }
+
+    /// Classifies `app_name` the way a poll with it in the foreground would right now,
+    /// without changing any state: the rules under the overlap precedence, strict mode and
+    /// the unknown-app policy, then over-budget categories and snoozes.
+    ///
+    /// Returns whether the app would count as blocked and as a work app; an app the
+    /// unknown-app policy ignores is neither.
+    pub fn classify_app(&self, app_name: &str) -> (bool, bool) {
+        if self.unknown_app_policy == UnknownAppPolicy::Ignore && !self.is_recognized(app_name) {
+            return (false, false);
+        }
+        let proc_name = self.apprules.normalize(app_name);
+        let (mut is_blocked, is_work_app) = self.rule_verdict(&proc_name);
+        if !is_blocked
+            && !is_work_app
+            && self.block_over_budget_categories
+            && self.category_over_budget(&proc_name).is_some()
+        {
+            is_blocked = true;
+        }
+        if is_blocked && self.is_snoozed(&proc_name) {
+            is_blocked = false;
+        }
+        (is_blocked, is_work_app)
+    }
+
+    /// Returns the category of `proc_name` with today's usage and budget, in seconds, if a
+    /// session is running and the category is over its budget.
+    fn category_over_budget(&self, proc_name: &str) -> Option<(String, i64, u64)> {
+        self.current_session.as_ref()?;
+        let category = self.apprules.category_of(proc_name)?.to_string();
+        let budget_secs = self.category_budgets.get(&category)?;
+        let used_secs = match self.category_secs_today(&category) {
+            Ok(secs) => secs,
+            Err(e) => {
+                eprintln!("Failed to read usage for category '{}': {}", category, e);
+                return None;
+            }
+        };
+        (used_secs >= budget_secs as i64).then_some((category, used_secs, budget_secs))
+    }
+
+    /// Returns whether `proc_name` should count as a distraction because its category is over
+    /// today's budget, reporting categories as they go over.
+    fn over_budget_distraction(&mut self, proc_name: &str) -> bool {
+        let Some((category, used_secs, budget_secs)) = self.category_over_budget(proc_name) else {
+            return false;
+        };
+        let today = local_date(self.now());
+        if self.over_budget_reported.get(&category) != Some(&today) {
+            self.over_budget_reported.insert(category.clone(), today);
+            println!(
+                "    Category '{}' is over budget: {}s used of {}s",
+                category, used_secs, budget_secs
+            );
+            tracing::warn!(category = %category, used_secs, budget_secs, "category over budget");
+            self.emit(SessionEvent::CategoryOverBudget {
+                category,
+                used_secs,
+                budget_secs,
+            });
+        }
+        self.block_over_budget_categories
+    }
+
+    /// Returns today's focus time in `category`: recorded events plus the app in focus now.
+    fn category_secs_today(&self, category: &str) -> Result<i64, SynapseError> {
+        let now = self.now();
+        let day_start = local_day_start(now);
+        let now_secs = now.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let mut used: i64 = self
+            .db_handle
+            .focus_secs_by_app_between(day_start, now_secs + 1)?
+            .into_iter()
+            .filter(|(app, _)| self.apprules.category_of(app) == Some(category))
+            .map(|(_, secs)| secs)
+            .sum();
+        if let (Some(app), Some(start)) = (&self.last_app, self.last_app_start) {
+            if self.apprules.category_of(app) == Some(category) {
+                let start_secs = start.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+                used += now_secs - start_secs.max(day_start);
+            }
+        }
+        Ok(used.max(0))
+    }
+
+    fn is_self_process(&self, proc_name: &str) -> bool {
+        self.self_process_name
+            .as_deref()
+            .is_some_and(|own| self.apprules.normalize(own) == self.apprules.normalize(proc_name))
+    }
+
+    /// Closes the previous app's interval without tracking Synapse's own window.
+    fn handle_self_foreground(&mut self) -> Result<(), SynapseError> {
+        if let (Some(app), Some(start)) = (self.last_app.take(), self.last_app_start.take()) {
+            self.record_app_interval(&app, start, self.now())?;
+        }
+        self.last_checked_process = None;
+        self.last_blocked = false;
+        self.last_distraction_app = None;
+        Ok(())
+    }
+
+    fn handle_no_foreground_process(&mut self) {
+        println!("Could not detect foreground app.");
+        self.last_checked_process = None;
+        self.last_blocked = false;
+        self.last_app = None;
+        self.last_app_start = None;
+        self.last_distraction_app = None;
+    }
 
     fn update_app_focus_duration(&mut self, proc_name: &str) -> Result<(), SynapseError> {
-        let now = SystemTime::now();
-        let now_secs = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
+        let now = self.now();
         if let Some(last_app) = self.last_app.take() {
             if last_app != proc_name {
                 if let Some(start_time) = self.last_app_start.take() {
-                    let start_time_secs =
-                        start_time.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
-                    let end_time = now_secs;
-                    let duration = end_time - start_time_secs;
-                    // Only record if a focus session is active
-                    if let Some(ref session) = self.current_session {
-                        let mut is_blocked = self.apprules.is_blocked(&last_app);
-                        // Check allowance for historical record too?
-                        // If it was allowed when it started, it should probably be recorded as allowed.
-                        // But strictly, we record status based on rules.
-                        // Ideally, we pass the status determined at detection time.
-                        // But `update_app_focus_duration` recalculates `is_blocked`.
-                        // Let's check allowance here too for consistency.
-                        if is_blocked {
-                            if let Some(allowed_until) =
-                                self.temporary_allowances.get(&last_app.to_lowercase())
-                            {
-                                // If allowed *now*, we count it as allowed. Ideally strictly checking ranges,
-                                // but this is good enough approximation.
-                                if SystemTime::now() < *allowed_until {
-                                    is_blocked = false;
-                                }
-                            }
-                        }
-
-                        let status = if is_blocked { "blocked" } else { "allowed" };
-                        let session_id = Some(session.id);
-                        let event_id = self.db_handle.insert_app_usage_event(
-                            &last_app,
-                            status,
-                            session_id,
-                            start_time_secs,
-                            end_time,
-                            duration,
-                        )?;
-                        self.last_app_event_id = Some(event_id);
-                        // Immediately send to Supabase
-                        if let Some(sync) = &self.supabase_sync {
-                            let event = crate::types::AppUsageEvent {
-                                id: Uuid::new_v4(),
-                                process_name: last_app.clone(),
-                                status: status.to_string(),
-                                session_id,
-                                start_time: start_time_secs,
-                                end_time,
-                                duration_secs: duration,
-                            };
-                            let sync = sync.clone();
-                            tokio::spawn(async move {
-                                let _ = sync.push_app_usage_events(&[event]).await;
-                            });
-                        }
-                    }
+                    self.record_app_interval(&last_app, start_time, now)?;
                 }
             } else {
                 // Same app, just update tracking fields
@@ -376,11 +1519,105 @@ impl SessionManager {
         // Start tracking the new app in focus
         self.last_app = Some(proc_name.to_string());
         self.last_app_start = Some(now);
+        self.last_app_raw = self.foreground_raw.clone();
+        Ok(())
+    }
+
+    /// Records a closed focus interval for `app` as a usage event.
+    ///
+    /// Intervals are only recorded while a focus session is active.
+    fn record_app_interval(
+        &mut self,
+        app: &str,
+        start: SystemTime,
+        end: SystemTime,
+    ) -> Result<(), SynapseError> {
+        let Some(current_session_id) = self.current_session.as_ref().map(|s| s.id) else {
+            return Ok(());
+        };
+        let start_time_secs = start.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
+        let end_time = end.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
+        let duration = end_time - start_time_secs;
+        let mut is_blocked = self.apprules.is_blocked(app)
+            || self.is_unknown_distraction(app)
+            || self.is_unlisted_distraction(app)
+            || (!self.apprules.is_work_app(app) && self.over_budget_distraction(app));
+        // Check allowance for historical record too?
+        // If it was allowed when it started, it should probably be recorded as allowed.
+        // But strictly, we record status based on rules.
+        // Ideally, we pass the status determined at detection time.
+        // But `record_app_interval` recalculates `is_blocked`.
+        // Let's check allowance here too for consistency.
+        if is_blocked {
+            if let Some(allowed_until) = self.temporary_allowances.get(&app.to_lowercase()) {
+                // If allowed *now*, we count it as allowed. Ideally strictly checking ranges,
+                // but this is good enough approximation.
+                if self.now() < *allowed_until {
+                    is_blocked = false;
+                }
+            }
+        }
+
+        let status = UsageStatus::from_blocked(is_blocked);
+        let matched_rule = if is_blocked {
+            self.apprules.blocking_rule(app).map(str::to_string)
+        } else {
+            None
+        };
+        let session_id = Some(current_session_id);
+        *self.app_focus_secs.entry(self.apprules.normalize(app)).or_default() += duration;
+        let usage_key = self.usage_key.key_for(app);
+        let raw_name = self
+            .last_app_raw
+            .as_deref()
+            .filter(|_| self.store_raw_process_names);
+        let event_id = self.db_handle.insert_app_usage_event(
+            &usage_key,
+            status,
+            session_id,
+            start_time_secs,
+            end_time,
+            duration,
+            matched_rule.as_deref(),
+            raw_name,
+        )?;
+        let reason = match self.pending_reason.take() {
+            Some((pending_app, reason)) if is_blocked && pending_app == app => {
+                self.db_handle
+                    .set_distraction_reason(current_session_id, &usage_key, &reason)?;
+                Some(reason)
+            }
+            other => {
+                self.pending_reason = other;
+                None
+            }
+        };
+        // Immediately send to Supabase
+        if let Some(sync) = &self.supabase_sync {
+            let event = crate::types::AppUsageEvent {
+                id: event_id,
+                process_name: usage_key,
+                status: status.to_string(),
+                session_id,
+                start_time: start_time_secs,
+                end_time,
+                duration_secs: duration,
+                matched_rule,
+                reason,
+            };
+            let task_sync = sync.clone();
+            let db_path = self.db_handle.path();
+            sync.spawn_limited(async move {
+                if task_sync.push_app_usage_events(&[event]).await.is_ok() {
+                    mark_events_synced_async(db_path, vec![event_id]).await;
+                }
+            });
+        }
         Ok(())
     }
 
     fn log_app_event(&mut self, proc_name: &str, is_blocked: bool) -> Result<(), SynapseError> {
-        let now = SystemTime::now();
+        let now = self.now();
         let now_secs = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
         log_event(
             Some(&self.db_handle),
@@ -403,6 +1640,9 @@ impl SessionManager {
         is_blocked: bool,
     ) -> Result<(), SynapseError> {
         if is_blocked {
+            if self.current_session.is_none() {
+                self.record_outside_session_distraction(proc_name)?;
+            }
             // Only count distraction and notify if it's a new distraction event
             // (i.e., different app than last time, or re-opening the same app after switching away)
             if self.last_distraction_app.as_deref() != Some(proc_name) {
@@ -419,65 +1659,511 @@ impl SessionManager {
                         }
                     }
                 }
+                if let Some(session) = &self.current_session {
+                    self.emit(SessionEvent::Distraction {
+                        session_id: session.id,
+                        app: proc_name.to_string(),
+                    });
+                }
 
-                if self.current_session.is_some() {
-                    if let Some(callback) = &self.on_distraction {
-                        callback(proc_name);
+                if self.current_session.is_some() && !self.in_pomodoro_break() {
+                    if self.enforcement_mode == EnforcementMode::Observe {
+                        println!("    Observe mode: not interrupting for {}", proc_name);
+                    } else if self.popup_recently_shown(proc_name) {
+                        println!("    Popup for {} shown recently; not repeating", proc_name);
+                    } else if self.daily_popup_cap_reached() {
+                        println!("    Daily popup cap reached: not interrupting for {}", proc_name);
+                    } else if self.system_in_dnd() {
+                        println!("    Do not disturb is on: not interrupting for {}", proc_name);
                     } else {
-                        // Fallback to native popup if no callback provided
-                        show_distraction_popup(proc_name).map_err(|e| {
-                            SynapseError::Platform(format!(
-                                "Failed to show distraction popup: {}",
-                                e
-                            ))
-                        })?;
+                        if let Some(callback) = &self.on_distraction {
+                            callback(proc_name);
+                        } else {
+                            // Fallback to native popup if no callback provided
+                            show_distraction_popup(proc_name).map_err(|e| {
+                                SynapseError::Platform(format!(
+                                    "Failed to show distraction popup: {}",
+                                    e
+                                ))
+                            })?;
+                        }
+                        self.play_popup_sound();
+                        self.record_popup_shown(proc_name);
+                    }
+                    if self.enforcement_mode == EnforcementMode::Enforce
+                        && self.kill_blocked_apps == KillBlockedApps::OnDetection
+                    {
+                        if let Err(e) = self.kill_blocked_app(proc_name) {
+                            eprintln!("Failed to close blocked app {}: {}", proc_name, e);
+                        }
                     }
                     self.last_distraction_app = Some(proc_name.to_string());
                 }
             }
         } else {
             self.last_distraction_app = None;
+            self.last_outside_session_distraction = None;
+        }
+        Ok(())
+    }
+
+    /// Records `proc_name` as a distraction outside any session, once per visit, if
+    /// `count_distractions_outside_sessions` is on. Observers get a `Distraction` event with
+    /// the nil session id.
+    fn record_outside_session_distraction(&mut self, proc_name: &str) -> Result<(), SynapseError> {
+        if !self.count_distractions_outside_sessions
+            || self.last_outside_session_distraction.as_deref() == Some(proc_name)
+        {
+            return Ok(());
         }
+        let now_secs = self.now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        self.db_handle
+            .record_outside_session_distraction(proc_name, now_secs)?;
+        println!("    Blocked app outside a session: {}", proc_name);
+        tracing::info!(app = %proc_name, "distraction outside session");
+        self.emit(SessionEvent::Distraction {
+            session_id: Uuid::nil(),
+            app: proc_name.to_string(),
+        });
+        self.last_outside_session_distraction = Some(proc_name.to_string());
         Ok(())
     }
 
+    /// Terminates every running process of the blocked app `app`, returning how many were
+    /// killed.
+    ///
+    /// # Errors
+    /// Returns `SynapseError::Config` unless killing is enabled with `kill_blocked_apps`, and
+    /// `SynapseError::Platform` if `app` is not blocked, isn't running, or can't be killed.
+    pub fn kill_blocked_app(&mut self, app: &str) -> Result<usize, SynapseError> {
+        if self.kill_blocked_apps == KillBlockedApps::Never {
+            return Err(SynapseError::Config(
+                "Closing apps is disabled; set kill_blocked_apps to on_request or on_detection"
+                    .to_string(),
+            ));
+        }
+        if !self.apprules.is_blocked(app) {
+            return Err(SynapseError::Platform(format!(
+                "Refusing to close {}: it is not a blocked app",
+                app
+            )));
+        }
+        let target = self.apprules.normalize(app);
+        let own_pid = std::process::id();
+        let pids: Vec<u32> = (self.process_lister)()?
+            .into_iter()
+            .filter(|(pid, name)| *pid != own_pid && self.apprules.normalize(name) == target)
+            .map(|(pid, _)| pid)
+            .collect();
+        if pids.is_empty() {
+            return Err(SynapseError::Platform(format!("No running process found for {}", app)));
+        }
+        for pid in &pids {
+            (self.process_killer)(*pid)?;
+        }
+        println!("    Closed blocked app {} ({} processes)", app, pids.len());
+        Ok(pids.len())
+    }
+
     fn start_new_session_if_needed(
         &mut self,
         running_processes: &[String],
     ) -> Result<(), SynapseError> {
-        if self.current_session.is_none() {
+        if self.in_pomodoro_break() {
+            return Ok(());
+        }
+        if self.current_session.is_none() && self.apprules.work_condition_met(running_processes) {
+            if self.resume_recent_session()? {
+                return Ok(());
+            }
             println!("\n--- Focus session started ---");
-            let work_apps: Vec<String> = running_processes
+            let work_apps = self.running_work_apps(running_processes);
+            let now = self.now();
+            self.begin_session(work_apps, now)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the running processes that may count as work. Under `BlacklistWins`,
+    /// blacklisted apps are left out even if they are also whitelisted.
+    fn work_candidates(&self, running_processes: &[String]) -> Vec<String> {
+        match self.overlap_precedence {
+            OverlapPrecedence::WhitelistWins => running_processes.to_vec(),
+            OverlapPrecedence::BlacklistWins => running_processes
                 .iter()
-                .filter(|name| self.apprules.is_work_app(name))
+                .filter(|name| !self.apprules.is_blocked(name))
                 .cloned()
-                .collect();
-            let session = FocusSession {
-                id: Uuid::new_v4(),
-                start_time: SystemTime::now(),
-                end_time: None,
-                work_apps: work_apps.clone(),
-                distraction_attempts: 0,
-            };
-            self.db_handle.execute_sql(
-                "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES (?1, ?2, NULL, ?3, ?4)",
-                &[
-                    &session.id.to_string(),
-                    &session.start_time.duration_since(SystemTime::UNIX_EPOCH)?.as_secs().to_string(),
-                    &work_apps.join(","),
-                    &session.distraction_attempts.to_string(),
-                ],
-            )?;
-            // Supabase: insert session at start
-            if let Some(sync) = &self.supabase_sync {
-                let session_clone = session.clone();
-                let sync = sync.clone();
-                tokio::spawn(async move {
-                    let _ = sync.insert_focus_session(&session_clone).await;
-                });
+                .collect(),
+        }
+    }
+
+    /// Returns the normalized, deduplicated work apps among `running_processes`.
+    fn running_work_apps(&self, running_processes: &[String]) -> Vec<String> {
+        let mut work_apps: Vec<String> = Vec::new();
+        for name in running_processes
+            .iter()
+            .filter(|name| self.apprules.is_work_app(name))
+        {
+            let name = self.apprules.normalize(name);
+            if !work_apps.contains(&name) {
+                work_apps.push(name);
             }
-            self.session_id = Some(SessionId::from(session.id));
-            self.current_session = Some(session);
+        }
+        work_apps
+    }
+
+    /// Creates and persists a new current session starting at `start_time`.
+    fn begin_session(
+        &mut self,
+        work_apps: Vec<String>,
+        start_time: SystemTime,
+    ) -> Result<(), SynapseError> {
+        self.app_focus_secs.clear();
+        let mut session = FocusSession {
+            id: Uuid::new_v4(),
+            start_time,
+            end_time: None,
+            work_apps,
+            distraction_attempts: 0,
+            project: self.current_project.clone(),
+        };
+        let truncated = self.cap_work_apps(&mut session);
+        self.db_handle.insert_session(&session)?;
+        self.record_work_apps_truncated(&session, truncated)?;
+        if let Some(project) = &session.project {
+            self.db_handle.set_session_project(session.id, Some(project))?;
+        }
+        // Supabase: insert session at start
+        if let Some(sync) = &self.supabase_sync {
+            let session_clone = session.clone();
+            let task_sync = sync.clone();
+            let db_path = self.db_handle.path();
+            sync.spawn_limited(async move {
+                match task_sync.insert_focus_session(&session_clone).await {
+                    Ok(()) => mark_session_synced_async(db_path, session_clone.id).await,
+                    Err(e) => {
+                        record_sync_failure_async(db_path, session_clone.id, e.to_string()).await
+                    }
+                }
+            });
+        }
+        tracing::info!(
+            session_id = %session.id,
+            start_time = session.start_time.duration_since(UNIX_EPOCH)?.as_secs(),
+            work_apps = ?session.work_apps,
+            "session started"
+        );
+        self.emit(SessionEvent::SessionStarted {
+            session_id: session.id,
+            start_time: session.start_time.duration_since(UNIX_EPOCH)?.as_secs() as i64,
+            work_apps: session.work_apps.clone(),
+        });
+        self.session_id = Some(SessionId::from(session.id));
+        self.current_session = Some(session);
+        Ok(())
+    }
+
+    /// Ends the current session and starts a fresh one at the same instant once it has
+    /// run for `max_session_length`, so long spans are stored as bounded chunks.
+    ///
+    /// Returns the session that was split off, if any.
+    fn split_session_if_too_long(&mut self) -> Result<Option<FocusSession>, SynapseError> {
+        if self.max_session_length.is_zero() {
+            return Ok(None);
+        }
+        let now = self.now();
+        let too_long = self
+            .current_session
+            .as_ref()
+            .is_some_and(|session| session.duration(now) >= self.max_session_length);
+        if !too_long {
+            return Ok(None);
+        }
+        println!("\n--- Focus session reached the maximum length; starting a new one ---");
+        self.roll_over_session("max_length")
+    }
+
+    /// Ends the session and starts another if the system's UTC offset changed since the last
+    /// poll and the policy is to split. The ended session closes at the transition's poll, so
+    /// it never runs backwards even when the new offset is behind the old one.
+    ///
+    /// Returns the session that was ended, if any.
+    fn split_session_if_timezone_changed(&mut self) -> Result<Option<FocusSession>, SynapseError> {
+        let offset = (self.utc_offset)(self.now());
+        let previous = self.last_utc_offset.replace(offset);
+        match previous {
+            Some(previous) if previous != offset && self.current_session.is_some() => {}
+            _ => return Ok(None),
+        }
+        match self.timezone_change_policy {
+            TimezoneChangePolicy::KeepSession => {
+                println!("    Timezone changed; keeping the current session");
+                Ok(None)
+            }
+            TimezoneChangePolicy::SplitSession => {
+                println!("\n--- Timezone changed; starting a new session ---");
+                self.roll_over_session("timezone_changed")
+            }
+        }
+    }
+
+    /// Tags the session with the project in the foreground work app's window title, and ends
+    /// it and starts another when a different project comes into focus.
+    ///
+    /// Returns the session that was ended, if any.
+    fn switch_project_if_changed(
+        &mut self,
+        foreground: Option<&str>,
+        title: Option<&str>,
+    ) -> Result<Option<FocusSession>, SynapseError> {
+        let Some(project) = self.project_from_title(foreground, title) else {
+            return Ok(None);
+        };
+        self.current_project = Some(project.clone());
+        let Some(session) = self.current_session.as_mut() else {
+            return Ok(None);
+        };
+        match &session.project {
+            Some(current) if *current == project => Ok(None),
+            Some(_) => {
+                println!("\n--- Switched to project {}; starting a new session ---", project);
+                self.roll_over_session("project_changed")
+            }
+            None => {
+                session.project = Some(project.clone());
+                if let Some(session_id) = self.session_id {
+                    self.db_handle
+                        .set_session_project(session_id.into(), Some(&project))?;
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Extracts the project from the foreground window's title if the foreground app is a
+    /// work app and the title matches the project pattern.
+    fn project_from_title(&self, foreground: Option<&str>, title: Option<&str>) -> Option<String> {
+        let pattern = self.project_pattern.as_ref()?;
+        if !self.apprules.is_work_app(&self.apprules.normalize(foreground?)) {
+            return None;
+        }
+        let captures = pattern.captures(title?)?;
+        let project = captures.get(1).or_else(|| captures.get(0))?.as_str().trim();
+        (!project.is_empty()).then(|| project.to_string())
+    }
+
+    /// Ends the current session and starts a fresh one with the same work apps at the same
+    /// instant, tagged with the current project.
+    ///
+    /// Returns the session that was ended, if any.
+    fn roll_over_session(&mut self, reason: &str) -> Result<Option<FocusSession>, SynapseError> {
+        let now = self.now();
+        // Attribute the in-flight app's time so far to the session being closed
+        self.finalize_last_app_usage_event()?;
+        let Some(session) = self.close_session_at(reason, now)? else {
+            return Ok(None);
+        };
+        self.begin_session(session.work_apps().clone(), now)?;
+        Ok(Some(session))
+    }
+
+    /// Ends the session once its distraction count reaches `max_distractions_per_session`,
+    /// and notifies the user. No new session starts until a work app is in focus again.
+    ///
+    /// Returns the session that was ended, if any.
+    fn end_session_if_distraction_limit_reached(
+        &mut self,
+    ) -> Result<Option<FocusSession>, SynapseError> {
+        let limit = self.max_distractions_per_session;
+        let reached = self
+            .current_session
+            .as_ref()
+            .is_some_and(|session| limit > 0 && session.distraction_attempts >= limit);
+        if !reached {
+            return Ok(None);
+        }
+        println!("\n--- Distraction limit of {} reached; ending the session ---", limit);
+        self.finalize_last_app_usage_event()?;
+        let now = self.now();
+        let ended = self.close_session_at("distraction_limit", now)?;
+        self.current_project = None;
+        self.awaiting_work_app = true;
+        if let Some(callback) = &self.on_distraction_limit {
+            callback(limit);
+        } else if let Err(e) = show_distraction_limit_reached(limit) {
+            eprintln!("Failed to show distraction limit notification: {}", e);
+        }
+        Ok(ended)
+    }
+
+    /// Ends the session and starts another if the system slept for at least `min_sleep`
+    /// since the previous poll and the policy says so. The ended session closes at the
+    /// moment the system went to sleep, so the time asleep never inflates it; under
+    /// `SplitSession` the new one starts at wake.
+    ///
+    /// Sleeps are measured with the OS's suspended-time counter, falling back to the gap
+    /// between polls, which only grows this large while the process is suspended.
+    ///
+    /// Returns the session that was ended, if any.
+    fn end_session_if_slept(
+        &mut self,
+        running_processes: &[String],
+    ) -> Result<Option<FocusSession>, SynapseError> {
+        let now = self.now();
+        let suspended = (self.time_suspended)();
+        let Some((previous_poll, previous_suspended)) = self.last_poll.replace((now, suspended))
+        else {
+            return Ok(None);
+        };
+        let gap = now.duration_since(previous_poll).unwrap_or(Duration::ZERO);
+        let slept = match (previous_suspended, suspended) {
+            (Some(before), Some(after)) => after.saturating_sub(before).min(gap),
+            _ => gap,
+        };
+        if self.min_sleep.is_zero()
+            || slept < self.min_sleep
+            || self.sleep_policy == SleepPolicy::KeepSession
+            || self.current_session.is_none()
+        {
+            return Ok(None);
+        }
+        let asleep_at = now - slept;
+        println!("\n--- System slept for {}s; ending the session ---", slept.as_secs());
+        // The in-flight app stopped at sleep and picks up again at wake
+        if let (Some(app), Some(start)) = (self.last_app.clone(), self.last_app_start) {
+            if asleep_at > start {
+                self.record_app_interval(&app, start, asleep_at)?;
+            }
+            self.last_app_start = Some(now);
+        }
+        let Some(session) = self.close_session_at("system_sleep", asleep_at)? else {
+            return Ok(None);
+        };
+        match self.sleep_policy {
+            SleepPolicy::SplitSession
+                if self.apprules.work_condition_met(running_processes)
+                    && !self.in_pomodoro_break() =>
+            {
+                let work_apps = self.running_work_apps(running_processes);
+                self.begin_session(work_apps, now)?;
+            }
+            SleepPolicy::EndSession => self.awaiting_work_app = true,
+            _ => {}
+        }
+        Ok(Some(session))
+    }
+
+    /// Ends the current session at `end` and persists it, without starting another.
+    ///
+    /// Returns the session that was ended, if any; the caller pushes it to Supabase.
+    fn close_session_at(
+        &mut self,
+        reason: &str,
+        end: SystemTime,
+    ) -> Result<Option<FocusSession>, SynapseError> {
+        let (Some(mut session), Some(session_id)) =
+            (self.current_session.take(), self.session_id.take())
+        else {
+            return Ok(None);
+        };
+        session.end_time = Some(end);
+        self.prune_unused_work_apps(&mut session);
+        let truncated = self.cap_work_apps(&mut session);
+        trace_session_end(&session, reason);
+        self.emit_session_ended(&session, reason);
+        let end_time = end.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
+        self.db_handle.update_session(
+            session_id.into(),
+            end_time,
+            &session.work_apps().join(","),
+            session.distraction_attempts() as i32,
+        )?;
+        self.record_work_apps_truncated(&session, truncated)?;
+        Ok(Some(session))
+    }
+
+    /// Re-opens the most recently ended session if it ended within the resume window.
+    ///
+    /// Returns `true` if a session was resumed.
+    fn resume_recent_session(&mut self) -> Result<bool, SynapseError> {
+        let Some((mut session, session_id)) = self.recently_ended.take() else {
+            return Ok(false);
+        };
+        let ended_at = session.end_time.unwrap_or(session.start_time);
+        let elapsed = self.now()
+            .duration_since(ended_at)
+            .unwrap_or(Duration::ZERO);
+        if elapsed > self.resume_window {
+            return Ok(false);
+        }
+        println!("\n--- Focus session resumed ({}s after it ended) ---", elapsed.as_secs());
+        session.end_time = None;
+        self.db_handle.reopen_session(session_id.into())?;
+        // Supabase: clear the end time again
+        if let Some(sync) = &self.supabase_sync {
+            let session_clone = session.clone();
+            let task_sync = sync.clone();
+            let db_path = self.db_handle.path();
+            sync.spawn_limited(async move {
+                match task_sync.update_focus_session(&session_clone).await {
+                    Ok(()) => mark_session_synced_async(db_path, session_clone.id).await,
+                    Err(e) => {
+                        queue_session_update_async(db_path, session_clone.id, e.to_string()).await
+                    }
+                }
+            });
+        }
+        self.session_id = Some(session_id);
+        self.current_project = session.project.clone();
+        self.current_session = Some(session);
+        Ok(true)
+    }
+
+    /// Drops work apps that were in the foreground for less than `min_work_app_focus_pct`
+    /// of the ended session, so apps left running in the background are not listed.
+    fn prune_unused_work_apps(&self, session: &mut FocusSession) {
+        let pct = i64::from(self.min_work_app_focus_pct);
+        // Measured to the recorded end; a session without one counts as zero
+        let session_secs = session.duration(session.start_time).as_secs() as i64;
+        if pct == 0 || session_secs == 0 {
+            return;
+        }
+        let before = session.work_apps.len();
+        session.work_apps.retain(|app| {
+            let focus = self.app_focus_secs.get(app).copied().unwrap_or(0);
+            focus * 100 >= pct * session_secs
+        });
+        if session.work_apps.len() < before {
+            println!(
+                "Dropped {} work app(s) in focus for under {}% of the session",
+                before - session.work_apps.len(),
+                pct
+            );
+        }
+    }
+
+    /// Trims the session's work apps to `max_work_apps`, keeping those with the most focus
+    /// time in the session (first seen wins ties). Returns how many were dropped.
+    fn cap_work_apps(&self, session: &mut FocusSession) -> u32 {
+        if session.work_apps.len() <= self.max_work_apps {
+            return 0;
+        }
+        let focus = |app: &String| self.app_focus_secs.get(app).copied().unwrap_or(0);
+        session
+            .work_apps
+            .sort_by_key(|app| std::cmp::Reverse(focus(app)));
+        let dropped = session.work_apps.len() - self.max_work_apps;
+        session.work_apps.truncate(self.max_work_apps);
+        dropped as u32
+    }
+
+    fn record_work_apps_truncated(
+        &self,
+        session: &FocusSession,
+        truncated: u32,
+    ) -> Result<(), SynapseError> {
+        if truncated > 0 {
+            self.db_handle.set_work_apps_truncated(session.id, truncated)?;
         }
         Ok(())
     }
@@ -488,25 +2174,61 @@ impl SessionManager {
                 .iter()
                 .filter(|name| self.apprules.is_work_app(name))
             {
-                if !session.work_apps.contains(name) {
-                    session.work_apps.push(name.clone());
+                let name = self.apprules.normalize(name);
+                if !session.work_apps.contains(&name) {
+                    session.work_apps.push(name);
                 }
             }
         }
     }
 
+    /// Moves the pomodoro to the phase at the current time, notifying on transitions.
+    /// Entering a break or finishing ends the active session, which is returned.
+    fn advance_pomodoro(&mut self) -> Result<Option<FocusSession>, SynapseError> {
+        let Some(pomodoro) = &self.pomodoro else {
+            return Ok(None);
+        };
+        let phase = pomodoro.phase_at(self.now());
+        if self.pomodoro_phase == Some(phase) {
+            return Ok(None);
+        }
+        let (kind, cycle) = phase;
+        println!("\n--- Pomodoro: {:?} (cycle {}) ---", kind, cycle + 1);
+        tracing::info!(phase = ?kind, cycle, "pomodoro phase");
+        if let Some(callback) = &self.on_pomodoro_phase {
+            callback(kind, cycle);
+        }
+        self.pomodoro_phase = Some(phase);
+        if kind == PomodoroPhase::Work {
+            return Ok(None);
+        }
+        if kind == PomodoroPhase::Finished {
+            self.pomodoro = None;
+            self.pomodoro_phase = None;
+        }
+        // Breaks are not focus time: close the session and don't let it be resumed
+        let ended = self.check_and_end_session(false)?;
+        self.recently_ended = None;
+        Ok(ended)
+    }
+
     fn check_and_end_session(
         &mut self,
-        any_work_app_running: bool,
+        work_condition_met: bool,
     ) -> Result<Option<FocusSession>, SynapseError> {
-        if self.current_session.is_some() && !any_work_app_running {
+        if self.current_session.is_some() && !work_condition_met {
             // Finalize last app usage event if any
             self.finalize_last_app_usage_event()?;
             if let Some(mut session) = self.current_session.take() {
                 println!("\n--- Focus session ended ---");
                 println!("Apps used: {:?}", session.work_apps());
-                let now = SystemTime::now();
+                let now = self.now();
                 session.end_time = Some(now);
+                self.prune_unused_work_apps(&mut session);
+                let truncated = self.cap_work_apps(&mut session);
+                trace_session_end(&session, "work_apps_closed");
+                self.emit_session_ended(&session, "work_apps_closed");
+                self.current_project = None;
                 if let Some(session_id) = self.session_id.take() {
                     let end_time = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
                     let work_apps_str = session.work_apps().join(",");
@@ -521,6 +2243,10 @@ impl SessionManager {
                         .map_err(|e| {
                             SynapseError::Db(rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
                         })?;
+                    self.record_work_apps_truncated(&session, truncated)?;
+                    if !self.resume_window.is_zero() {
+                        self.recently_ended = Some((session.clone(), session_id));
+                    }
                 }
                 return Ok(Some(session));
             }
@@ -528,20 +2254,16 @@ impl SessionManager {
         Ok(None)
     }
 
-    /// Finalizes the last app usage event by updating its end_time and duration_secs if needed.
+    /// Closes the in-flight app's focus interval at the current time so it is recorded
+    /// before the session ends. Tracking of the app resumes from now, so the flushed
+    /// interval is never counted twice.
     fn finalize_last_app_usage_event(&mut self) -> Result<(), SynapseError> {
-        if let (Some(event_id), Some(start_time), Some(app)) = (
-            self.last_app_event_id.take(),
-            self.last_app_start.take(),
-            self.last_app.take(),
-        ) {
-            let now = SystemTime::now();
-            let end_time = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
-            let start_time_secs =
-                start_time.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
-            let duration = end_time - start_time_secs;
-            self.db_handle
-                .update_app_usage_event(event_id, end_time, duration)?;
+        if let (Some(app), Some(start_time)) = (self.last_app.clone(), self.last_app_start) {
+            let now = self.now();
+            if now > start_time {
+                self.record_app_interval(&app, start_time, now)?;
+                self.last_app_start = Some(now);
+            }
         }
         Ok(())
     }
@@ -643,23 +2365,751 @@ mod tests {
         SessionManager::new(rules, db, None, None)
     }
 
-    #[test]
-    fn test_new_manager_initial_state() {
-        let mgr = setup_manager();
-        assert!(mgr.current_session.is_none());
-        assert!(mgr.last_distraction_app.is_none());
-        assert!(mgr.last_checked_process.is_none());
-        assert!(!mgr.last_blocked);
-        assert!(mgr.session_id.is_none());
-        assert!(mgr.last_app.is_none());
-        assert!(mgr.last_app_start.is_none());
-        assert!(mgr.last_app_event_id.is_none());
+    fn setup_manager_with_rules(rules: AppRules) -> SessionManager {
+        SessionManager::new(rules, DbHandle::test_in_memory_with_schema(), None, None)
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
     }
 
     #[test]
-    fn test_start_and_end_session() {
-        let mut mgr = setup_manager();
-        // Simulate starting a session
+    fn test_required_work_apps_gate_session_start() {
+        let rules = AppRules::from_file(crate::apprules::AppRulesFile {
+            whitelist: names(&["code", "alacritty"]),
+            required_work_apps: names(&["code", "alacritty"]),
+            ..Default::default()
+        });
+        let mut mgr = setup_manager_with_rules(rules);
+
+        // Only the editor is running: no session
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code", "bash"]))
+            .unwrap();
+        assert!(mgr.current_session().is_none());
+
+        // Editor and terminal: session starts
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code", "alacritty"]))
+            .unwrap();
+        assert!(mgr.current_session().is_some());
+
+        // Terminal closed: the session ends
+        let ended = mgr
+            .poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        assert_eq!(ended.len(), 1);
+        assert!(mgr.current_session().is_none());
+    }
+
+    #[test]
+    fn test_any_work_app_starts_session_by_default() {
+        let rules = AppRules::test_with_rules(names(&["code", "alacritty"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        assert!(mgr.current_session().is_some());
+    }
+
+    /// Runs a session with two separate visits to a blocked app and returns
+    /// the recorded distraction count and the number of popups shown.
+    fn run_distractions(mode: EnforcementMode) -> (u32, usize) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let popups = Arc::new(AtomicUsize::new(0));
+        let counter = popups.clone();
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(move |_: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        mgr.set_enforcement_mode(mode);
+        let (clock, time) = manual_clock(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        mgr.set_clock(clock);
+        let running = names(&["code", "steam"]);
+        for app in ["code", "steam", "code", "steam"] {
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+            *time.lock().unwrap() += Duration::from_secs(60);
+        }
+        (
+            mgr.current_session_distractions(),
+            popups.load(Ordering::SeqCst),
+        )
+    }
+
+    #[test]
+    fn test_dnd_skips_popup_but_records_distraction() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        let popups = Arc::new(AtomicUsize::new(0));
+        let counter = popups.clone();
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(move |_: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        let dnd = Arc::new(AtomicBool::new(true));
+        let dnd_signal = dnd.clone();
+        mgr.set_dnd_check(Arc::new(move || Ok(dnd_signal.load(Ordering::SeqCst))));
+        let running = names(&["code", "steam"]);
+        for app in ["code", "steam", "code"] {
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+        }
+        assert_eq!(mgr.current_session_distractions(), 1);
+        assert_eq!(popups.load(Ordering::SeqCst), 0);
+
+        // Once DND is off the next visit interrupts again
+        dnd.store(false, Ordering::SeqCst);
+        mgr.poll_snapshot(Some("steam".to_string()), running.clone())
+            .unwrap();
+        assert_eq!(mgr.current_session_distractions(), 2);
+        assert_eq!(popups.load(Ordering::SeqCst), 1);
+
+        // A failing check does not suppress popups
+        mgr.set_dnd_check(Arc::new(|| Err(SynapseError::Platform("no daemon".to_string()))));
+        mgr.set_popup_suppression_window(Duration::ZERO);
+        for app in ["code", "steam"] {
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+        }
+        assert_eq!(popups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_foreground_loss_within_window_shows_one_popup() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let popups = Arc::new(AtomicUsize::new(0));
+        let counter = popups.clone();
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(move |_: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        mgr.set_popup_suppression_window(Duration::from_secs(30));
+        let (clock, time) = manual_clock(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        mgr.set_clock(clock);
+        let running = names(&["code", "steam"]);
+        let mut poll_at = |secs: u64, app: Option<&str>| {
+            *time.lock().unwrap() = UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs);
+            mgr.poll_snapshot(app.map(str::to_string), running.clone())
+                .unwrap();
+        };
+
+        poll_at(0, Some("code"));
+        poll_at(1, Some("steam"));
+        poll_at(2, None);
+        poll_at(3, Some("steam"));
+        assert_eq!(popups.load(Ordering::SeqCst), 1);
+
+        // Past the window the same app triggers a popup again
+        poll_at(40, None);
+        poll_at(41, Some("steam"));
+        assert_eq!(popups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_daily_popup_cap_stops_popups_until_next_day() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let popups = Arc::new(AtomicUsize::new(0));
+        let counter = popups.clone();
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(move |_: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        mgr.set_popup_suppression_window(Duration::ZERO);
+        mgr.set_max_popups_per_day(2);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        let running = names(&["code", "steam"]);
+        let poll_at = |mgr: &mut SessionManager, secs: u64, app: &str| {
+            *time.lock().unwrap() = start + Duration::from_secs(secs);
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+        };
+
+        for visit in 0..3 {
+            poll_at(&mut mgr, visit * 10, "code");
+            poll_at(&mut mgr, visit * 10 + 5, "steam");
+        }
+        // The third distraction is counted but does not pop
+        assert_eq!(popups.load(Ordering::SeqCst), 2);
+        poll_at(&mut mgr, 40, "code");
+        assert_eq!(mgr.current_session_distractions(), 3);
+
+        // The next local day starts a fresh count
+        poll_at(&mut mgr, 24 * 60 * 60 + 40, "code");
+        poll_at(&mut mgr, 24 * 60 * 60 + 45, "steam");
+        assert_eq!(popups.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_popup_sound_follows_config_and_falls_back_for_missing_files() {
+        let played = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(|_: &str| {})),
+        );
+        let recorder = played.clone();
+        mgr.set_sound_player(Box::new(move |sound| {
+            recorder.lock().unwrap().push(sound.clone());
+            Ok(())
+        }));
+        mgr.set_popup_suppression_window(Duration::ZERO);
+        let running = names(&["code", "steam"]);
+        let distract = |mgr: &mut SessionManager| {
+            mgr.poll_snapshot(Some("code".to_string()), running.clone()).unwrap();
+            mgr.poll_snapshot(Some("steam".to_string()), running.clone()).unwrap();
+        };
+
+        // Silent by default
+        distract(&mut mgr);
+        assert!(played.lock().unwrap().is_empty());
+
+        mgr.set_popup_sound(PopupSound::System);
+        distract(&mut mgr);
+        let sound_file = std::env::temp_dir().join(format!("synapse_sound_{}.wav", Uuid::new_v4()));
+        mgr.set_popup_sound(PopupSound::Custom(sound_file.clone()));
+        distract(&mut mgr);
+        std::fs::write(&sound_file, b"RIFF").unwrap();
+        distract(&mut mgr);
+        let _ = std::fs::remove_file(&sound_file);
+
+        assert_eq!(
+            *played.lock().unwrap(),
+            vec![
+                PopupSound::System,
+                // The file did not exist yet
+                PopupSound::System,
+                PopupSound::Custom(sound_file),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_focus_reminder_fires_once_without_a_session() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let reminders = Arc::new(AtomicUsize::new(0));
+        let counter = reminders.clone();
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.set_on_focus_reminder(Some(Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })));
+        mgr.set_focus_reminder_after(Duration::from_secs(600));
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        let poll_at = |mgr: &mut SessionManager, secs: u64, running: &[&str]| {
+            *time.lock().unwrap() = start + Duration::from_secs(secs);
+            mgr.poll_snapshot(Some(running[0].to_string()), names(running))
+                .unwrap();
+        };
+
+        // Only non-work apps: the reminder fires once past the threshold
+        poll_at(&mut mgr, 0, &["steam"]);
+        poll_at(&mut mgr, 599, &["steam"]);
+        assert_eq!(reminders.load(Ordering::SeqCst), 0);
+        poll_at(&mut mgr, 600, &["steam"]);
+        poll_at(&mut mgr, 1200, &["steam"]);
+        assert_eq!(reminders.load(Ordering::SeqCst), 1);
+
+        // A running session never triggers it, however long it lasts
+        poll_at(&mut mgr, 1300, &["code"]);
+        poll_at(&mut mgr, 5000, &["code"]);
+        assert_eq!(reminders.load(Ordering::SeqCst), 1);
+
+        // The session ending re-arms the reminder
+        poll_at(&mut mgr, 5100, &["steam"]);
+        poll_at(&mut mgr, 5700, &["steam"]);
+        assert_eq!(reminders.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_over_budget_category_escalates_to_distraction() {
+        let rules = AppRules::from_file(crate::apprules::AppRulesFile {
+            whitelist: names(&["code"]),
+            categories: [("social".to_string(), names(&["discord"]))].into_iter().collect(),
+            ..Default::default()
+        });
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.set_category_budgets("social=60".parse().unwrap());
+        mgr.set_block_over_budget_categories(true);
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observed = events.clone();
+        mgr.set_on_event(Some(Box::new(move |event| {
+            if let SessionEvent::CategoryOverBudget { category, .. } = event {
+                observed.lock().unwrap().push(category.clone());
+            }
+        })));
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        let running = names(&["code", "discord"]);
+        let poll_at = |mgr: &mut SessionManager, secs: u64, app: &str| {
+            *time.lock().unwrap() = start + Duration::from_secs(secs);
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+        };
+
+        poll_at(&mut mgr, 0, "code");
+        poll_at(&mut mgr, 5, "discord");
+        poll_at(&mut mgr, 30, "discord");
+        assert_eq!(mgr.current_session_distractions(), 0);
+
+        // Past the budget the app in focus turns into a distraction, reported once
+        poll_at(&mut mgr, 70, "discord");
+        poll_at(&mut mgr, 80, "discord");
+        assert_eq!(mgr.current_session_distractions(), 1);
+        assert_eq!(*events.lock().unwrap(), vec!["social".to_string()]);
+
+        // Without escalation it is still reported but no longer blocked
+        mgr.set_block_over_budget_categories(false);
+        poll_at(&mut mgr, 90, "code");
+        poll_at(&mut mgr, 100, "discord");
+        assert_eq!(mgr.current_session_distractions(), 1);
+    }
+
+    #[test]
+    fn test_helper_processes_are_folded_into_their_parent() {
+        use crate::apprules::AppRulesFile;
+
+        let rules = AppRules::from_file(AppRulesFile {
+            whitelist: names(&["code"]),
+            blacklist: names(&["chrome", "steam"]),
+            helper_processes: [("steam".to_string(), names(&["steamwebhelper"]))].into(),
+            group_helper_processes: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            rules.fold_helper_processes(&names(&[
+                "code",
+                "Code Helper (Renderer)",
+                "chrome",
+                "chrome_crashpad_handler",
+                "steamwebhelper",
+            ])),
+            names(&["code", "chrome", "steam"])
+        );
+        // Without its parent running a look-alike name stays its own app
+        assert_eq!(rules.helper_parent("chrome_crashpad_handler", &names(&["code"])), None);
+
+        let mut mgr = setup_manager_with_rules(rules);
+        let (clock, time) = manual_clock(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        mgr.set_clock(clock);
+        let running = names(&["code", "Code Helper (GPU)", "chrome", "chrome_crashpad_handler"]);
+        for app in ["Code Helper (GPU)", "chrome_crashpad_handler", "steamwebhelper"] {
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+            *time.lock().unwrap() += Duration::from_secs(60);
+        }
+
+        // The editor helper kept the session going; the browser and steam helpers count
+        // as distractions just like their parents would
+        assert!(mgr.current_session.is_some());
+        assert_eq!(mgr.current_session_distractions(), 2);
+    }
+
+    #[test]
+    fn test_malformed_rules_reload_keeps_previous_rules() {
+        use crate::apprules::AppRulesFile;
+
+        let failures = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = setup_manager_with_rules(rules);
+        let seen = failures.clone();
+        mgr.set_on_rules_reload_failed(Some(Box::new(move |e| {
+            seen.lock().unwrap().push(e.to_string());
+        })));
+        let observed = events.clone();
+        mgr.set_on_event(Some(Box::new(move |event| {
+            observed.lock().unwrap().push(event.clone());
+        })));
+
+        let malformed = serde_json::from_str::<AppRulesFile>(r#"{"whitelist": ["code""#)
+            .map(AppRules::from_file)
+            .map_err(|e| SynapseError::Config(format!("Failed to parse apprules.json: {}", e)));
+        assert!(!mgr.reload_apprules(malformed));
+
+        assert_eq!(failures.lock().unwrap().len(), 1);
+        assert!(failures.lock().unwrap()[0].contains("Failed to parse apprules.json"));
+        assert!(matches!(
+            events.lock().unwrap().as_slice(),
+            [SessionEvent::RulesReloadFailed { .. }]
+        ));
+        assert!(mgr.apprules().is_work_app("code"));
+        assert!(mgr.apprules().is_blocked("steam"));
+
+        // A valid reload afterwards applies normally
+        let fixed = AppRules::test_with_rules(names(&["code", "slack"]), names(&["steam"]));
+        assert!(mgr.reload_apprules(Ok(fixed)));
+        assert!(mgr.apprules().is_work_app("slack"));
+        assert_eq!(failures.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rules_reload_from_path_updates_rules_in_place() {
+        let dir = std::env::temp_dir().join(format!("synapse_mgr_reload_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("apprules.json");
+        let mut mgr = setup_manager_with_rules(AppRules::test_with_rules(names(&["code"]), names(&["steam"])));
+        mgr.set_on_rules_reload_failed(Some(Box::new(|_| {})));
+
+        std::fs::write(&path, r#"{"whitelist": ["code", "steam"], "blacklist": []}"#).unwrap();
+        assert!(mgr.reload_apprules_from_path(&path));
+        assert!(mgr.apprules().is_work_app("steam"));
+
+        // A bad edit keeps the rules in effect
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(!mgr.reload_apprules_from_path(&path));
+        assert!(mgr.apprules().is_work_app("steam"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_observe_mode_records_distractions_without_popups() {
+        assert_eq!(run_distractions(EnforcementMode::Observe), (2, 0));
+    }
+
+    #[test]
+    fn test_enforce_mode_shows_popups() {
+        assert_eq!(run_distractions(EnforcementMode::Enforce), (2, 2));
+    }
+
+    /// Polls with an app that is both whitelisted and blacklisted in the foreground and
+    /// returns whether a session started and how many popups were shown.
+    fn run_overlap(precedence: OverlapPrecedence) -> (bool, usize) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let popups = Arc::new(AtomicUsize::new(0));
+        let counter = popups.clone();
+        let rules = AppRules::test_with_rules(names(&["code", "chrome"]), names(&["chrome"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(move |_: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        mgr.set_overlap_precedence(precedence);
+        mgr.poll_snapshot(Some("chrome".to_string()), names(&["chrome"]))
+            .unwrap();
+        let started = mgr.current_session().is_some();
+        // With a work app running, the overlapping app is focused mid-session
+        for app in ["code", "chrome"] {
+            mgr.poll_snapshot(Some(app.to_string()), names(&["code", "chrome"]))
+                .unwrap();
+        }
+        (started, popups.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn test_overlapping_app_starts_session_when_whitelist_wins() {
+        assert_eq!(run_overlap(OverlapPrecedence::WhitelistWins), (true, 0));
+    }
+
+    #[test]
+    fn test_overlapping_app_is_blocked_when_blacklist_wins() {
+        assert_eq!(run_overlap(OverlapPrecedence::BlacklistWins), (false, 1));
+    }
+
+    /// Focuses an exe missing from the installed-apps list during a session and returns
+    /// the tracked app, whether it was blocked, and the popup count.
+    fn run_unknown_app(policy: UnknownAppPolicy) -> (Option<String>, bool, usize) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let popups = Arc::new(AtomicUsize::new(0));
+        let counter = popups.clone();
+        let rules = AppRules::test_with_rules(names(&["code.exe"]), names(&["steam.exe"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(move |_: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        mgr.set_known_apps(Some(names(&["Code.exe", "notepad.exe"])));
+        mgr.set_unknown_app_policy(policy);
+        let running = names(&["code.exe", "mystery.exe"]);
+        for app in ["code.exe", "mystery.exe"] {
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+        }
+        (
+            mgr.last_checked_process().cloned(),
+            mgr.last_blocked(),
+            popups.load(Ordering::SeqCst),
+        )
+    }
+
+    #[test]
+    fn test_unknown_app_is_ignored() {
+        assert_eq!(run_unknown_app(UnknownAppPolicy::Ignore), (None, false, 0));
+    }
+
+    #[test]
+    fn test_unknown_app_uses_raw_name() {
+        assert_eq!(
+            run_unknown_app(UnknownAppPolicy::UseRawName),
+            (Some("mystery.exe".to_string()), false, 0)
+        );
+    }
+
+    #[test]
+    fn test_unknown_app_is_treated_as_distraction() {
+        assert_eq!(
+            run_unknown_app(UnknownAppPolicy::TreatAsDistraction),
+            (Some("mystery.exe".to_string()), true, 1)
+        );
+    }
+
+    /// Focuses each app in turn during a session and returns whether it was blocked, with the
+    /// session's distraction count at the end.
+    fn run_strict_mode(strict: bool) -> (Vec<bool>, u32) {
+        let rules = AppRules::from_file(crate::apprules::AppRulesFile {
+            whitelist: names(&["code"]),
+            neutral: names(&["explorer"]),
+            ..Default::default()
+        });
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.set_strict_mode(strict);
+        let running = names(&["code", "explorer", "mystery"]);
+        let blocked = ["code", "mystery", "code", "explorer"]
+            .iter()
+            .map(|app| {
+                mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                    .unwrap();
+                mgr.last_blocked()
+            })
+            .collect();
+        (blocked, mgr.current_session_distractions())
+    }
+
+    /// Alternates between a work app and a blocked one under `limit`, returning the
+    /// distraction count after each visit to the blocked app, the end reasons seen and the
+    /// limits notified.
+    fn run_distraction_limit(
+        limit: u32,
+        visits: usize,
+    ) -> (Vec<Option<u32>>, Vec<String>, Vec<u32>) {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let db = DbHandle::test_in_memory_with_schema();
+        let mut mgr = SessionManager::new(rules, db, None, Some(Box::new(|_| {})));
+        mgr.set_max_distractions_per_session(limit);
+        let reasons = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = reasons.clone();
+        mgr.set_on_event(Some(Box::new(move |event| {
+            if let SessionEvent::SessionEnded { reason, .. } = event {
+                seen.lock().unwrap().push(reason.clone());
+            }
+        })));
+        let notified = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = notified.clone();
+        mgr.set_on_distraction_limit(Some(Box::new(move |limit| {
+            recorder.lock().unwrap().push(limit)
+        })));
+        let running = names(&["code", "steam"]);
+        let counts = (0..visits)
+            .map(|_| {
+                mgr.poll_snapshot(Some("code".to_string()), running.clone())
+                    .unwrap();
+                mgr.poll_snapshot(Some("steam".to_string()), running.clone())
+                    .unwrap();
+                mgr.current_session().map(|s| s.distraction_attempts())
+            })
+            .collect();
+        let reasons = reasons.lock().unwrap().clone();
+        let notified = notified.lock().unwrap().clone();
+        (counts, reasons, notified)
+    }
+
+    #[test]
+    fn test_distraction_limit_ends_session_at_the_limit() {
+        let (counts, reasons, notified) = run_distraction_limit(3, 4);
+        // Ended on the third distraction; the next session waits for the work app
+        assert_eq!(counts, vec![Some(1), Some(2), None, Some(1)]);
+        assert_eq!(reasons, vec!["distraction_limit".to_string()]);
+        assert_eq!(notified, vec![3]);
+    }
+
+    #[test]
+    fn test_distraction_limit_disabled_keeps_session() {
+        let (counts, reasons, notified) = run_distraction_limit(0, 5);
+        assert_eq!(counts, vec![Some(1), Some(2), Some(3), Some(4), Some(5)]);
+        assert!(reasons.is_empty());
+        assert!(notified.is_empty());
+    }
+
+    #[test]
+    fn test_strict_mode_blocks_unlisted_apps() {
+        assert_eq!(run_strict_mode(true), (vec![false, true, false, false], 1));
+    }
+
+    #[test]
+    fn test_default_mode_allows_unlisted_apps() {
+        assert_eq!(run_strict_mode(false), (vec![false, false, false, false], 0));
+    }
+
+    #[test]
+    fn test_whitelisting_blocked_app_stops_blocking_it() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = setup_manager_with_rules(rules);
+        let rules_path =
+            std::env::temp_dir().join(format!("synapse_apprules_{}.json", Uuid::new_v4()));
+        let running = names(&["code", "steam"]);
+
+        mgr.poll_snapshot(Some("code".to_string()), running.clone())
+            .unwrap();
+        mgr.poll_snapshot(Some("steam".to_string()), running.clone())
+            .unwrap();
+        assert!(mgr.last_blocked());
+        assert_eq!(mgr.current_session_distractions(), 1);
+
+        mgr.whitelist_app("Steam", &rules_path).unwrap();
+        assert!(mgr.apprules().is_work_app("steam"));
+        assert!(!mgr.apprules().is_blocked("steam"));
+        let saved: crate::apprules::AppRulesFile =
+            serde_json::from_str(&std::fs::read_to_string(&rules_path).unwrap()).unwrap();
+        assert!(saved.whitelist.contains(&"steam".to_string()));
+        assert!(!saved.blacklist.contains(&"steam".to_string()));
+
+        // Still in focus, but no longer a distraction
+        mgr.poll_snapshot(Some("steam".to_string()), running.clone())
+            .unwrap();
+        assert!(!mgr.last_blocked());
+        assert_eq!(mgr.current_session_distractions(), 1);
+        std::fs::remove_file(rules_path).unwrap();
+    }
+
+    #[test]
+    fn test_distraction_reason_is_stored_on_event() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(|_: &str| {})),
+        );
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        let running = names(&["code", "steam"]);
+        let poll_at = |mgr: &mut SessionManager, secs: u64, app: &str| {
+            *time.lock().unwrap() = start + Duration::from_secs(secs);
+            mgr.poll_snapshot(Some(app.to_string()), running.clone())
+                .unwrap();
+        };
+
+        assert!(!mgr.annotate_distraction("steam", "bored").unwrap());
+        poll_at(&mut mgr, 0, "code");
+        let session_id = mgr.current_session().unwrap().id;
+        // Reason given while the blocked app is still in focus
+        poll_at(&mut mgr, 10, "steam");
+        assert!(mgr.annotate_distraction("Steam", " needed a break ").unwrap());
+        poll_at(&mut mgr, 20, "code");
+        // Reason given after switching away
+        poll_at(&mut mgr, 30, "steam");
+        poll_at(&mut mgr, 40, "code");
+        assert!(mgr.annotate_distraction("steam", "waiting on a build").unwrap());
+        assert!(!mgr.annotate_distraction("steam", "   ").unwrap());
+
+        let events = mgr
+            .db_handle()
+            .get_app_usage_events_for_session(session_id)
+            .unwrap();
+        let reason_at = |start: i64| {
+            events
+                .iter()
+                .find(|e| e.process_name == "steam" && e.start_time == start)
+                .unwrap()
+                .reason
+                .clone()
+        };
+        assert_eq!(reason_at(1_700_000_010).as_deref(), Some("needed a break"));
+        assert_eq!(reason_at(1_700_000_030).as_deref(), Some("waiting on a build"));
+        assert!(events
+            .iter()
+            .filter(|e| e.process_name == "code")
+            .all(|e| e.reason.is_none()));
+
+        let summary =
+            crate::api::distraction_reasons_summary(mgr.db_handle(), 1_700_000_000, 1_700_001_000)
+                .unwrap();
+        assert_eq!(summary.len(), 2);
+    }
+
+    #[test]
+    fn test_blocked_event_records_matching_rule() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["chrome", "steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(|_: &str| {})),
+        );
+        let running = names(&["code", "steam"]);
+        mgr.poll_snapshot(Some("code".to_string()), running.clone())
+            .unwrap();
+        let session_id = mgr.current_session().unwrap().id;
+        mgr.poll_snapshot(Some("steam".to_string()), running.clone())
+            .unwrap();
+        mgr.poll_snapshot(Some("code".to_string()), running).unwrap();
+
+        let events = mgr
+            .db_handle()
+            .get_app_usage_events_for_session(session_id)
+            .unwrap();
+        let blocked = events.iter().find(|e| e.process_name == "steam").unwrap();
+        assert_eq!(blocked.status, "blocked");
+        assert_eq!(blocked.matched_rule.as_deref(), Some("steam"));
+        let allowed = events.iter().find(|e| e.process_name == "code").unwrap();
+        assert_eq!(allowed.status, "allowed");
+        assert!(allowed.matched_rule.is_none());
+    }
+
+    #[test]
+    fn test_new_manager_initial_state() {
+        let mgr = setup_manager();
+        assert!(mgr.current_session.is_none());
+        assert!(mgr.last_distraction_app.is_none());
+        assert!(mgr.last_checked_process.is_none());
+        assert!(!mgr.last_blocked);
+        assert!(mgr.session_id.is_none());
+        assert!(mgr.last_app.is_none());
+        assert!(mgr.last_app_start.is_none());
+    }
+
+    #[test]
+    fn test_start_and_end_session() {
+        let mut mgr = setup_manager();
+        // Simulate starting a session
         let now = SystemTime::now();
         mgr.current_session = Some(FocusSession {
             id: Uuid::new_v4(), // Generate a new uuid for the new session
@@ -667,6 +3117,7 @@ mod tests {
             end_time: None,
             work_apps: vec!["notepad.exe".to_string()],
             distraction_attempts: 0,
+            project: None,
         });
         mgr.session_id = Some(SessionId::from(mgr.current_session.as_ref().unwrap().id));
         assert!(mgr.current_session.is_some());
@@ -676,6 +3127,761 @@ mod tests {
         assert!(mgr.session_id.is_none());
     }
 
+    /// Starts a session with `code`, then closes it so the session ends.
+    fn start_and_close_editor(mgr: &mut SessionManager) -> Uuid {
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        let id = mgr.current_session().unwrap().id;
+        let ended = mgr.poll_snapshot(None, names(&["bash"])).unwrap();
+        assert_eq!(ended.iter().map(|s| s.id).collect::<Vec<_>>(), vec![id]);
+        id
+    }
+
+    #[test]
+    fn test_refocus_within_window_resumes_session() {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.set_resume_window(Duration::from_secs(60));
+        let id = start_and_close_editor(&mut mgr);
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        let session = mgr.current_session().unwrap();
+        assert_eq!(session.id, id);
+        assert!(session.end_time().is_none());
+        let sessions: i64 = mgr
+            .db_handle()
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM focus_sessions WHERE end_time IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(sessions, 1);
+    }
+
+    #[test]
+    fn test_refocus_after_window_starts_new_session() {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.set_resume_window(Duration::from_secs(60));
+        let id = start_and_close_editor(&mut mgr);
+        // Pretend the session ended two minutes ago
+        if let Some((session, _)) = mgr.recently_ended.as_mut() {
+            session.end_time = Some(SystemTime::now() - Duration::from_secs(120));
+        }
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        assert_ne!(mgr.current_session().unwrap().id, id);
+    }
+
+    #[test]
+    fn test_resume_disabled_by_default() {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let id = start_and_close_editor(&mut mgr);
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        assert_ne!(mgr.current_session().unwrap().id, id);
+    }
+
+    /// Returns a clock fixed at `start` that the test can advance.
+    fn manual_clock(start: SystemTime) -> (Clock, Arc<std::sync::Mutex<SystemTime>>) {
+        let time = Arc::new(std::sync::Mutex::new(start));
+        let handle = time.clone();
+        (Arc::new(move || *handle.lock().unwrap()), time)
+    }
+
+    /// Runs a session that starts at UTC+1 and is polled again after moving to UTC-5,
+    /// returning what that poll ended and the manager.
+    fn run_timezone_change(
+        policy: TimezoneChangePolicy,
+    ) -> (Vec<FocusSession>, SessionManager, SystemTime) {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        let offset = Arc::new(std::sync::Mutex::new(3600));
+        let source = offset.clone();
+        mgr.set_utc_offset_source(Arc::new(move |_| *source.lock().unwrap()));
+        mgr.set_timezone_change_policy(policy);
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        *time.lock().unwrap() = start + Duration::from_secs(600);
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+
+        // Six hours behind: local clocks go backwards, UTC keeps moving forward
+        *offset.lock().unwrap() = -5 * 3600;
+        *time.lock().unwrap() = start + Duration::from_secs(1200);
+        let ended = mgr
+            .poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        (ended, mgr, start)
+    }
+
+    #[test]
+    fn test_timezone_change_splits_session() {
+        let (ended, mgr, start) = run_timezone_change(TimezoneChangePolicy::SplitSession);
+        let [ended]: [FocusSession; 1] = ended.try_into().expect("session ended at the timezone change");
+        assert_eq!(ended.start_time, start);
+        assert_eq!(ended.end_time, Some(start + Duration::from_secs(1200)));
+        let current = mgr.current_session().unwrap();
+        assert_ne!(current.id, ended.id);
+        assert_eq!(current.start_time, start + Duration::from_secs(1200));
+    }
+
+    #[test]
+    fn test_timezone_change_keeps_session_when_configured() {
+        let (ended, mgr, start) = run_timezone_change(TimezoneChangePolicy::KeepSession);
+        assert!(ended.is_empty());
+        assert_eq!(mgr.current_session().unwrap().start_time, start);
+    }
+
+    /// Polls `code` for two minutes, then simulates a three-hour sleep that started ten
+    /// seconds after the last poll, returning the wake poll's ended session and the manager.
+    fn run_sleep(policy: SleepPolicy) -> (Vec<FocusSession>, SessionManager, SystemTime) {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        let suspended = Arc::new(std::sync::Mutex::new(Duration::ZERO));
+        let source = suspended.clone();
+        mgr.set_time_suspended_source(Arc::new(move || Some(*source.lock().unwrap())));
+        mgr.set_sleep_policy(policy, Duration::from_secs(60));
+
+        for secs in [0, 60, 120] {
+            *time.lock().unwrap() = start + Duration::from_secs(secs);
+            mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+                .unwrap();
+        }
+        *suspended.lock().unwrap() = Duration::from_secs(3 * 3600);
+        *time.lock().unwrap() = start + Duration::from_secs(130 + 3 * 3600);
+        let ended = mgr
+            .poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        (ended, mgr, start)
+    }
+
+    #[test]
+    fn test_sleep_ends_session_when_the_system_went_to_sleep() {
+        let (ended, mgr, start) = run_sleep(SleepPolicy::SplitSession);
+        let [ended]: [FocusSession; 1] = ended.try_into().expect("session ended at the sleep");
+        assert_eq!(ended.start_time, start);
+        assert_eq!(ended.end_time, Some(start + Duration::from_secs(130)));
+        let stored_end: i64 = mgr
+            .db_handle()
+            .conn
+            .query_row(
+                "SELECT end_time FROM focus_sessions WHERE id = ?1",
+                [ended.id.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored_end, 1_700_000_130);
+        // The app in focus is not credited with the time asleep
+        let wake = start + Duration::from_secs(130 + 3 * 3600);
+        let focus = mgr
+            .db_handle()
+            .focus_secs_by_app_between(1_700_000_000, 1_700_000_000 + 4 * 3600)
+            .unwrap();
+        assert_eq!(focus, vec![("code".to_string(), 130)]);
+        // Work carries on in a new session from the moment of waking
+        let current = mgr.current_session().unwrap();
+        assert_ne!(current.id, ended.id);
+        assert_eq!(current.start_time, wake);
+    }
+
+    /// Installs a fake process table and returns the pids the manager kills.
+    fn fake_process_control(
+        mgr: &mut SessionManager,
+        processes: Vec<(u32, &str)>,
+    ) -> Arc<std::sync::Mutex<Vec<u32>>> {
+        let processes: Vec<(u32, String)> =
+            processes.into_iter().map(|(pid, name)| (pid, name.to_string())).collect();
+        let killed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = killed.clone();
+        mgr.set_process_control(
+            Arc::new(move || Ok(processes.clone())),
+            Arc::new(move |pid| {
+                recorder.lock().unwrap().push(pid);
+                Ok(())
+            }),
+        );
+        killed
+    }
+
+    #[test]
+    fn test_blocked_apps_are_only_killed_when_enabled() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(|_: &str| {})),
+        );
+        mgr.set_dnd_check(Arc::new(|| Ok(false)));
+        let killed = fake_process_control(&mut mgr, vec![(10, "steam")]);
+
+        let err = mgr.kill_blocked_app("steam").unwrap_err();
+        assert!(matches!(err, SynapseError::Config(_)));
+
+        // Detection does not kill either unless asked to
+        mgr.set_kill_blocked_apps(KillBlockedApps::OnRequest);
+        let running = names(&["code", "steam"]);
+        for app in ["code", "steam"] {
+            mgr.poll_snapshot(Some(app.to_string()), running.clone()).unwrap();
+        }
+        assert!(killed.lock().unwrap().is_empty());
+
+        assert_eq!(mgr.kill_blocked_app("steam").unwrap(), 1);
+        assert_eq!(*killed.lock().unwrap(), vec![10]);
+
+        mgr.set_kill_blocked_apps(KillBlockedApps::OnDetection);
+        for app in ["code", "steam"] {
+            mgr.poll_snapshot(Some(app.to_string()), running.clone()).unwrap();
+        }
+        assert_eq!(*killed.lock().unwrap(), vec![10, 10]);
+    }
+
+    #[test]
+    fn test_kill_resolves_pids_of_the_blocked_app_only() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.set_kill_blocked_apps(KillBlockedApps::OnRequest);
+        let own_pid = std::process::id();
+        let killed = fake_process_control(
+            &mut mgr,
+            vec![(10, "steam"), (11, "code"), (12, "Steam"), (own_pid, "steam")],
+        );
+
+        assert_eq!(mgr.kill_blocked_app("Steam").unwrap(), 2);
+        assert_eq!(*killed.lock().unwrap(), vec![10, 12]);
+
+        // Work apps are never killed, and a blocked app that isn't running is an error
+        assert!(mgr.kill_blocked_app("code").is_err());
+        let mut idle = setup_manager_with_rules(AppRules::test_with_rules(
+            names(&["code"]),
+            names(&["steam"]),
+        ));
+        idle.set_kill_blocked_apps(KillBlockedApps::OnRequest);
+        fake_process_control(&mut idle, vec![(11, "code")]);
+        assert!(matches!(idle.kill_blocked_app("steam"), Err(SynapseError::Platform(_))));
+        assert_eq!(killed.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_distractions_outside_sessions_are_counted_when_enabled() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(|_: &str| {})),
+        );
+        mgr.set_dnd_check(Arc::new(|| Ok(false)));
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = events.clone();
+        mgr.set_on_event(Some(Box::new(move |event| {
+            if let SessionEvent::Distraction { session_id, app } = event {
+                recorder.lock().unwrap().push((*session_id, app.clone()));
+            }
+        })));
+        let outside = |mgr: &SessionManager| {
+            mgr.db_handle()
+                .outside_session_distractions_between(0, i64::MAX)
+                .unwrap()
+        };
+
+        // Off by default: a blocked app without a session is ignored
+        mgr.poll_snapshot(Some("steam".to_string()), names(&["steam"])).unwrap();
+        assert_eq!(outside(&mgr), 0);
+
+        // Counted once per visit when enabled
+        mgr.set_count_distractions_outside_sessions(true);
+        for app in ["steam", "steam", "bash", "steam"] {
+            mgr.poll_snapshot(Some(app.to_string()), names(&["steam", "bash"])).unwrap();
+        }
+        assert_eq!(outside(&mgr), 2);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![(Uuid::nil(), "steam".to_string()), (Uuid::nil(), "steam".to_string())]
+        );
+
+        // During a session the distraction counts against the session instead
+        for app in ["code", "steam"] {
+            mgr.poll_snapshot(Some(app.to_string()), names(&["code", "steam"])).unwrap();
+        }
+        assert_eq!(outside(&mgr), 2);
+        assert_eq!(mgr.current_session_distractions(), 1);
+    }
+
+    #[test]
+    fn test_dry_run_poll_reports_decisions_without_changing_state() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(|_: &str| {})),
+        );
+        mgr.set_dnd_check(Arc::new(|| Ok(false)));
+
+        let work = mgr.dry_run_poll(Some("code".to_string()), &names(&["code"]));
+        assert!(work.is_work_app);
+        assert!(work.would_start_session);
+        assert!(!work.would_popup);
+
+        let blocked = mgr.dry_run_poll(Some("steam".to_string()), &names(&["code", "steam"]));
+        assert!(blocked.is_blocked);
+        assert!(blocked.would_popup);
+        assert_eq!(blocked.popup_skipped, None);
+
+        // Nothing was started or recorded
+        assert!(mgr.current_session().is_none());
+        let sessions: i64 = mgr
+            .db_handle()
+            .conn
+            .query_row("SELECT COUNT(*) FROM focus_sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sessions, 0);
+
+        // Without a work app there is no session to interrupt; in observe mode no popup either
+        let idle = mgr.dry_run_poll(Some("steam".to_string()), &names(&["steam"]));
+        assert!(!idle.would_start_session && !idle.would_popup);
+        assert_eq!(idle.popup_skipped.as_deref(), Some("no focus session"));
+        mgr.set_enforcement_mode(EnforcementMode::Observe);
+        let observed = mgr.dry_run_poll(Some("steam".to_string()), &names(&["code", "steam"]));
+        assert_eq!(observed.popup_skipped.as_deref(), Some("observe mode"));
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"])).unwrap();
+        let closing = mgr.dry_run_poll(None, &names(&["bash"]));
+        assert!(closing.would_end_session);
+        assert!(mgr.current_session().is_some());
+    }
+
+    #[test]
+    fn test_sleep_policies_end_or_keep_the_session() {
+        let (ended, mgr, _) = run_sleep(SleepPolicy::EndSession);
+        assert_eq!(ended.len(), 1);
+        // The wake poll already had a work app in the foreground, so a session started
+        assert!(mgr.current_session().is_some());
+
+        let (ended, mgr, start) = run_sleep(SleepPolicy::KeepSession);
+        assert!(ended.is_empty());
+        assert_eq!(mgr.current_session().unwrap().start_time, start);
+    }
+
+    #[test]
+    fn test_poll_returns_every_session_it_ended() {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        mgr.set_max_session_length(Duration::from_secs(3600));
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        // The session is split at its maximum length, then the closed editor ends the new one
+        *time.lock().unwrap() = start + Duration::from_secs(3610);
+        let ended = mgr.poll_snapshot(None, names(&["bash"])).unwrap();
+        assert_eq!(ended.len(), 2);
+        assert_eq!(ended[0].start_time, start);
+        assert_ne!(ended[0].id, ended[1].id);
+        assert!(mgr.current_session().is_none());
+    }
+
+    #[test]
+    fn test_long_session_is_split_into_contiguous_rows() {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        mgr.set_max_session_length(Duration::from_secs(3600));
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        let first_id = mgr.current_session().unwrap().id;
+        *time.lock().unwrap() = start + Duration::from_secs(1800);
+        assert!(mgr
+            .poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap()
+            .is_empty());
+
+        *time.lock().unwrap() = start + Duration::from_secs(3610);
+        let [split]: [FocusSession; 1] = mgr
+            .poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(split.id, first_id);
+        assert_ne!(mgr.current_session().unwrap().id, first_id);
+
+        let mut stmt = mgr
+            .db_handle()
+            .conn
+            .prepare("SELECT id, start_time, end_time FROM focus_sessions ORDER BY start_time")
+            .unwrap();
+        let rows: Vec<(String, i64, Option<i64>)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, first_id.to_string());
+        assert_eq!(rows[0].1, 1_700_000_000);
+        assert_eq!(rows[0].2, Some(1_700_003_610));
+        assert_eq!(rows[1].1, 1_700_003_610);
+        assert_eq!(rows[1].2, None);
+    }
+
+    #[test]
+    fn test_project_change_in_title_splits_session() {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        mgr.set_project_pattern(Some(Regex::new(r" - (.+) - Visual Studio Code$").unwrap()));
+        let mut poll_at = |secs: u64, foreground: &str, title: &str| {
+            *time.lock().unwrap() = start + Duration::from_secs(secs);
+            mgr.poll_snapshot_with_title(
+                Some(foreground.to_string()),
+                Some(title.to_string()),
+                names(&["code", "firefox"]),
+            )
+            .unwrap()
+        };
+
+        assert!(poll_at(0, "code", "main.rs - synapse - Visual Studio Code").is_empty());
+        assert!(poll_at(60, "code", "lib.rs - synapse - Visual Studio Code").is_empty());
+        // Titles of non-work apps and titles without a project leave the session alone
+        assert!(poll_at(120, "firefox", "website - Mozilla Firefox").is_empty());
+        assert!(poll_at(180, "code", "Welcome").is_empty());
+        let [ended]: [FocusSession; 1] =
+            poll_at(240, "code", "index.html - website - Visual Studio Code").try_into().unwrap();
+        assert_eq!(ended.project.as_deref(), Some("synapse"));
+        assert_eq!(ended.end_time, Some(start + Duration::from_secs(240)));
+        assert!(poll_at(300, "code", "style.css - website - Visual Studio Code").is_empty());
+        let current = mgr.current_session().unwrap();
+        assert_ne!(current.id, ended.id);
+        assert_eq!(current.project.as_deref(), Some("website"));
+
+        let mut stmt = mgr
+            .db_handle()
+            .conn
+            .prepare("SELECT project, start_time, end_time FROM focus_sessions ORDER BY start_time")
+            .unwrap();
+        let rows: Vec<(Option<String>, i64, Option<i64>)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (Some("synapse".to_string()), 1_700_000_000, Some(1_700_000_240)),
+                (Some("website".to_string()), 1_700_000_240, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pomodoro_breaks_are_not_focus_time() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let popups = Arc::new(AtomicUsize::new(0));
+        let counter = popups.clone();
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["steam"]));
+        let mut mgr = SessionManager::new(
+            rules,
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(move |_: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        mgr.set_resume_window(Duration::from_secs(600));
+        let transitions = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = transitions.clone();
+        mgr.set_on_pomodoro_phase(Some(Box::new(move |phase: PomodoroPhase, cycle: u32| {
+            seen.lock().unwrap().push((phase, cycle));
+        })));
+        mgr.start_pomodoro(Duration::from_secs(1500), Duration::from_secs(300), 2)
+            .unwrap();
+
+        let mut poll_at = |secs: u64, app: &str, running: &[&str]| {
+            *time.lock().unwrap() = start + Duration::from_secs(secs);
+            mgr.poll_snapshot(Some(app.to_string()), names(running))
+                .unwrap();
+            (mgr.pomodoro_phase(), mgr.current_session().is_some())
+        };
+        let work = PomodoroPhase::Work;
+        let brk = PomodoroPhase::Break;
+        assert_eq!(poll_at(0, "code", &["code", "steam"]), (Some((work, 0)), true));
+        assert_eq!(poll_at(1499, "code", &["code", "steam"]), (Some((work, 0)), true));
+        assert_eq!(poll_at(1500, "steam", &["code", "steam"]), (Some((brk, 0)), false));
+        assert_eq!(poll_at(1700, "code", &["code", "steam"]), (Some((brk, 0)), false));
+        assert_eq!(poll_at(1750, "steam", &["code", "steam"]), (Some((brk, 0)), false));
+        assert_eq!(poll_at(1800, "code", &["code"]), (Some((work, 1)), true));
+        assert_eq!(poll_at(3300, "code", &[]), (None, false));
+
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            vec![
+                (work, 0),
+                (brk, 0),
+                (work, 1),
+                (PomodoroPhase::Finished, 2)
+            ]
+        );
+        assert_eq!(popups.load(Ordering::SeqCst), 0);
+
+        // Two separate sessions covering only the work phases
+        let mut stmt = mgr
+            .db_handle()
+            .conn
+            .prepare("SELECT start_time, end_time FROM focus_sessions ORDER BY start_time")
+            .unwrap();
+        let rows: Vec<(i64, Option<i64>)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (1_700_000_000, Some(1_700_001_500)),
+                (1_700_001_800, Some(1_700_003_300))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_work_apps_are_capped_by_focus_time() {
+        let apps: Vec<String> = (0..300).map(|i| format!("app{}", i)).collect();
+        let rules = AppRules::test_with_rules(apps.clone(), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        mgr.set_max_work_apps(50);
+
+        mgr.poll_snapshot(Some("app7".to_string()), apps.clone())
+            .unwrap();
+        *time.lock().unwrap() = start + Duration::from_secs(100);
+        mgr.poll_snapshot(Some("app242".to_string()), apps.clone())
+            .unwrap();
+        *time.lock().unwrap() = start + Duration::from_secs(150);
+        let session = mgr.end_active_session().unwrap().unwrap();
+
+        assert_eq!(session.work_apps.len(), 50);
+        assert_eq!(session.work_apps[..3], ["app7", "app242", "app0"]);
+        let (stored, truncated): (String, i64) = mgr
+            .db_handle()
+            .conn
+            .query_row(
+                "SELECT work_apps, work_apps_truncated FROM focus_sessions WHERE id = ?1",
+                [session.id.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(stored.split(',').count(), 50);
+        assert!(stored.starts_with("app7,app242,app0,"));
+        assert_eq!(truncated, 250);
+    }
+
+    /// Runs a 10-minute session with `code` in the foreground and `docker` only in the
+    /// background, and returns the stored work apps.
+    fn run_background_work_app(min_focus_pct: u8) -> String {
+        let rules = AppRules::test_with_rules(names(&["code", "docker"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+        mgr.set_min_work_app_focus_pct(min_focus_pct);
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code", "docker"]))
+            .unwrap();
+        *time.lock().unwrap() = start + Duration::from_secs(600);
+        let session = mgr.end_active_session().unwrap().unwrap();
+        let stored: String = mgr
+            .db_handle()
+            .conn
+            .query_row(
+                "SELECT work_apps FROM focus_sessions WHERE id = ?1",
+                [session.id.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, session.work_apps.join(","));
+        stored
+    }
+
+    #[test]
+    fn test_background_work_app_is_pruned() {
+        assert_eq!(run_background_work_app(10), "code");
+    }
+
+    #[test]
+    fn test_background_work_app_is_kept_without_minimum() {
+        assert_eq!(run_background_work_app(0), "code,docker");
+    }
+
+    #[test]
+    fn test_end_session_flushes_inflight_app() {
+        let rules = AppRules::test_with_rules(names(&["code"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        let session_id = mgr.current_session().unwrap().id;
+        // The app has been in focus for a while without any switch
+        mgr.last_app_start = Some(SystemTime::now() - Duration::from_secs(30));
+
+        mgr.end_active_session().unwrap();
+        let events = mgr
+            .db_handle()
+            .get_app_usage_events_for_session(session_id)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].process_name, "code");
+        assert!(events[0].duration_secs >= 30);
+
+        // Tracking resumes from the flush, so persisting it doesn't double count
+        mgr.save_inflight_app().unwrap();
+        let (app, start, _) = mgr.db_handle().take_inflight_app().unwrap().unwrap();
+        assert_eq!(app, "code");
+        assert!(start >= events[0].end_time);
+    }
+
+    #[test]
+    fn test_self_process_is_not_tracked() {
+        let rules = AppRules::test_with_rules(names(&["code"]), names(&["synapse.exe"]));
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.on_distraction = Some(Box::new(|_: &str| panic!("self flagged as distraction")));
+        mgr.set_self_process_name(Some("Synapse.exe".to_string()));
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        let session_id = mgr.current_session().unwrap().id;
+        *time.lock().unwrap() = start + Duration::from_secs(60);
+        mgr.poll_snapshot(Some("synapse.exe".to_string()), names(&["code", "synapse.exe"]))
+            .unwrap();
+        assert!(mgr.last_checked_process().is_none());
+        *time.lock().unwrap() = start + Duration::from_secs(90);
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+        assert_eq!(mgr.current_session_distractions(), 0);
+        mgr.end_active_session().unwrap();
+
+        let events = mgr
+            .db_handle()
+            .get_app_usage_events_for_session(session_id)
+            .unwrap();
+        assert!(events.iter().all(|e| e.process_name == "code"));
+        let first = events.iter().find(|e| e.start_time == 1_700_000_000).unwrap();
+        assert_eq!(first.duration_secs, 60);
+    }
+
+    /// Collects the fields of every `tracing` event, formatted with `Debug`.
+    #[derive(Clone, Default)]
+    struct CapturedEvents(Arc<std::sync::Mutex<Vec<HashMap<String, String>>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for CapturedEvents {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            struct Fields<'a>(&'a mut HashMap<String, String>);
+            impl tracing::field::Visit for Fields<'_> {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    self.0.insert(field.name().to_string(), format!("{:?}", value));
+                }
+            }
+            let mut fields = HashMap::new();
+            event.record(&mut Fields(&mut fields));
+            self.0.lock().unwrap().push(fields);
+        }
+    }
+
+    #[test]
+    fn test_session_start_emits_tracing_event() {
+        use tracing_subscriber::layer::SubscriberExt;
+        let captured = CapturedEvents::default();
+        let subscriber = tracing_subscriber::registry().with(captured.clone());
+        let mut mgr = setup_manager_with_rules(AppRules::test_with_rules(names(&["code"]), vec![]));
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, _) = manual_clock(start);
+        mgr.set_clock(clock);
+
+        tracing::subscriber::with_default(subscriber, || {
+            mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+                .unwrap();
+        });
+
+        let session_id = mgr.current_session().unwrap().id.to_string();
+        let events = captured.0.lock().unwrap();
+        let started = events
+            .iter()
+            .find(|e| e.get("message").map(String::as_str) == Some("session started"))
+            .expect("session start event");
+        assert_eq!(started["session_id"], session_id);
+        assert_eq!(started["start_time"], "1700000000");
+        assert_eq!(started["work_apps"], r#"["code"]"#);
+    }
+
+    #[test]
+    fn test_observed_processes_mark_rules_as_used() {
+        let rules = AppRules::test_with_rules(names(&["code", "vim"]), names(&["steam", "discord"]));
+        let mut mgr = SessionManager::new(
+            rules.clone(),
+            DbHandle::test_in_memory_with_schema(),
+            None,
+            Some(Box::new(|_: &str| {})),
+        );
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let (clock, time) = manual_clock(start);
+        mgr.set_clock(clock);
+
+        mgr.poll_snapshot(Some("Steam".to_string()), names(&["code", "steam"]))
+            .unwrap();
+        *time.lock().unwrap() = start + Duration::from_secs(10);
+        mgr.poll_snapshot(Some("code".to_string()), names(&["code"]))
+            .unwrap();
+
+        let unused = crate::api::unused_rules(mgr.db_handle(), &rules, 1_700_000_000).unwrap();
+        assert_eq!(unused, names(&["vim", "discord"]));
+        // Repeated matches within the interval are not written again
+        let count: i64 = mgr
+            .db_handle()
+            .conn
+            .query_row(
+                "SELECT match_count FROM rule_matches WHERE rule = 'code'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
     #[test]
     fn test_distraction_attempts_increment() {
         let mut mgr = setup_manager();
@@ -686,6 +3892,7 @@ mod tests {
             end_time: None,
             work_apps: vec!["notepad.exe".to_string()],
             distraction_attempts: 0,
+            project: None,
         });
         if let Some(session) = mgr.current_session.as_mut() {
             session.distraction_attempts += 1;
@@ -696,6 +3903,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_current_session_distractions() {
+        let mut mgr = setup_manager();
+        assert_eq!(mgr.current_session_distractions(), 0);
+        let mut session = FocusSession::new(SystemTime::now(), vec!["notepad.exe".to_string()]);
+        for _ in 0..3 {
+            session.increment_distraction_attempts();
+        }
+        mgr.set_current_session(session);
+        assert_eq!(mgr.current_session_distractions(), 3);
+    }
+
+    #[test]
+    fn session_duration_runs_to_end_or_now() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut session = FocusSession::new(start, vec!["code".to_string()]);
+
+        // Active: measured up to `now`
+        assert_eq!(session.duration(start + Duration::from_secs(90)), Duration::from_secs(90));
+        // A clock behind the start clamps to zero
+        assert_eq!(session.duration(start - Duration::from_secs(5)), Duration::ZERO);
+
+        // Ended: `now` no longer matters
+        session.end_time = Some(start + Duration::from_secs(1500));
+        assert_eq!(session.duration(start + Duration::from_secs(9000)), Duration::from_secs(1500));
+        session.end_time = Some(start - Duration::from_secs(1));
+        assert_eq!(session.duration(start), Duration::ZERO);
+    }
+
     #[test]
     fn test_end_active_session_no_session() {
         let mut mgr = setup_manager();
@@ -703,6 +3939,127 @@ mod tests {
         assert!(mgr.end_active_session().is_ok());
     }
 
+    #[test]
+    fn test_aliased_names_collapse_in_logging_and_metrics() {
+        let mut aliases = HashMap::new();
+        aliases.insert("code.exe".to_string(), "vscode".to_string());
+        aliases.insert("code - insiders".to_string(), "vscode".to_string());
+        let mut mgr = setup_manager();
+        mgr.set_apprules(AppRules::test_with_aliases(
+            vec!["vscode".to_string()],
+            vec![],
+            aliases,
+        ));
+        let mut metrics = crate::metrics::Metrics::new();
+        for raw in ["Code.exe", "Code - Insiders", "vscode"] {
+            mgr.handle_foreground_process(raw.to_string(), &[], false)
+                .unwrap();
+            assert_eq!(mgr.last_checked_process().map(String::as_str), Some("vscode"));
+            metrics.update_from_session(&mgr);
+        }
+        assert_eq!(metrics.app_frequency.len(), 1);
+        assert_eq!(*metrics.app_frequency.get("vscode").unwrap(), 3);
+        assert_eq!(mgr.last_app.as_deref(), Some("vscode"));
+    }
+
+    #[test]
+    fn test_inflight_app_bridges_restart() {
+        let path = std::env::temp_dir().join(format!("synapse_inflight_{}.db", Uuid::new_v4()));
+        let path_str = path.to_str().unwrap().to_string();
+        let rules = AppRules::test_with_rules(vec!["code.exe".to_string()], vec![]);
+        let focus_start = SystemTime::now() - Duration::from_secs(120);
+        {
+            let mut mgr = SessionManager::new(rules.clone(), DbHandle::open(&path_str).unwrap(), None, None);
+            mgr.last_app = Some("code.exe".to_string());
+            mgr.last_app_start = Some(focus_start);
+            mgr.save_inflight_app().unwrap();
+        }
+
+        // Simulated restart: a fresh manager over the same database
+        let mut mgr = SessionManager::new(rules, DbHandle::open(&path_str).unwrap(), None, None);
+        assert!(mgr.restore_inflight_app().unwrap());
+        assert_eq!(mgr.last_app.as_deref(), Some("code.exe"));
+        let session = FocusSession::new(SystemTime::now(), vec!["code.exe".to_string()]);
+        let session_id = session.id;
+        mgr.db_handle()
+            .execute_sql(
+                "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES (?1, ?2, NULL, ?3, 0)",
+                &[&session_id.to_string(), &0, &"code.exe"],
+            )
+            .unwrap();
+        mgr.set_current_session(session);
+
+        // Switching away closes the restored interval with its pre-restart start time
+        mgr.update_app_focus_duration("other.exe").unwrap();
+        let events = mgr
+            .db_handle()
+            .get_app_usage_events_for_session(session_id)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].process_name, "code.exe");
+        assert!(events[0].duration_secs >= 120);
+        assert!(!mgr.restore_inflight_app().unwrap());
+        drop(mgr);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_usage_aggregates_by_executable_across_display_names() {
+        let path = std::env::temp_dir().join(format!("synapse_usage_key_{}.db", Uuid::new_v4()));
+        let path_str = path.to_str().unwrap().to_string();
+        let rules = AppRules::test_with_rules(names(&["code.exe"]), vec![]);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        // Each run sees the editor under a different display name and path
+        let runs = [
+            ("Visual Studio Code", "Code.exe", 0),
+            ("VS Code", "C:\\Tools\\code.exe", 1000),
+        ];
+        for (display, reported, offset) in runs {
+            let mut mgr =
+                SessionManager::new(rules.clone(), DbHandle::open(&path_str).unwrap(), None, None);
+            let (clock, time) = manual_clock(start + Duration::from_secs(offset));
+            mgr.set_clock(clock);
+            mgr.register_installed_apps(vec![(display.to_string(), reported.to_string())])
+                .unwrap();
+            mgr.poll_snapshot(Some(reported.to_string()), names(&["code.exe"]))
+                .unwrap();
+            *time.lock().unwrap() += Duration::from_secs(300);
+            mgr.end_active_session().unwrap();
+        }
+
+        let db = DbHandle::open(&path_str).unwrap();
+        let usage = crate::api::app_usage_between(&db, 0, i64::MAX).unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].app_key, "code.exe");
+        assert_eq!(usage[0].display_name, "VS Code");
+        assert_eq!((usage[0].focus_secs, usage[0].events), (600, 2));
+        drop(db);
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// Focuses `reported` for five minutes in a session and returns the stored names.
+    fn stored_raw_names(store: bool, reported: &str) -> Vec<(String, String)> {
+        let rules = AppRules::test_with_rules(names(&["code.exe"]), vec![]);
+        let mut mgr = setup_manager_with_rules(rules);
+        mgr.set_store_raw_process_names(store);
+        let (clock, time) = manual_clock(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        mgr.set_clock(clock);
+        mgr.poll_snapshot(Some(reported.to_string()), names(&["code.exe"]))
+            .unwrap();
+        *time.lock().unwrap() += Duration::from_secs(300);
+        mgr.end_active_session().unwrap();
+        mgr.db_handle().raw_process_names_between(0, i64::MAX).unwrap()
+    }
+
+    #[test]
+    fn test_usage_events_store_raw_and_resolved_names() {
+        assert_eq!(
+            stored_raw_names(true, "C:\\Tools\\Code.exe"),
+            vec![("code.exe".to_string(), "C:\\Tools\\Code.exe".to_string())]
+        );
+        assert!(stored_raw_names(false, "C:\\Tools\\Code.exe").is_empty());
+    }
+
     #[test]
     fn test_focus_session_clone_and_debug() {
         let now = SystemTime::now();
@@ -712,6 +4069,7 @@ mod tests {
             end_time: Some(now + Duration::from_secs(3600)),
             work_apps: vec!["notepad.exe".to_string(), "word.exe".to_string()],
             distraction_attempts: 2,
+            project: None,
         };
         let session2 = session.clone();
         assert_eq!(session.work_apps, session2.work_apps);
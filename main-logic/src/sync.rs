@@ -4,10 +4,25 @@ use serde_json;
 use dotenvy::dotenv;
 use std::env;
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::collections::HashMap;
-use crate::error::SupabaseError;
+use std::future::Future;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use crate::config::{SyncField, SyncFields};
+use crate::constants::{
+    DEFAULT_SYNC_FAILURE_THRESHOLD, DEFAULT_SYNC_MAX_CONCURRENCY, SYNC_PAUSE_BASE_SECS,
+    SYNC_PAUSE_MAX_SECS,
+};
+use crate::db::DbHandle;
+use crate::error::{SupabaseError, SynapseError};
 use crate::types::AppUsageEvent;
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Deserializer};
+use uuid::Uuid;
+
+/// `AppUsageEvent` fields stored locally but not in the remote `app_usage_events` table.
+const LOCAL_ONLY_EVENT_COLUMNS: [&str; 2] = ["matched_rule", "reason"];
 
 /// Supabase sync client module
 #[derive(Clone)]
@@ -15,6 +30,10 @@ pub struct SupabaseSync {
     pub client: Client,
     pub api_key: String,
     pub base_url: String,
+    /// Shared between clones; bounds how many background syncs run at once.
+    permits: Arc<Semaphore>,
+    /// Optional data included in uploads.
+    fields: SyncFields,
 }
 
 impl SupabaseSync {
@@ -23,7 +42,53 @@ impl SupabaseSync {
             client: Client::new(),
             api_key,
             base_url,
+            permits: Arc::new(Semaphore::new(DEFAULT_SYNC_MAX_CONCURRENCY)),
+            fields: SyncFields::default(),
+        }
+    }
+
+    /// Uploads only the optional data in `fields`; session ids and timing are always sent.
+    pub fn with_fields(mut self, fields: SyncFields) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Serializes `session` for upload, leaving out fields excluded by `sync_fields`.
+    fn session_payload(&self, session: &FocusSession) -> serde_json::Value {
+        let mut payload = serde_json::to_value(session).unwrap_or_default();
+        if let Some(object) = payload.as_object_mut() {
+            for (field, key) in [
+                (SyncField::WorkApps, "work_apps"),
+                (SyncField::DistractionAttempts, "distraction_attempts"),
+                (SyncField::Project, "project"),
+            ] {
+                if !self.fields.includes(field) {
+                    object.remove(key);
+                }
+            }
         }
+        payload
+    }
+
+    /// Limits background syncs started with [`spawn_limited`](Self::spawn_limited) to
+    /// `max_concurrent` at a time (at least 1).
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.permits = Arc::new(Semaphore::new(max_concurrent.max(1)));
+        self
+    }
+
+    /// Spawns a background sync task that waits for a free permit before running `task`,
+    /// so bursts of syncs are queued rather than all sent at once.
+    pub fn spawn_limited<F>(&self, task: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let permits = self.permits.clone();
+        tokio::spawn(async move {
+            // The semaphore is never closed, so acquiring only waits for a free permit
+            let _permit = permits.acquire_owned().await;
+            task.await;
+        })
     }
 
     /// Initialize SupabaseSync from environment variables (.env)
@@ -42,7 +107,7 @@ impl SupabaseSync {
         let resp = self.client.post(&url)
             .header("apikey", &self.api_key)
             .header("Content-Type", "application/json")
-            .json(session)
+            .json(&self.session_payload(session))
             .send()
             .await?;
         if resp.status().is_success() {
@@ -54,16 +119,35 @@ impl SupabaseSync {
         }
     }
 
+    /// Serializes `events` for upload, leaving out the columns that only exist locally;
+    /// the remote `app_usage_events` table has none of them and PostgREST would reject the
+    /// whole batch.
+    fn events_payload(events: &[AppUsageEvent]) -> serde_json::Value {
+        let mut payload = serde_json::to_value(events).unwrap_or_default();
+        if let Some(rows) = payload.as_array_mut() {
+            for object in rows.iter_mut().filter_map(|row| row.as_object_mut()) {
+                for key in LOCAL_ONLY_EVENT_COLUMNS {
+                    object.remove(key);
+                }
+            }
+        }
+        payload
+    }
+
     pub async fn push_app_usage_events(&self, events: &[AppUsageEvent]) -> Result<(), SupabaseError> {
+        let payload = Self::events_payload(events);
         // Debug: print the events being sent
-        println!("[DEBUG] Sending app_usage_events to Supabase: {}", serde_json::to_string_pretty(&events).unwrap_or_else(|_| "<serialization error>".to_string()));
-        let url = format!("{}/app_usage_events", self.base_url.trim_end_matches('/'));
+        println!("[DEBUG] Sending app_usage_events to Supabase: {}", serde_json::to_string_pretty(&payload).unwrap_or_else(|_| "<serialization error>".to_string()));
+        // Upsert on id, so pushing the same events again after a partial failure is a no-op
+        let url = format!("{}/app_usage_events?on_conflict=id", self.base_url.trim_end_matches('/'));
         let resp = self.client.post(&url)
             .header("apikey", &self.api_key)
             .header("Content-Type", "application/json")
-            .json(events)
+            .header("Prefer", "resolution=ignore-duplicates")
+            .json(&payload)
             .send()
             .await?;
+        tracing::info!(count = events.len(), status = resp.status().as_u16(), "supabase app usage push");
         if resp.status().is_success() {
             Ok(())
         } else {
@@ -72,6 +156,37 @@ impl SupabaseSync {
             Err(SupabaseError::Api(format!("Supabase sync failed: {} - {}", status, body)))
         }
     }
+
+    /// Checks that the URL is valid, the server is reachable and the API key is accepted,
+    /// by reading at most one row of `focus_sessions`.
+    ///
+    /// # Errors
+    /// Returns `SupabaseError::Config` for a malformed URL, `Unreachable` if no response
+    /// arrives, `Unauthorized` if the key is rejected and `Api` for any other failure.
+    pub async fn health_check(&self) -> Result<(), SupabaseError> {
+        let base = reqwest::Url::parse(&self.base_url)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or_else(|| {
+                SupabaseError::Config(format!("invalid Supabase URL: '{}'", self.base_url))
+            })?;
+        let url = format!("{}/focus_sessions?select=id&limit=1", base.as_str().trim_end_matches('/'));
+        let resp = self.client.get(&url)
+            .header("apikey", &self.api_key)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| SupabaseError::Unreachable(format!("could not reach {}: {}", self.base_url, e)))?;
+        let status = resp.status();
+        if status.is_success() {
+            Ok(())
+        } else if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            Err(SupabaseError::Unauthorized(format!("Supabase rejected the API key ({})", status)))
+        } else {
+            let body = resp.text().await.unwrap_or_default();
+            Err(SupabaseError::Api(format!("Supabase health check failed: {} - {}", status, body)))
+        }
+    }
 }
 
 /// Tracks the status of the last sync attempt
@@ -80,6 +195,14 @@ pub struct SyncStatus {
     pub last_sync_time: Option<SystemTime>,
     pub last_result: Option<bool>, // true = success, false = failure
     pub last_error: Option<String>,
+    /// Failed attempts since the last success.
+    pub consecutive_failures: u32,
+    /// Consecutive failures that pause syncing; `0` never pauses.
+    pub failure_threshold: u32,
+    /// Pauses since syncing last recovered; each one doubles the next pause.
+    pub pauses: u32,
+    /// Syncing is paused until this time, then resumes once a health check passes.
+    pub paused_until: Option<SystemTime>,
 }
 
 impl SyncStatus {
@@ -88,13 +211,65 @@ impl SyncStatus {
             last_sync_time: None,
             last_result: None,
             last_error: None,
+            consecutive_failures: 0,
+            failure_threshold: DEFAULT_SYNC_FAILURE_THRESHOLD,
+            pauses: 0,
+            paused_until: None,
         }
     }
 
+    /// Pauses syncing after `threshold` consecutive failures (`0` never pauses).
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold;
+        self
+    }
+
     pub fn update(&mut self, success: bool, error: Option<String>) {
-        self.last_sync_time = Some(SystemTime::now());
+        let now = SystemTime::now();
+        self.last_sync_time = Some(now);
         self.last_result = Some(success);
         self.last_error = error;
+        if success {
+            self.consecutive_failures = 0;
+            return;
+        }
+        self.consecutive_failures += 1;
+        let tripped = self.failure_threshold > 0
+            && self.consecutive_failures >= self.failure_threshold;
+        if tripped && self.paused_until.is_none() {
+            self.pause(now);
+        }
+    }
+
+    /// Pauses syncing from `now` for a cooldown that doubles with every pause since the
+    /// last recovery, capped at `SYNC_PAUSE_MAX_SECS`.
+    pub fn pause(&mut self, now: SystemTime) {
+        let secs = SYNC_PAUSE_BASE_SECS
+            .saturating_mul(1u64 << self.pauses.min(32))
+            .min(SYNC_PAUSE_MAX_SECS);
+        self.paused_until = Some(now + Duration::from_secs(secs));
+        self.pauses += 1;
+        eprintln!(
+            "[Supabase] {} consecutive sync failures, pausing sync for {}s",
+            self.consecutive_failures, secs
+        );
+    }
+
+    /// Whether sync attempts are paused at `now`.
+    pub fn is_paused(&self, now: SystemTime) -> bool {
+        self.paused_until.is_some_and(|until| now < until)
+    }
+
+    /// Whether a pause has run out and a health check must pass before syncing resumes.
+    pub fn needs_probe(&self, now: SystemTime) -> bool {
+        self.paused_until.is_some_and(|until| now >= until)
+    }
+
+    /// Resumes syncing after a passing health check and resets the pause backoff.
+    pub fn resume(&mut self) {
+        self.consecutive_failures = 0;
+        self.pauses = 0;
+        self.paused_until = None;
     }
 }
 
@@ -108,7 +283,7 @@ impl SupabaseSync {
         let resp = self.client.post(&url)
             .header("apikey", &self.api_key)
             .header("Content-Type", "application/json")
-            .json(session)
+            .json(&self.session_payload(session))
             .send()
             .await;
         match resp {
@@ -149,8 +324,8 @@ impl SupabaseSync {
             .send()
             .await?;
         if resp.status().is_success() {
-            let sessions: Vec<FocusSession> = resp.json().await?;
-            Ok(sessions)
+            let rows: Vec<RemoteFocusSession> = resp.json().await?;
+            Ok(rows.into_iter().map(FocusSession::from).collect())
         } else {
             let status = resp.status();
             let body = resp.text().await.unwrap_or_default();
@@ -163,7 +338,7 @@ impl SupabaseSync {
         let url = format!("{}/focus_sessions", self.base_url.trim_end_matches('/'));
         let mut session_clone = session.clone();
         session_clone.end_time = None;
-        let payload = serde_json::to_string(&session_clone).unwrap();
+        let payload = self.session_payload(&session_clone).to_string();
         println!("[Supabase][insert_focus_session] URL: {}", url);
         println!("[Supabase][insert_focus_session] Payload: {}", payload);
         let resp = self.client.post(&url)
@@ -176,6 +351,7 @@ impl SupabaseSync {
         let body = resp.text().await.unwrap_or_default();
         println!("[Supabase][insert_focus_session] Response status: {}", status);
         println!("[Supabase][insert_focus_session] Response body: {}", body);
+        tracing::info!(session_id = %session.id, status = status.as_u16(), "supabase session insert");
         if status.is_success() {
             println!("[Supabase][insert_focus_session] Insert successful");
             Ok(())
@@ -191,11 +367,13 @@ impl SupabaseSync {
         let url = format!("{}/focus_sessions?id=eq.{}", self.base_url.trim_end_matches('/'), session.id);
         let end_time_val = session.end_time.map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs());
         println!("[Supabase][update_focus_session][DEBUG] end_time (as Option<u64>): {:?}", end_time_val);
-        let patch = serde_json::json!({
-            "end_time": end_time_val,
-            "work_apps": session.work_apps,
-            "distraction_attempts": session.distraction_attempts,
-        });
+        let mut patch = serde_json::json!({ "end_time": end_time_val });
+        if self.fields.includes(SyncField::WorkApps) {
+            patch["work_apps"] = serde_json::json!(session.work_apps);
+        }
+        if self.fields.includes(SyncField::DistractionAttempts) {
+            patch["distraction_attempts"] = serde_json::json!(session.distraction_attempts);
+        }
         println!("[Supabase][update_focus_session][DEBUG] Patch object: {}", patch);
         let payload = serde_json::to_string(&patch).unwrap();
         println!("[Supabase][update_focus_session] URL: {}", url);
@@ -212,6 +390,7 @@ impl SupabaseSync {
         println!("[Supabase][update_focus_session] Response status: {}", status);
         println!("[Supabase][update_focus_session] Response headers: {}", headers);
         println!("[Supabase][update_focus_session] Response body: {}", body);
+        tracing::info!(session_id = %session.id, status = status.as_u16(), "supabase session update");
         if status.is_success() {
             println!("[Supabase][update_focus_session] Update successful");
             Ok(())
@@ -220,6 +399,282 @@ impl SupabaseSync {
             Err(SupabaseError::Api(format!("Supabase update failed: {} - {}", status, body)))
         }
     }
+
+    /// Pushes an ended session's app usage events, then its final state, marking each
+    /// synced in `db` once its push succeeds.
+    ///
+    /// If the events fail the session update is skipped, so the remote session is never
+    /// finalized without its events. Either push failing queues the session in `db`, and
+    /// [`retry_session_updates`](Self::retry_session_updates) pushes whatever is still
+    /// pending; the events push is an upsert, so events that already landed aren't duplicated.
+    /// When `app_usage_events` is excluded from `sync_fields` the events are not uploaded,
+    /// but are still marked synced so they aren't retried.
+    ///
+    /// # Errors
+    /// Returns the first push's `SupabaseError`, which is also recorded in the session's sync
+    /// failure history.
+    pub async fn sync_ended_session(
+        &self,
+        db: &DbHandle,
+        session: &FocusSession,
+        events: &[AppUsageEvent],
+    ) -> Result<(), SupabaseError> {
+        if !events.is_empty() && self.fields.includes(SyncField::AppUsageEvents) {
+            if let Err(e) = self.push_app_usage_events(events).await {
+                if let Err(db_err) = db.queue_session_update(session.id, &e.to_string(), now_secs()) {
+                    eprintln!("[Supabase] Failed to queue session {} for retry: {}", session.id, db_err);
+                }
+                return Err(e);
+            }
+        }
+        let event_ids: Vec<uuid::Uuid> = events.iter().map(|event| event.id).collect();
+        // The events landed; a failure here only leaves them to be pushed again
+        if let Err(e) = db.mark_events_synced(&event_ids) {
+            eprintln!("[Supabase] Failed to mark events of session {} synced: {}", session.id, e);
+        }
+        self.push_session_update(db, session).await
+    }
+
+    /// Retries the ended sessions queued in `db` that are due at `now` (see
+    /// [`DbHandle::due_session_updates`]), oldest first, and returns how many went through.
+    /// Each is pushed through
+    /// [`sync_ended_session`](Self::sync_ended_session) with its events not yet synced, so a
+    /// session whose events failed gets them uploaded before its update. Stops at the first
+    /// failure, which stays queued.
+    ///
+    /// # Errors
+    /// Returns `SynapseError` if the queue can't be read or a push fails again.
+    pub async fn retry_session_updates(&self, db: &DbHandle, now: i64) -> Result<usize, SynapseError> {
+        let pending = db.due_session_updates(now)?;
+        let mut retried = 0;
+        for session in &pending {
+            let events = db.get_unsynced_app_usage_events_for_session(session.id)?;
+            self.sync_ended_session(db, session, &events).await?;
+            retried += 1;
+        }
+        Ok(retried)
+    }
+
+    /// Pushes `session`'s final state, marking it synced on success and queueing it for a
+    /// retry on failure.
+    async fn push_session_update(&self, db: &DbHandle, session: &FocusSession) -> Result<(), SupabaseError> {
+        if let Err(e) = self.update_focus_session(session).await {
+            if let Err(db_err) = db.queue_session_update(session.id, &e.to_string(), now_secs()) {
+                eprintln!("[Supabase] Failed to queue update for session {}: {}", session.id, db_err);
+            }
+            return Err(e);
+        }
+        // The update landed; a failure here only leaves it to be pushed again
+        if let Err(e) = db.mark_session_synced(session.id) {
+            eprintln!("[Supabase] Failed to mark session {} synced: {}", session.id, e);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Retries queued session updates through [`SupabaseSync::retry_session_updates`] unless
+/// `status` has syncing paused, recording the outcome in `status` when anything was tried.
+/// Once a pause runs out, a health check must pass first, as in [`sync_allowed`].
+///
+/// # Errors
+/// Returns `SynapseError` if an update fails again; it stays queued.
+pub async fn retry_queued_session_updates(
+    db: &DbHandle,
+    sync: &SupabaseSync,
+    status: Option<&SharedSyncStatus>,
+) -> Result<usize, SynapseError> {
+    if !sync_allowed(sync, status).await? {
+        return Ok(0);
+    }
+    let retried = sync.retry_session_updates(db, now_secs()).await;
+    if let Some(shared) = status {
+        if !matches!(retried, Ok(0)) {
+            let mut s = shared.lock().unwrap();
+            s.update(retried.is_ok(), retried.as_ref().err().map(|e| e.to_string()));
+        }
+    }
+    if let Ok(count @ 1..) = &retried {
+        println!("[Supabase] Retried {} queued session update(s)", count);
+    }
+    retried
+}
+
+/// What [`finalize_and_sync_session`] did with an ended session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// The session and its events were pushed.
+    Synced,
+    /// Syncing is paused; the session was queued for the retry after syncing resumes.
+    Deferred,
+}
+
+/// Whether a push may go ahead under `status`: `false` while syncing is paused. Once the
+/// pause runs out a health check must pass, which resumes syncing; a failing check pauses
+/// syncing again for longer and is returned as the error.
+async fn sync_allowed(
+    sync: &SupabaseSync,
+    status: Option<&SharedSyncStatus>,
+) -> Result<bool, SynapseError> {
+    let Some(shared) = status else {
+        return Ok(true);
+    };
+    let now = SystemTime::now();
+    let (paused, needs_probe) = {
+        let s = shared.lock().unwrap();
+        (s.is_paused(now), s.needs_probe(now))
+    };
+    if paused {
+        return Ok(false);
+    }
+    if needs_probe {
+        if let Err(e) = sync.health_check().await {
+            shared.lock().unwrap().pause(SystemTime::now());
+            return Err(e.into());
+        }
+        shared.lock().unwrap().resume();
+        println!("[Supabase] Health check passed, resuming sync");
+    }
+    Ok(true)
+}
+
+/// Pushes a session that just ended, with its app usage events, through
+/// [`SupabaseSync::sync_ended_session`] and records the outcome in `status`.
+///
+/// This is the end-of-session path shared by the binary and the library's main loops,
+/// both after a poll ends a session and on shutdown.
+///
+/// While `status` has syncing paused nothing is pushed: the session is queued in the
+/// database, [`retry_queued_session_updates`] uploads it once syncing resumes, and
+/// `SyncOutcome::Deferred` is returned. Once the pause runs out, a health check must pass
+/// before the push is attempted; a failing check pauses syncing again for longer.
+///
+/// # Errors
+/// Returns `SynapseError` if the session's events can't be read, the health check fails or
+/// the push fails; the session and its events stay queued locally for a retry either way.
+pub async fn finalize_and_sync_session(
+    session: &FocusSession,
+    db_handle: &DbHandle,
+    sync: &SupabaseSync,
+    status: Option<&SharedSyncStatus>,
+) -> Result<SyncOutcome, SynapseError> {
+    tracing::debug!(session = ?session, "pushing ended session");
+    let allowed = sync_allowed(sync, status).await;
+    if !matches!(allowed, Ok(true)) {
+        db_handle.defer_session_sync(session.id, now_secs())?;
+        println!("[Supabase] Sync paused after repeated failures, session {} queued", session.id);
+        return allowed.map(|_| SyncOutcome::Deferred);
+    }
+    let synced = match db_handle.get_app_usage_events_for_session(session.id) {
+        Ok(events) => sync
+            .sync_ended_session(db_handle, session, &events)
+            .await
+            .map_err(SynapseError::from),
+        Err(e) => Err(e),
+    };
+    if let Some(shared) = status {
+        let mut s = shared.lock().unwrap();
+        s.update(synced.is_ok(), synced.as_ref().err().map(|e| e.to_string()));
+    }
+    if synced.is_ok() {
+        println!("[Supabase] Ended session and its app usage events synced");
+    }
+    synced.map(|()| SyncOutcome::Synced)
+}
+
+/// A `focus_sessions` row as returned by Supabase.
+///
+/// Kept apart from [`FocusSession`] so the wire format can differ from the domain type:
+/// timestamps may be epoch seconds or ISO 8601 strings, `work_apps` may be an array or a
+/// JSON-encoded text column, nullable columns may be `null`, and unknown columns are ignored.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RemoteFocusSession {
+    pub id: Uuid,
+    /// Start time, in seconds since the Unix epoch.
+    #[serde(deserialize_with = "epoch_secs")]
+    pub start_time: i64,
+    /// End time, in seconds since the Unix epoch; `None` while the session is running.
+    #[serde(default, deserialize_with = "optional_epoch_secs")]
+    pub end_time: Option<i64>,
+    #[serde(default, deserialize_with = "work_apps_column")]
+    pub work_apps: Vec<String>,
+    #[serde(default)]
+    pub distraction_attempts: Option<u32>,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+impl From<RemoteFocusSession> for FocusSession {
+    fn from(row: RemoteFocusSession) -> Self {
+        let to_time = |secs: i64| UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64);
+        FocusSession {
+            id: row.id,
+            start_time: to_time(row.start_time),
+            end_time: row.end_time.map(to_time),
+            work_apps: row.work_apps,
+            distraction_attempts: row.distraction_attempts.unwrap_or(0),
+            project: row.project,
+        }
+    }
+}
+
+/// A timestamp column: epoch seconds, or an ISO 8601 string with or without an offset.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RemoteTimestamp {
+    Epoch(i64),
+    Iso(String),
+}
+
+impl RemoteTimestamp {
+    fn epoch_secs(self) -> Result<i64, String> {
+        match self {
+            RemoteTimestamp::Epoch(secs) => Ok(secs),
+            RemoteTimestamp::Iso(text) => DateTime::parse_from_rfc3339(&text)
+                .map(|t| t.timestamp())
+                // Postgres `timestamp` columns carry no offset; they are stored as UTC
+                .or_else(|_| {
+                    NaiveDateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S%.f")
+                        .map(|t| t.and_utc().timestamp())
+                })
+                .map_err(|e| format!("invalid timestamp '{}': {}", text, e)),
+        }
+    }
+}
+
+fn epoch_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    RemoteTimestamp::deserialize(deserializer)?
+        .epoch_secs()
+        .map_err(serde::de::Error::custom)
+}
+
+fn optional_epoch_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    Option::<RemoteTimestamp>::deserialize(deserializer)?
+        .map(RemoteTimestamp::epoch_secs)
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+/// A `work_apps` column: a JSON array, a text column holding a JSON array, or `null`.
+fn work_apps_column<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum WorkApps {
+        List(Vec<String>),
+        Text(String),
+    }
+    match Option::<WorkApps>::deserialize(deserializer)? {
+        None => Ok(Vec::new()),
+        Some(WorkApps::List(apps)) => Ok(apps),
+        Some(WorkApps::Text(text)) => serde_json::from_str(&text).map_err(serde::de::Error::custom),
+    }
 }
 
 /// Merge local and remote sessions using last-write-wins on start_time.
@@ -258,6 +713,53 @@ mod tests {
     use super::*;
     use std::env;
 
+    #[test]
+    fn test_remote_focus_session_row_converts_to_focus_session() {
+        let payload = r#"[
+            {
+                "id": "6f1c2a7e-3b4d-4e5f-8a9b-0c1d2e3f4a5b",
+                "start_time": "2024-03-04T09:00:00+00:00",
+                "end_time": 1709546400,
+                "work_apps": "[\"code\",\"alacritty\"]",
+                "distraction_attempts": 3,
+                "project": "synapse",
+                "user_id": "0b0e4c6a-0000-4000-8000-000000000000",
+                "created_at": "2024-03-04T09:00:01.123456"
+            },
+            {
+                "id": "7a2d3b8f-4c5e-4f60-9bac-1d2e3f4a5b6c",
+                "start_time": "2024-03-04T11:30:00.5",
+                "end_time": null,
+                "work_apps": null,
+                "distraction_attempts": null,
+                "project": null
+            }
+        ]"#;
+        let rows: Vec<RemoteFocusSession> = serde_json::from_str(payload).unwrap();
+        assert_eq!(rows[0].start_time, 1_709_542_800);
+        assert_eq!(rows[1].start_time, 1_709_551_800);
+
+        let sessions: Vec<FocusSession> = rows.into_iter().map(FocusSession::from).collect();
+        let ended = &sessions[0];
+        assert_eq!(ended.id.to_string(), "6f1c2a7e-3b4d-4e5f-8a9b-0c1d2e3f4a5b");
+        assert_eq!(ended.start_time, UNIX_EPOCH + Duration::from_secs(1_709_542_800));
+        assert_eq!(ended.end_time, Some(UNIX_EPOCH + Duration::from_secs(1_709_546_400)));
+        assert_eq!(ended.work_apps, vec!["code", "alacritty"]);
+        assert_eq!(ended.distraction_attempts, 3);
+        assert_eq!(ended.project.as_deref(), Some("synapse"));
+
+        let running = &sessions[1];
+        assert!(running.end_time.is_none());
+        assert!(running.work_apps.is_empty());
+        assert_eq!(running.distraction_attempts, 0);
+        assert!(running.project.is_none());
+
+        assert!(serde_json::from_str::<RemoteFocusSession>(
+            r#"{"id": "6f1c2a7e-3b4d-4e5f-8a9b-0c1d2e3f4a5b", "start_time": "yesterday"}"#
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_from_env_missing_api_key_and_url() {
         use std::env;
@@ -380,4 +882,432 @@ mod tests {
         let synapse_result: Result<(), crate::error::SynapseError> = result.map_err(crate::error::SynapseError::from);
         assert!(matches!(synapse_result, Err(crate::error::SynapseError::Supabase(_))));
     }
+
+    /// A time by which every queued session is due for a retry.
+    fn retry_time() -> i64 {
+        now_secs() + crate::constants::SESSION_RETRY_MAX_SECS
+    }
+
+    /// Serves a single request with `response` and returns the server's base URL.
+    async fn serve_once(response: &'static str) -> String {
+        serve_in_order(vec![response]).await
+    }
+
+    /// Serves one request per entry of `responses`, in order, and returns the base URL.
+    async fn serve_in_order(responses: Vec<&'static str>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    /// Stores an ended session with one app usage event and returns them as read back.
+    fn ended_session_with_event(db: &DbHandle) -> (FocusSession, Vec<AppUsageEvent>) {
+        let start = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let mut session = FocusSession::new(start, vec!["code".to_string()]);
+        session.end_time = Some(start + std::time::Duration::from_secs(600));
+        db.execute_sql(
+            "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES (?1, ?2, ?3, ?4, 0)",
+            &[&session.id.to_string(), &1_700_000_000i64, &1_700_000_600i64, &"code"],
+        )
+        .unwrap();
+        db.insert_app_usage_event(
+            "code",
+            crate::types::UsageStatus::Allowed,
+            Some(session.id),
+            1_700_000_000,
+            1_700_000_600,
+            600,
+            None,
+            None,
+        )
+        .unwrap();
+        let events = db.get_app_usage_events_for_session(session.id).unwrap();
+        (session, events)
+    }
+
+    fn synced_flags(db: &DbHandle) -> (i64, i64) {
+        let session: i64 = db
+            .conn
+            .query_row("SELECT is_synced FROM focus_sessions", [], |row| row.get(0))
+            .unwrap();
+        let events: i64 = db
+            .conn
+            .query_row("SELECT MIN(is_synced) FROM app_usage_events", [], |row| row.get(0))
+            .unwrap();
+        (session, events)
+    }
+
+    #[tokio::test]
+    async fn test_failed_event_push_leaves_ended_session_pending() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (session, events) = ended_session_with_event(&db);
+        let url = serve_once("HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+
+        assert!(sync.sync_ended_session(&db, &session, &events).await.is_err());
+        assert_eq!(synced_flags(&db), (0, 0));
+        let failures: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM sync_failures", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(failures, 1);
+
+        // The whole session is queued, and a retry pushes its events before the update
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let url = serve_in_order(vec![ok, ok]).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        assert_eq!(sync.retry_session_updates(&db, retry_time()).await.unwrap(), 1);
+        assert_eq!(synced_flags(&db), (1, 1));
+    }
+
+    #[test]
+    fn test_local_only_event_columns_are_left_out_of_the_payload() {
+        let event = AppUsageEvent {
+            id: Uuid::new_v4(),
+            process_name: "steam".to_string(),
+            status: "blocked".to_string(),
+            session_id: None,
+            start_time: 100,
+            end_time: 110,
+            duration_secs: 10,
+            matched_rule: Some("steam".to_string()),
+            reason: Some("checking a download".to_string()),
+        };
+        let payload = SupabaseSync::events_payload(&[event]);
+        let row = payload[0].as_object().unwrap();
+        assert!(!row.contains_key("matched_rule"));
+        assert!(!row.contains_key("reason"));
+        assert_eq!(row["process_name"], "steam");
+        assert_eq!(row["status"], "blocked");
+    }
+
+    #[tokio::test]
+    async fn test_app_usage_events_push_is_an_upsert_on_id() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let read = socket.read(&mut buf).await.unwrap();
+            let _ = socket
+                .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await;
+            String::from_utf8_lossy(&buf[..read]).to_lowercase()
+        });
+        let db = DbHandle::test_in_memory_with_schema();
+        let (_, events) = ended_session_with_event(&db);
+        SupabaseSync::new("key".to_string(), url)
+            .push_app_usage_events(&events)
+            .await
+            .unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("post /app_usage_events?on_conflict=id "), "{}", request);
+        assert!(request.contains("prefer: resolution=ignore-duplicates"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn test_ended_session_and_events_marked_synced_together() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (session, events) = ended_session_with_event(&db);
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let url = serve_in_order(vec![ok, ok]).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+
+        sync.sync_ended_session(&db, &session, &events).await.unwrap();
+        assert_eq!(synced_flags(&db), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_failed_session_update_stays_queued_until_a_retry_succeeds() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (session, events) = ended_session_with_event(&db);
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let failed = "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let queued = |db: &DbHandle| -> Vec<(String, i64)> {
+            let mut stmt = db
+                .conn
+                .prepare("SELECT session_id, attempts FROM pending_session_updates")
+                .unwrap();
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        // The events land but the session update doesn't
+        let url = serve_in_order(vec![ok, failed]).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        assert!(sync.sync_ended_session(&db, &session, &events).await.is_err());
+        assert_eq!(synced_flags(&db), (0, 1));
+        assert_eq!(queued(&db), vec![(session.id.to_string(), 1)]);
+
+        // A failing retry keeps it queued
+        let url = serve_once(failed).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        assert!(sync.retry_session_updates(&db, retry_time()).await.is_err());
+        assert_eq!(synced_flags(&db), (0, 1));
+        assert_eq!(queued(&db), vec![(session.id.to_string(), 2)]);
+
+        // A successful retry clears the queue and marks the session synced
+        let url = serve_once(ok).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        assert_eq!(sync.retry_session_updates(&db, retry_time()).await.unwrap(), 1);
+        assert_eq!(synced_flags(&db), (1, 1));
+        assert!(queued(&db).is_empty());
+        assert_eq!(sync.retry_session_updates(&db, retry_time()).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_excluded_sync_fields_are_left_out_of_session_payload() {
+        let start = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let mut session = FocusSession::new(start, vec!["code".to_string()]);
+        session.end_time = Some(start + std::time::Duration::from_secs(600));
+        session.project = Some("synapse".to_string());
+        let sync = SupabaseSync::new("key".to_string(), "http://localhost".to_string());
+        let full = sync.session_payload(&session);
+        assert_eq!(full["work_apps"], serde_json::json!(["code"]));
+        assert_eq!(full["project"], "synapse");
+
+        let fields: SyncFields = "distraction_attempts".parse().unwrap();
+        let payload = sync.with_fields(fields).session_payload(&session);
+        let object = payload.as_object().unwrap();
+        assert!(!object.contains_key("work_apps"));
+        assert!(!object.contains_key("project"));
+        assert_eq!(object["distraction_attempts"], 0);
+        for key in ["id", "start_time", "end_time"] {
+            assert_eq!(object[key], full[key], "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ended_session_is_only_pushed_through_sync_ended_session() {
+        use crate::apprules::AppRules;
+        use crate::session::SessionManager;
+
+        // Nothing listens here, so any push made while ending the session would fail and queue it
+        let url = serve_in_order(vec![]).await;
+        let rules = AppRules::test_with_rules(vec!["code".to_string()], vec![]);
+        let db = DbHandle::test_in_memory_with_schema();
+        let sync = SupabaseSync::new("key".to_string(), url);
+        let mut mgr = SessionManager::new(rules, db, Some(sync), None);
+        mgr.poll_snapshot(Some("code".to_string()), vec!["code".to_string()])
+            .unwrap();
+        let session = mgr.end_active_session().unwrap().unwrap();
+        assert!(mgr.db_handle().due_session_updates(i64::MAX).unwrap().is_empty());
+
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let sync = SupabaseSync::new("key".to_string(), serve_in_order(vec![ok, ok]).await);
+        finalize_and_sync_session(&session, mgr.db_handle(), &sync, None).await.unwrap();
+        let synced: i64 = mgr
+            .db_handle()
+            .conn
+            .query_row("SELECT is_synced FROM focus_sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(synced, 1);
+    }
+
+    #[tokio::test]
+    async fn test_excluded_app_usage_events_are_not_pushed() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (session, events) = ended_session_with_event(&db);
+        // Only the session update is served; pushing the events would fail
+        let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+        let sync = SupabaseSync::new("key".to_string(), url).with_fields("none".parse().unwrap());
+
+        sync.sync_ended_session(&db, &session, &events).await.unwrap();
+        assert_eq!(synced_flags(&db), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_finalize_and_sync_session_updates_status_and_flags() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (session, _) = ended_session_with_event(&db);
+        let status: SharedSyncStatus = Arc::new(Mutex::new(SyncStatus::new()));
+
+        // A failing backend leaves everything pending and reports the error
+        let url = serve_once("HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        assert!(finalize_and_sync_session(&session, &db, &sync, Some(&status)).await.is_err());
+        assert_eq!(synced_flags(&db), (0, 0));
+        assert_eq!(status.lock().unwrap().last_result, Some(false));
+        assert!(status.lock().unwrap().last_error.is_some());
+
+        // Retrying against a healthy backend syncs the session and its events together
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let url = serve_in_order(vec![ok, ok]).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        finalize_and_sync_session(&session, &db, &sync, Some(&status)).await.unwrap();
+        assert_eq!(synced_flags(&db), (1, 1));
+        assert_eq!(status.lock().unwrap().last_result, Some(true));
+        assert_eq!(status.lock().unwrap().last_error, None);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_failures_pause_sync_until_health_check_passes() {
+        let db = DbHandle::test_in_memory_with_schema();
+        let (session, _) = ended_session_with_event(&db);
+        let status: SharedSyncStatus = Arc::new(Mutex::new(SyncStatus::new().with_failure_threshold(2)));
+        let failures = |db: &DbHandle| -> i64 {
+            db.conn
+                .query_row("SELECT COUNT(*) FROM sync_failures", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let error = "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let url = serve_in_order(vec![error, error]).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        for _ in 0..2 {
+            assert!(finalize_and_sync_session(&session, &db, &sync, Some(&status)).await.is_err());
+        }
+        assert!(status.lock().unwrap().is_paused(SystemTime::now()));
+        assert_eq!(failures(&db), 2);
+
+        // While paused nothing is attempted (the server above has no responses left), and a
+        // session ending meanwhile is queued rather than reported as synced
+        let (later, _) = ended_session_with_event(&db);
+        assert_eq!(
+            finalize_and_sync_session(&later, &db, &sync, Some(&status)).await.unwrap(),
+            SyncOutcome::Deferred
+        );
+        assert_eq!(failures(&db), 2);
+        let mut queued: Vec<Uuid> = db
+            .pending_session_updates()
+            .unwrap()
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        let mut expected = vec![session.id, later.id];
+        queued.sort();
+        expected.sort();
+        assert_eq!(queued, expected);
+
+        // Once the pause runs out a passing health check resumes syncing, and the retry
+        // uploads both sessions with their events once the first one's backoff has passed
+        status.lock().unwrap().paused_until = Some(SystemTime::now() - Duration::from_secs(1));
+        db.conn
+            .execute("UPDATE sync_failures SET failed_at = failed_at - 3600", [])
+            .unwrap();
+        let healthy = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]";
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let url = serve_in_order(vec![healthy, ok, ok, ok, ok]).await;
+        let sync = SupabaseSync::new("key".to_string(), url);
+        assert_eq!(retry_queued_session_updates(&db, &sync, Some(&status)).await.unwrap(), 2);
+        assert!(db.pending_session_updates().unwrap().is_empty());
+        let unsynced: i64 = db
+            .conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM focus_sessions WHERE is_synced = 0) + (SELECT COUNT(*) FROM app_usage_events WHERE is_synced = 0)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(unsynced, 0);
+        let status = status.lock().unwrap();
+        assert_eq!((status.paused_until, status.pauses, status.consecutive_failures), (None, 0, 0));
+    }
+
+    #[test]
+    fn test_sync_pauses_grow_and_are_capped() {
+        let now = SystemTime::now();
+        let mut status = SyncStatus::new();
+        let mut pauses = Vec::new();
+        for _ in 0..8 {
+            status.pause(now);
+            pauses.push(status.paused_until.unwrap().duration_since(now).unwrap().as_secs());
+        }
+        assert_eq!(pauses, vec![60, 120, 240, 480, 960, 1920, 3600, 3600]);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_passes_with_accepted_key() {
+        let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]").await;
+        let sync = SupabaseSync::new("good-key".to_string(), url);
+        assert!(sync.health_check().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_reports_rejected_key() {
+        let url = serve_once("HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+        let sync = SupabaseSync::new("bad-key".to_string(), url);
+        assert!(matches!(sync.health_check().await, Err(SupabaseError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_reports_bad_url_and_unreachable_server() {
+        let sync = SupabaseSync::new("key".to_string(), "not a url".to_string());
+        assert!(matches!(sync.health_check().await, Err(SupabaseError::Config(_))));
+
+        // Nothing listens on a port once its listener is dropped
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let sync = SupabaseSync::new("key".to_string(), format!("http://{}", addr));
+        assert!(matches!(sync.health_check().await, Err(SupabaseError::Unreachable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_limited_caps_concurrent_requests() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        // Mock server that holds each request open for a while and records the peak
+        // number of requests in flight.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let served = Arc::new(AtomicUsize::new(0));
+        {
+            let (in_flight, peak, served) = (in_flight.clone(), peak.clone(), served.clone());
+            tokio::spawn(async move {
+                loop {
+                    let (mut socket, _) = listener.accept().await.unwrap();
+                    let (in_flight, peak, served) = (in_flight.clone(), peak.clone(), served.clone());
+                    tokio::spawn(async move {
+                        let mut buf = [0u8; 4096];
+                        let _ = socket.read(&mut buf).await;
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        served.fetch_add(1, Ordering::SeqCst);
+                        let _ = socket.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+                    });
+                }
+            });
+        }
+
+        let sync = SupabaseSync::new("key".to_string(), format!("http://{}", addr)).with_max_concurrent(2);
+        let handles: Vec<_> = (0..6)
+            .map(|_| {
+                let session = FocusSession::new(SystemTime::now(), vec!["code".to_string()]);
+                let sync_clone = sync.clone();
+                sync.spawn_limited(async move {
+                    sync_clone.update_focus_session(&session).await.unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(served.load(Ordering::SeqCst), 6);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
 } 
\ No newline at end of file
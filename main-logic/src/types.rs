@@ -1,6 +1,8 @@
 //! Shared newtypes for strong typing across the codebase.
 
+use chrono::{NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,7 +20,7 @@ impl Into<Uuid> for SessionId {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppUsageEvent {
     pub id: Uuid,
     pub process_name: String,
@@ -27,4 +29,101 @@ pub struct AppUsageEvent {
     pub start_time: i64,
     pub end_time: i64,
     pub duration_secs: i64,
+    /// The blacklist rule that caused a "blocked" status, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_rule: Option<String>,
+    /// Why the user says they got distracted, if they gave a reason.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Whether an app usage event was allowed or blocked, stored as `"allowed"` or `"blocked"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageStatus {
+    Allowed,
+    Blocked,
+}
+
+impl UsageStatus {
+    pub fn from_blocked(blocked: bool) -> Self {
+        if blocked {
+            UsageStatus::Blocked
+        } else {
+            UsageStatus::Allowed
+        }
+    }
+
+    /// Returns the canonical string stored in the `status` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageStatus::Allowed => "allowed",
+            UsageStatus::Blocked => "blocked",
+        }
+    }
+}
+
+impl std::fmt::Display for UsageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for UsageStatus {
+    type Err = String;
+
+    /// Parses a status case-insensitively, accepting the legacy `active` and `distraction`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "allowed" | "active" => Ok(UsageStatus::Allowed),
+            "blocked" | "distraction" => Ok(UsageStatus::Blocked),
+            other => Err(format!("expected 'allowed' or 'blocked', got '{}'", other)),
+        }
+    }
+}
+
+/// An [`AppUsageEvent`] with its epoch times also rendered as local ISO-8601 strings,
+/// for raw inspection and CSV export. The epoch fields remain the source of truth.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalTimeEvent {
+    #[serde(flatten)]
+    pub event: AppUsageEvent,
+    /// `event.start_time` as local time, e.g. `2024-05-08T09:30:00+02:00`.
+    pub start_local: String,
+    /// `event.end_time` as local time.
+    pub end_local: String,
+}
+
+/// What a focus goal applies to: one calendar date, or every occurrence of a weekday.
+///
+/// Parses from `"2024-01-31"` or a weekday name such as `"mon"` / `"Monday"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalKey {
+    Date(NaiveDate),
+    Weekday(Weekday),
+}
+
+impl GoalKey {
+    /// Returns the key the goal is stored under in the `focus_goals` table.
+    pub fn storage_key(&self) -> String {
+        match self {
+            GoalKey::Date(date) => date.format("%Y-%m-%d").to_string(),
+            GoalKey::Weekday(weekday) => weekday.to_string().to_lowercase(),
+        }
+    }
+}
+
+impl FromStr for GoalKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(GoalKey::Date(date));
+        }
+        s.parse::<Weekday>()
+            .map(GoalKey::Weekday)
+            .map_err(|_| format!("expected a YYYY-MM-DD date or a weekday, got '{}'", s))
+    }
 }
@@ -0,0 +1,147 @@
+//! Watcher module: reloads app rules when the rules file changes on disk.
+
+use crate::apprules::AppRules;
+use crate::constants::APPRULES_RELOAD_DEBOUNCE_MS;
+use crate::session::SessionManager;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Watches the app rules file at `path` on a background thread and reloads it into
+/// `session_mgr` when it changes, until `shutdown_flag` is set. Changes arriving within
+/// `APPRULES_RELOAD_DEBOUNCE_MS` of the first are folded into a single reload. While
+/// `SYNAPSE_APPRULES_JSON` is set, the rules come from it and file changes are ignored.
+pub fn spawn_apprules_watcher(
+    path: PathBuf,
+    session_mgr: Arc<Mutex<SessionManager>>,
+    shutdown_flag: Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let (tx, rx) = channel();
+        println!("[Watcher] Starting file watcher for: {}", path.display());
+        let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())
+            .expect("Failed to create watcher");
+        watcher
+            .watch(&path, RecursiveMode::NonRecursive)
+            .expect("Failed to watch apprules.json");
+        println!("[Watcher] File watcher started successfully");
+        let window = Duration::from_millis(APPRULES_RELOAD_DEBOUNCE_MS);
+        debounce_changes(&rx, &shutdown_flag, window, || {
+            if AppRules::env_json_is_set() {
+                log::info!("[Watcher] SYNAPSE_APPRULES_JSON is set, ignoring apprules.json change");
+                return;
+            }
+            log::info!("[Watcher] Detected apprules.json change, reloading...");
+            if session_mgr.lock().unwrap().reload_apprules_from_path(&path) {
+                println!("[Watcher] AppRules reloaded successfully");
+            }
+        });
+        println!("[Watcher] File watcher stopped");
+    })
+}
+
+/// Calls `on_change` once per burst of modify events on `rx`: after the first, further
+/// events are collected until `window` has passed. Returns when `shutdown_flag` is set or
+/// the sender is gone.
+fn debounce_changes(
+    rx: &Receiver<notify::Result<Event>>,
+    shutdown_flag: &AtomicBool,
+    window: Duration,
+    mut on_change: impl FnMut(),
+) {
+    while !shutdown_flag.load(Ordering::SeqCst) {
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) if is_modify(&event) => {
+                let deadline = Instant::now() + window;
+                loop {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break;
+                    }
+                    match rx.recv_timeout(remaining) {
+                        Ok(_) => continue,
+                        Err(_) => break,
+                    }
+                }
+                on_change();
+            }
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+fn is_modify(event: &notify::Result<Event>) -> bool {
+    matches!(
+        event,
+        Ok(Event {
+            kind: EventKind::Modify(_),
+            ..
+        })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, ModifyKind};
+    use std::sync::mpsc::Sender;
+
+    fn modify() -> notify::Result<Event> {
+        Ok(Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content))))
+    }
+
+    fn count_reloads(rx: Receiver<notify::Result<Event>>, window: Duration) -> usize {
+        let mut reloads = 0;
+        debounce_changes(&rx, &AtomicBool::new(false), window, || reloads += 1);
+        reloads
+    }
+
+    fn send_all(tx: &Sender<notify::Result<Event>>, events: Vec<notify::Result<Event>>) {
+        for event in events {
+            tx.send(event).unwrap();
+        }
+    }
+
+    #[test]
+    fn burst_of_modify_events_reloads_once() {
+        let (tx, rx) = channel();
+        send_all(&tx, vec![modify(), modify(), modify()]);
+        drop(tx);
+        assert_eq!(count_reloads(rx, Duration::from_millis(300)), 1);
+    }
+
+    #[test]
+    fn changes_after_the_window_reload_again() {
+        let (tx, rx) = channel();
+        let sender = thread::spawn(move || {
+            send_all(&tx, vec![modify(), modify()]);
+            thread::sleep(Duration::from_millis(250));
+            send_all(&tx, vec![modify()]);
+        });
+        assert_eq!(count_reloads(rx, Duration::from_millis(50)), 2);
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn non_modify_events_are_ignored() {
+        let (tx, rx) = channel();
+        send_all(&tx, vec![Ok(Event::new(EventKind::Create(CreateKind::File)))]);
+        drop(tx);
+        assert_eq!(count_reloads(rx, Duration::from_millis(50)), 0);
+    }
+
+    #[test]
+    fn shutdown_stops_the_loop() {
+        let (_tx, rx) = channel::<notify::Result<Event>>();
+        let mut reloads = 0;
+        debounce_changes(&rx, &AtomicBool::new(true), Duration::from_millis(50), || {
+            reloads += 1
+        });
+        assert_eq!(reloads, 0);
+    }
+}
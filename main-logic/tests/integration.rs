@@ -377,3 +377,52 @@ fn test_api_today_vs_past_entries() {
         expected_focus_time
     );
 }
+
+#[cfg(feature = "http-api")]
+#[tokio::test]
+async fn test_http_api_serves_today_stats() {
+    use main_logic::http_api::{self, HttpApiState};
+    use main_logic::AsyncDbHandle;
+
+    let path = std::env::temp_dir().join(format!("synapse_http_api_{}.db", uuid::Uuid::new_v4()));
+    let path_str = path.to_str().unwrap().to_string();
+    let mut db = DbHandle::open(&path_str).unwrap();
+    // Starts now and ends in the future, so it counts as today whatever the time
+    let now = chrono::Local::now().timestamp();
+    db.test_conn()
+        .execute(
+            "INSERT INTO focus_sessions (id, start_time, end_time, work_apps, distraction_attempts) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params!["http-today", now, now + 600, "code.exe", 2],
+        )
+        .unwrap();
+
+    let mgr = SessionManager::new(
+        AppRules::test_with_rules(vec![], vec![]),
+        DbHandle::test_in_memory_with_schema(),
+        None,
+        None,
+    );
+    let state = HttpApiState::new(AsyncDbHandle::open(path_str), Arc::new(Mutex::new(mgr)));
+    let addr = http_api::start("127.0.0.1:0".parse().unwrap(), state)
+        .await
+        .unwrap();
+
+    let stats: serde_json::Value = reqwest::get(format!("http://{}/stats/today", addr))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(stats["focus_secs"], 600);
+    assert_eq!(stats["distractions"], 2);
+    assert_eq!(stats["sessions"], 1);
+
+    let current: serde_json::Value = reqwest::get(format!("http://{}/session/current", addr))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(current.is_null());
+    let _ = std::fs::remove_file(path);
+}
@@ -1,5 +1,11 @@
+use chrono::NaiveDate;
 use dotenvy;
-use main_logic::{api, apprules, BackendCommand, DbHandle}; // Added apprules and BackendCommand
+use main_logic::pomodoro::{self, PomodoroPhase};
+use main_logic::types::GoalKey;
+use main_logic::{
+    api, apprules, AppRules, AsyncDbHandle, BackendCommand, Config, DbHandle, NonWorkingDays,
+    PollOutcome, PollTimingStats, SessionManager, SessionManagerSlot,
+}; // Added apprules and BackendCommand
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -15,6 +21,7 @@ struct BackendState {
     handle: Mutex<Option<JoinHandle<()>>>,
     shutdown_flag: Arc<AtomicBool>,
     command_tx: Mutex<Option<Sender<BackendCommand>>>,
+    session_mgr: SessionManagerSlot,
 }
 
 impl BackendState {
@@ -23,6 +30,16 @@ impl BackendState {
             handle: Mutex::new(None),
             shutdown_flag: Arc::new(AtomicBool::new(false)),
             command_tx: Mutex::new(None),
+            session_mgr: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the rules the running backend is using, or loads them from disk if it isn't running.
+    fn current_rules(&self) -> Result<AppRules, String> {
+        let slot = self.session_mgr.lock().unwrap();
+        match slot.as_ref() {
+            Some(mgr) => Ok(mgr.lock().unwrap().apprules().clone()),
+            None => AppRules::new().map_err(|e| format!("{:?}", e)),
         }
     }
 }
@@ -56,8 +73,9 @@ fn start_monitoring_cmd(
         }
     }) as Box<dyn Fn(&str) + Send + Sync>);
 
+    let session_slot = state.session_mgr.clone();
     *handle_guard = Some(thread::spawn(move || {
-        main_logic::run_backend_with_shutdown(shutdown_flag, on_distraction, rx);
+        main_logic::run_backend_with_shutdown(shutdown_flag, on_distraction, rx, session_slot);
     }));
     println!("[Tauri] Backend monitoring started");
     Ok(())
@@ -78,10 +96,28 @@ fn stop_monitoring_cmd(state: State<BackendState>) -> Result<(), String> {
     Ok(())
 }
 
+/// Sends the user's distraction reason, if any, ahead of the popup action.
+fn send_reason(
+    tx: &Sender<BackendCommand>,
+    app_name: &str,
+    reason: Option<String>,
+) -> Result<(), String> {
+    if let Some(reason) = reason.filter(|r| !r.trim().is_empty()) {
+        tx.send(BackendCommand::Annotate(app_name.to_string(), reason))
+            .map_err(|e| format!("Failed to send distraction reason: {}", e))?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
-fn kill_app_cmd(state: State<BackendState>, app_name: String) -> Result<(), String> {
+fn kill_app_cmd(
+    state: State<BackendState>,
+    app_name: String,
+    reason: Option<String>,
+) -> Result<(), String> {
     let tx_guard = state.command_tx.lock().unwrap();
     if let Some(tx) = &*tx_guard {
+        send_reason(tx, &app_name, reason)?;
         tx.send(BackendCommand::Kill(app_name))
             .map_err(|e| format!("Failed to send kill command: {}", e))?;
         Ok(())
@@ -95,9 +131,11 @@ fn snooze_app_cmd(
     state: State<BackendState>,
     app_name: String,
     duration_secs: u64,
+    reason: Option<String>,
 ) -> Result<(), String> {
     let tx_guard = state.command_tx.lock().unwrap();
     if let Some(tx) = &*tx_guard {
+        send_reason(tx, &app_name, reason)?;
         tx.send(BackendCommand::Snooze(
             app_name,
             std::time::Duration::from_secs(duration_secs),
@@ -109,6 +147,18 @@ fn snooze_app_cmd(
     }
 }
 
+#[tauri::command]
+fn whitelist_app_cmd(state: State<BackendState>, app_name: String) -> Result<(), String> {
+    let tx_guard = state.command_tx.lock().unwrap();
+    if let Some(tx) = &*tx_guard {
+        tx.send(BackendCommand::AddToWhitelist(app_name))
+            .map_err(|e| format!("Failed to send whitelist command: {}", e))?;
+        Ok(())
+    } else {
+        Err("Backend not running".to_string())
+    }
+}
+
 #[tauri::command]
 fn is_monitoring_cmd(state: State<BackendState>) -> Result<bool, String> {
     let handle_guard = state.handle.lock().unwrap();
@@ -116,23 +166,427 @@ fn is_monitoring_cmd(state: State<BackendState>) -> Result<bool, String> {
 }
 
 #[tauri::command]
-fn total_focus_time_today_cmd() -> Result<i64, String> {
-    let db = DbHandle::new().map_err(|e| format!("{:?}", e))?;
-    let result = api::total_focus_time_today(&db);
-    // println!("total_focus_time_today_cmd result: {:?}", result);
-    result.map_err(|e| format!("{:?}", e))
+fn current_session_distractions_cmd(state: State<BackendState>) -> Result<u32, String> {
+    let slot = state.session_mgr.lock().unwrap();
+    Ok(slot
+        .as_ref()
+        .map(|mgr| mgr.lock().unwrap().current_session_distractions())
+        .unwrap_or(0))
 }
 
 #[tauri::command]
-fn total_distractions_today_cmd() -> Result<i64, String> {
-    let db = DbHandle::new().map_err(|e| format!("{:?}", e))?;
-    api::total_distractions_today(&db).map_err(|e| format!("{:?}", e))
+fn set_distraction_limit_cmd(state: State<BackendState>, limit: u32) -> Result<(), String> {
+    let slot = state.session_mgr.lock().unwrap();
+    let Some(mgr) = slot.as_ref() else {
+        return Err("Backend not running".to_string());
+    };
+    mgr.lock().unwrap().set_max_distractions_per_session(limit);
+    Ok(())
 }
 
 #[tauri::command]
-fn total_focus_sessions_today_cmd() -> Result<i64, String> {
-    let db = DbHandle::new().map_err(|e| format!("{:?}", e))?;
-    api::total_focus_sessions_today(&db).map_err(|e| format!("{:?}", e))
+fn dry_run_poll_cmd(
+    state: State<BackendState>,
+    foreground: Option<String>,
+    running: Vec<String>,
+) -> Result<PollOutcome, String> {
+    let slot = state.session_mgr.lock().unwrap();
+    let Some(mgr) = slot.as_ref() else {
+        return Err("Backend not running".to_string());
+    };
+    let outcome = mgr.lock().unwrap().dry_run_poll(foreground, &running);
+    Ok(outcome)
+}
+
+#[tauri::command]
+fn poll_timing_stats_cmd(state: State<BackendState>) -> Result<PollTimingStats, String> {
+    let slot = state.session_mgr.lock().unwrap();
+    let Some(mgr) = slot.as_ref() else {
+        return Err("Backend not running".to_string());
+    };
+    let stats = api::poll_timing_stats(&mgr.lock().unwrap());
+    Ok(stats)
+}
+
+#[tauri::command]
+fn start_pomodoro_cmd(
+    app_handle: tauri::AppHandle,
+    state: State<BackendState>,
+    work_min: u64,
+    break_min: u64,
+    cycles: u32,
+) -> Result<(), String> {
+    let work = pomodoro::minutes(work_min).map_err(|e| format!("{:?}", e))?;
+    let brk = pomodoro::minutes(break_min).map_err(|e| format!("{:?}", e))?;
+    let slot = state.session_mgr.lock().unwrap();
+    let Some(mgr) = slot.as_ref() else {
+        return Err("Backend not running".to_string());
+    };
+    let mut mgr = mgr.lock().unwrap();
+    mgr.set_on_pomodoro_phase(Some(Box::new(move |phase: PomodoroPhase, cycle: u32| {
+        let payload = serde_json::json!({ "phase": phase, "cycle": cycle });
+        if let Err(e) = app_handle.emit("pomodoro-phase", payload) {
+            eprintln!("[Tauri] Failed to emit pomodoro-phase event: {}", e);
+        }
+    })));
+    mgr.start_pomodoro(work, brk, cycles).map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn total_focus_time_today_cmd() -> Result<i64, String> {
+    AsyncDbHandle::from_env()
+        .read(api::total_focus_time_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn total_distractions_today_cmd() -> Result<i64, String> {
+    AsyncDbHandle::from_env()
+        .read(api::total_distractions_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn total_focus_sessions_today_cmd() -> Result<i64, String> {
+    AsyncDbHandle::from_env()
+        .read(api::total_focus_sessions_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn export_day_cmd(day_start: i64) -> Result<api::DayExport, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::export_day(db, day_start))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn activity_bounds_cmd(day_start: i64) -> Result<Option<(i64, i64)>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::activity_bounds(db, day_start))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn focus_efficiency_today_cmd() -> Result<f64, String> {
+    AsyncDbHandle::from_env()
+        .read(api::focus_efficiency_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn import_day_cmd(export: api::DayExport) -> Result<usize, String> {
+    AsyncDbHandle::from_env()
+        .import_day(export)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn all_time_stats_cmd() -> Result<api::AllTimeStats, String> {
+    AsyncDbHandle::from_env()
+        .read(api::all_time_stats)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn distraction_reasons_summary_cmd(
+    start: i64,
+    end: i64,
+) -> Result<Vec<(String, i64)>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::distraction_reasons_summary(db, start, end))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn unused_rules_cmd(
+    state: State<'_, BackendState>,
+    since: i64,
+) -> Result<Vec<String>, String> {
+    let rules = state.current_rules()?;
+    AsyncDbHandle::from_env()
+        .read(move |db| api::unused_rules(db, &rules, since))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn pace_vs_yesterday_cmd() -> Result<api::PaceComparison, String> {
+    AsyncDbHandle::from_env()
+        .read(api::pace_vs_yesterday)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn focus_time_trend_cmd(days: u32) -> Result<Vec<(i64, f64)>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::focus_time_trend(db, days))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn app_usage_between_cmd(start: i64, end: i64) -> Result<Vec<api::AppUsage>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::app_usage_between(db, start, end))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn recent_foreground_apps_cmd(seconds: i64) -> Result<Vec<(String, i64)>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::recent_foreground_apps(db, seconds))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn category_usage_today_cmd() -> Result<Vec<(String, i64, i64)>, String> {
+    AsyncDbHandle::from_env()
+        .read(api::category_usage_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn consistency_score_cmd(days: u32) -> Result<f64, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::consistency_score(db, days))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn longest_distraction_free_span_today_cmd() -> Result<i64, String> {
+    AsyncDbHandle::from_env()
+        .read(api::longest_distraction_free_span_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn pending_sync_summary_cmd() -> Result<api::PendingSummary, String> {
+    AsyncDbHandle::from_env()
+        .read(api::pending_sync_summary)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn unsynced_sessions_cmd() -> Result<Vec<api::UnsyncedSession>, String> {
+    AsyncDbHandle::from_env()
+        .read(api::unsynced_sessions)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn effective_config_cmd() -> Result<api::EffectiveConfig, String> {
+    api::effective_config().map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn test_supabase_connection_cmd(url: String, key: String) -> Result<(), String> {
+    api::test_supabase_connection(url, key)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn most_productive_day_cmd(start: i64, end: i64) -> Result<Option<(i64, i64)>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::most_productive_day(db, start, end))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn session_length_histogram_cmd(
+    bucket_secs: i64,
+    start: i64,
+    end: i64,
+) -> Result<Vec<(i64, i64)>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::session_length_histogram(db, bucket_secs, start, end))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn set_goal_cmd(key: String, goal_secs: i64) -> Result<(), String> {
+    let key: GoalKey = key.parse()?;
+    AsyncDbHandle::from_env()
+        .run(move |db| db.set_goal(key, goal_secs))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+/// Returns the configured non-working days, or none if the config can't be loaded.
+fn non_working_days() -> NonWorkingDays {
+    Config::load()
+        .map(|config| config.non_working_days)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+async fn weekly_report_html_cmd(week_start: NaiveDate) -> Result<String, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::weekly_report_html(db, week_start))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn goal_history_cmd(start: NaiveDate, end: NaiveDate) -> Result<Vec<api::GoalDay>, String> {
+    let non_working = non_working_days();
+    AsyncDbHandle::from_env()
+        .read(move |db| api::goal_history(db, start, end, &non_working))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn month_activity_cmd(month_start: NaiveDate) -> Result<Vec<api::DayActivity>, String> {
+    AsyncDbHandle::from_env()
+        .read(move |db| api::month_activity(db, month_start))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn distractions_outside_sessions_today_cmd() -> Result<i64, String> {
+    AsyncDbHandle::from_env()
+        .read(api::distractions_outside_sessions_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn goal_streak_cmd() -> Result<u32, String> {
+    let non_working = non_working_days();
+    AsyncDbHandle::from_env()
+        .read(move |db| api::goal_streak(db, &non_working))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn scaled_goal_today_cmd() -> Result<i64, String> {
+    let working_hours = Config::load()
+        .map(|config| config.working_hours)
+        .unwrap_or_default();
+    AsyncDbHandle::from_env()
+        .read(move |db| api::scaled_goal_today(db, &working_hours))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn eta_to_goal_cmd() -> Result<Option<i64>, String> {
+    AsyncDbHandle::from_env()
+        .read(api::eta_to_goal)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn diagnostic_snapshot_cmd(state: State<'_, BackendState>) -> Result<String, String> {
+    let rules = state.current_rules()?;
+    let (session, poll_timing) = {
+        let slot = state.session_mgr.lock().unwrap();
+        match slot.as_ref() {
+            Some(mgr) => {
+                let mgr = mgr.lock().unwrap();
+                (mgr.current_session().cloned(), Some(api::poll_timing_stats(&mgr)))
+            }
+            None => (None, None),
+        }
+    };
+    let config = Config::load().unwrap_or_default();
+    let snapshot = AsyncDbHandle::from_env()
+        .run(move |db| {
+            api::diagnostic_snapshot(db, &rules, session, poll_timing, config, |key| {
+                std::env::var(key).ok()
+            })
+        })
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn reset_today_cmd(state: State<'_, BackendState>) -> Result<usize, String> {
+    // End the active session first so its row is deleted along with the rest of today.
+    // Ending it only writes locally; the backend pushes it, off the manager's lock
+    let mgr = state.session_mgr.lock().unwrap().clone();
+    let ended = match mgr {
+        Some(mgr) => mgr
+            .lock()
+            .unwrap()
+            .end_active_session()
+            .map_err(|e| format!("{:?}", e))?,
+        None => None,
+    };
+    if let Some(session) = ended {
+        let tx_guard = state.command_tx.lock().unwrap();
+        if let Some(tx) = &*tx_guard {
+            tx.send(BackendCommand::SyncEndedSession(session))
+                .map_err(|e| format!("Failed to send sync command: {}", e))?;
+        }
+    }
+    AsyncDbHandle::from_env()
+        .run(api::reset_today)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn get_setting_cmd(key: String) -> Result<Option<String>, String> {
+    AsyncDbHandle::from_env()
+        .run(move |db| db.get_setting(&key))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn add_manual_session_cmd(
+    start: i64,
+    end: i64,
+    work_apps: Vec<String>,
+    distraction_attempts: u32,
+) -> Result<String, String> {
+    AsyncDbHandle::from_env()
+        .run(move |db| api::add_manual_session(db, start, end, &work_apps, distraction_attempts))
+        .await
+        .map(|id| id.to_string())
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn schema_version_cmd() -> Result<i64, String> {
+    AsyncDbHandle::from_env()
+        .run(|db| db.schema_version())
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+async fn set_setting_cmd(key: String, value: String) -> Result<(), String> {
+    AsyncDbHandle::from_env()
+        .run(move |db| db.set_setting(&key, &value))
+        .await
+        .map_err(|e| format!("{:?}", e))
 }
 
 #[cfg(target_os = "windows")]
@@ -141,6 +595,21 @@ fn get_installed_apps_cmd() -> Vec<(String, String)> {
     main_logic::api::get_installed_apps_api()
 }
 
+#[cfg(target_os = "windows")]
+#[tauri::command]
+fn refresh_apps_cmd(state: State<'_, BackendState>) -> Result<usize, String> {
+    let count = main_logic::platform::installed_apps::refresh_installed_apps_cache();
+    // Let a running backend recognize newly installed apps right away
+    let mgr = state.session_mgr.lock().unwrap().clone();
+    if let Some(mgr) = mgr {
+        mgr.lock()
+            .unwrap()
+            .register_installed_apps(main_logic::api::get_installed_apps_api())
+            .map_err(|e| format!("{:?}", e))?;
+    }
+    Ok(count)
+}
+
 #[tauri::command]
 fn update_app_rules_cmd(whitelist: Vec<String>, blacklist: Vec<String>) -> Result<(), String> {
     println!(
@@ -158,6 +627,21 @@ fn update_app_rules_cmd(whitelist: Vec<String>, blacklist: Vec<String>) -> Resul
     result.map_err(|e| format!("{:?}", e))
 }
 
+#[tauri::command]
+fn evaluate_app_cmd(state: State<BackendState>, app_name: String) -> Result<api::AppVerdict, String> {
+    let slot = state.session_mgr.lock().unwrap();
+    if let Some(mgr) = slot.as_ref() {
+        return Ok(api::evaluate_app(&mgr.lock().unwrap(), &app_name));
+    }
+    drop(slot);
+    // Not monitoring: classify with the rules and settings a new run would use
+    let rules = state.current_rules()?;
+    let db = DbHandle::new().map_err(|e| format!("{:?}", e))?;
+    let mut mgr = SessionManager::new(rules, db, None, None);
+    mgr.apply_config(&Config::load().unwrap_or_default());
+    Ok(api::evaluate_app(&mgr, &app_name))
+}
+
 #[tauri::command]
 fn start_focus_mode_cmd() -> Result<String, String> {
     // For now, just return success - in a real implementation this would trigger the session manager
@@ -206,15 +690,58 @@ pub fn run() {
             total_focus_time_today_cmd,
             total_distractions_today_cmd,
             total_focus_sessions_today_cmd,
+            all_time_stats_cmd,
+            pace_vs_yesterday_cmd,
+            longest_distraction_free_span_today_cmd,
+            most_productive_day_cmd,
+            test_supabase_connection_cmd,
+            focus_time_trend_cmd,
+            consistency_score_cmd,
+            recent_foreground_apps_cmd,
+            category_usage_today_cmd,
+            app_usage_between_cmd,
+            distraction_reasons_summary_cmd,
+            unused_rules_cmd,
+            pending_sync_summary_cmd,
+            unsynced_sessions_cmd,
+            effective_config_cmd,
+            dry_run_poll_cmd,
+            set_distraction_limit_cmd,
+            session_length_histogram_cmd,
+            export_day_cmd,
+            activity_bounds_cmd,
+            focus_efficiency_today_cmd,
+            import_day_cmd,
+            set_goal_cmd,
+            goal_history_cmd,
+            weekly_report_html_cmd,
+            month_activity_cmd,
+            distractions_outside_sessions_today_cmd,
+            goal_streak_cmd,
+            eta_to_goal_cmd,
+            scaled_goal_today_cmd,
+            diagnostic_snapshot_cmd,
+            poll_timing_stats_cmd,
+            reset_today_cmd,
+            get_setting_cmd,
+            set_setting_cmd,
+            schema_version_cmd,
+            add_manual_session_cmd,
             start_focus_mode_cmd,
             #[cfg(target_os = "windows")]
             get_installed_apps_cmd,
+            #[cfg(target_os = "windows")]
+            refresh_apps_cmd,
             update_app_rules_cmd,
+            evaluate_app_cmd,
             start_monitoring_cmd,
             stop_monitoring_cmd,
             is_monitoring_cmd,
+            current_session_distractions_cmd,
+            start_pomodoro_cmd,
             kill_app_cmd,
             snooze_app_cmd,
+            whitelist_app_cmd,
             backend_spotify_token_exchange,
             backend_spotify_refresh_token
         ])
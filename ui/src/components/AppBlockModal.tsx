@@ -5,9 +5,10 @@ import { AlertOctagon } from "lucide-react"
 
 interface AppBlockModalProps {
   isVisible: boolean
-  onClose: () => void
-  onUseFor5Mins: () => void
+  onClose: (reason?: string) => void
+  onUseFor5Mins: (reason?: string) => void
   onShowAgain?: () => void
+  onWhitelist?: () => void
   isStandalone?: boolean
 }
 
@@ -16,12 +17,14 @@ export default function AppBlockModal({
   onClose,
   onUseFor5Mins,
   onShowAgain,
+  onWhitelist,
   isStandalone = false
 }: AppBlockModalProps) {
   // ... state ...
   // Initialize based on props to ensure immediate render if needed
   const [isAnimating, setIsAnimating] = useState(isVisible)
   const [shouldRender, setShouldRender] = useState(isVisible)
+  const [reason, setReason] = useState("")
 
   useEffect(() => {
     if (isVisible) {
@@ -36,16 +39,23 @@ export default function AppBlockModal({
     }
   }, [isVisible])
 
+  const trimmedReason = () => reason.trim() || undefined
+
   const handleCloseApp = () => {
-    onClose()
+    onClose(trimmedReason())
     console.log("App closed")
   }
 
   const handleUseFor5Mins = () => {
-    onUseFor5Mins()
+    onUseFor5Mins(trimmedReason())
     console.log("Using app for 5 minutes")
   }
 
+  const handleWhitelist = () => {
+    onWhitelist?.()
+    console.log("Whitelisting app")
+  }
+
   const handleShowAgain = () => {
     onShowAgain?.()
   }
@@ -110,6 +120,17 @@ export default function AppBlockModal({
                 This app is in your block list
               </p>
 
+              {/* Optional reason for the distraction */}
+              <input
+                type="text"
+                value={reason}
+                onChange={(e) => setReason(e.target.value)}
+                placeholder="Why? (optional)"
+                maxLength={200}
+                className={`w-full max-w-[240px] py-1.5 px-3 rounded-xl bg-transparent border border-synapse-dark/30 text-synapse-dark placeholder:text-synapse-dark/50 text-xs sm:text-sm font-wix focus:outline-none focus:border-synapse-dark/60 transition-all duration-500 ease-out delay-300 ${isAnimating ? "opacity-100 translate-y-0" : "opacity-0 translate-y-2"
+                  }`}
+              />
+
               {/* Action Buttons with Staggered Animation */}
               <div
                 className={`flex flex-col sm:flex-row gap-2 w-full max-w-[240px] transition-all duration-500 ease-out delay-400 ${isAnimating ? "opacity-100 translate-y-0" : "opacity-0 translate-y-3"
@@ -131,6 +152,17 @@ export default function AppBlockModal({
                   Use for 5 mins
                 </button>
               </div>
+
+              {/* False positive: whitelist the app */}
+              {onWhitelist && (
+                <button
+                  onClick={handleWhitelist}
+                  className={`text-xs text-synapse-dark/60 underline underline-offset-2 hover:text-synapse-dark transition-all duration-500 ease-out delay-500 font-wix ${isAnimating ? "opacity-100" : "opacity-0"
+                    }`}
+                >
+                  This is work, whitelist it
+                </button>
+              )}
             </div>
           </div>
         </div>
@@ -47,12 +47,12 @@ export default function BlockWarningPage() {
         }
     }, [])
 
-    const handleClose = async () => {
+    const handleClose = async (reason?: string) => {
         setIsVisible(false)
         if (appName) {
             console.log("Killing app:", appName);
             try {
-                await invoke('kill_app_cmd', { appName });
+                await invoke('kill_app_cmd', { appName, reason });
             } catch (e) {
                 console.error("Failed to kill app:", e);
             }
@@ -62,13 +62,13 @@ export default function BlockWarningPage() {
         }, 300) // Match animation duration
     }
 
-    const handleUseFor5Mins = async () => {
+    const handleUseFor5Mins = async (reason?: string) => {
         setIsVisible(false)
         console.log("Using for 5 mins from separate window")
         if (appName) {
             console.log("Snoozing app:", appName);
             try {
-                await invoke('snooze_app_cmd', { appName, durationSecs: 300 });
+                await invoke('snooze_app_cmd', { appName, durationSecs: 300, reason });
             } catch (e) {
                 console.error("Failed to snooze app:", e);
             }
@@ -78,6 +78,21 @@ export default function BlockWarningPage() {
         }, 300)
     }
 
+    const handleWhitelist = async () => {
+        setIsVisible(false)
+        if (appName) {
+            console.log("Whitelisting app:", appName);
+            try {
+                await invoke('whitelist_app_cmd', { appName });
+            } catch (e) {
+                console.error("Failed to whitelist app:", e);
+            }
+        }
+        setTimeout(async () => {
+            await getCurrentWindow().close()
+        }, 300)
+    }
+
     console.log("BlockWarningPage rendering, isVisible:", isVisible, "appName:", appName);
     return (
         <div className="h-screen w-screen bg-transparent flex items-center justify-center overflow-hidden">
@@ -85,6 +100,7 @@ export default function BlockWarningPage() {
                 isVisible={isVisible}
                 onClose={handleClose}
                 onUseFor5Mins={handleUseFor5Mins}
+                onWhitelist={handleWhitelist}
                 isStandalone={true}
             />
         </div>